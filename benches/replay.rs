@@ -0,0 +1,97 @@
+//! Benchmarks of parsing, decompressing, decoding and re-encoding replays.
+//!
+//! Run with `cargo bench --features testing`.
+
+use std::hint::black_box;
+use std::ops::ControlFlow;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use osu_replay_parser::decoder::decode_frames_pipelined;
+use osu_replay_parser::replay::GameMode;
+use osu_replay_parser::testing::{generate_replay, GeneratorOptions};
+use osu_replay_parser::writer::compress_frames;
+use osu_replay_parser::Replay;
+
+/// Benchmark a replay in its own group, with the size of the replay file as throughput.
+fn bench_replay(c: &mut Criterion, group: &str, replay: &Replay, sample_size: usize) {
+    let bytes = replay.to_bytes().expect("Error writing replay");
+    let frames = replay
+        .get_actions_with_progress(|_, _| {})
+        .expect("Error decoding frames");
+
+    let mut group = c.benchmark_group(group);
+    group.sample_size(sample_size);
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+    group.bench_function("parse", |b| {
+        b.iter(|| Replay::parse(black_box(&bytes)).expect("Error parsing replay"))
+    });
+    group.bench_function("decompress", |b| {
+        b.iter(|| {
+            let mut decompressed = Vec::new();
+            lzma_rs::lzma_decompress(
+                &mut black_box(&replay.compressed_data[..]),
+                &mut decompressed,
+            )
+            .expect("Error decompressing frames");
+            decompressed
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            black_box(replay)
+                .get_actions_with_progress(|_, _| {})
+                .expect("Error decoding frames")
+        })
+    });
+    group.bench_function("pipelined", |b| {
+        b.iter(|| {
+            let mut frames = Vec::new();
+            decode_frames_pipelined(black_box(&replay.compressed_data), |frame| {
+                frames.push(frame);
+                ControlFlow::<()>::Continue(())
+            })
+            .expect("Error decoding frames");
+            frames
+        })
+    });
+    group.bench_function("re-encode", |b| {
+        b.iter(|| compress_frames(black_box(&frames)))
+    });
+
+    group.finish();
+}
+
+fn sample(c: &mut Criterion) {
+    let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    bench_replay(c, "sample", &replay, 20);
+}
+
+fn short(c: &mut Criterion) {
+    let replay = generate_replay(&GeneratorOptions {
+        duration: 60_000,
+        ..GeneratorOptions::default()
+    });
+    bench_replay(c, "short", &replay, 20);
+}
+
+fn marathon(c: &mut Criterion) {
+    let replay = generate_replay(&GeneratorOptions {
+        duration: 30 * 60_000,
+        ..GeneratorOptions::default()
+    });
+    bench_replay(c, "marathon", &replay, 10);
+}
+
+fn mania_7k(c: &mut Criterion) {
+    let replay = generate_replay(&GeneratorOptions {
+        game_mode: GameMode::Mania,
+        key_count: 7,
+        duration: 3 * 60_000,
+        ..GeneratorOptions::default()
+    });
+    bench_replay(c, "mania 7K", &replay, 20);
+}
+
+criterion_group!(benches, sample, short, marathon, mania_7k);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    // Link arguments of the Node.js addon, e.g. dynamic lookup of Node-API on macOS
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
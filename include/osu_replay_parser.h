@@ -0,0 +1,77 @@
+#ifndef OSU_REPLAY_PARSER_H
+#define OSU_REPLAY_PARSER_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A parsed replay owned by the caller, created by [`osr_parse`] and released by [`osr_free`].
+ */
+typedef struct OsrReplay OsrReplay;
+
+/**
+ * A frame of the replay as seen from C.
+ */
+typedef struct OsrFrame {
+  /**
+   * Time in milliseconds since the previous frame.
+   */
+  int64_t time;
+  /**
+   * x coordinate of the cursor, or the pressed columns in osu!mania.
+   */
+  float x;
+  /**
+   * y coordinate of the cursor.
+   */
+  float y;
+  /**
+   * Bit flags of the pressed keys.
+   */
+  uint32_t keys;
+} OsrFrame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parse a replay from `len` bytes at `data`.
+ *
+ * Returns a null pointer if `data` is null or the replay cannot be parsed. The returned replay
+ * must be released with [`osr_free`].
+ *
+ * # Safety
+ * `data` must be null or point to at least `len` readable bytes.
+ */
+OsrReplay *osr_parse(const uint8_t *data, size_t len);
+
+/**
+ * Decode the frames of the replay and store their number in `len`.
+ *
+ * Returns a null pointer if `replay` is null or the frames cannot be decoded. The frames are
+ * owned by the replay and stay valid until it is released with [`osr_free`].
+ *
+ * # Safety
+ * `replay` must be null or a replay returned by [`osr_parse`] that was not released yet.
+ * `len` must be null or point to a writable `size_t`.
+ */
+const OsrFrame *osr_get_frames(OsrReplay *replay, size_t *len);
+
+/**
+ * Release a replay returned by [`osr_parse`] together with its frames.
+ *
+ * # Safety
+ * `replay` must be null or a replay returned by [`osr_parse`] that was not released yet.
+ */
+void osr_free(OsrReplay *replay);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OSU_REPLAY_PARSER_H */
@@ -0,0 +1,37 @@
+/** Header of an osu! replay, as returned by `parse`. */
+export interface Replay {
+  gameMode: number;
+  version: number;
+  beatmapMd5: string;
+  playerName: string;
+  replayMd5: string;
+  n300: number;
+  n100: number;
+  n50: number;
+  nGeki: number;
+  nKatu: number;
+  nMiss: number;
+  totalScore: number;
+  greatestCombo: number;
+  perfect: number;
+  mods: number;
+  lifeBar: string;
+  timeStamp: number;
+  /** Passed as a string, as score IDs exceed the exact integer range of numbers. */
+  onlineScoreId: string;
+  accuracy: number;
+}
+
+/** A frame of the replay, `time` is the delta to the previous frame in milliseconds. */
+export interface Frame {
+  time: number;
+  x: number;
+  y: number;
+  keys: number;
+}
+
+/** Parse the header of a replay file. Throws if the replay cannot be parsed. */
+export function parse(data: Buffer): Replay;
+
+/** Decode the frames of a replay file. Throws if the replay or its frames cannot be decoded. */
+export function decodeFrames(data: Buffer): Frame[];
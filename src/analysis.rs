@@ -0,0 +1,39 @@
+//! Analysis of decoded replay frames, optionally together with the played [`Beatmap`](crate::beatmap::Beatmap).
+
+mod aim;
+mod analyzer;
+mod annotate;
+mod hit_errors;
+mod hold_times;
+mod interpolation;
+mod life_bar;
+mod sections;
+mod slider_breaks;
+mod tapping;
+mod validation;
+
+pub use aim::{aim_tightness, AimTightness};
+pub use analyzer::{Analyzer, Analyzers, Finding, FrameVisitor, Report, StreamingAnalyzer};
+pub use annotate::{annotate, FrameAnnotation};
+pub use hit_errors::{
+    classify_hit_error, hit_error_array, hit_errors, unstable_rate, write_hit_errors, HitError,
+    Judgement,
+};
+pub use hold_times::{
+    hold_histograms, hold_times, Hold, HoldHistogram, HoldTimes, SHORT_HOLD_MAX_FRACTION,
+    SHORT_HOLD_THRESHOLD,
+};
+pub use interpolation::{interp_error, InterpolationError};
+pub use life_bar::{
+    fail_events, parse_life_bar, pass_status, write_life_bar, FailEvent, LifeBarPoint, PassStatus,
+};
+pub use sections::{segment, Section, SectionKind};
+pub use slider_breaks::{slider_breaks, SliderBreak, SliderBreakReason, FOLLOW_CIRCLE_SCALE};
+pub use tapping::{
+    tapping_bpm_timeline, tapping_report, BpmPoint, KeyTapping, SegmentKind, TapSegment,
+    TappingBpmOptions, TappingReport, STREAM_MAX_INTERVAL, STREAM_MIN_TAPS,
+};
+pub use validation::{
+    frame_rate, validate_frames, FrameIssue, FrameIssueKind, MAX_CURSOR_COORDINATE, MAX_FRAME_RATE,
+    MIN_FRAME_RATE, TELEPORT_DISTANCE, TELEPORT_MAX_DELTA,
+};
@@ -0,0 +1,82 @@
+use crate::beatmap::Beatmap;
+use crate::game_math::circle_radius;
+use crate::replay::{Mods, ReplayData};
+
+use super::hit_errors::judge_hits;
+
+/// Distribution of the distance between the cursor and the center of the hit objects at the
+/// time they were hit, a measure of aim precision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AimTightness {
+    /// Distances of all hits in osu! pixels, sorted ascending.
+    pub distances: Vec<f32>,
+    /// Mean distance in osu! pixels.
+    pub mean: f32,
+    /// Radius of the hit circles in osu! pixels, the largest distance that can hit.
+    pub radius: f32,
+}
+
+impl AimTightness {
+    /// Distance in osu! pixels below or at which `p` percent of the hits are, nearest rank method.
+    /// # Panics
+    /// Panics if `p` is not in the range `0.0..=100.0`.
+    pub fn percentile(&self, p: f64) -> f32 {
+        assert!((0.0..=100.0).contains(&p), "percentile must be in 0..=100");
+        let rank = (p / 100.0 * self.distances.len() as f64).ceil() as usize;
+        self.distances[rank.saturating_sub(1)]
+    }
+
+    /// Median distance in osu! pixels.
+    pub fn median(&self) -> f32 {
+        self.percentile(50.0)
+    }
+
+    /// Mean distance as a fraction of the circle radius, comparable between circle sizes.
+    pub fn normalized_mean(&self) -> f32 {
+        self.mean / self.radius
+    }
+}
+
+/// Compute the distribution of cursor distances from the centers of all hit circles and slider
+/// heads at the time they were hit.
+///
+/// Hits are reconstructed like [`hit_errors`](super::hit_errors), misses are left out.
+/// Returns `None` if no object was hit.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::aim_tightness;
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::ReplayData;
+///
+/// let beatmap = Beatmap::parse("[HitObjects]\n100,100,1000,1,0\n300,200,2000,1,0\n").unwrap();
+/// let frames = [
+///     ReplayData { time: 1000, x: 103.0, y: 104.0, keys: 1 },
+///     ReplayData { time: 20, x: 103.0, y: 104.0, keys: 0 },
+///     ReplayData { time: 980, x: 300.0, y: 215.0, keys: 2 },
+/// ];
+///
+/// let aim = aim_tightness(&frames, &beatmap, Mods::NONE).unwrap();
+/// assert_eq!(aim.distances, [5.0, 15.0]);
+/// assert_eq!(aim.mean, 10.0);
+/// assert_eq!(aim.median(), 5.0);
+/// assert_eq!(aim.percentile(95.0), 15.0);
+/// ```
+pub fn aim_tightness(frames: &[ReplayData], beatmap: &Beatmap, mods: Mods) -> Option<AimTightness> {
+    let mut distances: Vec<f32> = judge_hits(frames, beatmap, mods)
+        .into_iter()
+        .filter_map(|(_, distance)| distance)
+        .collect();
+    if distances.is_empty() {
+        return None;
+    }
+    distances.sort_by(f32::total_cmp);
+
+    let mean = distances.iter().sum::<f32>() / distances.len() as f32;
+    let radius = circle_radius(beatmap.difficulty.with_mods(mods).circle_size);
+    Some(AimTightness {
+        distances,
+        mean,
+        radius,
+    })
+}
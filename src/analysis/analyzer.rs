@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+
+use crate::errors::ReplayDataError;
+use crate::replay::{Replay, ReplayData};
+
+/// Something an [`Analyzer`] noticed in a replay, e.g. a suspicious section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Time of the finding in milliseconds, `None` if it concerns the whole replay.
+    pub time: Option<i64>,
+    /// Description of the finding.
+    pub message: String,
+}
+
+/// Result of an [`Analyzer`], consisting of named metrics and findings.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Report {
+    /// Named values computed by the analyzer.
+    pub metrics: BTreeMap<String, f64>,
+    /// Noteworthy observations, e.g. of a cheat detector.
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    /// Add a metric to the report.
+    pub fn metric(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.metrics.insert(name.into(), value);
+        self
+    }
+
+    /// Add a finding to the report.
+    pub fn finding(mut self, time: Option<i64>, message: impl Into<String>) -> Self {
+        self.findings.push(Finding {
+            time,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Whether the analyzer reported any findings.
+    pub fn has_findings(&self) -> bool {
+        !self.findings.is_empty()
+    }
+}
+
+/// An analysis of a replay and its decoded frames, e.g. a cheat detector or a statistics module.
+///
+/// Analyzers are composed with [`Analyzers`], which decodes the frames once and runs all of them.
+pub trait Analyzer {
+    /// Name of the analyzer, used to identify its report.
+    fn name(&self) -> &str;
+
+    /// Analyze the replay and its decoded frames.
+    fn analyze(&self, replay: &Replay, frames: &[ReplayData]) -> Report;
+}
+
+/// State of a [`StreamingAnalyzer`] while it is fed the frames of one replay.
+pub trait FrameVisitor {
+    /// Process the next frame.
+    fn visit(&mut self, frame: &ReplayData);
+
+    /// Create the report after all frames were visited.
+    fn finish(self: Box<Self>) -> Report;
+}
+
+/// An analysis that looks at each frame once in order, so that [`Analyzers`] can run many of them
+/// in a single pass over the frames.
+///
+/// Prefer this over [`Analyzer`] for long replays, where every additional pass over hundreds of
+/// thousands of frames is noticeable.
+pub trait StreamingAnalyzer {
+    /// Name of the analyzer, used to identify its report.
+    fn name(&self) -> &str;
+
+    /// Create the visitor for the frames of the replay.
+    fn start(&self, replay: &Replay) -> Box<dyn FrameVisitor>;
+}
+
+enum Entry {
+    Slice(Box<dyn Analyzer>),
+    Streaming(Box<dyn StreamingAnalyzer>),
+}
+
+impl Entry {
+    fn name(&self) -> &str {
+        match self {
+            Entry::Slice(analyzer) => analyzer.name(),
+            Entry::Streaming(analyzer) => analyzer.name(),
+        }
+    }
+}
+
+/// A set of [`Analyzer`]s and [`StreamingAnalyzer`]s run together over the same frames.
+///
+/// All streaming analyzers are fed in one shared pass over the frames.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{Analyzer, Analyzers, Report};
+/// use osu_replay_parser::{Replay, ReplayData};
+///
+/// struct FrameCount;
+///
+/// impl Analyzer for FrameCount {
+///     fn name(&self) -> &str {
+///         "frame_count"
+///     }
+///
+///     fn analyze(&self, _replay: &Replay, frames: &[ReplayData]) -> Report {
+///         Report::new().metric("frames", frames.len() as f64)
+///     }
+/// }
+///
+/// struct OffscreenCursor;
+///
+/// impl Analyzer for OffscreenCursor {
+///     fn name(&self) -> &str {
+///         "offscreen_cursor"
+///     }
+///
+///     fn analyze(&self, _replay: &Replay, frames: &[ReplayData]) -> Report {
+///         let mut report = Report::new();
+///         for frame in frames.iter().filter(|frame| frame.x < -500.0) {
+///             report = report.finding(Some(frame.time), "cursor far outside the playfield");
+///         }
+///         report
+///     }
+/// }
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let reports = Analyzers::new()
+///     .register(FrameCount)
+///     .register(OffscreenCursor)
+///     .run_replay(&replay)
+///     .expect("Error decoding frames");
+/// assert_eq!(reports[0].0, "frame_count");
+/// assert_eq!(reports[0].1.metrics["frames"], 11984.0);
+/// assert!(!reports[1].1.has_findings());
+/// ```
+///
+/// Streaming analyzers keep their state in a [`FrameVisitor`]:
+/// ```
+/// use osu_replay_parser::analysis::{Analyzers, FrameVisitor, Report, StreamingAnalyzer};
+/// use osu_replay_parser::{Replay, ReplayData};
+///
+/// #[derive(Default)]
+/// struct KeyPresses {
+///     previous: u32,
+///     presses: u32,
+/// }
+///
+/// impl FrameVisitor for KeyPresses {
+///     fn visit(&mut self, frame: &ReplayData) {
+///         self.presses += (frame.keys & !self.previous).count_ones();
+///         self.previous = frame.keys;
+///     }
+///
+///     fn finish(self: Box<Self>) -> Report {
+///         Report::new().metric("presses", self.presses.into())
+///     }
+/// }
+///
+/// struct KeyPressCount;
+///
+/// impl StreamingAnalyzer for KeyPressCount {
+///     fn name(&self) -> &str {
+///         "key_presses"
+///     }
+///
+///     fn start(&self, _replay: &Replay) -> Box<dyn FrameVisitor> {
+///         Box::new(KeyPresses::default())
+///     }
+/// }
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let reports = Analyzers::new()
+///     .register_streaming(KeyPressCount)
+///     .run_replay(&replay)
+///     .expect("Error decoding frames");
+/// assert!(reports[0].1.metrics["presses"] > 0.0);
+/// ```
+#[derive(Default)]
+pub struct Analyzers {
+    analyzers: Vec<Entry>,
+}
+
+impl Analyzers {
+    /// Create an empty set of analyzers.
+    pub fn new() -> Self {
+        Analyzers::default()
+    }
+
+    /// Add an analyzer, analyzers are run in the order they were registered.
+    pub fn register<A: Analyzer + 'static>(mut self, analyzer: A) -> Self {
+        self.analyzers.push(Entry::Slice(Box::new(analyzer)));
+        self
+    }
+
+    /// Add a streaming analyzer, analyzers are run in the order they were registered.
+    pub fn register_streaming<A: StreamingAnalyzer + 'static>(mut self, analyzer: A) -> Self {
+        self.analyzers.push(Entry::Streaming(Box::new(analyzer)));
+        self
+    }
+
+    /// Number of registered analyzers.
+    pub fn len(&self) -> usize {
+        self.analyzers.len()
+    }
+
+    /// Whether no analyzers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.analyzers.is_empty()
+    }
+
+    /// Names of the registered analyzers.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.analyzers.iter().map(Entry::name)
+    }
+
+    /// Run all analyzers over already decoded frames, returning the reports by analyzer name.
+    ///
+    /// The streaming analyzers are fed in a single pass over the frames, the other analyzers
+    /// receive the whole slice.
+    pub fn run(&self, replay: &Replay, frames: &[ReplayData]) -> Vec<(String, Report)> {
+        let mut visitors: Vec<Option<Box<dyn FrameVisitor>>> = self
+            .analyzers
+            .iter()
+            .map(|entry| match entry {
+                Entry::Slice(_) => None,
+                Entry::Streaming(analyzer) => Some(analyzer.start(replay)),
+            })
+            .collect();
+        if visitors.iter().any(Option::is_some) {
+            for frame in frames {
+                for visitor in visitors.iter_mut().flatten() {
+                    visitor.visit(frame);
+                }
+            }
+        }
+
+        self.analyzers
+            .iter()
+            .zip(visitors)
+            .map(|(entry, visitor)| {
+                let report = match (entry, visitor) {
+                    (Entry::Slice(analyzer), _) => analyzer.analyze(replay, frames),
+                    (Entry::Streaming(_), Some(visitor)) => visitor.finish(),
+                    (Entry::Streaming(_), None) => unreachable!(),
+                };
+                (entry.name().to_string(), report)
+            })
+            .collect()
+    }
+
+    /// Decode the frames of the replay once and run all analyzers over them.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded.
+    pub fn run_replay(
+        &self,
+        replay: &Replay,
+    ) -> Result<Vec<(String, Report)>, ReplayDataError<'static>> {
+        let frames = replay.get_actions_with_progress(|_, _| {})?;
+        Ok(self.run(replay, &frames))
+    }
+}
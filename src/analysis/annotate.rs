@@ -0,0 +1,79 @@
+use crate::beatmap::Beatmap;
+use crate::frames::FramesExt;
+use crate::replay::ReplayData;
+
+/// Position of a frame relative to the hit objects of the beatmap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameAnnotation {
+    /// Absolute time of the frame in milliseconds.
+    pub time: i64,
+    /// Index of the hit object whose start time is closest to the frame, `None` if the beatmap
+    /// has no hit objects.
+    pub nearest_object: Option<usize>,
+    /// Distance of the cursor to the position of the nearest object in osu! pixels.
+    pub distance: Option<f32>,
+    /// Time until the start of the next hit object in milliseconds, `None` after the last one.
+    pub time_to_next_object: Option<i64>,
+}
+
+/// Annotate every frame with its position relative to the hit objects of the beatmap.
+///
+/// The annotations are produced lazily in the order of [`FramesExt::timed`], one for every frame
+/// except the seed frame, so they can be zipped with the frames. Apply the mods of the replay to
+/// the beatmap first with [`Beatmap::with_mods`], as Hard Rock flips the hit objects.
+/// Stacking is not taken into account.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::annotate;
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::ReplayData;
+///
+/// let beatmap = Beatmap::parse("[HitObjects]\n100,100,1000,1,0\n200,100,2000,1,0\n").unwrap();
+/// let frames = vec![
+///     ReplayData { time: 900, x: 100.0, y: 130.0, keys: 0 },
+///     ReplayData { time: 700, x: 200.0, y: 100.0, keys: 0 },
+/// ];
+/// let annotations: Vec<_> = annotate(&frames, &beatmap).collect();
+///
+/// assert_eq!(annotations[0].nearest_object, Some(0));
+/// assert_eq!(annotations[0].distance, Some(30.0));
+/// assert_eq!(annotations[0].time_to_next_object, Some(100));
+///
+/// assert_eq!(annotations[1].nearest_object, Some(1));
+/// assert_eq!(annotations[1].time_to_next_object, Some(400));
+/// ```
+pub fn annotate<'a>(
+    frames: &'a [ReplayData],
+    beatmap: &'a Beatmap,
+) -> impl Iterator<Item = FrameAnnotation> + 'a {
+    let objects = &beatmap.hit_objects;
+    // Index of the first object starting at or after the current frame
+    let mut next = 0;
+
+    frames.timed().map(move |(time, frame)| {
+        while next < objects.len() && objects[next].time < time {
+            next += 1;
+        }
+
+        let nearest_object = match (next.checked_sub(1), objects.get(next)) {
+            (Some(previous), Some(upcoming))
+                if time - objects[previous].time < upcoming.time - time =>
+            {
+                Some(previous)
+            }
+            (_, Some(_)) => Some(next),
+            (previous, None) => previous,
+        };
+        let distance = nearest_object.map(|index| {
+            let object = &objects[index];
+            (frame.x - object.x).hypot(frame.y - object.y)
+        });
+
+        FrameAnnotation {
+            time,
+            nearest_object,
+            distance,
+            time_to_next_object: objects.get(next).map(|object| object.time - time),
+        }
+    })
+}
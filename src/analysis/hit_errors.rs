@@ -0,0 +1,212 @@
+use std::fmt::{self, Formatter};
+use std::io::{self, Write};
+
+use crate::beatmap::{Beatmap, HitObjectKind};
+use crate::frames::{FramesExt, Keys};
+use crate::game_math::{circle_radius, HitWindows, MISS_WINDOW};
+use crate::replay::{Mods, ReplayData};
+
+/// Judgement of a hit object in osu!standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Judgement {
+    /// A 300.
+    Great,
+    /// A 100.
+    Ok,
+    /// A 50.
+    Meh,
+    /// A miss.
+    Miss,
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Judgement::Great => write!(f, "300"),
+            Judgement::Ok => write!(f, "100"),
+            Judgement::Meh => write!(f, "50"),
+            Judgement::Miss => write!(f, "miss"),
+        }
+    }
+}
+
+/// Timing error of a single hit object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HitError {
+    /// Time of the hit object in milliseconds.
+    pub time: i64,
+    /// Signed offset of the hit in milliseconds, negative for early hits.
+    ///
+    /// `None` for objects that were not hit at all.
+    pub offset: Option<i64>,
+    /// Judgement of the hit.
+    pub judgement: Judgement,
+}
+
+/// Classify a timing error in milliseconds using the given hit windows.
+///
+/// The error and the windows have to be in the same time base; with [`HitWindows::for_mods`]
+/// both are in real time, so errors measured in beatmap time have to be divided by
+/// [`Mods::speed_multiplier`] first. Errors outside of the 50 window are misses.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{classify_hit_error, Judgement};
+/// use osu_replay_parser::game_math::HitWindows;
+/// use osu_replay_parser::replay::Mods;
+///
+/// // 30ms early in beatmap time is a 300 at OD 8, but only 20ms in real time with Double Time
+/// let mods = Mods::DOUBLE_TIME;
+/// let windows = HitWindows::for_mods(8.0, mods);
+/// assert_eq!(classify_hit_error(-30.0 / mods.speed_multiplier(), &windows), Judgement::Great);
+/// assert_eq!(classify_hit_error(-30.0, &windows), Judgement::Ok);
+/// ```
+pub fn classify_hit_error(err_ms: f64, windows: &HitWindows) -> Judgement {
+    let offset = err_ms.abs();
+    if offset <= f64::from(windows.great) {
+        Judgement::Great
+    } else if offset <= f64::from(windows.ok) {
+        Judgement::Ok
+    } else if offset <= f64::from(windows.meh) {
+        Judgement::Meh
+    } else {
+        Judgement::Miss
+    }
+}
+
+/// Reconstruct the timing error of every hit circle and slider head of an osu!standard beatmap.
+///
+/// The beatmap is adjusted for the given mods. Hits are reconstructed from the key presses and
+/// cursor positions: a press hits the earliest object not yet judged if the cursor is inside its
+/// circle, presses on later objects are ignored (note lock). Spinners are skipped.
+///
+/// This is an approximation of osu!'s judgement logic and does not account for stacking.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{hit_errors, Judgement};
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::ReplayData;
+///
+/// let beatmap = Beatmap::parse(
+///     "[Difficulty]\nOverallDifficulty:8\n\n[HitObjects]\n100,100,1000,1,0\n300,200,2000,1,0\n",
+/// )
+/// .expect("Error parsing beatmap");
+/// let frames = [
+///     ReplayData { time: 990, x: 100.0, y: 100.0, keys: 1 },
+///     ReplayData { time: 30, x: 100.0, y: 100.0, keys: 0 },
+/// ];
+///
+/// let errors = hit_errors(&frames, &beatmap, Mods::NONE);
+/// assert_eq!(errors[0].offset, Some(-10));
+/// assert_eq!(errors[0].judgement, Judgement::Great);
+/// assert_eq!(errors[1].judgement, Judgement::Miss);
+/// ```
+pub fn hit_errors(frames: &[ReplayData], beatmap: &Beatmap, mods: Mods) -> Vec<HitError> {
+    judge_hits(frames, beatmap, mods)
+        .into_iter()
+        .map(|(error, _)| error)
+        .collect()
+}
+
+/// Reconstruct the hits like [`hit_errors`], together with the distance of the cursor to the
+/// center of the object at the hit, `None` for misses.
+pub(super) fn judge_hits(
+    frames: &[ReplayData],
+    beatmap: &Beatmap,
+    mods: Mods,
+) -> Vec<(HitError, Option<f32>)> {
+    let windows = HitWindows::for_mods(beatmap.difficulty.overall_difficulty, mods);
+    let speed = mods.speed_multiplier();
+    let beatmap = beatmap.clone().with_mods(mods);
+    let radius = circle_radius(beatmap.difficulty.circle_size);
+
+    let mut objects = beatmap
+        .hit_objects
+        .iter()
+        .filter(|object| !matches!(object.kind, HitObjectKind::Spinner | HitObjectKind::Hold))
+        .peekable();
+    let mut hits = Vec::with_capacity(beatmap.hit_objects.len());
+    let miss = |time| {
+        let error = HitError {
+            time,
+            offset: None,
+            judgement: Judgement::Miss,
+        };
+        (error, None)
+    };
+
+    for (time, frame, pressed) in frames.key_presses() {
+        if !pressed.intersects(Keys::HIT) {
+            continue;
+        }
+
+        while let Some(object) = objects
+            .next_if(|object| time as f64 > object.time as f64 + f64::from(windows.meh) * speed)
+        {
+            hits.push(miss(object.time));
+        }
+
+        let Some(object) = objects.peek() else {
+            break;
+        };
+        let distance = (frame.x - object.x).hypot(frame.y - object.y);
+        if (time as f64) < object.time as f64 - f64::from(MISS_WINDOW) || distance > radius {
+            continue;
+        }
+
+        let offset = time - object.time;
+        let error = HitError {
+            time: object.time,
+            offset: Some(offset),
+            judgement: classify_hit_error(offset as f64 / speed, &windows),
+        };
+        hits.push((error, Some(distance)));
+        objects.next();
+    }
+
+    hits.extend(objects.map(|object| miss(object.time)));
+
+    hits
+}
+
+/// Unstable rate of the hits, ten times the standard deviation of their offsets.
+///
+/// Offsets are converted to real time with the speed multiplier of the mods, like the
+/// converted unstable rate shown by osu!. Returns `None` if fewer than two objects were hit.
+pub fn unstable_rate(errors: &[HitError], mods: Mods) -> Option<f64> {
+    let speed = mods.speed_multiplier();
+    let offsets: Vec<f64> = errors
+        .iter()
+        .filter_map(|error| error.offset)
+        .map(|offset| offset as f64 / speed)
+        .collect();
+    if offsets.len() < 2 {
+        return None;
+    }
+
+    let mean = offsets.iter().sum::<f64>() / offsets.len() as f64;
+    let variance = offsets
+        .iter()
+        .map(|offset| (offset - mean).powi(2))
+        .sum::<f64>()
+        / offsets.len() as f64;
+    Some(10.0 * variance.sqrt())
+}
+
+/// Signed offsets of all hit objects that were hit, the format used by hit error bar overlays.
+pub fn hit_error_array(errors: &[HitError]) -> Vec<i64> {
+    errors.iter().filter_map(|error| error.offset).collect()
+}
+
+/// Write hit errors as CSV with the columns `time,offset,judgement`.
+///
+/// The offset column is empty for objects that were not hit.
+pub fn write_hit_errors<W: Write>(mut writer: W, errors: &[HitError]) -> io::Result<()> {
+    writeln!(writer, "time,offset,judgement")?;
+    for error in errors {
+        let offset = error.offset.map(|offset| offset.to_string()).unwrap_or_default();
+        writeln!(writer, "{},{},{}", error.time, offset, error.judgement)?;
+    }
+
+    Ok(())
+}
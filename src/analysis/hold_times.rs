@@ -0,0 +1,195 @@
+use crate::frames::{FramesExt, Keys};
+use crate::replay::{Replay, ReplayData};
+
+use super::analyzer::{Analyzer, Report};
+
+/// Holds shorter than this, in milliseconds, are too short for a human press and typical of macros.
+pub const SHORT_HOLD_THRESHOLD: i64 = 10;
+
+/// Fraction of short holds of a key above which [`HoldTimes`] reports a finding.
+pub const SHORT_HOLD_MAX_FRACTION: f64 = 0.05;
+
+/// A single press of a key, from pressing it down until releasing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hold {
+    /// The key, one of [`Keys::M1`], [`Keys::M2`], [`Keys::K1`] or [`Keys::K2`].
+    pub key: Keys,
+    /// Time the key was pressed in milliseconds.
+    pub time: i64,
+    /// Time the key was held in milliseconds.
+    pub duration: i64,
+}
+
+/// Distribution of the hold durations of a single key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldHistogram {
+    /// The key, one of [`Keys::M1`], [`Keys::M2`], [`Keys::K1`] or [`Keys::K2`].
+    pub key: Keys,
+    /// Width of the bins in milliseconds.
+    pub bin_width: i64,
+    /// Number of holds per bin, bin `i` counts durations in `i * bin_width..(i + 1) * bin_width`.
+    pub counts: Vec<usize>,
+    /// Number of holds of the key.
+    pub holds: usize,
+    /// Mean hold duration in milliseconds.
+    pub mean: f64,
+    /// Number of holds shorter than [`SHORT_HOLD_THRESHOLD`].
+    pub short_holds: usize,
+}
+
+impl HoldHistogram {
+    /// Fraction of the holds shorter than [`SHORT_HOLD_THRESHOLD`].
+    pub fn short_fraction(&self) -> f64 {
+        self.short_holds as f64 / self.holds as f64
+    }
+}
+
+/// Find every press of a key and how long it was held.
+///
+/// Keyboard keys are reported separately from mouse buttons, see [`Keys::sources`].
+/// Keys still held at the last frame are not reported.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::hold_times;
+/// use osu_replay_parser::frames::Keys;
+/// use osu_replay_parser::ReplayData;
+///
+/// let frames = [
+///     ReplayData { time: 1000, x: 0.0, y: 0.0, keys: 5 },
+///     ReplayData { time: 40, x: 0.0, y: 0.0, keys: 15 },
+///     ReplayData { time: 30, x: 0.0, y: 0.0, keys: 10 },
+///     ReplayData { time: 5, x: 0.0, y: 0.0, keys: 0 },
+/// ];
+/// let holds = hold_times(&frames);
+/// assert_eq!(holds.len(), 2);
+/// assert_eq!((holds[0].key, holds[0].time, holds[0].duration), (Keys::K1, 1000, 70));
+/// assert_eq!((holds[1].key, holds[1].time, holds[1].duration), (Keys::K2, 1040, 35));
+/// ```
+pub fn hold_times(frames: &[ReplayData]) -> Vec<Hold> {
+    let mut holds = Vec::new();
+    let mut held: Vec<(Keys, i64)> = Vec::new();
+
+    for (time, frame) in frames.timed() {
+        let keys = frame
+            .pressed_keys()
+            .sources()
+            .fold(Keys::empty(), |keys, key| keys | key);
+
+        held.retain(|&(key, start)| {
+            if keys.contains(key) {
+                return true;
+            }
+            holds.push(Hold {
+                key,
+                time: start,
+                duration: time - start,
+            });
+            false
+        });
+        for key in keys.iter() {
+            if !held.iter().any(|&(held_key, _)| held_key == key) {
+                held.push((key, time));
+            }
+        }
+    }
+
+    holds.sort_by_key(|hold| hold.time);
+    holds
+}
+
+/// Histograms of the hold durations of every key that was pressed at least once.
+/// # Panics
+/// Panics if `bin_width` is not positive.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{hold_histograms, hold_times};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let histograms = hold_histograms(&hold_times(&actions), 10);
+/// assert!(histograms.iter().all(|histogram| histogram.counts.iter().sum::<usize>() == histogram.holds));
+/// ```
+pub fn hold_histograms(holds: &[Hold], bin_width: i64) -> Vec<HoldHistogram> {
+    assert!(bin_width > 0, "bin width must be positive");
+
+    [Keys::K1, Keys::K2, Keys::M1, Keys::M2]
+        .into_iter()
+        .filter_map(|key| {
+            let durations: Vec<i64> = holds
+                .iter()
+                .filter(|hold| hold.key == key)
+                .map(|hold| hold.duration.max(0))
+                .collect();
+            if durations.is_empty() {
+                return None;
+            }
+
+            let bins = durations.iter().max().map_or(0, |max| max / bin_width) as usize + 1;
+            let mut counts = vec![0; bins];
+            for duration in &durations {
+                counts[(duration / bin_width) as usize] += 1;
+            }
+            Some(HoldHistogram {
+                key,
+                bin_width,
+                counts,
+                holds: durations.len(),
+                mean: durations.iter().sum::<i64>() as f64 / durations.len() as f64,
+                short_holds: durations
+                    .iter()
+                    .filter(|&&duration| duration < SHORT_HOLD_THRESHOLD)
+                    .count(),
+            })
+        })
+        .collect()
+}
+
+/// [`Analyzer`] reporting the mean hold duration and the fraction of short holds of every key,
+/// with a finding for keys where more than [`SHORT_HOLD_MAX_FRACTION`] of the holds are shorter
+/// than [`SHORT_HOLD_THRESHOLD`].
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{Analyzers, HoldTimes};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let reports = Analyzers::new().register(HoldTimes).run_replay(&replay).expect("Error decoding frames");
+/// assert_eq!(reports[0].0, "hold_times");
+/// assert!(!reports[0].1.has_findings());
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HoldTimes;
+
+impl Analyzer for HoldTimes {
+    fn name(&self) -> &str {
+        "hold_times"
+    }
+
+    fn analyze(&self, _replay: &Replay, frames: &[ReplayData]) -> Report {
+        let mut report = Report::new();
+        for histogram in hold_histograms(&hold_times(frames), SHORT_HOLD_THRESHOLD) {
+            let name = histogram
+                .key
+                .iter_names()
+                .next()
+                .map_or("", |(name, _)| name);
+            report = report
+                .metric(format!("{}_mean", name), histogram.mean)
+                .metric(
+                    format!("{}_short_fraction", name),
+                    histogram.short_fraction(),
+                );
+            if histogram.short_fraction() > SHORT_HOLD_MAX_FRACTION {
+                report = report.finding(
+                    None,
+                    format!(
+                        "{} of {} holds of {} are shorter than {}ms",
+                        histogram.short_holds, histogram.holds, name, SHORT_HOLD_THRESHOLD
+                    ),
+                );
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,96 @@
+use crate::frames::{FramesExt, MAX_INTERPOLATION_GAP};
+use crate::render::{timeline, RenderFrame};
+use crate::replay::ReplayData;
+
+/// How well the recorded frames of a replay are reproduced after resampling them to a fixed
+/// frame rate, see [`interp_error`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpolationError {
+    /// The frame rate the frames were resampled to.
+    pub hz: f64,
+    /// Number of samples taken.
+    pub samples: usize,
+    /// Number of recorded frames compared.
+    pub frames: usize,
+    /// Mean distance in osu! pixels between the recorded and the reconstructed cursor positions.
+    pub mean_distance: f32,
+    /// Largest distance in osu! pixels between the recorded and the reconstructed cursor positions.
+    pub max_distance: f32,
+    /// Number of recorded frames whose keys differ from the reconstructed keys, e.g. taps shorter
+    /// than the sample interval.
+    pub key_mismatches: usize,
+}
+
+/// Measure the information lost by resampling frames to `hz`, e.g. for a dataset, see
+/// [`render::timeline`](crate::render::timeline).
+///
+/// The cursor is reconstructed at the time of every recorded frame by interpolating linearly
+/// between the samples, the keys are taken from the last sample at or before the frame.
+/// Comparing several rates shows the lowest one that keeps the replay intact.
+/// # Panics
+/// Panics if `hz` is not positive.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::interp_error;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+///
+/// let coarse = interp_error(&actions, 30.0);
+/// let fine = interp_error(&actions, 240.0);
+/// assert!(fine.samples > coarse.samples);
+/// assert!(fine.mean_distance < coarse.mean_distance);
+/// assert!(fine.key_mismatches <= coarse.key_mismatches);
+/// ```
+pub fn interp_error(frames: &[ReplayData], hz: f64) -> InterpolationError {
+    let samples = timeline(frames, hz);
+
+    let mut compared = 0;
+    let mut total_distance = 0.0;
+    let mut max_distance: f32 = 0.0;
+    let mut key_mismatches = 0;
+    for (time, frame) in frames.timed() {
+        let Some((x, y, keys)) = reconstruct(&samples, time as f64) else {
+            continue;
+        };
+        let distance = (frame.x - x).hypot(frame.y - y);
+        compared += 1;
+        total_distance += f64::from(distance);
+        max_distance = max_distance.max(distance);
+        if frame.keys != keys {
+            key_mismatches += 1;
+        }
+    }
+
+    InterpolationError {
+        hz,
+        samples: samples.len(),
+        frames: compared,
+        mean_distance: if compared == 0 {
+            0.0
+        } else {
+            (total_distance / compared as f64) as f32
+        },
+        max_distance,
+        key_mismatches,
+    }
+}
+
+/// Cursor position and keys at a time, interpolated between the samples.
+fn reconstruct(samples: &[RenderFrame], time: f64) -> Option<(f32, f32, u32)> {
+    let next = samples.partition_point(|sample| sample.time <= time);
+    let previous = &samples[next.checked_sub(1)?];
+    let cursor = previous.cursor;
+    match samples.get(next) {
+        Some(next) if next.time - previous.time <= MAX_INTERPOLATION_GAP as f64 => {
+            let t = ((time - previous.time) / (next.time - previous.time)) as f32;
+            Some((
+                cursor.x + (next.cursor.x - cursor.x) * t,
+                cursor.y + (next.cursor.y - cursor.y) * t,
+                cursor.keys,
+            ))
+        }
+        _ => Some((cursor.x, cursor.y, cursor.keys)),
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Write as _;
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::frames::FramesExt;
+use crate::replay::{Mods, Replay};
+use crate::writer::compress_frames;
+
+/// A sample of the life bar graph stored in the replay header.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LifeBarPoint {
+    /// Time in milliseconds.
+    pub time: i64,
+    /// Health in the range `0.0..=1.0`.
+    pub hp: f32,
+}
+
+/// Parse the life bar graph of a replay, stored as comma separated `time|hp` pairs.
+/// # Errors
+/// Returns a `ReplayDataError` if a pair is incomplete or not a number.
+pub fn parse_life_bar(life_bar: &str) -> Result<Vec<LifeBarPoint>, ReplayDataError<'static>> {
+    life_bar
+        .split(',')
+        .filter(|point| !point.trim().is_empty())
+        .map(|point| {
+            let (time, hp) = point.split_once('|').ok_or(ParseError::MissingValue)?;
+            Ok(LifeBarPoint {
+                time: time.trim().parse()?,
+                hp: hp.trim().parse()?,
+            })
+        })
+        .collect()
+}
+
+/// Build the life bar graph of a replay from its samples, the inverse of [`parse_life_bar`].
+///
+/// The health is clamped to `0.0..=1.0`, every sample is followed by a comma like in replays
+/// written by osu!.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{parse_life_bar, write_life_bar, LifeBarPoint};
+///
+/// let points = vec![
+///     LifeBarPoint { time: 0, hp: 1.0 },
+///     LifeBarPoint { time: 2000, hp: 0.75 },
+///     LifeBarPoint { time: 4000, hp: 1.2 },
+/// ];
+/// let life_bar = write_life_bar(&points).unwrap();
+/// assert_eq!(life_bar, "0|1,2000|0.75,4000|1,");
+/// assert_eq!(parse_life_bar(&life_bar).unwrap()[..2], points[..2]);
+///
+/// let reversed: Vec<_> = points.iter().rev().copied().collect();
+/// assert!(write_life_bar(&reversed).is_err());
+/// ```
+/// # Errors
+/// Returns `ParseError::InvalidValue` if the times are decreasing or a health is NaN.
+pub fn write_life_bar(points: &[LifeBarPoint]) -> Result<String, ReplayDataError<'static>> {
+    let mut life_bar = String::new();
+    let mut previous = i64::MIN;
+    for point in points {
+        if point.time < previous || point.hp.is_nan() {
+            return Err(ParseError::InvalidValue.into());
+        }
+        previous = point.time;
+        let _ = write!(life_bar, "{}|{},", point.time, point.hp.clamp(0.0, 1.0));
+    }
+
+    Ok(life_bar)
+}
+
+impl Replay {
+    /// The samples of the life bar graph, see [`parse_life_bar`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the life bar graph is malformed.
+    pub fn life_bar_points(&self) -> Result<Vec<LifeBarPoint>, ReplayDataError<'static>> {
+        parse_life_bar(&self.life_bar)
+    }
+
+    /// Replace the life bar graph with the given samples, see [`write_life_bar`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the samples are invalid, the life bar graph is unchanged then.
+    pub fn set_life_bar_points(
+        &mut self,
+        points: &[LifeBarPoint],
+    ) -> Result<(), ReplayDataError<'static>> {
+        self.life_bar = write_life_bar(points)?;
+        Ok(())
+    }
+
+    /// Cut the replay off where the player failed, like the replays osu! saves for failed plays.
+    ///
+    /// The fail is found in the life bar graph, see [`pass_status`]. Frames after the fail and the
+    /// life bar samples after it are removed, the seed frame is kept. The hit counts and the score
+    /// are not changed, as they cannot be judged without the beatmap.
+    /// Returns `None` if the play was not failed.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// assert!(replay.truncate_at_fail().unwrap().is_none());
+    ///
+    /// replay.life_bar = "0|1,5000|0.5,10000|0,12000|0.4,".to_string();
+    /// let failed = replay.truncate_at_fail().unwrap().expect("The play was failed");
+    /// assert_eq!(failed.life_bar, "0|1,5000|0.5,10000|0,");
+    /// let actions = failed.get_actions().expect("Error getting actions");
+    /// let (end, _) = actions.timed().last().unwrap();
+    /// assert!((9900..=10000).contains(&end));
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the life bar graph or the compressed replay data is malformed.
+    pub fn truncate_at_fail(&self) -> Result<Option<Replay>, ReplayDataError<'static>> {
+        let points = self.life_bar_points()?;
+        let mods = self.mods();
+        if pass_status(&points, mods) != PassStatus::Failed {
+            return Ok(None);
+        }
+        // Easy revives the player as long as the health recovers
+        let Some(fail) = fail_events(&points)
+            .into_iter()
+            .find(|event| !mods.contains(Mods::EASY) || event.recovered_at.is_none())
+        else {
+            return Ok(None);
+        };
+
+        let frames = self.get_actions_with_progress(|_, _| {})?;
+        let kept = frames
+            .timed()
+            .take_while(|&(time, _)| time <= fail.time)
+            .count();
+        let mut truncated: Vec<_> = frames
+            .iter()
+            .filter(|frame| !frame.is_seed_frame())
+            .take(kept)
+            .copied()
+            .collect();
+        truncated.extend(frames.seed_frame());
+
+        let life_bar = self
+            .life_bar
+            .split_inclusive(',')
+            .filter(|sample| !sample.trim_end_matches(',').trim().is_empty())
+            .zip(&points)
+            .take_while(|(_, point)| point.time <= fail.time)
+            .map(|(sample, _)| sample)
+            .collect();
+        Ok(Some(Replay {
+            compressed_data: compress_frames(&truncated),
+            life_bar,
+            ..self.clone()
+        }))
+    }
+}
+
+/// A period in which the health of the player was at zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailEvent {
+    /// Time of the first sample at zero health.
+    pub time: i64,
+    /// Time of the first sample with health above zero again, `None` if the health never recovered.
+    pub recovered_at: Option<i64>,
+}
+
+/// Whether a play was passed, judged from its life bar graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PassStatus {
+    /// The health never reached zero.
+    Passed,
+    /// The health reached zero, but No Fail prevented the fail.
+    SavedByNoFail,
+    /// The health reached zero and was restored by the revives of Easy.
+    Revived,
+    /// The health reached zero and the play was failed.
+    Failed,
+}
+
+/// Find the periods in which the health reached zero.
+///
+/// The life bar graph is sampled sparsely, so very short periods at zero health can be missed.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{fail_events, parse_life_bar, FailEvent};
+///
+/// let points = parse_life_bar("0|1,2000|0.3,4000|0,6000|1,8000|0").unwrap();
+/// assert_eq!(
+///     fail_events(&points),
+///     vec![
+///         FailEvent { time: 4000, recovered_at: Some(6000) },
+///         FailEvent { time: 8000, recovered_at: None },
+///     ]
+/// );
+/// ```
+pub fn fail_events(points: &[LifeBarPoint]) -> Vec<FailEvent> {
+    let mut events: Vec<FailEvent> = Vec::new();
+    for point in points {
+        let at_zero = point.hp <= 0.0;
+        let failing = events
+            .last_mut()
+            .filter(|event| event.recovered_at.is_none());
+        match failing {
+            Some(event) if !at_zero => event.recovered_at = Some(point.time),
+            None if at_zero => events.push(FailEvent {
+                time: point.time,
+                recovered_at: None,
+            }),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Classify a play as passed or failed from its life bar graph and mods.
+///
+/// Unlike guessing from the hit counts, this detects plays saved by No Fail and revives with Easy.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{parse_life_bar, pass_status, PassStatus};
+/// use osu_replay_parser::replay::Mods;
+///
+/// let points = parse_life_bar("0|1,2000|0,4000|0.5").unwrap();
+/// assert_eq!(pass_status(&points, Mods::NO_FAIL), PassStatus::SavedByNoFail);
+/// assert_eq!(pass_status(&points, Mods::EASY), PassStatus::Revived);
+/// assert_eq!(pass_status(&points, Mods::NONE), PassStatus::Failed);
+/// ```
+pub fn pass_status(points: &[LifeBarPoint], mods: Mods) -> PassStatus {
+    let events = fail_events(points);
+    if events.is_empty() {
+        PassStatus::Passed
+    } else if mods.contains(Mods::NO_FAIL) {
+        PassStatus::SavedByNoFail
+    } else if mods.contains(Mods::EASY) && events.iter().all(|event| event.recovered_at.is_some()) {
+        PassStatus::Revived
+    } else {
+        PassStatus::Failed
+    }
+}
@@ -0,0 +1,190 @@
+use crate::beatmap::Beatmap;
+use crate::frames::{FramesExt, MAX_INTERPOLATION_GAP};
+use crate::replay::{Mods, ReplayData};
+use crate::statistics::HitStatistics;
+
+use super::hit_errors::{hit_errors, unstable_rate, HitError, Judgement};
+
+/// Kind of a section of a beatmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionKind {
+    /// Regular gameplay.
+    Normal,
+    /// Gameplay during kiai time.
+    Kiai,
+    /// A break period.
+    Break,
+}
+
+/// A section of a replay with the statistics of the play in it, see [`segment`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Kind of the section.
+    pub kind: SectionKind,
+    /// Start time of the section in milliseconds.
+    pub start_time: i64,
+    /// End time of the section in milliseconds.
+    pub end_time: i64,
+    /// Timing errors of the hit objects in the section.
+    pub hit_errors: Vec<HitError>,
+    /// Unstable rate of the hits in the section, see [`unstable_rate`].
+    pub unstable_rate: Option<f64>,
+    /// Accuracy in the range `0.0..=1.0` of the hit objects in the section, `None` if it has none.
+    pub accuracy: Option<f64>,
+    /// Mean cursor speed in osu!pixels per second, gaps longer than [`MAX_INTERPOLATION_GAP`]
+    /// are left out.
+    pub cursor_speed: f32,
+}
+
+/// Split an osu!standard replay into sections of normal gameplay, kiai time and breaks of the
+/// beatmap, each with its own unstable rate, accuracy and cursor speed.
+///
+/// The sections cover the replay from its first to its last frame, breaks take precedence over
+/// kiai time. Hits are reconstructed like [`hit_errors`], objects outside of the replay are
+/// counted to the first or last section.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{segment, SectionKind};
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::ReplayData;
+///
+/// let beatmap = Beatmap::parse(
+///     "[Events]\n2,1500,3000\n\n[TimingPoints]\n0,500,4,2,0,100,1,0\n3000,500,4,2,0,100,1,1\n\n\
+///      [HitObjects]\n100,100,1000,1,0\n300,200,3500,1,0\n",
+/// )
+/// .unwrap();
+/// let frames = [
+///     ReplayData { time: 0, x: 100.0, y: 100.0, keys: 0 },
+///     ReplayData { time: 1000, x: 100.0, y: 100.0, keys: 1 },
+///     ReplayData { time: 20, x: 100.0, y: 100.0, keys: 0 },
+///     ReplayData { time: 2480, x: 300.0, y: 200.0, keys: 0 },
+///     ReplayData { time: 500, x: 300.0, y: 200.0, keys: 0 },
+/// ];
+///
+/// let sections = segment(&frames, &beatmap, Mods::NONE);
+/// let kinds: Vec<_> = sections.iter().map(|section| (section.kind, section.start_time)).collect();
+/// assert_eq!(
+///     kinds,
+///     [(SectionKind::Normal, 0), (SectionKind::Break, 1500), (SectionKind::Kiai, 3000)]
+/// );
+/// assert_eq!(sections[0].accuracy, Some(1.0));
+/// assert_eq!(sections[1].accuracy, None);
+/// assert_eq!(sections[2].accuracy, Some(0.0));
+/// ```
+pub fn segment(frames: &[ReplayData], beatmap: &Beatmap, mods: Mods) -> Vec<Section> {
+    let timed: Vec<(i64, &ReplayData)> = frames.timed().collect();
+    let (Some(&(start, _)), Some(&(end, _))) = (timed.first(), timed.last()) else {
+        return Vec::new();
+    };
+
+    let kind_at = |time: i64| {
+        if beatmap
+            .breaks
+            .iter()
+            .any(|period| (period.start_time..period.end_time).contains(&time))
+        {
+            SectionKind::Break
+        } else if beatmap.is_kiai(time as f64) {
+            SectionKind::Kiai
+        } else {
+            SectionKind::Normal
+        }
+    };
+
+    let mut boundaries: Vec<i64> = beatmap
+        .timing_points
+        .iter()
+        .map(|point| point.time.ceil() as i64)
+        .chain(
+            beatmap
+                .breaks
+                .iter()
+                .flat_map(|period| [period.start_time, period.end_time]),
+        )
+        .filter(|&time| time > start && time < end)
+        .collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut spans: Vec<(SectionKind, i64, i64)> = Vec::new();
+    for (section_start, section_end) in std::iter::once(start)
+        .chain(boundaries.iter().copied())
+        .zip(boundaries.iter().copied().chain(std::iter::once(end)))
+    {
+        let kind = kind_at(section_start);
+        match spans.last_mut() {
+            Some(last) if last.0 == kind => last.2 = section_end,
+            _ => spans.push((kind, section_start, section_end)),
+        }
+    }
+
+    let errors = hit_errors(frames, beatmap, mods);
+    let last = spans.len() - 1;
+    spans
+        .iter()
+        .enumerate()
+        .map(|(index, &(kind, section_start, section_end))| {
+            let contains = |time: i64| {
+                (index == 0 || time >= section_start) && (index == last || time < section_end)
+            };
+            let hit_errors: Vec<HitError> = errors
+                .iter()
+                .filter(|error| contains(error.time))
+                .copied()
+                .collect();
+            Section {
+                kind,
+                start_time: section_start,
+                end_time: section_end,
+                unstable_rate: unstable_rate(&hit_errors, mods),
+                accuracy: accuracy(&hit_errors),
+                cursor_speed: cursor_speed(&timed, section_start, section_end),
+                hit_errors,
+            }
+        })
+        .collect()
+}
+
+/// osu!standard accuracy of the judgements, `None` if there are none.
+fn accuracy(errors: &[HitError]) -> Option<f64> {
+    if errors.is_empty() {
+        return None;
+    }
+    let count = |judgement| {
+        errors
+            .iter()
+            .filter(|error| error.judgement == judgement)
+            .count() as u16
+    };
+    let statistics = HitStatistics::Osu {
+        n300: count(Judgement::Great),
+        n100: count(Judgement::Ok),
+        n50: count(Judgement::Meh),
+        n_geki: 0,
+        n_katu: 0,
+        n_miss: count(Judgement::Miss),
+    };
+    Some(statistics.accuracy())
+}
+
+/// Mean cursor speed in osu!pixels per second between the frames in `start..=end`.
+fn cursor_speed(timed: &[(i64, &ReplayData)], start: i64, end: i64) -> f32 {
+    let mut distance = 0.0;
+    let mut duration = 0;
+    for pair in timed.windows(2) {
+        let ((previous_time, previous), (time, frame)) = (pair[0], pair[1]);
+        let delta = time - previous_time;
+        if previous_time < start || time > end || delta > MAX_INTERPOLATION_GAP {
+            continue;
+        }
+        distance += (frame.x - previous.x).hypot(frame.y - previous.y);
+        duration += delta;
+    }
+
+    if duration == 0 {
+        0.0
+    } else {
+        distance / duration as f32 * 1000.0
+    }
+}
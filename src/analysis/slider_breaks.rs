@@ -0,0 +1,130 @@
+use crate::beatmap::{Beatmap, HitObjectKind};
+use crate::frames::{Keys, Timeline};
+use crate::game_math::circle_radius;
+use crate::replay::{Mods, ReplayData};
+
+/// Radius of the follow circle relative to the circle radius.
+pub const FOLLOW_CIRCLE_SCALE: f32 = 2.4;
+
+/// Ticks closer than this to the end of a slide, in milliseconds, are not created.
+const MIN_TICK_DISTANCE_TO_END: f64 = 10.0;
+
+/// Why a slider was not tracked at a tick or repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SliderBreakReason {
+    /// The cursor was outside of the follow circle while a key was held.
+    LeftFollowCircle,
+    /// No key was held.
+    Released,
+}
+
+/// A slider tick or repeat that was missed, breaking the combo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliderBreak {
+    /// Start time of the slider in milliseconds.
+    pub slider_time: i64,
+    /// Time of the missed tick or repeat in milliseconds.
+    pub time: i64,
+    /// Why the tick or repeat was missed.
+    pub reason: SliderBreakReason,
+}
+
+/// Find the slider ticks and repeats missed in an osu!standard replay.
+///
+/// The ball position is reconstructed from the slider paths of the beatmap. A tick or repeat is
+/// missed if no key is held or the cursor is outside of the follow circle at its time. Only the
+/// first miss of every slider is reported, as it breaks the combo. Missed slider heads are
+/// reported by [`hit_errors`](super::hit_errors), missed slider ends do not break the combo.
+///
+/// Stacking and the extended follow circle while tracking are not taken into account, so
+/// misses close to the edge of the follow circle may be inaccurate.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{slider_breaks, SliderBreakReason};
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::ReplayData;
+///
+/// // A straight slider lasting two beats with a tick in the middle
+/// let beatmap = Beatmap::parse(
+///     "[Difficulty]\nCircleSize:4\nSliderMultiplier:1\nSliderTickRate:1\n\
+///      [TimingPoints]\n0,500,4,2,0,100,1,0\n\
+///      [HitObjects]\n100,100,1000,2,0,L|300:100,1,200\n",
+/// )
+/// .unwrap();
+///
+/// // The cursor stays on the head while the ball moves on
+/// let frames = vec![
+///     ReplayData { time: 1000, x: 100.0, y: 100.0, keys: 1 },
+///     ReplayData { time: 1000, x: 100.0, y: 100.0, keys: 0 },
+/// ];
+/// let breaks = slider_breaks(&frames, &beatmap, Mods::NONE);
+/// assert_eq!(breaks[0].time, 1500);
+/// assert_eq!(breaks[0].reason, SliderBreakReason::LeftFollowCircle);
+/// ```
+pub fn slider_breaks(frames: &[ReplayData], beatmap: &Beatmap, mods: Mods) -> Vec<SliderBreak> {
+    let beatmap = beatmap.clone().with_mods(mods);
+    let follow_radius = circle_radius(beatmap.difficulty.circle_size) * FOLLOW_CIRCLE_SCALE;
+    let timeline = Timeline::new(frames);
+    let mut breaks = Vec::new();
+
+    for object in &beatmap.hit_objects {
+        let HitObjectKind::Slider { slides, .. } = object.kind else {
+            continue;
+        };
+        let Some(path) = object.slider_path() else {
+            continue;
+        };
+        let slides = slides.max(1);
+        let slide_duration = (object.end_time - object.time) as f64 / f64::from(slides);
+        if slide_duration <= 0.0 {
+            continue;
+        }
+
+        let (beat_length, _) = beatmap.timing_at(object.time as f64);
+        let tick_interval = beat_length / beatmap.difficulty.slider_tick_rate.max(0.01);
+        let ball_at = |time: f64| {
+            let progress = (time - object.time as f64) / slide_duration;
+            let slide = (progress.floor() as u32).min(slides - 1);
+            let mut fraction = (progress - f64::from(slide)).clamp(0.0, 1.0);
+            if slide % 2 == 1 {
+                fraction = 1.0 - fraction;
+            }
+            path.position_at(path.length() * fraction as f32)
+        };
+
+        // Ticks of every slide followed by the repeat at its end, except for the last slide
+        let mut checks = Vec::new();
+        for slide in 0..slides {
+            let start = object.time as f64 + slide_duration * f64::from(slide);
+            let mut tick = start + tick_interval;
+            while tick < start + slide_duration - MIN_TICK_DISTANCE_TO_END {
+                checks.push(tick);
+                tick += tick_interval;
+            }
+            if slide + 1 < slides {
+                checks.push(start + slide_duration);
+            }
+        }
+
+        let missed = checks.into_iter().find_map(|time| {
+            let state = timeline.state_at(time)?;
+            let (x, y) = ball_at(time);
+            let reason = if !Keys::from_bits_truncate(state.keys).intersects(Keys::HIT) {
+                SliderBreakReason::Released
+            } else if (state.x - x).hypot(state.y - y) > follow_radius {
+                SliderBreakReason::LeftFollowCircle
+            } else {
+                return None;
+            };
+            Some(SliderBreak {
+                slider_time: object.time,
+                time: time.round() as i64,
+                reason,
+            })
+        });
+        breaks.extend(missed);
+    }
+
+    breaks
+}
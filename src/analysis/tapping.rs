@@ -0,0 +1,219 @@
+use crate::frames::{FramesExt, Keys};
+use crate::replay::ReplayData;
+
+/// Taps further apart than this, in milliseconds, end a burst or stream.
+pub const STREAM_MAX_INTERVAL: i64 = 150;
+/// Minimum number of taps of a segment to be considered a stream instead of a burst.
+pub const STREAM_MIN_TAPS: usize = 8;
+
+/// Tapping statistics of a single key.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KeyTapping {
+    /// The key, one of [`Keys::M1`], [`Keys::M2`], [`Keys::K1`] or [`Keys::K2`].
+    pub key: Keys,
+    /// Number of presses of the key.
+    pub presses: usize,
+    /// Mean time between two presses of this key in milliseconds.
+    pub mean_interval: f64,
+    /// Standard deviation of the time between two presses of this key in milliseconds.
+    pub interval_deviation: f64,
+}
+
+/// Kind of a tapping segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    /// Fewer than [`STREAM_MIN_TAPS`] taps in quick succession.
+    Burst,
+    /// At least [`STREAM_MIN_TAPS`] taps in quick succession.
+    Stream,
+}
+
+/// A run of taps with at most [`STREAM_MAX_INTERVAL`] milliseconds between consecutive taps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapSegment {
+    /// Time of the first tap in milliseconds.
+    pub start_time: i64,
+    /// Time of the last tap in milliseconds.
+    pub end_time: i64,
+    /// Number of taps in the segment.
+    pub taps: usize,
+    /// Fraction of consecutive taps in the segment that switch between keys.
+    pub alternation: f64,
+    /// Whether the segment is a burst or a stream.
+    pub kind: SegmentKind,
+}
+
+/// Per-key tapping statistics, alternation and burst/stream segmentation of a replay.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TappingReport {
+    /// Statistics of every key that was pressed at least once.
+    pub keys: Vec<KeyTapping>,
+    /// Fraction of consecutive taps over the whole replay that switch between keys.
+    pub alternation: f64,
+    /// Bursts and streams of at least two taps.
+    pub segments: Vec<TapSegment>,
+}
+
+fn taps(frames: &[ReplayData]) -> Vec<(i64, Keys)> {
+    frames
+        .key_presses()
+        .flat_map(|(time, _, pressed)| pressed.sources().map(move |key| (time, key)))
+        .collect()
+}
+
+fn alternation(taps: &[(i64, Keys)]) -> f64 {
+    if taps.len() < 2 {
+        return 0.0;
+    }
+    let switches = taps.windows(2).filter(|pair| pair[0].1 != pair[1].1).count();
+    switches as f64 / (taps.len() - 1) as f64
+}
+
+fn key_tapping(key: Keys, taps: &[(i64, Keys)]) -> KeyTapping {
+    let times: Vec<i64> = taps.iter().filter(|(_, k)| *k == key).map(|(t, _)| *t).collect();
+    let intervals: Vec<f64> = times.windows(2).map(|pair| (pair[1] - pair[0]) as f64).collect();
+
+    let (mean_interval, interval_deviation) = if intervals.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        let variance = intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+        (mean, variance.sqrt())
+    };
+
+    KeyTapping {
+        key,
+        presses: times.len(),
+        mean_interval,
+        interval_deviation,
+    }
+}
+
+/// Analyze the tapping of an osu!standard replay.
+///
+/// Every press of a key or mouse button counts as a tap, keyboard keys are reported separately from mouse buttons.
+/// # Example
+/// ```
+/// use osu_replay_parser::{analysis, Replay};
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let report = analysis::tapping_report(&actions);
+/// assert!(report.keys.iter().map(|key| key.presses).sum::<usize>() > 0);
+/// ```
+pub fn tapping_report(frames: &[ReplayData]) -> TappingReport {
+    let taps = taps(frames);
+
+    let keys = [Keys::K1, Keys::K2, Keys::M1, Keys::M2]
+        .into_iter()
+        .map(|key| key_tapping(key, &taps))
+        .filter(|key| key.presses > 0)
+        .collect();
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for end in 1..=taps.len() {
+        let split = end == taps.len() || taps[end].0 - taps[end - 1].0 > STREAM_MAX_INTERVAL;
+        if !split {
+            continue;
+        }
+        let run = &taps[start..end];
+        if run.len() >= 2 {
+            segments.push(TapSegment {
+                start_time: run[0].0,
+                end_time: run[run.len() - 1].0,
+                taps: run.len(),
+                alternation: alternation(run),
+                kind: if run.len() >= STREAM_MIN_TAPS {
+                    SegmentKind::Stream
+                } else {
+                    SegmentKind::Burst
+                },
+            });
+        }
+        start = end;
+    }
+
+    TappingReport {
+        keys,
+        alternation: alternation(&taps),
+        segments,
+    }
+}
+
+/// Options for [`tapping_bpm_timeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TappingBpmOptions {
+    /// Taps per beat, 4 assumes the taps are 1/4 notes as in most streams.
+    pub divisor: u32,
+    /// Number of intervals between taps averaged for each point.
+    pub window: usize,
+}
+
+impl Default for TappingBpmOptions {
+    fn default() -> Self {
+        TappingBpmOptions {
+            divisor: 4,
+            window: 8,
+        }
+    }
+}
+
+/// Effective tapping speed at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BpmPoint {
+    /// Time of the tap in milliseconds.
+    pub time: i64,
+    /// Tapping speed in beats per minute, assuming [`TappingBpmOptions::divisor`] taps per beat.
+    pub bpm: f64,
+}
+
+/// Estimate the tapping speed over time from the intervals between taps, without the beatmap.
+///
+/// There is a point for every tap of a burst or stream, see [`TapSegment`], averaging the
+/// intervals to the previous taps of the segment within the window. Taps further apart than
+/// [`STREAM_MAX_INTERVAL`] are not part of the timeline.
+/// # Panics
+/// Panics if the divisor or the window is 0.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{tapping_bpm_timeline, TappingBpmOptions};
+/// use osu_replay_parser::ReplayData;
+///
+/// // 1/4 notes at 200 BPM with alternating keys
+/// let frames: Vec<_> = (0..8)
+///     .map(|i| ReplayData { time: if i == 0 { 1000 } else { 75 }, x: 0.0, y: 0.0, keys: [5, 10][i % 2] })
+///     .collect();
+/// let timeline = tapping_bpm_timeline(&frames, &TappingBpmOptions::default());
+/// assert_eq!(timeline.len(), 7);
+/// assert_eq!(timeline[0].time, 1075);
+/// assert!(timeline.iter().all(|point| point.bpm == 200.0));
+///
+/// // The same taps as 1/3 notes
+/// let options = TappingBpmOptions { divisor: 3, ..Default::default() };
+/// assert_eq!(tapping_bpm_timeline(&frames, &options)[0].bpm, 800.0 / 3.0);
+/// ```
+pub fn tapping_bpm_timeline(frames: &[ReplayData], options: &TappingBpmOptions) -> Vec<BpmPoint> {
+    assert!(options.divisor > 0, "divisor must be positive");
+    assert!(options.window > 0, "window must be positive");
+    let taps = taps(frames);
+
+    let mut timeline = Vec::new();
+    let mut start = 0;
+    for end in 1..taps.len() {
+        if taps[end].0 - taps[end - 1].0 > STREAM_MAX_INTERVAL {
+            start = end;
+            continue;
+        }
+        let first = start.max(end.saturating_sub(options.window));
+        let interval = (taps[end].0 - taps[first].0) as f64 / (end - first) as f64;
+        if interval > 0.0 {
+            timeline.push(BpmPoint {
+                time: taps[end].0,
+                bpm: 60_000.0 / (interval * f64::from(options.divisor)),
+            });
+        }
+    }
+
+    timeline
+}
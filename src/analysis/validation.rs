@@ -0,0 +1,197 @@
+use crate::frames::{FramesExt, Keys, MAX_INTERPOLATION_GAP};
+use crate::replay::{GameMode, Mods, ReplayData};
+
+/// Coordinates further from the origin than this, in osu! pixels, are considered corrupt.
+pub const MAX_CURSOR_COORDINATE: f32 = 4096.0;
+
+/// Cursor movements longer than this, in osu! pixels, within [`TELEPORT_MAX_DELTA`] are reported as teleports.
+pub const TELEPORT_DISTANCE: f32 = 400.0;
+
+/// Longest time between two frames, in milliseconds, for a movement to be reported as a teleport.
+pub const TELEPORT_MAX_DELTA: i64 = 16;
+
+/// Lowest frame rate, in frames per second of real time, of replays recorded by osu!.
+pub const MIN_FRAME_RATE: f64 = 30.0;
+
+/// Highest frame rate, in frames per second of real time, of replays recorded by osu!.
+pub const MAX_FRAME_RATE: f64 = 250.0;
+
+/// Shortest active time, in milliseconds, for which the frame rate is checked.
+const MIN_FRAME_RATE_DURATION: i64 = 5000;
+
+/// Number of frames at the start of a replay that may have negative times and are followed by a
+/// jump to the first real cursor position.
+///
+/// osu!stable writes two frames before the first real one, the second with a time of -1.
+const LEADING_FRAMES: usize = 2;
+
+/// Kind of a problem found by [`validate_frames`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FrameIssueKind {
+    /// The time delta of the frame is negative.
+    NegativeTime,
+    /// A coordinate is not finite or further than [`MAX_CURSOR_COORDINATE`] from the origin.
+    OutOfBounds,
+    /// The cursor moved at least [`TELEPORT_DISTANCE`] within [`TELEPORT_MAX_DELTA`].
+    Teleport,
+    /// No key was pressed in the whole replay.
+    NoKeyPresses,
+    /// The frame rate is outside of [`MIN_FRAME_RATE`] and [`MAX_FRAME_RATE`], a sign of
+    /// tool-assisted or converted replays, see [`frame_rate`].
+    AbnormalFrameRate,
+}
+
+impl FrameIssueKind {
+    /// Whether the check is done for replays of the game mode with the mods.
+    ///
+    /// Some checks do not apply to legitimate replays:
+    /// - Relax replays have no key data, as hit objects are hit automatically.
+    /// - Autopilot replays move the cursor automatically, including instant jumps between objects.
+    /// - Only osu!standard replays store the cursor position, the other modes use the coordinates
+    ///   for the catcher position or the pressed columns.
+    /// - osu!mania replays only have frames when the pressed columns change.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::analysis::FrameIssueKind;
+    /// use osu_replay_parser::replay::{GameMode, Mods};
+    ///
+    /// assert!(FrameIssueKind::NoKeyPresses.applies_to(GameMode::Osu, Mods::HIDDEN));
+    /// assert!(!FrameIssueKind::NoKeyPresses.applies_to(GameMode::Osu, Mods::RELAX));
+    /// assert!(!FrameIssueKind::Teleport.applies_to(GameMode::Osu, Mods::RELAX2));
+    /// assert!(!FrameIssueKind::OutOfBounds.applies_to(GameMode::Mania, Mods::NONE));
+    /// ```
+    pub fn applies_to(self, mode: GameMode, mods: Mods) -> bool {
+        match self {
+            FrameIssueKind::NegativeTime => true,
+            FrameIssueKind::OutOfBounds => mode != GameMode::Mania,
+            FrameIssueKind::Teleport => mode == GameMode::Osu && !mods.contains(Mods::RELAX2),
+            FrameIssueKind::NoKeyPresses => mode == GameMode::Osu && !mods.contains(Mods::RELAX),
+            FrameIssueKind::AbnormalFrameRate => mode != GameMode::Mania,
+        }
+    }
+}
+
+/// A problem with the frames of a replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameIssue {
+    /// Index of the frame, `None` for problems of the whole replay.
+    pub index: Option<usize>,
+    /// Kind of the problem.
+    pub kind: FrameIssueKind,
+}
+
+/// Average number of frames per second of real time while playing, `None` without active time.
+///
+/// Gaps longer than [`MAX_INTERPOLATION_GAP`], such as the skipped intro and pauses, are left
+/// out. The times are converted to real time with the speed multiplier of the mods, as osu!
+/// records frames at the same rate regardless of Double Time and Half Time.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::frame_rate;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::Replay;
+///
+/// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.take_actions().expect("Error getting actions");
+/// let rate = frame_rate(&actions, replay.mods()).unwrap();
+/// assert!((55.0..70.0).contains(&rate));
+/// ```
+pub fn frame_rate(frames: &[ReplayData], mods: Mods) -> Option<f64> {
+    let (count, time) = frames
+        .timed()
+        .map(|(time, _)| time)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|&gap| (0..=MAX_INTERPOLATION_GAP).contains(&gap))
+        .fold((0usize, 0i64), |(count, time), gap| (count + 1, time + gap));
+    if time == 0 {
+        return None;
+    }
+
+    Some(count as f64 * 1000.0 / time as f64 * mods.speed_multiplier())
+}
+
+/// Check the frames of a replay for signs of corruption or tampering.
+///
+/// Checks that do not apply to the game mode and mods are skipped, see [`FrameIssueKind::applies_to`],
+/// so legitimate Relax and Autopilot replays are not rejected. The seed frame is ignored.
+/// # Example
+/// ```
+/// use osu_replay_parser::analysis::{validate_frames, FrameIssueKind};
+/// use osu_replay_parser::replay::{GameMode, Mods};
+/// use osu_replay_parser::ReplayData;
+///
+/// // A cursor without key presses jumping across the playfield
+/// let frames = vec![
+///     ReplayData { time: 0, x: 256.0, y: -500.0, keys: 0 },
+///     ReplayData { time: -1, x: 256.0, y: -500.0, keys: 0 },
+///     ReplayData { time: 1000, x: 0.0, y: 0.0, keys: 0 },
+///     ReplayData { time: 16, x: 500.0, y: 0.0, keys: 0 },
+/// ];
+/// let kinds = |mods| -> Vec<_> {
+///     validate_frames(&frames, GameMode::Osu, mods).iter().map(|issue| issue.kind).collect()
+/// };
+///
+/// assert_eq!(kinds(Mods::NONE), [FrameIssueKind::Teleport, FrameIssueKind::NoKeyPresses]);
+/// assert_eq!(kinds(Mods::RELAX), [FrameIssueKind::Teleport]);
+/// assert_eq!(kinds(Mods::RELAX2), [FrameIssueKind::NoKeyPresses]);
+/// ```
+pub fn validate_frames(frames: &[ReplayData], mode: GameMode, mods: Mods) -> Vec<FrameIssue> {
+    let check = |kind: FrameIssueKind| kind.applies_to(mode, mods);
+    let mut issues = Vec::new();
+    let mut previous: Option<&ReplayData> = None;
+    let mut any_keys = false;
+
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.is_seed_frame() {
+            continue;
+        }
+        let mut report = |kind| {
+            issues.push(FrameIssue {
+                index: Some(index),
+                kind,
+            })
+        };
+
+        if frame.time < 0 && index >= LEADING_FRAMES && check(FrameIssueKind::NegativeTime) {
+            report(FrameIssueKind::NegativeTime);
+        }
+        let in_bounds = |c: f32| c.is_finite() && c.abs() <= MAX_CURSOR_COORDINATE;
+        if !(in_bounds(frame.x) && in_bounds(frame.y)) && check(FrameIssueKind::OutOfBounds) {
+            report(FrameIssueKind::OutOfBounds);
+        }
+        if let Some(previous) = previous {
+            let distance = (frame.x - previous.x).hypot(frame.y - previous.y);
+            if distance >= TELEPORT_DISTANCE
+                && (0..=TELEPORT_MAX_DELTA).contains(&frame.time)
+                && index > LEADING_FRAMES
+                && check(FrameIssueKind::Teleport)
+            {
+                report(FrameIssueKind::Teleport);
+            }
+        }
+
+        any_keys |= Keys::from_bits_truncate(frame.keys).intersects(Keys::HIT);
+        previous = Some(frame);
+    }
+
+    if !frames.is_empty() && !any_keys && check(FrameIssueKind::NoKeyPresses) {
+        issues.push(FrameIssue {
+            index: None,
+            kind: FrameIssueKind::NoKeyPresses,
+        });
+    }
+    let abnormal_rate = frames.active_time() >= MIN_FRAME_RATE_DURATION
+        && frame_rate(frames, mods)
+            .is_some_and(|rate| !(MIN_FRAME_RATE..=MAX_FRAME_RATE).contains(&rate));
+    if abnormal_rate && check(FrameIssueKind::AbnormalFrameRate) {
+        issues.push(FrameIssue {
+            index: None,
+            kind: FrameIssueKind::AbnormalFrameRate,
+        });
+    }
+
+    issues
+}
@@ -0,0 +1,359 @@
+//! Replays claiming to be online scores are checked against the score stored by the osu! API,
+//! detecting local replays whose online score ID was spoofed.
+//!
+//! The HTTP requests are sent through an [`ApiClient`], either [`UreqClient`] or the HTTP client
+//! of the application.
+
+use std::fmt;
+use std::io;
+
+use serde_json::Value;
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::replay::{GameMode, Mods, Replay, ReplayFormat};
+
+/// Base URL of version 2 of the osu! API.
+pub const API_BASE_URL: &str = "https://osu.ppy.sh/api/v2";
+
+/// URL requesting OAuth tokens for the osu! API.
+pub const OAUTH_TOKEN_URL: &str = "https://osu.ppy.sh/oauth/token";
+
+/// Sends requests to the osu! API.
+///
+/// Implemented for closures taking the URL and returning the body of the response.
+pub trait ApiClient {
+    /// Send an authenticated `GET` request and return the body of the response.
+    /// # Errors
+    /// Returns an error if the request fails or the response is not successful.
+    fn get(&self, url: &str) -> io::Result<String>;
+}
+
+impl<F> ApiClient for F
+where
+    F: Fn(&str) -> io::Result<String>,
+{
+    fn get(&self, url: &str) -> io::Result<String> {
+        self(url)
+    }
+}
+
+/// An [`ApiClient`] sending the requests with [ureq](https://docs.rs/ureq), authenticated with
+/// an OAuth access token.
+/// # Example
+/// ```no_run
+/// use osu_replay_parser::api::UreqClient;
+/// use osu_replay_parser::Replay;
+///
+/// // The credentials of an OAuth application registered in the osu! account settings
+/// let client = UreqClient::client_credentials("12345", "secret").expect("Error authenticating");
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let mismatches = replay.verify_online(&client).expect("Error verifying replay");
+/// assert!(mismatches.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct UreqClient {
+    agent: ureq::Agent,
+    access_token: String,
+}
+
+impl fmt::Debug for UreqClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The access token is a secret
+        f.debug_struct("UreqClient").finish_non_exhaustive()
+    }
+}
+
+impl UreqClient {
+    /// Create a client sending requests with the given access token.
+    pub fn new(access_token: impl Into<String>) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(true)
+            .build()
+            .into();
+        UreqClient {
+            agent,
+            access_token: access_token.into(),
+        }
+    }
+
+    /// Request an access token with the client credentials grant of an OAuth application, which
+    /// has access to the public scope.
+    /// # Errors
+    /// Returns an error if the request fails or the response contains no access token.
+    pub fn client_credentials(client_id: &str, client_secret: &str) -> io::Result<Self> {
+        let mut client = UreqClient::new("");
+        let body = client
+            .agent
+            .post(OAUTH_TOKEN_URL)
+            .header("Accept", "application/json")
+            .send_form([
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("grant_type", "client_credentials"),
+                ("scope", "public"),
+            ])
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(io::Error::other)?;
+        client.access_token = serde_json::from_str::<Value>(&body)
+            .ok()
+            .as_ref()
+            .and_then(|token| token.get("access_token"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing access token"))?
+            .to_string();
+        Ok(client)
+    }
+}
+
+impl ApiClient for UreqClient {
+    fn get(&self, url: &str) -> io::Result<String> {
+        self.agent
+            .get(url)
+            .header("Accept", "application/json")
+            .header("Authorization", format!("Bearer {}", self.access_token))
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(io::Error::other)
+    }
+}
+
+/// A field of a replay that differs from the online score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// Name of the field, e.g. `total_score` or `n300`.
+    pub field: &'static str,
+    /// Value in the replay.
+    pub local: String,
+    /// Value of the online score.
+    pub online: String,
+}
+
+/// URL of the online score of a replay in the osu! API, `None` if the score was not submitted or
+/// its game mode is [`GameMode::Unknown`].
+///
+/// osu!stable scores are looked up per game mode, osu!lazer scores share one ID space, like
+/// [`score_url`](crate::format::score_url).
+pub fn score_api_url(replay: &Replay) -> Option<String> {
+    if replay.online_score_id <= 0 {
+        return None;
+    }
+    if replay.format() == ReplayFormat::Lazer {
+        return Some(format!(
+            "{}/scores/{}",
+            API_BASE_URL, replay.online_score_id
+        ));
+    }
+
+    Some(format!(
+        "{}/scores/{}/{}",
+        API_BASE_URL,
+        mode_name(replay.game_mode)?,
+        replay.online_score_id
+    ))
+}
+
+impl Replay {
+    /// Fetch the online score of the replay with its `online_score_id` and compare them, see
+    /// [`Self::compare_online_score`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay {
+    ///     online_score_id: 4321,
+    ///     version: 20240101,
+    ///     n300: 500,
+    ///     greatest_combo: 700,
+    ///     total_score: 12345678,
+    ///     mods: 8,
+    ///     ..Replay::default()
+    /// };
+    /// let client = |url: &str| {
+    ///     assert_eq!(url, "https://osu.ppy.sh/api/v2/scores/osu/4321");
+    ///     Ok(r#"{"id": 4321, "score": 12345678, "max_combo": 700, "mods": ["HD"],
+    ///         "statistics": {"count_300": 498, "count_100": 2, "count_50": 0,
+    ///         "count_geki": 0, "count_katu": 0, "count_miss": 0}}"#
+    ///         .to_string())
+    /// };
+    ///
+    /// let mismatches = replay.verify_online(&client).expect("Error verifying replay");
+    /// let fields: Vec<_> = mismatches.iter().map(|mismatch| mismatch.field).collect();
+    /// assert_eq!(fields, ["n300", "n100"]);
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::MissingValue` if the replay has no online score ID, a
+    /// `ReplayDataError::Io` if the request fails and a `ReplayDataError` if the response is not
+    /// a score.
+    pub fn verify_online(
+        &self,
+        client: &dyn ApiClient,
+    ) -> Result<Vec<FieldMismatch>, ReplayDataError<'static>> {
+        let url = score_api_url(self).ok_or(ParseError::MissingValue)?;
+        self.compare_online_score(&client.get(&url)?)
+    }
+
+    /// Compare the replay with a score returned by the osu! API, both in the legacy format of
+    /// osu!stable scores and the format of osu!lazer scores.
+    ///
+    /// The total score, maximum combo, mods and hit counts are compared, the beatmap hash and
+    /// player name if the response includes them. Mods only known to osu!lazer are ignored. Returns
+    /// no mismatches if the replay matches the score.
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if the response is not valid JSON and
+    /// `ParseError::MissingValue` if it lacks the score, combo or hit counts.
+    pub fn compare_online_score(
+        &self,
+        json: &str,
+    ) -> Result<Vec<FieldMismatch>, ReplayDataError<'static>> {
+        let score: Value = serde_json::from_str(json).map_err(|_| ParseError::InvalidValue)?;
+        let mut mismatches = Vec::new();
+        let mut compare = |field, local: String, online: String| {
+            if local != online {
+                mismatches.push(FieldMismatch {
+                    field,
+                    local,
+                    online,
+                });
+            }
+        };
+
+        let total_score = if self.format() == ReplayFormat::Lazer {
+            score.get("total_score")
+        } else {
+            score
+                .get("legacy_total_score")
+                .filter(|value| value.as_i64().is_some_and(|score| score > 0))
+                .or_else(|| score.get("score"))
+                .or_else(|| score.get("total_score"))
+        };
+        let total_score = total_score
+            .and_then(Value::as_i64)
+            .ok_or(ParseError::MissingValue)?;
+        compare(
+            "total_score",
+            self.total_score.to_string(),
+            total_score.to_string(),
+        );
+
+        let max_combo = score
+            .get("max_combo")
+            .and_then(Value::as_i64)
+            .ok_or(ParseError::MissingValue)?;
+        compare(
+            "greatest_combo",
+            self.greatest_combo.to_string(),
+            max_combo.to_string(),
+        );
+
+        if let Some(mods) = score.get("mods").and_then(Value::as_array) {
+            let online = mods
+                .iter()
+                .filter_map(|value| value.as_str().or_else(|| value.get("acronym")?.as_str()))
+                .filter_map(|acronym| Mods::from_acronyms(acronym).ok())
+                .fold(Mods::NONE, |mods, acronym| mods | acronym);
+            compare("mods", self.mods().acronyms(), online.acronyms());
+        }
+
+        let statistics = score.get("statistics").ok_or(ParseError::MissingValue)?;
+        let local = [
+            self.n300,
+            self.n100,
+            self.n50,
+            self.n_geki,
+            self.n_katu,
+            self.n_miss,
+        ];
+        let keys = if statistics.get("count_300").is_some() {
+            LEGACY_STATISTICS
+        } else {
+            lazer_statistics(self.game_mode)
+        };
+        for ((field, key), local) in HIT_COUNT_FIELDS.into_iter().zip(keys).zip(local) {
+            // osu!lazer does not report every hit count of osu!stable
+            let Some(key) = key else {
+                continue;
+            };
+            let online = statistics.get(key).and_then(Value::as_i64).unwrap_or(0);
+            compare(field, local.to_string(), online.to_string());
+        }
+
+        if let Some(checksum) = score
+            .get("beatmap")
+            .and_then(|beatmap| beatmap.get("checksum"))
+            .and_then(Value::as_str)
+        {
+            compare(
+                "beatmap_md5",
+                self.beatmap_md5.to_ascii_lowercase(),
+                checksum.to_ascii_lowercase(),
+            );
+        }
+        if let Some(username) = score
+            .get("user")
+            .and_then(|user| user.get("username"))
+            .and_then(Value::as_str)
+        {
+            compare(
+                "player_name",
+                self.player_name.clone(),
+                username.to_string(),
+            );
+        }
+
+        Ok(mismatches)
+    }
+}
+
+/// Names of the hit count fields of a replay.
+const HIT_COUNT_FIELDS: [&str; 6] = ["n300", "n100", "n50", "n_geki", "n_katu", "n_miss"];
+
+/// Keys of the hit counts in the statistics of osu!stable scores.
+const LEGACY_STATISTICS: [Option<&str>; 6] = [
+    Some("count_300"),
+    Some("count_100"),
+    Some("count_50"),
+    Some("count_geki"),
+    Some("count_katu"),
+    Some("count_miss"),
+];
+
+/// Keys of the hit counts in the statistics of osu!lazer scores, in the order of
+/// [`HIT_COUNT_FIELDS`].
+fn lazer_statistics(mode: GameMode) -> [Option<&'static str>; 6] {
+    match mode {
+        GameMode::Osu | GameMode::Taiko | GameMode::Unknown(_) => [
+            Some("great"),
+            Some("ok"),
+            Some("meh"),
+            None,
+            None,
+            Some("miss"),
+        ],
+        GameMode::CatchTheBeat => [
+            Some("great"),
+            Some("large_tick_hit"),
+            Some("small_tick_hit"),
+            None,
+            Some("small_tick_miss"),
+            Some("miss"),
+        ],
+        GameMode::Mania => [
+            Some("great"),
+            Some("ok"),
+            Some("meh"),
+            Some("perfect"),
+            Some("good"),
+            Some("miss"),
+        ],
+    }
+}
+
+fn mode_name(mode: GameMode) -> Option<&'static str> {
+    match mode {
+        GameMode::Osu => Some("osu"),
+        GameMode::Taiko => Some("taiko"),
+        GameMode::CatchTheBeat => Some("fruits"),
+        GameMode::Mania => Some("mania"),
+        GameMode::Unknown(_) => None,
+    }
+}
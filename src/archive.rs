@@ -0,0 +1,304 @@
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+
+use crate::errors::ReplayDataError;
+use crate::replay::Replay;
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Default of the largest decompressed size of an entry, see [`ReplayArchive::with_max_entry_size`].
+pub const DEFAULT_MAX_ENTRY_SIZE: usize = 256 << 20;
+
+/// Largest number of bytes allocated for an entry before any is decompressed, so size fields
+/// of crafted archives cannot allocate gigabytes up front.
+const MAX_PREALLOCATION: usize = 1 << 20;
+
+/// Compression of an entry in an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Stored,
+    Deflate,
+}
+
+/// A file inside a [`ReplayArchive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file inside the archive.
+    pub name: String,
+    /// Uncompressed size of the file in bytes.
+    pub size: usize,
+    offset: usize,
+    compressed_size: usize,
+    compression: Compression,
+    /// CRC-32 of the uncompressed file, `None` for tar archives.
+    crc32: Option<u32>,
+}
+
+/// A zip archive (including `.osk` and `.osz` bundles) or uncompressed tar archive containing replays.
+///
+/// Entries are only decompressed and parsed when accessed, so large replay packs can be iterated without extracting them.
+/// # Example
+/// ```
+/// use osu_replay_parser::archive::ReplayArchive;
+///
+/// let archive = ReplayArchive::open("assets/replays.zip").expect("Error opening archive");
+/// for (name, replay) in archive.replays() {
+///     match replay {
+///         Ok(replay) => println!("{}: {}", name, replay.player_name),
+///         Err(e) => println!("{}: {:?}", name, e),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayArchive {
+    data: Vec<u8>,
+    entries: Vec<ArchiveEntry>,
+    max_entry_size: usize,
+}
+
+/// Error of a malformed or truncated archive.
+fn invalid(message: String) -> ReplayDataError<'static> {
+    ReplayDataError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn bytes_at(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ReplayDataError<'static>> {
+    data.get(offset..offset.saturating_add(len))
+        .ok_or_else(|| invalid(format!("archive truncated at offset {}", offset)))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, ReplayDataError<'static>> {
+    let bytes = bytes_at(data, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, ReplayDataError<'static>> {
+    let bytes = bytes_at(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn zip_entries(data: &[u8]) -> Result<Vec<ArchiveEntry>, ReplayDataError<'static>> {
+    // The end of central directory record is at least 22 bytes and followed by a comment of up to 64 KiB
+    let search_start = data.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_start..data.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| u32_at(data, offset).ok() == Some(ZIP_END_OF_DIRECTORY))
+        .ok_or_else(|| invalid("zip end of central directory not found".to_string()))?;
+
+    let count = u16_at(data, end + 10)?;
+    let mut offset = u32_at(data, end + 16)? as usize;
+    let mut entries = Vec::with_capacity(count.into());
+
+    for _ in 0..count {
+        if u32_at(data, offset)? != ZIP_CENTRAL_HEADER {
+            return Err(invalid(format!(
+                "no zip central directory header at offset {}",
+                offset
+            )));
+        }
+        let compression = match u16_at(data, offset + 10)? {
+            0 => Compression::Stored,
+            8 => Compression::Deflate,
+            method => {
+                return Err(invalid(format!(
+                    "unsupported zip compression method {}",
+                    method
+                )))
+            }
+        };
+        let crc32 = u32_at(data, offset + 16)?;
+        let compressed_size = u32_at(data, offset + 20)? as usize;
+        let size = u32_at(data, offset + 24)? as usize;
+        let name_length = u16_at(data, offset + 28)? as usize;
+        let extra_length = u16_at(data, offset + 30)? as usize;
+        let comment_length = u16_at(data, offset + 32)? as usize;
+        let local_offset = u32_at(data, offset + 42)? as usize;
+        let name = String::from_utf8_lossy(bytes_at(data, offset + 46, name_length)?).into_owned();
+
+        if u32_at(data, local_offset)? != ZIP_LOCAL_HEADER {
+            return Err(invalid(format!("no zip local header for {}", name)));
+        }
+        let local_name_length = u16_at(data, local_offset + 26)? as usize;
+        let local_extra_length = u16_at(data, local_offset + 28)? as usize;
+
+        entries.push(ArchiveEntry {
+            name,
+            size,
+            offset: local_offset + 30 + local_name_length + local_extra_length,
+            compressed_size,
+            compression,
+            crc32: Some(crc32),
+        });
+        offset += 46 + name_length + extra_length + comment_length;
+    }
+
+    Ok(entries)
+}
+
+fn tar_entries(data: &[u8]) -> Result<Vec<ArchiveEntry>, ReplayDataError<'static>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while let Some(header) = data.get(offset..offset + TAR_BLOCK_SIZE) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let field = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let checksum = u32::from_str_radix(&field(148..156), 8).ok();
+        let sum = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    u32::from(b' ')
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum::<u32>();
+        if checksum != Some(sum) {
+            return Err(invalid(format!(
+                "tar header checksum mismatch at offset {}",
+                offset
+            )));
+        }
+        let size = usize::from_str_radix(&field(124..136), 8)
+            .map_err(|_| invalid(format!("invalid tar entry size at offset {}", offset)))?;
+        let prefix = field(345..500);
+        let name = match prefix.is_empty() {
+            true => field(0..100),
+            false => format!("{}/{}", prefix, field(0..100)),
+        };
+
+        // Only regular files are of interest
+        if matches!(header[156], 0 | b'0') {
+            entries.push(ArchiveEntry {
+                name,
+                size,
+                offset: offset + TAR_BLOCK_SIZE,
+                compressed_size: size,
+                compression: Compression::Stored,
+                crc32: None,
+            });
+        }
+        offset += TAR_BLOCK_SIZE + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+    }
+
+    Ok(entries)
+}
+
+impl ReplayArchive {
+    /// Read an archive from disk.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read or is not a supported archive.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ReplayDataError<'static>> {
+        ReplayArchive::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Read an archive from its bytes. The format is detected from the content.
+    ///
+    /// Zip archives with stored or deflated entries and uncompressed tar archives are supported.
+    /// # Errors
+    /// Returns `ReplayDataError::Io` with the kind `InvalidData` if the data is not a supported
+    /// archive or its directory is malformed.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, ReplayDataError<'static>> {
+        let entries = if data.starts_with(b"PK") {
+            zip_entries(&data)?
+        } else if data.get(257..262) == Some(b"ustar") {
+            tar_entries(&data)?
+        } else {
+            return Err(invalid("not a zip or tar archive".to_string()));
+        };
+
+        Ok(ReplayArchive {
+            data,
+            entries,
+            max_entry_size: DEFAULT_MAX_ENTRY_SIZE,
+        })
+    }
+
+    /// Set the largest number of bytes a compressed entry may decompress to,
+    /// [`DEFAULT_MAX_ENTRY_SIZE`] by default.
+    ///
+    /// Entries with a larger stored size are rejected before they are decompressed, and
+    /// decompression stops once an entry grows larger than its stored size, so crafted archives
+    /// cannot exhaust memory.
+    pub fn with_max_entry_size(mut self, limit: usize) -> Self {
+        self.max_entry_size = limit;
+        self
+    }
+
+    /// All files in the archive.
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    /// Read the uncompressed content of a file in the archive, verifying the CRC-32 of zip entries.
+    /// # Errors
+    /// Returns `ReplayDataError::Io` with the kind `InvalidData` if the entry data is truncated,
+    /// cannot be decompressed, does not match its stored size or CRC-32 or is larger than the
+    /// limit of [`Self::with_max_entry_size`].
+    pub fn read(&self, entry: &ArchiveEntry) -> Result<Vec<u8>, ReplayDataError<'static>> {
+        if entry.size > self.max_entry_size {
+            return Err(invalid(format!(
+                "{} exceeds the entry size limit",
+                entry.name
+            )));
+        }
+        let compressed = bytes_at(&self.data, entry.offset, entry.compressed_size)?;
+
+        let data = match entry.compression {
+            Compression::Stored => compressed.to_vec(),
+            Compression::Deflate => {
+                // One more byte than the stored size is read to detect larger entries
+                let mut data = Vec::with_capacity(entry.size.min(MAX_PREALLOCATION));
+                DeflateDecoder::new(compressed)
+                    .take(entry.size as u64 + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|e| {
+                        invalid(format!("{} cannot be decompressed: {}", entry.name, e))
+                    })?;
+                data
+            }
+        };
+        if data.len() != entry.size {
+            return Err(invalid(format!(
+                "{} does not match its stored size",
+                entry.name
+            )));
+        }
+        if let Some(expected) = entry.crc32 {
+            let mut crc = Crc::new();
+            crc.update(&data);
+            if crc.sum() != expected {
+                return Err(invalid(format!("{} does not match its CRC-32", entry.name)));
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Iterate over all `.osr` files in the archive, parsing each one when it is reached.
+    pub fn replays(
+        &self,
+    ) -> impl Iterator<Item = (&str, Result<Replay, ReplayDataError<'static>>)> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| entry.name.to_ascii_lowercase().ends_with(".osr"))
+            .map(|entry| {
+                let replay = self
+                    .read(entry)
+                    .and_then(|data| Replay::parse(&data).map_err(ReplayDataError::into_owned));
+                (entry.name.as_str(), replay)
+            })
+    }
+}
@@ -0,0 +1,125 @@
+//! Frames are exported as [`RecordBatch`]es of [arrow-rs](https://docs.rs/arrow). Other Arrow
+//! implementations such as polars and pyarrow import them without copying through the
+//! [Arrow C stream interface], see [`frames_to_stream`].
+//!
+//! [Arrow C stream interface]: https://arrow.apache.org/docs/format/CStreamInterface.html
+
+use std::sync::Arc;
+
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
+use arrow_array::{
+    ArrayRef, Float32Array, Int64Array, RecordBatch, RecordBatchIterator, UInt32Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+use crate::frames::FramesExt;
+use crate::replay::ReplayData;
+
+/// Frames transposed into columns, with absolute times.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FrameColumns {
+    /// Absolute time of each frame in milliseconds.
+    pub time: Vec<i64>,
+    /// x coordinates.
+    pub x: Vec<f32>,
+    /// y coordinates.
+    pub y: Vec<f32>,
+    /// Bit flags of the pressed keys.
+    pub keys: Vec<u32>,
+}
+
+impl FrameColumns {
+    /// Transpose the frames into columns, see [`FramesExt::timed`]. The seed frame is skipped.
+    pub fn from_frames(frames: &[ReplayData]) -> Self {
+        let mut columns = FrameColumns::default();
+        for (time, frame) in frames.timed() {
+            columns.time.push(time);
+            columns.x.push(frame.x);
+            columns.y.push(frame.y);
+            columns.keys.push(frame.keys);
+        }
+        columns
+    }
+
+    /// Number of frames.
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    /// Whether there are no frames.
+    pub fn is_empty(&self) -> bool {
+        self.time.is_empty()
+    }
+
+    /// The schema of the record batches, with the non-nullable fields `time` (int64),
+    /// `x` (float32), `y` (float32) and `keys` (uint32).
+    pub fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("time", DataType::Int64, false),
+            Field::new("x", DataType::Float32, false),
+            Field::new("y", DataType::Float32, false),
+            Field::new("keys", DataType::UInt32, false),
+        ]))
+    }
+
+    /// Export the columns as a record batch with the [`Self::schema`], without copying them.
+    /// # Panics
+    /// Panics if the columns have different lengths.
+    pub fn into_record_batch(self) -> RecordBatch {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(self.time)),
+            Arc::new(Float32Array::from(self.x)),
+            Arc::new(Float32Array::from(self.y)),
+            Arc::new(UInt32Array::from(self.keys)),
+        ];
+        RecordBatch::try_new(FrameColumns::schema(), columns)
+            .expect("columns must have the same length")
+    }
+}
+
+/// Export frames as an Arrow record batch, see [`FrameColumns::into_record_batch`].
+/// # Example
+/// ```
+/// use arrow_array::cast::AsArray;
+/// use arrow_array::types::Float32Type;
+/// use osu_replay_parser::arrow::frames_to_record_batch;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let batch = frames_to_record_batch(&actions);
+///
+/// let fields: Vec<_> = batch.schema().fields().iter().map(|field| field.name().clone()).collect();
+/// assert_eq!(fields, ["time", "x", "y", "keys"]);
+///
+/// // Without the seed frame
+/// assert_eq!(batch.num_rows(), actions.len() - 1);
+/// let x = batch.column(1).as_primitive::<Float32Type>();
+/// assert_eq!(x.value(1), actions[1].x);
+/// ```
+pub fn frames_to_record_batch(frames: &[ReplayData]) -> RecordBatch {
+    FrameColumns::from_frames(frames).into_record_batch()
+}
+
+/// Export frames as an `ArrowArrayStream` of the C stream interface with a single record batch,
+/// e.g. for `pyarrow.RecordBatchReader._import_from_c` or polars.
+/// # Example
+/// ```
+/// use arrow_array::ffi_stream::ArrowArrayStreamReader;
+/// use osu_replay_parser::arrow::frames_to_stream;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+///
+/// // The consumer takes ownership of the stream and releases it when done
+/// let stream = frames_to_stream(&actions);
+/// let reader = ArrowArrayStreamReader::try_new(stream).expect("Error importing stream");
+/// let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+/// assert_eq!(rows, actions.len() - 1);
+/// ```
+pub fn frames_to_stream(frames: &[ReplayData]) -> FFI_ArrowArrayStream {
+    let batch = frames_to_record_batch(frames);
+    let reader = RecordBatchIterator::new([Ok(batch)], FrameColumns::schema());
+    FFI_ArrowArrayStream::new(Box::new(reader))
+}
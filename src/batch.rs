@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::errors::ReplayDataError;
+use crate::parser::ParseOptions;
+use crate::replay::{Mods, Replay, ReplayData};
+
+/// Width of the buckets used for [`Aggregate::score_histogram`].
+pub const SCORE_BUCKET_SIZE: u32 = 100_000;
+
+/// Aggregated statistics over a group of replays, e.g. all replays of one player.
+#[derive(Debug, Default, Clone)]
+pub struct Aggregate {
+    /// Number of replays in the group.
+    pub play_count: usize,
+    /// Mean of [`Replay::accuracy`] over all replays in the group.
+    pub average_accuracy: f64,
+    /// Number of replays per mod combination.
+    pub mod_distribution: HashMap<Mods, usize>,
+    /// Number of replays per total score bucket, keyed by the lower bound of the bucket.
+    ///
+    /// Buckets are [`SCORE_BUCKET_SIZE`] wide.
+    pub score_histogram: BTreeMap<u32, usize>,
+}
+
+impl Aggregate {
+    fn add(&mut self, replay: &Replay) {
+        let accuracy_sum = self.average_accuracy * self.play_count as f64 + replay.accuracy();
+        self.play_count += 1;
+        self.average_accuracy = accuracy_sum / self.play_count as f64;
+
+        *self.mod_distribution.entry(replay.mods()).or_default() += 1;
+
+        let bucket = replay.total_score / SCORE_BUCKET_SIZE * SCORE_BUCKET_SIZE;
+        *self.score_histogram.entry(bucket).or_default() += 1;
+    }
+}
+
+/// Statistics of a set of replays, grouped by player and by beatmap.
+#[derive(Debug, Default, Clone)]
+pub struct Statistics {
+    /// Aggregates of all replays, regardless of player or beatmap.
+    pub total: Aggregate,
+    /// Aggregates per player name.
+    pub players: HashMap<String, Aggregate>,
+    /// Aggregates per beatmap MD5.
+    pub beatmaps: HashMap<String, Aggregate>,
+}
+
+/// Aggregate play counts, accuracy, mods and scores of many replays.
+///
+/// Only the header of the replays is used, so replays parsed with
+/// [`ParseOptions::header_only`](crate::ParseOptions::header_only) are sufficient.
+/// # Example
+/// ```
+/// use osu_replay_parser::{batch, Replay};
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let statistics = batch::aggregate([&replay]);
+/// assert_eq!(statistics.players["RenLephy"].play_count, 1);
+/// ```
+pub fn aggregate<'a, I>(replays: I) -> Statistics
+where
+    I: IntoIterator<Item = &'a Replay>,
+{
+    let mut statistics = Statistics::default();
+
+    for replay in replays {
+        statistics.total.add(replay);
+        statistics
+            .players
+            .entry(replay.player_name.clone())
+            .or_default()
+            .add(replay);
+        statistics
+            .beatmaps
+            .entry(replay.beatmap_md5.clone())
+            .or_default()
+            .add(replay);
+    }
+
+    statistics
+}
+
+/// Default limit in bytes of the buffer memory an [`Arena`] keeps between files.
+pub const DEFAULT_ARENA_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Memory reused between files by a [`Parser`] created with [`Parser::with_arena`].
+///
+/// The file contents are read into a reused buffer and frames are decoded into a pooled vector.
+/// Buffers growing beyond the limit, e.g. after a marathon replay, are released after the file.
+///
+/// Header strings are not interned: every [`Replay`] owns its player name and hashes as
+/// `String`, so an interned copy would still be cloned into each replay. Group replays with
+/// [`aggregate`] to keep one copy per player and beatmap instead.
+#[derive(Debug)]
+pub struct Arena {
+    input: Vec<u8>,
+    frames: Vec<ReplayData>,
+    limit: usize,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::new(DEFAULT_ARENA_LIMIT)
+    }
+}
+
+impl Arena {
+    /// Create an arena keeping at most `limit` bytes of buffers between files.
+    pub fn new(limit: usize) -> Self {
+        Arena {
+            input: Vec::new(),
+            frames: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Bytes currently allocated by the pooled buffers.
+    pub fn buffer_capacity(&self) -> usize {
+        self.input.capacity() + self.frames.capacity() * std::mem::size_of::<ReplayData>()
+    }
+
+    fn trim(&mut self) {
+        if self.input.capacity() > self.limit {
+            self.input = Vec::new();
+        }
+        if self.frames.capacity() * std::mem::size_of::<ReplayData>() > self.limit {
+            self.frames = Vec::new();
+        }
+    }
+}
+
+/// Callback invoked by [`Parser`] after each file.
+pub type FileParsedCallback<'a> =
+    Box<dyn FnMut(&Path, &Result<Replay, ReplayDataError<'static>>) + 'a>;
+
+/// Parser for many replay files, e.g. a whole replay folder.
+/// # Example
+/// ```
+/// use osu_replay_parser::batch::Parser;
+/// use osu_replay_parser::ParseOptions;
+///
+/// let mut parsed = 0;
+/// let results = Parser::new()
+///     .options(ParseOptions::header_only())
+///     .on_file_parsed(|_path, _result| parsed += 1)
+///     .parse_dir("assets")
+///     .expect("Error reading directory");
+/// assert_eq!(results.len(), 1);
+/// ```
+#[derive(Default)]
+pub struct Parser<'a> {
+    options: ParseOptions,
+    on_file_parsed: Option<FileParsedCallback<'a>>,
+    arena: Option<Arena>,
+}
+
+impl<'a> Parser<'a> {
+    /// Create a parser using the default [`ParseOptions`].
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Set the options used to parse each file.
+    pub fn options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set a callback invoked after each file with its path and the parse result, e.g. to report progress.
+    pub fn on_file_parsed<F>(mut self, on_file_parsed: F) -> Self
+    where
+        F: FnMut(&Path, &Result<Replay, ReplayDataError<'static>>) + 'a,
+    {
+        self.on_file_parsed = Some(Box::new(on_file_parsed));
+        self
+    }
+
+    /// Reuse buffers between files with a default [`Arena`], reducing allocations when parsing many files.
+    pub fn with_arena(self) -> Self {
+        self.arena(Arena::default())
+    }
+
+    /// Reuse buffers between files with the given [`Arena`].
+    pub fn arena(mut self, arena: Arena) -> Self {
+        self.arena = Some(arena);
+        self
+    }
+
+    /// The arena of the parser, `None` if it was not created with one.
+    pub fn arena_mut(&mut self) -> Option<&mut Arena> {
+        self.arena.as_mut()
+    }
+
+    fn parse(&mut self, path: &Path) -> Result<Replay, ReplayDataError<'static>> {
+        let parse = |input: &[u8]| {
+            Replay::parse_with_options(input, &self.options).map_err(ReplayDataError::into_owned)
+        };
+
+        match &mut self.arena {
+            Some(arena) => {
+                arena.input.clear();
+                File::open(path)?.read_to_end(&mut arena.input)?;
+                parse(&arena.input)
+            }
+            None => parse(&std::fs::read(path)?),
+        }
+    }
+
+    /// Parse a single replay file.
+    pub fn parse_file(&mut self, path: &Path) -> Result<Replay, ReplayDataError<'static>> {
+        let result = self.parse(path);
+        if let Some(arena) = &mut self.arena {
+            arena.trim();
+        }
+        if let Some(on_file_parsed) = &mut self.on_file_parsed {
+            on_file_parsed(path, &result);
+        }
+
+        result
+    }
+
+    /// Parse a replay file and pass it to `f` together with its decoded frames.
+    ///
+    /// With an arena the frames are decoded into a pooled vector, so they are only borrowed.
+    /// The compressed data is released before `f` is called.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::batch::Parser;
+    /// use std::path::Path;
+    ///
+    /// let mut parser = Parser::new().with_arena();
+    /// let frame_count = parser
+    ///     .parse_file_frames(Path::new("assets/replay.osr"), |_replay, frames| frames.len())
+    ///     .expect("Error parsing replay");
+    /// assert_eq!(frame_count, 11984);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read, parsed or its frames decoded.
+    pub fn parse_file_frames<T, F>(
+        &mut self,
+        path: &Path,
+        f: F,
+    ) -> Result<T, ReplayDataError<'static>>
+    where
+        F: FnOnce(&Replay, &[ReplayData]) -> T,
+    {
+        let mut replay = self.parse_file(path)?;
+        let compressed_data = std::mem::take(&mut replay.compressed_data);
+        let mut frames = match &mut self.arena {
+            Some(arena) => std::mem::take(&mut arena.frames),
+            None => Vec::new(),
+        };
+        let result = Replay::decode_actions_into(&compressed_data, &mut frames, |_, _| {})
+            .map(|_| f(&replay, &frames));
+        if let Some(arena) = &mut self.arena {
+            arena.frames = frames;
+            arena.trim();
+        }
+
+        result
+    }
+
+    /// Parse the given replay files.
+    pub fn parse_files<I, P>(
+        &mut self,
+        paths: I,
+    ) -> Vec<(PathBuf, Result<Replay, ReplayDataError<'static>>)>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        paths
+            .into_iter()
+            .map(|path| {
+                let path = path.into();
+                let result = self.parse_file(&path);
+                (path, result)
+            })
+            .collect()
+    }
+
+    /// Parse all `.osr` files in a directory, sorted by path. Subdirectories are not searched.
+    /// # Errors
+    /// Returns an error if the directory cannot be read. Errors of individual files are part of the result.
+    pub fn parse_dir<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+    ) -> io::Result<Vec<(PathBuf, Result<Replay, ReplayDataError<'static>>)>> {
+        Ok(self.parse_files(replay_files(dir.as_ref())?))
+    }
+}
+
+/// All `.osr` files in a directory, sorted by path.
+pub fn replay_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_replay = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("osr"));
+        if is_replay && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
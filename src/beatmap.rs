@@ -0,0 +1,606 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::game_math::PLAYFIELD_HEIGHT;
+use crate::replay::{GameMode, Mods};
+
+/// Kind of a hit object and the data specific to it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HitObjectKind {
+    /// A hit circle.
+    Circle,
+    /// A slider.
+    Slider {
+        /// Curve type of the slider (`B`, `C`, `L` or `P`).
+        curve_type: char,
+        /// Control points of the slider curve, excluding the head position.
+        curve_points: Vec<(f32, f32)>,
+        /// Number of times the slider is traversed, 1 for sliders without repeats.
+        slides: u32,
+        /// Visual length of the slider in osu!pixels.
+        length: f32,
+    },
+    /// A spinner.
+    Spinner,
+    /// A mania hold note.
+    Hold,
+}
+
+/// A single hit object of a beatmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitObject {
+    /// The x-coordinate of the hit object.
+    pub x: f32,
+    /// The y-coordinate of the hit object.
+    pub y: f32,
+    /// Time of the hit object in milliseconds.
+    pub time: i64,
+    /// Time the hit object ends in milliseconds, equal to `time` for circles.
+    pub end_time: i64,
+    /// Whether the hit object starts a new combo.
+    pub new_combo: bool,
+    /// Kind of the hit object.
+    pub kind: HitObjectKind,
+}
+
+/// Number of points each curve segment is approximated with.
+const CURVE_SEGMENT_POINTS: usize = 50;
+
+/// The path of a slider, approximated by a polyline.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SliderPath {
+    points: Vec<(f32, f32)>,
+    // Distance along the path at each point
+    distances: Vec<f32>,
+    length: f32,
+}
+
+impl SliderPath {
+    /// Compute the path of a slider from its head and curve.
+    ///
+    /// The path is cut off or extended in a straight line to the visual `length` of the slider.
+    pub fn new(head: (f32, f32), curve_type: char, curve_points: &[(f32, f32)], length: f32) -> Self {
+        let mut control_points = vec![head];
+        control_points.extend_from_slice(curve_points);
+
+        let points = match curve_type {
+            'L' => control_points,
+            'P' if control_points.len() == 3 => perfect_curve(&control_points)
+                .unwrap_or_else(|| bezier_curve(&control_points)),
+            'C' => catmull_curve(&control_points),
+            _ => bezier_curve(&control_points),
+        };
+        let mut distances = Vec::with_capacity(points.len());
+        let mut distance = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                let previous = points[i - 1];
+                distance += (point.0 - previous.0).hypot(point.1 - previous.1);
+            }
+            distances.push(distance);
+        }
+
+        SliderPath {
+            points,
+            distances,
+            length,
+        }
+    }
+
+    /// The visual length of the path in osu!pixels.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// Position at the given distance from the head, clamped to the length of the path.
+    ///
+    /// A negative or NaN length is treated as 0.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::beatmap::SliderPath;
+    ///
+    /// let path = SliderPath::new((0.0, 0.0), 'L', &[(100.0, 0.0)], 100.0);
+    /// assert_eq!(path.position_at(50.0), (50.0, 0.0));
+    /// assert_eq!(path.position_at(150.0), (100.0, 0.0));
+    ///
+    /// let broken = SliderPath::new((0.0, 0.0), 'L', &[(100.0, 0.0)], f32::NAN);
+    /// assert_eq!(broken.position_at(50.0), (0.0, 0.0));
+    /// ```
+    pub fn position_at(&self, distance: f32) -> (f32, f32) {
+        let distance = distance.max(0.0).min(self.length.max(0.0));
+        let Some(&last) = self.points.last() else {
+            return (0.0, 0.0);
+        };
+        if self.points.len() == 1 {
+            return last;
+        }
+
+        // Segment containing the distance, the last segment is extended if the curve is too short
+        let i = self
+            .distances
+            .partition_point(|&d| d < distance)
+            .clamp(1, self.points.len() - 1);
+        let (start, end) = (self.points[i - 1], self.points[i]);
+        let segment = self.distances[i] - self.distances[i - 1];
+        if segment <= 0.0 {
+            return start;
+        }
+        let t = (distance - self.distances[i - 1]) / segment;
+        (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t)
+    }
+}
+
+/// Points of a Bézier curve, split into separate curves at repeated control points.
+fn bezier_curve(control_points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut points = Vec::new();
+    let mut start = 0;
+    for end in 1..=control_points.len() {
+        if end < control_points.len() && control_points[end] != control_points[end - 1] {
+            continue;
+        }
+        let segment = &control_points[start..end];
+        start = end;
+        if segment.len() < 2 {
+            points.extend_from_slice(segment);
+            continue;
+        }
+        for step in 0..=CURVE_SEGMENT_POINTS {
+            let t = step as f32 / CURVE_SEGMENT_POINTS as f32;
+            // De Casteljau's algorithm
+            let mut work = segment.to_vec();
+            for level in 1..work.len() {
+                for j in 0..work.len() - level {
+                    work[j] = (
+                        work[j].0 + (work[j + 1].0 - work[j].0) * t,
+                        work[j].1 + (work[j + 1].1 - work[j].1) * t,
+                    );
+                }
+            }
+            points.push(work[0]);
+        }
+    }
+    points
+}
+
+/// Points of a Catmull-Rom spline through the control points.
+fn catmull_curve(control_points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    if control_points.len() < 2 {
+        return control_points.to_vec();
+    }
+    let point = |i: isize| control_points[i.clamp(0, control_points.len() as isize - 1) as usize];
+    let mut points = Vec::new();
+    for i in 0..control_points.len() as isize - 1 {
+        let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+        for step in 0..=CURVE_SEGMENT_POINTS {
+            let t = step as f32 / CURVE_SEGMENT_POINTS as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let coordinate = |a: f32, b: f32, c: f32, d: f32| {
+                0.5 * (2.0 * b
+                    + (-a + c) * t
+                    + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+                    + (-a + 3.0 * b - 3.0 * c + d) * t3)
+            };
+            points.push((coordinate(p0.0, p1.0, p2.0, p3.0), coordinate(p0.1, p1.1, p2.1, p3.1)));
+        }
+    }
+    points
+}
+
+/// Points of the circular arc through three control points, `None` if they are collinear.
+fn perfect_curve(control_points: &[(f32, f32)]) -> Option<Vec<(f32, f32)>> {
+    let [a, b, c] = [control_points[0], control_points[1], control_points[2]]
+        .map(|(x, y)| (f64::from(x), f64::from(y)));
+    let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+    if d.abs() < 1e-3 {
+        return None;
+    }
+    let square = |p: (f64, f64)| p.0 * p.0 + p.1 * p.1;
+    let center = (
+        (square(a) * (b.1 - c.1) + square(b) * (c.1 - a.1) + square(c) * (a.1 - b.1)) / d,
+        (square(a) * (c.0 - b.0) + square(b) * (a.0 - c.0) + square(c) * (b.0 - a.0)) / d,
+    );
+    let radius = (a.0 - center.0).hypot(a.1 - center.1);
+    let angle = |p: (f64, f64)| (p.1 - center.1).atan2(p.0 - center.0);
+    let (start, end) = (angle(a), angle(c));
+
+    // Go around the circle in the direction that passes the middle point
+    let tau = std::f64::consts::TAU;
+    let mut sweep = (end - start).rem_euclid(tau);
+    if (angle(b) - start).rem_euclid(tau) > sweep {
+        sweep -= tau;
+    }
+    let steps = CURVE_SEGMENT_POINTS * 2;
+    Some(
+        (0..=steps)
+            .map(|step| {
+                let theta = start + sweep * step as f64 / steps as f64;
+                (
+                    (center.0 + radius * theta.cos()) as f32,
+                    (center.1 + radius * theta.sin()) as f32,
+                )
+            })
+            .collect(),
+    )
+}
+
+impl HitObject {
+    /// The path of the slider, `None` if the hit object is not a slider.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::beatmap::{HitObject, HitObjectKind};
+    ///
+    /// let slider = HitObject {
+    ///     x: 100.0,
+    ///     y: 100.0,
+    ///     time: 1000,
+    ///     end_time: 1500,
+    ///     new_combo: true,
+    ///     kind: HitObjectKind::Slider {
+    ///         curve_type: 'L',
+    ///         curve_points: vec![(300.0, 100.0)],
+    ///         slides: 1,
+    ///         length: 150.0,
+    ///     },
+    /// };
+    /// let path = slider.slider_path().unwrap();
+    /// assert_eq!(path.position_at(50.0), (150.0, 100.0));
+    /// // The path ends at the visual length
+    /// assert_eq!(path.position_at(200.0), (250.0, 100.0));
+    /// ```
+    pub fn slider_path(&self) -> Option<SliderPath> {
+        match &self.kind {
+            HitObjectKind::Slider {
+                curve_type,
+                curve_points,
+                length,
+                ..
+            } => Some(SliderPath::new((self.x, self.y), *curve_type, curve_points, *length)),
+            _ => None,
+        }
+    }
+}
+
+/// A timing point of a beatmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingPoint {
+    /// Start time of the timing section in milliseconds.
+    pub time: f64,
+    /// Duration of a beat in milliseconds for uninherited timing points,
+    /// negative inverse slider velocity percentage for inherited ones.
+    pub beat_length: f64,
+    /// Whether the timing point is uninherited (red line).
+    pub uninherited: bool,
+    /// Whether kiai time is enabled.
+    pub kiai: bool,
+}
+
+/// A break period of a beatmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Break {
+    /// Start time of the break in milliseconds.
+    pub start_time: i64,
+    /// End time of the break in milliseconds.
+    pub end_time: i64,
+}
+
+/// Difficulty settings of a beatmap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    /// HP drain rate.
+    pub hp_drain_rate: f32,
+    /// Circle size, the key count in mania.
+    pub circle_size: f32,
+    /// Overall difficulty.
+    pub overall_difficulty: f32,
+    /// Approach rate.
+    pub approach_rate: f32,
+    /// Base slider velocity in hundreds of osu!pixels per beat.
+    pub slider_multiplier: f64,
+    /// Number of slider ticks per beat.
+    pub slider_tick_rate: f64,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            hp_drain_rate: 5.0,
+            circle_size: 5.0,
+            overall_difficulty: 5.0,
+            approach_rate: 5.0,
+            slider_multiplier: 1.4,
+            slider_tick_rate: 1.0,
+        }
+    }
+}
+
+impl Difficulty {
+    /// Apply the difficulty changes of Hard Rock and Easy.
+    pub fn with_mods(self, mods: Mods) -> Self {
+        let factor = if mods.contains(Mods::HARD_ROCK) {
+            1.4
+        } else if mods.contains(Mods::EASY) {
+            0.5
+        } else {
+            return self;
+        };
+        let cs_factor = if mods.contains(Mods::HARD_ROCK) { 1.3 } else { 0.5 };
+
+        Difficulty {
+            hp_drain_rate: (self.hp_drain_rate * factor).min(10.0),
+            circle_size: (self.circle_size * cs_factor).min(10.0),
+            overall_difficulty: (self.overall_difficulty * factor).min(10.0),
+            approach_rate: (self.approach_rate * factor).min(10.0),
+            ..self
+        }
+    }
+}
+
+/// The parts of an osu! beatmap (`.osu` file) relevant for replay analysis.
+///
+/// Use [Self::parse] to parse a beatmap.
+#[derive(Debug, Clone, Default)]
+pub struct Beatmap {
+    /// Game mode of the beatmap.
+    pub game_mode: GameMode,
+    /// Title of the song.
+    pub title: String,
+    /// Artist of the song.
+    pub artist: String,
+    /// Name of the difficulty.
+    pub version: String,
+    /// Online ID of the beatmap, 0 if unknown.
+    pub beatmap_id: u32,
+    /// Online ID of the beatmap set, 0 if unknown.
+    pub beatmap_set_id: u32,
+    /// Stack leniency of the beatmap.
+    pub stack_leniency: f32,
+    /// Difficulty settings of the beatmap.
+    pub difficulty: Difficulty,
+    /// Timing points sorted by time.
+    pub timing_points: Vec<TimingPoint>,
+    /// Break periods.
+    pub breaks: Vec<Break>,
+    /// Hit objects sorted by time.
+    pub hit_objects: Vec<HitObject>,
+}
+
+fn parse_value<T: FromStr>(value: Option<&str>) -> Result<T, ReplayDataError<'static>> {
+    value
+        .ok_or(ParseError::MissingValue)?
+        .trim()
+        .parse()
+        .map_err(|_| ParseError::InvalidValue.into())
+}
+
+fn parse_hit_object(line: &str) -> Result<HitObject, ReplayDataError<'static>> {
+    let mut split = line.split(',');
+    let x: f32 = parse_value(split.next())?;
+    let y: f32 = parse_value(split.next())?;
+    let time = parse_value::<f64>(split.next())? as i64;
+    let object_type: u32 = parse_value(split.next())?;
+    let _hit_sound = split.next();
+
+    let (kind, end_time) = if object_type & 2 != 0 {
+        let mut curve = split.next().ok_or(ParseError::MissingValue)?.split('|');
+        let curve_type = curve
+            .next()
+            .and_then(|t| t.chars().next())
+            .ok_or(ParseError::MissingValue)?;
+        let curve_points = curve
+            .map(|point| {
+                let (x, y) = point.split_once(':').ok_or(ParseError::InvalidValue)?;
+                Ok((parse_value(Some(x))?, parse_value(Some(y))?))
+            })
+            .collect::<Result<_, ReplayDataError<'static>>>()?;
+        let slides = parse_value(split.next())?;
+        let length: f32 = parse_value(split.next())?;
+        if !length.is_finite() || length < 0.0 {
+            return Err(ParseError::InvalidValue.into());
+        }
+        let kind = HitObjectKind::Slider {
+            curve_type,
+            curve_points,
+            slides,
+            length,
+        };
+        // The end time depends on the timing points and is filled in later
+        (kind, time)
+    } else if object_type & 8 != 0 {
+        (HitObjectKind::Spinner, parse_value::<f64>(split.next())? as i64)
+    } else if object_type & 128 != 0 {
+        let end_time = split.next().and_then(|params| params.split(':').next());
+        (HitObjectKind::Hold, parse_value::<f64>(end_time)? as i64)
+    } else {
+        (HitObjectKind::Circle, time)
+    };
+
+    Ok(HitObject {
+        x,
+        y,
+        time,
+        end_time,
+        new_combo: object_type & 4 != 0,
+        kind,
+    })
+}
+
+fn parse_timing_point(line: &str) -> Result<TimingPoint, ReplayDataError<'static>> {
+    let split: Vec<&str> = line.split(',').collect();
+    let time = parse_value(split.first().copied())?;
+    let beat_length = parse_value(split.get(1).copied())?;
+    let uninherited = split.get(6).map_or(Ok(1), |v| parse_value::<u8>(Some(v)))? == 1;
+    let effects = split.get(7).map_or(Ok(0), |v| parse_value::<u32>(Some(v)))?;
+
+    Ok(TimingPoint {
+        time,
+        beat_length,
+        uninherited,
+        kiai: effects & 1 != 0,
+    })
+}
+
+impl Beatmap {
+    /// Parse the contents of an `.osu` file.
+    ///
+    /// Storyboard, colour and editor information is ignored.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::beatmap::Beatmap;
+    ///
+    /// let slider = |length| format!("[HitObjects]\n0,0,1000,2,0,L|100:0,1,{}\n", length);
+    /// assert!(Beatmap::parse(&slider("100")).is_ok());
+    /// assert!(Beatmap::parse(&slider("-5")).is_err());
+    /// assert!(Beatmap::parse(&slider("NaN")).is_err());
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if a required value is missing or invalid, e.g. a negative or
+    /// non-finite slider length.
+    pub fn parse(input: &str) -> Result<Self, ReplayDataError<'static>> {
+        let mut beatmap = Beatmap {
+            stack_leniency: crate::game_math::DEFAULT_STACK_LENIENCY,
+            ..Default::default()
+        };
+        let mut section = "";
+
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = &line[1..line.len() - 1];
+                continue;
+            }
+
+            match section {
+                "General" | "Metadata" | "Difficulty" => {
+                    let Some((key, raw_value)) = line.split_once(':') else {
+                        continue;
+                    };
+                    let value = Some(raw_value);
+                    let difficulty = &mut beatmap.difficulty;
+                    match key.trim() {
+                        "Mode" => beatmap.game_mode = GameMode::try_from(parse_value::<u8>(value)?)?,
+                        "StackLeniency" => beatmap.stack_leniency = parse_value(value)?,
+                        "Title" => beatmap.title = raw_value.trim().to_string(),
+                        "Artist" => beatmap.artist = raw_value.trim().to_string(),
+                        "Version" => beatmap.version = raw_value.trim().to_string(),
+                        "BeatmapID" => beatmap.beatmap_id = parse_value(value)?,
+                        "BeatmapSetID" => beatmap.beatmap_set_id = parse_value::<i64>(value)?.max(0) as u32,
+                        "HPDrainRate" => difficulty.hp_drain_rate = parse_value(value)?,
+                        "CircleSize" => difficulty.circle_size = parse_value(value)?,
+                        "OverallDifficulty" => {
+                            difficulty.overall_difficulty = parse_value(value)?;
+                            // Old beatmaps have no approach rate and use the overall difficulty instead
+                            if !input.contains("ApproachRate:") {
+                                difficulty.approach_rate = difficulty.overall_difficulty;
+                            }
+                        }
+                        "ApproachRate" => difficulty.approach_rate = parse_value(value)?,
+                        "SliderMultiplier" => difficulty.slider_multiplier = parse_value(value)?,
+                        "SliderTickRate" => difficulty.slider_tick_rate = parse_value(value)?,
+                        _ => {}
+                    }
+                }
+                "Events" => {
+                    let mut split = line.split(',');
+                    if matches!(split.next(), Some("2" | "Break")) {
+                        beatmap.breaks.push(Break {
+                            start_time: parse_value::<f64>(split.next())? as i64,
+                            end_time: parse_value::<f64>(split.next())? as i64,
+                        });
+                    }
+                }
+                "TimingPoints" => beatmap.timing_points.push(parse_timing_point(line)?),
+                "HitObjects" => beatmap.hit_objects.push(parse_hit_object(line)?),
+                _ => {}
+            }
+        }
+
+        beatmap.timing_points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        beatmap.hit_objects.sort_by_key(|object| object.time);
+        beatmap.compute_slider_end_times();
+
+        Ok(beatmap)
+    }
+
+    /// Read and parse an `.osu` file from disk.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read or the beatmap cannot be parsed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ReplayDataError<'static>> {
+        let input = std::fs::read(path)?;
+        let input = String::from_utf8_lossy(&input);
+
+        Beatmap::parse(&input)
+    }
+
+    /// Beat length in milliseconds and slider velocity multiplier active at the given time.
+    pub fn timing_at(&self, time: f64) -> (f64, f64) {
+        let mut beat_length = self
+            .timing_points
+            .iter()
+            .find(|point| point.uninherited)
+            .map_or(1000.0, |point| point.beat_length);
+        let mut velocity = 1.0;
+
+        for point in self.timing_points.iter().take_while(|point| point.time <= time) {
+            if point.uninherited {
+                beat_length = point.beat_length;
+                velocity = 1.0;
+            } else if point.beat_length < 0.0 {
+                velocity = (-100.0 / point.beat_length).clamp(0.1, 10.0);
+            }
+        }
+
+        (beat_length, velocity)
+    }
+
+    /// Whether the given time lies inside a kiai section.
+    pub fn is_kiai(&self, time: f64) -> bool {
+        self.timing_points
+            .iter()
+            .take_while(|point| point.time <= time)
+            .last()
+            .is_some_and(|point| point.kiai)
+    }
+
+    fn compute_slider_end_times(&mut self) {
+        let mut end_times = Vec::with_capacity(self.hit_objects.len());
+        for object in &self.hit_objects {
+            if let HitObjectKind::Slider { slides, length, .. } = object.kind {
+                let (beat_length, velocity) = self.timing_at(object.time as f64);
+                let pixels_per_beat = self.difficulty.slider_multiplier * 100.0 * velocity;
+                let duration = f64::from(length) / pixels_per_beat * beat_length * f64::from(slides);
+                end_times.push(object.time + duration as i64);
+            } else {
+                end_times.push(object.end_time);
+            }
+        }
+
+        for (object, end_time) in self.hit_objects.iter_mut().zip(end_times) {
+            object.end_time = end_time;
+        }
+    }
+
+    /// Apply the effects of the given mods to the beatmap.
+    ///
+    /// Hard Rock and Easy change the difficulty settings, Hard Rock additionally flips
+    /// all hit objects vertically, matching the coordinates recorded in the replay.
+    pub fn with_mods(mut self, mods: Mods) -> Self {
+        self.difficulty = self.difficulty.with_mods(mods);
+
+        if mods.contains(Mods::HARD_ROCK) {
+            for object in &mut self.hit_objects {
+                object.y = PLAYFIELD_HEIGHT - object.y;
+                if let HitObjectKind::Slider { curve_points, .. } = &mut object.kind {
+                    for point in curve_points {
+                        point.1 = PLAYFIELD_HEIGHT - point.1;
+                    }
+                }
+            }
+        }
+
+        self
+    }
+}
@@ -0,0 +1,138 @@
+//! Command line tool for inspecting osu! replays.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use osu_replay_parser::beatmap::Beatmap;
+use osu_replay_parser::plot::{self, PlotOptions, PlotStyle};
+use osu_replay_parser::Replay;
+
+const USAGE: &str = "\
+Usage: osr plot <replay.osr> [options]
+
+Plot the cursor of a replay as a PNG or SVG image.
+
+Options:
+    --path              Plot the cursor path and key presses (default)
+    --heatmap           Plot where the cursor spent its time
+    --out <image>       Output file, SVG if it ends with .svg, <replay>.png by default
+    --beatmap <map.osu> Draw the hit objects of the beatmap
+    --width <pixels>    Width of the image, 1024 by default
+    --from <ms>         Only plot frames after this time
+    --to <ms>           Only plot frames before this time";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("plot") => parse_plot_args(&args[1..]).and_then(|args| run_plot(&args)),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(format!("Missing or unknown command\n\n{}", USAGE)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct PlotArgs {
+    replay: PathBuf,
+    out: Option<PathBuf>,
+    beatmap: Option<PathBuf>,
+    options: PlotOptions,
+    from: Option<i64>,
+    to: Option<i64>,
+}
+
+fn parse_plot_args(args: &[String]) -> Result<PlotArgs, String> {
+    let mut replay = None;
+    let mut out = None;
+    let mut beatmap = None;
+    let mut options = PlotOptions::default();
+    let (mut from, mut to) = (None, None);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value of {}\n\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--path" => options.style = PlotStyle::Path,
+            "--heatmap" => options.style = PlotStyle::Heatmap,
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--beatmap" => beatmap = Some(PathBuf::from(value()?)),
+            "--width" => options.width = parse_number(arg, value()?)?,
+            "--from" => from = Some(parse_number(arg, value()?)?),
+            "--to" => to = Some(parse_number(arg, value()?)?),
+            _ if arg.starts_with('-') || replay.is_some() => {
+                return Err(format!("Unexpected argument {}\n\n{}", arg, USAGE))
+            }
+            _ => replay = Some(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(PlotArgs {
+        replay: replay.ok_or_else(|| format!("Missing replay file\n\n{}", USAGE))?,
+        out,
+        beatmap,
+        options,
+        from,
+        to,
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value of {}: {}", arg, value))
+}
+
+fn run_plot(args: &PlotArgs) -> Result<(), String> {
+    let replay = Replay::from_file(&args.replay)
+        .map_err(|e| format!("Error reading {}: {:?}", args.replay.display(), e))?;
+    let mods = replay.mods();
+    let frames = replay
+        .get_actions()
+        .map_err(|e| format!("Error decoding {}: {:?}", args.replay.display(), e))?;
+    let beatmap = args
+        .beatmap
+        .as_ref()
+        .map(|path| {
+            Beatmap::from_file(path)
+                .map(|beatmap| beatmap.with_mods(mods))
+                .map_err(|e| format!("Error reading {}: {:?}", path.display(), e))
+        })
+        .transpose()?;
+
+    let mut options = args.options.clone();
+    if options.width == 0 {
+        return Err("The width must be positive".to_string());
+    }
+    if args.from.is_some() || args.to.is_some() {
+        options.time_range = Some((args.from.unwrap_or(i64::MIN), args.to.unwrap_or(i64::MAX)));
+    }
+
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| args.replay.with_extension("png"));
+    let image = if is_svg(&out) {
+        plot::svg(&frames, beatmap.as_ref(), &options).map(String::into_bytes)
+    } else {
+        plot::png(&frames, beatmap.as_ref(), &options)
+    }
+    .map_err(|e| format!("Error plotting {}: {:?}", args.replay.display(), e))?;
+    std::fs::write(&out, image).map_err(|e| format!("Error writing {}: {}", out.display(), e))
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
@@ -0,0 +1,179 @@
+use std::fmt::{self, Formatter};
+
+use crate::frames::{CursorState, Timeline};
+use crate::hash::{sha256, to_hex};
+use crate::replay::{Replay, ReplayData};
+
+/// Several replays sampled on a common timeline.
+#[derive(Debug, Default, Clone)]
+pub struct Alignment {
+    /// Sample times in milliseconds of beatmap time.
+    pub times: Vec<f64>,
+    /// Cursor states of each replay at the sample times, in the order the replays were given.
+    ///
+    /// A state is `None` where the replay has no recorded frames, e.g. before its first or after its last frame.
+    pub streams: Vec<Vec<Option<CursorState>>>,
+}
+
+/// Align several replays of the same beatmap onto a common timeline, sampled every `interval` milliseconds.
+///
+/// Frame times are recorded in beatmap time, so replays with different speed mods line up without
+/// conversion; use [`Mods::speed_multiplier`](crate::replay::Mods::speed_multiplier) to convert the
+/// timeline to playback time of a specific replay. Skipped intros are not interpolated, the cursor
+/// stays at the last position before the skip.
+///
+/// # Panics
+/// Panics if `interval` is not positive.
+/// # Example
+/// ```
+/// use osu_replay_parser::{compare, ReplayData};
+///
+/// let a = [ReplayData { time: 0, x: 0.0, y: 0.0, keys: 0 }, ReplayData { time: 100, x: 100.0, y: 0.0, keys: 0 }];
+/// let b = [ReplayData { time: 50, x: 0.0, y: 0.0, keys: 0 }, ReplayData { time: 100, x: 0.0, y: 100.0, keys: 0 }];
+///
+/// let alignment = compare::align(&[&a, &b], 50.0);
+/// assert_eq!(alignment.times, [0.0, 50.0, 100.0, 150.0]);
+/// assert_eq!(alignment.streams[0][1].unwrap().x, 50.0);
+/// assert!(alignment.streams[1][0].is_none());
+/// ```
+pub fn align(replays: &[&[ReplayData]], interval: f64) -> Alignment {
+    assert!(interval > 0.0, "interval must be positive");
+
+    let timelines: Vec<Timeline> = replays.iter().map(|frames| Timeline::new(frames)).collect();
+    let start = timelines.iter().filter_map(Timeline::start_time).min();
+    let end = timelines.iter().filter_map(Timeline::end_time).max();
+    let (Some(start), Some(end)) = (start, end) else {
+        return Alignment {
+            times: Vec::new(),
+            streams: vec![Vec::new(); replays.len()],
+        };
+    };
+
+    let samples = ((end - start) as f64 / interval).floor() as usize + 1;
+    let times: Vec<f64> = (0..samples).map(|i| start as f64 + i as f64 * interval).collect();
+    let streams = timelines
+        .iter()
+        .map(|timeline| {
+            let (Some(first), Some(last)) = (timeline.start_time(), timeline.end_time()) else {
+                return vec![None; samples];
+            };
+            times
+                .iter()
+                .map(|&time| {
+                    if time < first as f64 || time > last as f64 {
+                        None
+                    } else {
+                        timeline.state_at(time)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    Alignment { times, streams }
+}
+
+/// A header field that differs between two replays, see [`Replay::meta_diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// Name of the field in [`Replay`].
+    pub field: &'static str,
+    /// Value in the first replay.
+    pub left: String,
+    /// Value in the second replay.
+    pub right: String,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.left, self.right)
+    }
+}
+
+/// Header fields that differ between two replays.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MetaDiff {
+    /// The differing fields, in the order they appear in the replay file.
+    pub fields: Vec<FieldDiff>,
+}
+
+impl MetaDiff {
+    /// Whether all header fields are equal.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Whether the field with the given name differs.
+    pub fn contains(&self, field: &str) -> bool {
+        self.fields.iter().any(|diff| diff.field == field)
+    }
+
+    /// Whether only the given fields differ, e.g. fields expected to change on re-submission.
+    pub fn only_in(&self, fields: &[&str]) -> bool {
+        self.fields.iter().all(|diff| fields.contains(&diff.field))
+    }
+}
+
+impl Replay {
+    /// Compare the header fields of two replays, ignoring the frames.
+    ///
+    /// The osu!lazer trailer is compared byte-wise and reported by its SHA-256 hash.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let original = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let mut resubmitted = original.clone();
+    /// resubmitted.time_stamp += 1;
+    ///
+    /// let diff = original.meta_diff(&resubmitted);
+    /// assert!(diff.contains("time_stamp"));
+    /// assert!(diff.only_in(&["time_stamp", "online_score_id"]));
+    /// ```
+    pub fn meta_diff(&self, other: &Replay) -> MetaDiff {
+        let mut fields = Vec::new();
+        let mut compare = |field: &'static str, left: String, right: String| {
+            if left != right {
+                fields.push(FieldDiff { field, left, right });
+            }
+        };
+        macro_rules! compare {
+            ($($field:ident),*) => {
+                $(compare(
+                    stringify!($field),
+                    format!("{:?}", self.$field),
+                    format!("{:?}", other.$field),
+                );)*
+            };
+        }
+
+        compare!(
+            game_mode,
+            version,
+            beatmap_md5,
+            player_name,
+            replay_md5,
+            n300,
+            n100,
+            n50,
+            n_geki,
+            n_katu,
+            n_miss,
+            total_score,
+            greatest_combo,
+            perfect,
+            mods,
+            life_bar,
+            time_stamp,
+            online_score_id,
+            additional_mod_info
+        );
+        compare(
+            "lazer_data",
+            format!("{:?}", self.lazer_data.as_deref().map(|data| to_hex(&sha256(data)))),
+            format!("{:?}", other.lazer_data.as_deref().map(|data| to_hex(&sha256(data)))),
+        );
+
+        MetaDiff { fields }
+    }
+}
@@ -0,0 +1,571 @@
+pub(crate) mod encoder;
+
+use std::io::Write;
+
+use lzma_rs::decompress::{Options, Stream};
+use lzma_rs::lzma_decompress_with_options;
+
+use crate::decoder::{looks_like_lzma, DecodeLimits};
+use crate::errors::{FrameError, LimitExceeded, ParseError, ReplayDataError};
+use crate::replay::{Replay, ReplayData};
+use crate::writer::encode_frames;
+
+/// Size of the header of an LZMA stream: properties byte, dictionary size and uncompressed size.
+pub const LZMA_HEADER_SIZE: usize = 13;
+
+/// Properties stored in the header of an LZMA stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LzmaProperties {
+    /// Number of literal context bits.
+    pub lc: u8,
+    /// Number of literal position bits.
+    pub lp: u8,
+    /// Number of position bits.
+    pub pb: u8,
+    /// Dictionary size in bytes.
+    pub dict_size: u32,
+    /// Uncompressed size, `None` if the stream is terminated by an end marker instead.
+    pub unpacked_size: Option<u64>,
+}
+
+impl LzmaProperties {
+    /// Read the properties from the header of an LZMA stream.
+    ///
+    /// Returns `None` if the data is shorter than [`LZMA_HEADER_SIZE`] or the properties byte is invalid.
+    pub fn from_header(data: &[u8]) -> Option<Self> {
+        let header = data.get(..LZMA_HEADER_SIZE)?;
+        let mut props = header[0];
+        if props >= 9 * 5 * 5 {
+            return None;
+        }
+        let lc = props % 9;
+        props /= 9;
+        let lp = props % 5;
+        let pb = props / 5;
+        let dict_size = u32::from_le_bytes(header[1..5].try_into().ok()?);
+        let unpacked_size = u64::from_le_bytes(header[5..13].try_into().ok()?);
+
+        Some(LzmaProperties {
+            lc,
+            lp,
+            pb,
+            dict_size,
+            unpacked_size: (unpacked_size != u64::MAX).then_some(unpacked_size),
+        })
+    }
+}
+
+/// Estimated size of the compressed replay data when re-encoded with different coordinate
+/// precision and LZMA settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReencodeEstimate {
+    /// Number of decimal places the coordinates are rounded to, `None` for full precision.
+    pub precision: Option<usize>,
+    /// Properties of the re-encoded LZMA stream.
+    pub properties: LzmaProperties,
+    /// Matches at least this long were taken by the encoder without searching further.
+    pub nice_len: usize,
+    /// Size of the re-encoded compressed data in bytes.
+    pub compressed_size: usize,
+}
+
+/// Size and compression information of the compressed replay data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionInfo {
+    /// Size of the compressed data in bytes.
+    pub compressed_size: usize,
+    /// Size of the decompressed frame text in bytes.
+    pub decompressed_size: usize,
+    /// Compressed size divided by decompressed size.
+    pub ratio: f64,
+    /// Properties of the LZMA stream.
+    pub properties: LzmaProperties,
+    /// Compressed size when re-encoded with the encoder of [`Replay::reencode_optimized`].
+    ///
+    /// The full precision frames are estimated under every parameter set tried by
+    /// [`Replay::reencode_optimized`], the frames with rounded coordinates under the parameter
+    /// set that is smallest at full precision.
+    pub reencode_estimates: Vec<ReencodeEstimate>,
+}
+
+/// Literal context bits, literal position bits, position bits and nice match length of the
+/// parameter sets tried by [`Replay::reencode_optimized`], starting with the LZMA defaults
+/// osu! uses.
+const OPTIMIZE_PARAMETERS: [(u32, u32, u32, usize); 5] = [
+    (3, 0, 2, 32),
+    (0, 0, 0, 32),
+    (0, 0, 2, 32),
+    (0, 2, 2, 32),
+    (0, 0, 0, 64),
+];
+
+/// Encoder settings of the parameter sets of [`OPTIMIZE_PARAMETERS`] for `len` bytes of data,
+/// with a dictionary holding all of the data.
+fn optimize_settings(len: usize) -> impl Iterator<Item = encoder::Settings> {
+    let dict_size = len.next_power_of_two().clamp(1 << 12, 1 << 26) as u32;
+    OPTIMIZE_PARAMETERS
+        .into_iter()
+        .map(move |(lc, lp, pb, nice_len)| encoder::Settings {
+            lc,
+            lp,
+            pb,
+            dict_size,
+            nice_len,
+            depth: 48,
+        })
+}
+
+/// Result of [`Replay::reencode_optimized`].
+#[derive(Debug, Clone)]
+pub struct OptimizedReencode {
+    /// The replay with the smallest compressed data found.
+    pub replay: Replay,
+    /// Size of the original compressed data in bytes.
+    pub original_size: usize,
+    /// Size of the optimized compressed data in bytes, at most the original size.
+    pub optimized_size: usize,
+    /// Properties of the new LZMA stream, `None` if the original data was already the smallest.
+    pub properties: Option<LzmaProperties>,
+}
+
+impl OptimizedReencode {
+    /// Number of bytes saved.
+    pub fn savings(&self) -> usize {
+        self.original_size - self.optimized_size
+    }
+
+    /// Fraction of the original size saved, in the range `0.0..=1.0`.
+    pub fn savings_ratio(&self) -> f64 {
+        self.savings() as f64 / self.original_size.max(1) as f64
+    }
+}
+
+/// Rounded coordinate precisions used for [`CompressionInfo::reencode_estimates`].
+const ESTIMATE_PRECISIONS: [Option<usize>; 2] = [Some(2), Some(0)];
+
+/// Estimate the compressed size of the frame text under the encoder settings.
+fn estimate(text: &str, precision: Option<usize>, settings: &encoder::Settings) -> ReencodeEstimate {
+    let encoded = encoder::encode(text.as_bytes(), settings);
+    ReencodeEstimate {
+        precision,
+        properties: LzmaProperties::from_header(&encoded).expect("Encoder writes a valid header"),
+        nice_len: settings.nice_len,
+        compressed_size: encoded.len(),
+    }
+}
+
+impl Replay {
+    /// Report compressed and decompressed sizes, LZMA properties and estimated re-encode sizes of the frame data.
+    ///
+    /// The estimates encode the frames seven times, see [`Replay::reencode_optimized`] for the
+    /// time this takes.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let info = replay.frame_compression_info().expect("Error reading compressed data");
+    /// assert_eq!(info.compressed_size, replay.compressed_data.len());
+    /// assert!(info.decompressed_size > info.compressed_size);
+    ///
+    /// // The stream written by osu! is about as small as a re-encoded one
+    /// let full_precision = info.reencode_estimates.iter().filter(|estimate| estimate.precision.is_none());
+    /// let smallest = full_precision.map(|estimate| estimate.compressed_size).min().unwrap();
+    /// assert!(smallest.abs_diff(info.compressed_size) < info.compressed_size / 10);
+    ///
+    /// // Rounding the coordinates saves more
+    /// let rounded = info.reencode_estimates.iter().find(|estimate| estimate.precision == Some(0));
+    /// assert!(rounded.unwrap().compressed_size < smallest);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed data cannot be decoded.
+    pub fn frame_compression_info(&self) -> Result<CompressionInfo, ReplayDataError<'static>> {
+        let properties = LzmaProperties::from_header(&self.compressed_data)
+            .ok_or(ParseError::InvalidValue)?;
+        let decompressed_data = Replay::decompress_lzma(&self.compressed_data)?;
+        let decompressed_size = decompressed_data.len();
+        let mut frames = Vec::new();
+        Replay::parse_frames_into(&decompressed_data, &mut frames, |_, _| {})?;
+
+        let mut reencode_estimates: Vec<ReencodeEstimate> = optimize_settings(decompressed_size)
+            .map(|settings| estimate(&decompressed_data, None, &settings))
+            .collect();
+        let best = optimize_settings(decompressed_size)
+            .zip(&reencode_estimates)
+            .min_by_key(|(_, estimate)| estimate.compressed_size)
+            .map(|(settings, _)| settings)
+            .expect("There are several parameter sets");
+        for precision in ESTIMATE_PRECISIONS {
+            let text = encode_frames(&frames, precision);
+            reencode_estimates.push(estimate(&text, precision, &best));
+        }
+
+        Ok(CompressionInfo {
+            compressed_size: self.compressed_data.len(),
+            decompressed_size,
+            ratio: self.compressed_data.len() as f64 / decompressed_size.max(1) as f64,
+            properties,
+            reencode_estimates,
+        })
+    }
+
+    /// Re-encode the compressed replay data with a full LZMA encoder under several parameter
+    /// sets and keep the smallest result if it is smaller, e.g. to shrink replays written by this
+    /// crate before archiving them.
+    ///
+    /// Five sets of literal context, literal position and position bits and nice match lengths
+    /// are tried, each taking about 0.3 s for `assets/replay.osr` in release builds.
+    /// [`writer::compress`](crate::writer::compress) does not search for matches, the streams it
+    /// writes shrink to less than half, e.g. from 117 KB to 54 KB for `assets/replay.osr`, while
+    /// the parameter sets differ by less than 1%. Streams written by osu! are already as small
+    /// and are kept.
+    ///
+    /// The decompressed frame text is kept byte for byte and a new stream is decoded again
+    /// before it is accepted, otherwise the next smallest one is tried. Like the streams written by osu!, it stores the uncompressed size in
+    /// the header, so osu!stable and osu!lazer can read it.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::writer::compress_frames;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let mut actions = replay.take_actions().expect("Error getting actions");
+    /// actions.truncate(1500);
+    /// replay.compressed_data = compress_frames(&actions);
+    ///
+    /// let optimized = replay.reencode_optimized().expect("Error re-encoding replay");
+    /// assert!(optimized.savings_ratio() > 0.3);
+    /// assert_eq!(optimized.replay.compressed_data.len(), optimized.optimized_size);
+    ///
+    /// // The frame text compresses best without literal context and position bits
+    /// let properties = optimized.properties.unwrap();
+    /// assert_eq!((properties.lc, properties.lp, properties.pb), (0, 0, 0));
+    /// assert_eq!(optimized.replay.get_actions().unwrap(), actions);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed data cannot be decoded.
+    pub fn reencode_optimized(&self) -> Result<OptimizedReencode, ReplayDataError<'static>> {
+        let data = Replay::decompress_lzma(&self.compressed_data)?.into_bytes();
+        let mut candidates: Vec<Vec<u8>> = optimize_settings(data.len())
+            .map(|settings| encoder::encode(&data, &settings))
+            .filter(|candidate| candidate.len() < self.compressed_data.len())
+            .collect();
+        candidates.sort_by_key(Vec::len);
+        let best = candidates.into_iter().find(|candidate| {
+            Replay::decompress_lzma(candidate).is_ok_and(|decoded| decoded.as_bytes() == data)
+        });
+
+        let original_size = self.compressed_data.len();
+        let mut replay = self.clone();
+        let properties = best.map(|candidate| {
+            replay.compressed_data = candidate;
+            LzmaProperties::from_header(&replay.compressed_data)
+                .expect("Encoder writes a valid header")
+        });
+        Ok(OptimizedReencode {
+            optimized_size: replay.compressed_data.len(),
+            replay,
+            original_size,
+            properties,
+        })
+    }
+}
+
+/// An LZMA stream found in corrupted compressed replay data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveredStream {
+    /// Offset of the stream header in the compressed data.
+    pub offset: usize,
+    /// Number of compressed bytes that could be decoded, including the header.
+    pub decoded_length: usize,
+    /// Number of decompressed bytes.
+    pub decompressed_length: usize,
+    /// Decompressed size declared in the stream header, `None` if the stream uses an end marker instead.
+    pub declared_length: Option<u64>,
+    /// Whether the stream was decoded up to its end.
+    pub complete: bool,
+    /// Number of frames recovered from the stream.
+    pub frames: usize,
+}
+
+/// Frames recovered from corrupted compressed replay data, see [`recover_frames`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameRecovery {
+    /// The recovered frames of all streams in order.
+    pub frames: Vec<ReplayData>,
+    /// The LZMA streams frames were recovered from.
+    pub streams: Vec<RecoveredStream>,
+    /// Percentage of the frame data that was recovered, in the range `0.0..=100.0`.
+    ///
+    /// Compares the decompressed size of each stream with the size declared in its header.
+    /// Streams without a declared size are assumed to be fully recovered.
+    pub coverage: f64,
+}
+
+fn incomplete_stream<W: Write>(options: &Options, output: W) -> Stream<W> {
+    let options = Options {
+        allow_incomplete: true,
+        ..*options
+    };
+    Stream::new_with_options(&options, output)
+}
+
+/// Collects decompressed data, refusing to grow beyond a limit.
+struct BoundedWriter<W> {
+    output: W,
+    remaining: u64,
+    exceeded: bool,
+}
+
+impl<W> BoundedWriter<W> {
+    fn new(output: W, remaining: u64) -> Self {
+        BoundedWriter {
+            output,
+            remaining,
+            exceeded: false,
+        }
+    }
+}
+
+impl<W: Write> Write for BoundedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            self.exceeded = true;
+            return Err(std::io::Error::other("decompressed size limit exceeded"));
+        }
+        self.remaining -= buf.len() as u64;
+        self.output.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Whether decoding failed because the dictionary buffer reached the memory limit.
+fn is_memlimit_error(error: &impl std::fmt::Display) -> bool {
+    error.to_string().contains("exceeded memory limit")
+}
+
+/// Decode a possibly truncated stream, `None` if not even the prefix is valid.
+fn decode_prefix(data: &[u8], options: &Options) -> Option<Vec<u8>> {
+    let mut stream = incomplete_stream(options, Vec::new());
+    stream.write_all(data).ok()?;
+    stream.finish().ok()
+}
+
+/// Decode as much of the stream at the start of `data` as possible, decompressing at most
+/// `remaining` bytes.
+///
+/// Returns the decompressed data, the number of decoded compressed bytes and whether the stream
+/// was complete, `None` if the stream decompresses to more than `remaining` bytes.
+fn decode_stream(
+    data: &[u8],
+    options: &Options,
+    remaining: u64,
+) -> Option<(Vec<u8>, usize, bool)> {
+    let mut input = data;
+    let mut writer = BoundedWriter::new(Vec::new(), remaining);
+    let decoded = lzma_decompress_with_options(&mut input, &mut writer, options);
+    if writer.exceeded || decoded.as_ref().is_err_and(is_memlimit_error) {
+        return None;
+    }
+    if decoded.is_ok() {
+        return Some((writer.output, data.len() - input.len(), true));
+    }
+
+    // The stream decoder discards its output on errors, so it is first fed byte by byte to find
+    // the longest decodable prefix, which is then decoded again
+    let mut writer = BoundedWriter::new(std::io::sink(), remaining);
+    let mut stream = incomplete_stream(options, &mut writer);
+    let length = data
+        .iter()
+        .position(|&byte| stream.write_all(&[byte]).is_err())
+        .unwrap_or(data.len());
+    drop(stream);
+    if writer.exceeded {
+        return None;
+    }
+    let decompressed = decode_prefix(&data[..length], options).unwrap_or_default();
+    Some((decompressed, length, false))
+}
+
+/// Length of the complete LZMA stream at the start of `data`, found by decompressing it within
+/// `limits`. `None` if the stream is invalid, truncated or exceeds the limits.
+pub(crate) fn complete_stream_length(data: &[u8], limits: &DecodeLimits) -> Option<usize> {
+    limits.check_declared_size(data).ok()?;
+    let remaining = limits.max_decompressed_size.unwrap_or(u64::MAX);
+    let mut input = data;
+    let mut writer = BoundedWriter::new(std::io::sink(), remaining);
+    lzma_decompress_with_options(&mut input, &mut writer, &limits.lzma_options()).ok()?;
+    Some(data.len() - input.len())
+}
+
+/// Parse the frames of a decompressed stream, stopping at the first invalid frame or frame longer
+/// than `max_frame_length`.
+///
+/// The last frame of an incomplete stream is dropped, as it may be cut off.
+fn parse_recovered_frames(
+    decompressed: &[u8],
+    complete: bool,
+    max_frame_length: Option<usize>,
+) -> Vec<ReplayData> {
+    let text = String::from_utf8_lossy(decompressed);
+    let mut chunks: Vec<&str> = text.split_terminator(',').collect();
+    if !complete {
+        chunks.pop();
+    }
+
+    chunks
+        .into_iter()
+        .take_while(|chunk| max_frame_length.is_none_or(|limit| chunk.len() <= limit))
+        .map_while(|chunk| Replay::parse_frame(chunk).ok())
+        .collect()
+}
+
+/// Recover the frames of corrupted compressed replay data, e.g. files containing several
+/// concatenated LZMA streams or streams that are cut off.
+///
+/// The data is searched for LZMA stream headers, every stream found is decoded as far as
+/// possible and the frames of all streams are concatenated. Frames after the first invalid frame
+/// of a stream are dropped.
+///
+/// No limits are enforced, use [`recover_frames_with_limits`] for data from untrusted sources.
+/// # Example
+/// ```
+/// use osu_replay_parser::compression::recover_frames;
+/// use osu_replay_parser::{writer, Replay};
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let (first, second) = actions.split_at(6000);
+///
+/// let mut data = writer::compress_frames(first);
+/// let second = writer::compress_frames(second);
+/// // The second stream is cut off in the middle
+/// data.extend_from_slice(&second[..second.len() / 2]);
+///
+/// let recovery = recover_frames(&data);
+/// assert_eq!(recovery.streams.len(), 2);
+/// assert!(recovery.streams[0].complete && !recovery.streams[1].complete);
+/// assert_eq!(recovery.frames[..6000], actions[..6000]);
+/// assert!(recovery.frames.len() > 6000);
+/// assert!(recovery.coverage > 50.0 && recovery.coverage < 100.0);
+/// ```
+pub fn recover_frames(data: &[u8]) -> FrameRecovery {
+    recover_frames_with_limits(data, &DecodeLimits::unlimited())
+        .expect("Recovery without limits cannot exceed a limit")
+}
+
+/// Recover the frames of corrupted compressed replay data like [`recover_frames`], failing as
+/// soon as a limit is exceeded.
+///
+/// The decompressed size and frame count limits apply to all streams together. Streams whose
+/// header declares a size or dictionary size above the decompressed size limit are skipped like
+/// invalid streams, and frames longer than the frame length limit end their stream like invalid
+/// frames.
+/// # Example
+/// ```
+/// use osu_replay_parser::compression::recover_frames_with_limits;
+/// use osu_replay_parser::decoder::DecodeLimits;
+/// use osu_replay_parser::errors::{FrameError, LimitExceeded, ReplayDataError};
+/// use osu_replay_parser::{writer, Replay};
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let mut data = writer::compress_frames(&actions[..6000]);
+/// data.extend_from_slice(&writer::compress_frames(&actions[6000..]));
+///
+/// let recovery = recover_frames_with_limits(&data, &DecodeLimits::untrusted())
+///     .expect("Error recovering frames");
+/// assert_eq!(recovery.frames, actions);
+///
+/// let limits = DecodeLimits { max_frames: Some(8000), ..DecodeLimits::untrusted() };
+/// assert!(matches!(
+///     recover_frames_with_limits(&data, &limits),
+///     Err(ReplayDataError::Frame(FrameError::LimitExceeded(LimitExceeded::Frames { limit: 8000 })))
+/// ));
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if a limit is exceeded.
+pub fn recover_frames_with_limits(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<FrameRecovery, ReplayDataError<'static>> {
+    let options = limits.lzma_options();
+    let mut frames = Vec::new();
+    let mut streams = Vec::new();
+    let (mut recovered, mut expected) = (0, 0);
+    let mut offset = 0;
+
+    while let Some(start) = (offset..data.len()).find(|&i| {
+        looks_like_lzma(&data[i..]) && limits.check_declared_size(&data[i..]).is_ok()
+    }) {
+        let remaining = limits
+            .max_decompressed_size
+            .map_or(u64::MAX, |limit| limit.saturating_sub(recovered));
+        let Some((decompressed, decoded_length, complete)) =
+            decode_stream(&data[start..], &options, remaining)
+        else {
+            let limit = limits.max_decompressed_size.unwrap_or_default();
+            return Err(FrameError::LimitExceeded(LimitExceeded::DecompressedSize { limit }).into());
+        };
+        let stream_frames =
+            parse_recovered_frames(&decompressed, complete, limits.max_frame_length);
+        if stream_frames.is_empty() {
+            // A false positive header or a stream without usable frames
+            offset = start + 1;
+            continue;
+        }
+        if let Some(limit) = limits
+            .max_frames
+            .filter(|&limit| frames.len() + stream_frames.len() > limit)
+        {
+            return Err(FrameError::LimitExceeded(LimitExceeded::Frames { limit }).into());
+        }
+
+        let declared_length = LzmaProperties::from_header(&data[start..])
+            .and_then(|properties| properties.unpacked_size);
+        recovered += decompressed.len() as u64;
+        expected += declared_length.unwrap_or(decompressed.len() as u64);
+        streams.push(RecoveredStream {
+            offset: start,
+            decoded_length,
+            decompressed_length: decompressed.len(),
+            declared_length,
+            complete,
+            frames: stream_frames.len(),
+        });
+        frames.extend(stream_frames);
+        offset = start + decoded_length.max(1);
+    }
+
+    Ok(FrameRecovery {
+        frames,
+        streams,
+        coverage: if expected == 0 {
+            0.0
+        } else {
+            (recovered as f64 / expected as f64 * 100.0).min(100.0)
+        },
+    })
+}
+
+impl Replay {
+    /// Recover as many frames as possible from corrupted compressed replay data, see [`recover_frames`].
+    pub fn recover_actions(&self) -> FrameRecovery {
+        recover_frames(&self.compressed_data)
+    }
+
+    /// Recover as many frames as possible from corrupted compressed replay data, failing as soon
+    /// as a limit is exceeded, see [`recover_frames_with_limits`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if a limit is exceeded.
+    pub fn recover_actions_with_limits(
+        &self,
+        limits: &DecodeLimits,
+    ) -> Result<FrameRecovery, ReplayDataError<'static>> {
+        recover_frames_with_limits(&self.compressed_data, limits)
+    }
+}
@@ -0,0 +1,1075 @@
+//! LZMA encoder with a binary tree match finder and a price based optimal parser, producing
+//! `.lzma` streams as written by osu!.
+
+/// Number of bits of the probabilities of the range coder.
+const PROB_BITS: u32 = 11;
+/// Initial probability, 0.5.
+const PROB_INIT: u16 = 1 << (PROB_BITS - 1);
+/// Number of bits probabilities move towards the coded bit.
+const MOVE_BITS: u32 = 5;
+/// Prices are in 1/16 bits.
+const PRICE_SHIFT: u32 = 4;
+
+const STATES: usize = 12;
+const POS_STATES_MAX: usize = 16;
+const MIN_MATCH_LEN: usize = 2;
+const MAX_MATCH_LEN: usize = 273;
+const END_POS_MODEL_INDEX: u32 = 14;
+const ALIGN_BITS: u32 = 4;
+
+const HASH_BITS: u32 = 16;
+/// Number of positions parsed together, the first half of the parse is coded before the prices
+/// are updated and the next window is parsed.
+const WINDOW_SIZE: usize = 1024;
+
+/// Settings of the encoder, the first four are stored in the header of the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Settings {
+    pub lc: u32,
+    pub lp: u32,
+    pub pb: u32,
+    pub dict_size: u32,
+    /// Matches at least this long are taken without searching further.
+    pub nice_len: usize,
+    /// Number of earlier positions compared per position.
+    pub depth: usize,
+}
+
+/// Sink for the bits of the stream, either the range encoder or a price estimate.
+trait Coder {
+    fn bit(&mut self, prob: &mut u16, bit: u32);
+
+    fn direct_bits(&mut self, value: u32, count: u32);
+
+    /// Encode the bits of `value`, most significant first, in a tree of probabilities.
+    fn tree(&mut self, probs: &mut [u16], bits: u32, value: u32) {
+        let mut index = 1;
+        for i in (0..bits).rev() {
+            let bit = (value >> i) & 1;
+            self.bit(&mut probs[index], bit);
+            index = (index << 1) | bit as usize;
+        }
+    }
+
+    /// Encode the bits of `value`, least significant first, in a tree of probabilities.
+    fn reverse_tree(&mut self, probs: &mut [u16], bits: u32, value: u32) {
+        let mut index = 1;
+        for i in 0..bits {
+            let bit = (value >> i) & 1;
+            self.bit(&mut probs[index], bit);
+            index = (index << 1) | bit as usize;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RangeEncoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    output: Vec<u8>,
+}
+
+impl RangeEncoder {
+    fn new(output: Vec<u8>) -> Self {
+        RangeEncoder {
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            output,
+        }
+    }
+
+    fn shift_low(&mut self) {
+        if self.low < 0xFF00_0000 || self.low > 0xFFFF_FFFF {
+            let carry = (self.low >> 32) as u8;
+            let mut byte = self.cache;
+            loop {
+                self.output.push(byte.wrapping_add(carry));
+                byte = 0xFF;
+                self.cache_size -= 1;
+                if self.cache_size == 0 {
+                    break;
+                }
+            }
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00FF_FFFF) << 8;
+    }
+
+    fn normalize(&mut self) {
+        while self.range < 1 << 24 {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+        self.output
+    }
+}
+
+impl Coder for RangeEncoder {
+    fn bit(&mut self, prob: &mut u16, bit: u32) {
+        let bound = (self.range >> PROB_BITS) * u32::from(*prob);
+        if bit == 0 {
+            self.range = bound;
+            *prob += ((1 << PROB_BITS) - *prob) >> MOVE_BITS;
+        } else {
+            self.low += u64::from(bound);
+            self.range -= bound;
+            *prob -= *prob >> MOVE_BITS;
+        }
+        self.normalize();
+    }
+
+    fn direct_bits(&mut self, value: u32, count: u32) {
+        for i in (0..count).rev() {
+            self.range >>= 1;
+            if (value >> i) & 1 != 0 {
+                self.low += u64::from(self.range);
+            }
+            self.normalize();
+        }
+    }
+}
+
+/// Sums the cost of the coded bits without updating the probabilities.
+struct Price<'a> {
+    table: &'a [u32],
+    total: u32,
+}
+
+impl Price<'_> {
+    /// Cost of a bit with probability `p / 128` in 1/16 bits, indexed by `p`.
+    fn table() -> Vec<u32> {
+        (0..128)
+            .map(|i| (-((i as f64 + 0.5) / 128.0).log2() * f64::from(1 << PRICE_SHIFT)) as u32)
+            .collect()
+    }
+}
+
+impl Coder for Price<'_> {
+    fn bit(&mut self, prob: &mut u16, bit: u32) {
+        let prob = if bit == 0 {
+            *prob
+        } else {
+            (1 << PROB_BITS) - *prob
+        };
+        self.total += self.table[usize::from(prob >> (PROB_BITS - 7))];
+    }
+
+    fn direct_bits(&mut self, _value: u32, count: u32) {
+        self.total += count << PRICE_SHIFT;
+    }
+}
+
+#[derive(Clone)]
+struct LengthEncoder {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 8]; POS_STATES_MAX],
+    mid: [[u16; 8]; POS_STATES_MAX],
+    high: [u16; 256],
+}
+
+impl LengthEncoder {
+    fn new() -> Self {
+        LengthEncoder {
+            choice: PROB_INIT,
+            choice2: PROB_INIT,
+            low: [[PROB_INIT; 8]; POS_STATES_MAX],
+            mid: [[PROB_INIT; 8]; POS_STATES_MAX],
+            high: [PROB_INIT; 256],
+        }
+    }
+
+    fn encode(&mut self, coder: &mut impl Coder, len: usize, pos_state: usize) {
+        let value = (len - MIN_MATCH_LEN) as u32;
+        if value < 8 {
+            coder.bit(&mut self.choice, 0);
+            coder.tree(&mut self.low[pos_state], 3, value);
+        } else if value < 16 {
+            coder.bit(&mut self.choice, 1);
+            coder.bit(&mut self.choice2, 0);
+            coder.tree(&mut self.mid[pos_state], 3, value - 8);
+        } else {
+            coder.bit(&mut self.choice, 1);
+            coder.bit(&mut self.choice2, 1);
+            coder.tree(&mut self.high, 8, value - 16);
+        }
+    }
+}
+
+/// A single symbol of the stream.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Literal,
+    /// A single byte at the last distance.
+    ShortRep,
+    /// A match at one of the last four distances.
+    Rep {
+        index: usize,
+        len: usize,
+    },
+    /// A match at a new distance, `distance` is the offset minus one.
+    Match {
+        distance: usize,
+        len: usize,
+    },
+}
+
+impl Op {
+    fn len(self) -> usize {
+        match self {
+            Op::Literal | Op::ShortRep => 1,
+            Op::Rep { len, .. } | Op::Match { len, .. } => len,
+        }
+    }
+}
+
+/// State and last four distances, they depend on the symbols coded before.
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    state: usize,
+    reps: [usize; 4],
+}
+
+impl Context {
+    fn after(mut self, op: Op) -> Self {
+        let after_literal = self.state < 7;
+        self.state = match op {
+            Op::Literal if self.state < 4 => 0,
+            Op::Literal if self.state < 10 => self.state - 3,
+            Op::Literal => self.state - 6,
+            Op::ShortRep if after_literal => 9,
+            Op::Rep { .. } if after_literal => 8,
+            Op::Match { .. } if after_literal => 7,
+            Op::ShortRep | Op::Rep { .. } => 11,
+            Op::Match { .. } => 10,
+        };
+        match op {
+            Op::Rep { index, .. } => {
+                let distance = self.reps[index];
+                self.reps.copy_within(0..index, 1);
+                self.reps[0] = distance;
+            }
+            Op::Match { distance, .. } => {
+                self.reps.copy_within(0..3, 1);
+                self.reps[0] = distance;
+            }
+            Op::Literal | Op::ShortRep => {}
+        }
+        self
+    }
+}
+
+/// Adaptive probabilities shared with the decoder.
+#[derive(Clone)]
+struct Model {
+    settings: Settings,
+    literal: Vec<u16>,
+    is_match: [u16; STATES * POS_STATES_MAX],
+    is_rep: [u16; STATES],
+    is_rep_g0: [u16; STATES],
+    is_rep_g1: [u16; STATES],
+    is_rep_g2: [u16; STATES],
+    is_rep0_long: [u16; STATES * POS_STATES_MAX],
+    pos_slot: [[u16; 64]; 4],
+    pos: [u16; 115],
+    align: [u16; 1 << ALIGN_BITS],
+    len: LengthEncoder,
+    rep_len: LengthEncoder,
+}
+
+impl Model {
+    fn new(settings: Settings) -> Self {
+        Model {
+            settings,
+            literal: vec![PROB_INIT; 0x300 << (settings.lc + settings.lp)],
+            is_match: [PROB_INIT; STATES * POS_STATES_MAX],
+            is_rep: [PROB_INIT; STATES],
+            is_rep_g0: [PROB_INIT; STATES],
+            is_rep_g1: [PROB_INIT; STATES],
+            is_rep_g2: [PROB_INIT; STATES],
+            is_rep0_long: [PROB_INIT; STATES * POS_STATES_MAX],
+            pos_slot: [[PROB_INIT; 64]; 4],
+            pos: [PROB_INIT; 115],
+            align: [PROB_INIT; 1 << ALIGN_BITS],
+            len: LengthEncoder::new(),
+            rep_len: LengthEncoder::new(),
+        }
+    }
+
+    /// Code the symbol `op` at `pos`, the context is not updated.
+    fn encode(
+        &mut self,
+        coder: &mut impl Coder,
+        data: &[u8],
+        pos: usize,
+        context: Context,
+        op: Op,
+    ) {
+        let Context { state, reps } = context;
+        let pos_state = pos & ((1 << self.settings.pb) - 1);
+        let is_match = &mut self.is_match[(state << 4) + pos_state];
+        let is_rep0_long = &mut self.is_rep0_long[(state << 4) + pos_state];
+
+        match op {
+            Op::Literal => {
+                coder.bit(is_match, 0);
+                // After a match the byte at the last distance is the context until the first difference
+                let match_byte = (state >= 7).then(|| data[pos - reps[0] - 1]);
+                self.literal(coder, data, pos, match_byte);
+            }
+            Op::ShortRep => {
+                coder.bit(is_match, 1);
+                coder.bit(&mut self.is_rep[state], 1);
+                coder.bit(&mut self.is_rep_g0[state], 0);
+                coder.bit(is_rep0_long, 0);
+            }
+            Op::Rep { index, len } => {
+                coder.bit(is_match, 1);
+                coder.bit(&mut self.is_rep[state], 1);
+                if index == 0 {
+                    coder.bit(&mut self.is_rep_g0[state], 0);
+                    coder.bit(is_rep0_long, 1);
+                } else {
+                    coder.bit(&mut self.is_rep_g0[state], 1);
+                    if index == 1 {
+                        coder.bit(&mut self.is_rep_g1[state], 0);
+                    } else {
+                        coder.bit(&mut self.is_rep_g1[state], 1);
+                        coder.bit(&mut self.is_rep_g2[state], index as u32 - 2);
+                    }
+                }
+                self.rep_len.encode(coder, len, pos_state);
+            }
+            Op::Match { distance, len } => {
+                coder.bit(is_match, 1);
+                coder.bit(&mut self.is_rep[state], 0);
+                self.len.encode(coder, len, pos_state);
+                self.distance(coder, distance as u32, len);
+            }
+        }
+    }
+
+    fn literal(&mut self, coder: &mut impl Coder, data: &[u8], pos: usize, match_byte: Option<u8>) {
+        let Settings { lc, lp, .. } = self.settings;
+        let previous = if pos > 0 { data[pos - 1] } else { 0 };
+        let lit_state = ((pos & ((1 << lp) - 1)) << lc) + (usize::from(previous) >> (8 - lc));
+        let probs = &mut self.literal[lit_state * 0x300..(lit_state + 1) * 0x300];
+
+        let byte = u32::from(data[pos]);
+        let mut match_byte = match_byte.map(u32::from);
+        let mut index = 1;
+        for i in (0..8).rev() {
+            let bit = (byte >> i) & 1;
+            match match_byte {
+                Some(matched) => {
+                    let match_bit = (matched >> i) & 1;
+                    coder.bit(&mut probs[((1 + match_bit as usize) << 8) + index], bit);
+                    if match_bit != bit {
+                        match_byte = None;
+                    }
+                }
+                None => coder.bit(&mut probs[index], bit),
+            }
+            index = (index << 1) | bit as usize;
+        }
+    }
+
+    fn distance(&mut self, coder: &mut impl Coder, distance: u32, len: usize) {
+        let slot = pos_slot(distance);
+        let len_state = (len - MIN_MATCH_LEN).min(3);
+        coder.tree(&mut self.pos_slot[len_state], 6, slot);
+        if slot >= 4 {
+            let direct_bits = (slot >> 1) - 1;
+            let base = (2 | (slot & 1)) << direct_bits;
+            let reduced = distance - base;
+            if slot < END_POS_MODEL_INDEX {
+                let offset = (base - slot) as usize;
+                coder.reverse_tree(&mut self.pos[offset..], direct_bits, reduced);
+            } else {
+                coder.direct_bits(reduced >> ALIGN_BITS, direct_bits - ALIGN_BITS);
+                coder.reverse_tree(
+                    &mut self.align,
+                    ALIGN_BITS,
+                    reduced & ((1 << ALIGN_BITS) - 1),
+                );
+            }
+        }
+    }
+
+    /// Price of coding `op` at `pos` with the current probabilities.
+    fn price(&mut self, table: &[u32], data: &[u8], pos: usize, context: Context, op: Op) -> u32 {
+        let mut price = Price { table, total: 0 };
+        self.encode(&mut price, data, pos, context, op);
+        price.total
+    }
+}
+
+/// Slot of a distance, its highest two bits.
+fn pos_slot(distance: u32) -> u32 {
+    if distance < 4 {
+        return distance;
+    }
+    let bits = 31 - distance.leading_zeros();
+    (bits << 1) | ((distance >> (bits - 1)) & 1)
+}
+
+/// Binary trees over the positions of the input, rooted at their first three bytes and sorted by
+/// the following bytes, and the last position of every pair of bytes.
+struct MatchFinder {
+    head: Vec<u32>,
+    /// Older positions sorting before and after every position.
+    tree: Vec<u32>,
+    pairs: Vec<u32>,
+}
+
+impl MatchFinder {
+    fn new(len: usize) -> Self {
+        MatchFinder {
+            head: vec![u32::MAX; 1 << HASH_BITS],
+            tree: vec![u32::MAX; 2 * len],
+            pairs: vec![u32::MAX; 1 << 16],
+        }
+    }
+
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let value =
+            u32::from(data[pos]) | u32::from(data[pos + 1]) << 8 | u32::from(data[pos + 2]) << 16;
+        (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    /// Append the earlier matches of the data at `pos` as offset minus one and length, then
+    /// insert `pos` as the new root of its tree.
+    ///
+    /// Every match is longer and further away than the one before.
+    fn matches(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        settings: &Settings,
+        matches: &mut Vec<(usize, usize)>,
+    ) {
+        if self.tree.len() < 2 * (pos + 1) {
+            self.tree.resize(2 * (pos + 1), u32::MAX);
+        }
+        let max_len = (data.len() - pos).min(MAX_MATCH_LEN);
+        let dict_size = settings.dict_size as usize;
+        if pos + 2 > data.len() {
+            return;
+        }
+        let pair = usize::from(data[pos]) | usize::from(data[pos + 1]) << 8;
+        let closest = self.pairs[pair];
+        self.pairs[pair] = pos as u32;
+        let mut best = 1;
+        if closest != u32::MAX && pos - closest as usize <= dict_size {
+            let start = closest as usize;
+            best = match_len(data, start, pos, max_len);
+            matches.push((pos - start - 1, best));
+        }
+        if pos + 3 > data.len() {
+            return;
+        }
+
+        let hash = MatchFinder::hash(data, pos);
+        let mut candidate = self.head[hash];
+        self.head[hash] = pos as u32;
+        // The tree is only sorted up to `limit` bytes, longer matches are extended afterwards
+        let limit = settings.nice_len.min(max_len);
+        let (mut smaller, mut larger) = (2 * pos, 2 * pos + 1);
+        let (mut smaller_len, mut larger_len) = (0, 0);
+        let mut depth = settings.depth;
+        loop {
+            if candidate == u32::MAX || depth == 0 || pos - candidate as usize > dict_size {
+                self.tree[smaller] = u32::MAX;
+                self.tree[larger] = u32::MAX;
+                break;
+            }
+            depth -= 1;
+
+            let start = candidate as usize;
+            // Both subtrees share a prefix of at least this length with the data at `pos`
+            let mut len = smaller_len.min(larger_len);
+            while len < limit && data[start + len] == data[pos + len] {
+                len += 1;
+            }
+            if len == limit {
+                if len > best {
+                    matches.push((pos - start - 1, match_len(data, start, pos, max_len)));
+                }
+                self.tree[smaller] = self.tree[2 * start];
+                self.tree[larger] = self.tree[2 * start + 1];
+                break;
+            }
+            if len > best {
+                best = len;
+                matches.push((pos - start - 1, len));
+            }
+
+            if data[start + len] < data[pos + len] {
+                self.tree[smaller] = candidate;
+                smaller = 2 * start + 1;
+                smaller_len = len;
+                candidate = self.tree[smaller];
+            } else {
+                self.tree[larger] = candidate;
+                larger = 2 * start;
+                larger_len = len;
+                candidate = self.tree[larger];
+            }
+        }
+    }
+
+    /// Drop the positions before `shift` and move the others back by `shift`, after the first
+    /// `shift` bytes of the data were dropped.
+    fn rebase(&mut self, shift: usize) {
+        self.tree.drain(..2 * shift);
+        for position in self
+            .head
+            .iter_mut()
+            .chain(&mut self.tree)
+            .chain(&mut self.pairs)
+        {
+            *position = match position.checked_sub(shift as u32) {
+                Some(rebased) if *position != u32::MAX => rebased,
+                _ => u32::MAX,
+            };
+        }
+    }
+}
+
+fn match_len(data: &[u8], start: usize, pos: usize, max_len: usize) -> usize {
+    data[start..]
+        .iter()
+        .zip(&data[pos..pos + max_len])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// The 13 byte header of an `.lzma` stream, with room for `capacity` bytes of compressed data.
+fn header(settings: &Settings, len: usize, capacity: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(capacity + 13);
+    header.push(((settings.pb * 5 + settings.lp) * 9 + settings.lc) as u8);
+    header.extend_from_slice(&settings.dict_size.to_le_bytes());
+    header.extend_from_slice(&(len as u64).to_le_bytes());
+    header
+}
+
+/// Cheapest known way to reach a position of the block.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    price: u32,
+    context: Context,
+    from: usize,
+    op: Op,
+}
+
+/// Compress the data into an `.lzma` stream with the uncompressed size in the header.
+///
+/// The data is split into blocks, each is parsed into the sequence of symbols with the lowest
+/// total price under the probabilities at the start of the block.
+pub(crate) fn encode(data: &[u8], settings: &Settings) -> Vec<u8> {
+    let table = Price::table();
+    let mut rc = RangeEncoder::new(header(settings, data.len(), data.len() / 4));
+    let mut model = Model::new(*settings);
+    let mut context = Context {
+        state: 0,
+        reps: [0; 4],
+    };
+
+    let mut finder = MatchFinder::new(data.len());
+    let mut offsets = Vec::with_capacity(data.len() + 1);
+    let mut matches = Vec::new();
+    for pos in 0..data.len() {
+        offsets.push(matches.len());
+        finder.matches(data, pos, settings, &mut matches);
+    }
+    offsets.push(matches.len());
+
+    let mut nodes = Vec::with_capacity(WINDOW_SIZE + 1);
+    let mut ops = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let window_start = pos;
+        let window_end = (window_start + WINDOW_SIZE).min(data.len());
+        let unreached = Node {
+            price: u32::MAX,
+            context,
+            from: 0,
+            op: Op::Literal,
+        };
+        nodes.clear();
+        nodes.resize(window_end - window_start + 1, unreached);
+        nodes[0].price = 0;
+
+        for pos in window_start..window_end {
+            let node = nodes[pos - window_start];
+            let max_len = (window_end - pos).min(MAX_MATCH_LEN);
+
+            let mut relax = |model: &mut Model, op: Op| {
+                let price = node.price + model.price(&table, data, pos, node.context, op);
+                let target = &mut nodes[pos + op.len() - window_start];
+                if price < target.price {
+                    *target = Node {
+                        price,
+                        context: node.context.after(op),
+                        from: pos,
+                        op,
+                    };
+                }
+            };
+
+            relax(&mut model, Op::Literal);
+            let reps = node.context.reps;
+            if reps[0] < pos && data[pos] == data[pos - reps[0] - 1] {
+                relax(&mut model, Op::ShortRep);
+            }
+            for (index, &rep) in reps.iter().enumerate() {
+                if rep >= pos {
+                    continue;
+                }
+                let rep_len = match_len(data, pos - rep - 1, pos, max_len);
+                for len in MIN_MATCH_LEN..=rep_len {
+                    relax(&mut model, Op::Rep { index, len });
+                }
+            }
+            let mut len = MIN_MATCH_LEN;
+            for &(distance, match_len) in &matches[offsets[pos]..offsets[pos + 1]] {
+                while len <= match_len.min(max_len) {
+                    relax(&mut model, Op::Match { distance, len });
+                    len += 1;
+                }
+            }
+        }
+
+        ops.clear();
+        let mut end = window_end;
+        while end > window_start {
+            let node = nodes[end - window_start];
+            ops.push(node.op);
+            end = node.from;
+        }
+        // The end of the window is forced, so only the start of the parse is kept
+        let commit_end = if window_end == data.len() {
+            window_end
+        } else {
+            window_start + WINDOW_SIZE / 2
+        };
+        for &op in ops.iter().rev() {
+            if pos >= commit_end {
+                break;
+            }
+            model.encode(&mut rc, data, pos, context, op);
+            context = context.after(op);
+            pos += op.len();
+        }
+    }
+
+    rc.finish()
+}
+
+/// Encoder for data arriving over time, the data is coded greedily as soon as enough bytes
+/// follow to search for matches.
+///
+/// Only the last dictionary of coded data is kept to find matches, so the memory besides the
+/// compressed output is bounded by about twice the dictionary size.
+pub(crate) struct StreamEncoder {
+    settings: Settings,
+    table: Vec<u32>,
+    rc: RangeEncoder,
+    model: Model,
+    context: Context,
+    finder: MatchFinder,
+    matches: Vec<(usize, usize)>,
+    /// The data from `base` on.
+    data: Vec<u8>,
+    /// Number of bytes dropped from the start of the data.
+    base: usize,
+    /// Position of the first byte that was not coded yet in `data`.
+    pos: usize,
+}
+
+impl StreamEncoder {
+    /// Number of bytes that are held back to find matches reaching into them.
+    const LOOKAHEAD: usize = 64;
+
+    pub(crate) fn new(settings: Settings) -> Self {
+        StreamEncoder {
+            settings,
+            table: Price::table(),
+            rc: RangeEncoder::new(Vec::new()),
+            model: Model::new(settings),
+            context: Context {
+                state: 0,
+                reps: [0; 4],
+            },
+            finder: MatchFinder::new(0),
+            matches: Vec::new(),
+            data: Vec::new(),
+            base: 0,
+            pos: 0,
+        }
+    }
+
+    /// Number of compressed bytes produced so far, without the header and the held back bytes.
+    pub(crate) fn compressed_len(&self) -> usize {
+        self.rc.output.len()
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+        while self.pos + StreamEncoder::LOOKAHEAD <= self.data.len() {
+            let op = self.next_op();
+            for skipped in self.pos + 1..self.pos + op.len() {
+                self.finder
+                    .matches(&self.data, skipped, &self.settings, &mut self.matches);
+            }
+            self.model
+                .encode(&mut self.rc, &self.data, self.pos, self.context, op);
+            self.context = self.context.after(op);
+            self.pos += op.len();
+        }
+
+        // Matches reach back at most a dictionary, older data is dropped once it doubled. The
+        // shift keeps the low bits of the positions used by the position states.
+        let dict_size = self.settings.dict_size as usize;
+        if self.pos > 2 * dict_size {
+            let shift = (self.pos - dict_size) & !0xf;
+            self.data.drain(..shift);
+            self.finder.rebase(shift);
+            self.base += shift;
+            self.pos -= shift;
+        }
+    }
+
+    /// Choose the symbol with the lowest price per byte at the current position.
+    fn next_op(&mut self) -> Op {
+        let (data, pos, context) = (&self.data, self.pos, self.context);
+        let max_len = (data.len() - pos).min(MAX_MATCH_LEN);
+        self.matches.clear();
+        self.finder
+            .matches(data, pos, &self.settings, &mut self.matches);
+
+        let mut candidates = vec![Op::Literal];
+        let reps = context.reps;
+        if reps[0] < pos && data[pos] == data[pos - reps[0] - 1] {
+            candidates.push(Op::ShortRep);
+        }
+        for (index, &rep) in reps.iter().enumerate() {
+            if rep < pos {
+                let len = match_len(data, pos - rep - 1, pos, max_len);
+                if len >= MIN_MATCH_LEN {
+                    candidates.push(Op::Rep { index, len });
+                }
+            }
+        }
+        if let Some(&(distance, len)) = self.matches.last() {
+            if len >= MIN_MATCH_LEN {
+                candidates.push(Op::Match { distance, len });
+            }
+        }
+
+        let mut best = (Op::Literal, u32::MAX);
+        for op in candidates {
+            let price = self.model.price(&self.table, data, pos, context, op);
+            // Compare the prices per byte
+            if u64::from(price) * (best.0.len() as u64) < u64::from(best.1) * (op.len() as u64) {
+                best = (op, price);
+            }
+        }
+        best.0
+    }
+
+    /// The `.lzma` stream of all bytes written so far, the encoder can be written to afterwards.
+    pub(crate) fn finish(&self) -> Vec<u8> {
+        let mut rc = self.rc.clone();
+        let mut model = self.model.clone();
+        let mut context = self.context;
+        for pos in self.pos..self.data.len() {
+            model.encode(&mut rc, &self.data, pos, context, Op::Literal);
+            context = context.after(Op::Literal);
+        }
+
+        let len = self.base + self.data.len();
+        let mut stream = header(&self.settings, len, rc.output.len() + 5);
+        stream.extend(rc.finish());
+        stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator for test data.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn settings(lc: u32, lp: u32, pb: u32, dict_size: u32) -> Settings {
+        Settings {
+            lc,
+            lp,
+            pb,
+            dict_size,
+            nice_len: 32,
+            depth: 16,
+        }
+    }
+
+    fn decode(stream: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        lzma_rs::lzma_decompress(&mut &stream[..], &mut decoded).expect("Error decoding stream");
+        decoded
+    }
+
+    /// Frame text like in replays, with coordinates wandering around.
+    fn frame_text(rng: &mut Rng, len: usize) -> Vec<u8> {
+        let mut text = String::new();
+        let (mut x, mut y) = (256.0f32, 192.0f32);
+        while text.len() < len {
+            x = (x + rng.below(21) as f32 - 10.0).clamp(0.0, 512.0);
+            y = (y + rng.below(21) as f32 - 10.0).clamp(0.0, 384.0);
+            let x = x + rng.below(4) as f32 / 4.0;
+            text.push_str(&format!(
+                "{}|{}|{}|{},",
+                15 + rng.below(3),
+                x,
+                y,
+                rng.below(16)
+            ));
+        }
+        text.into_bytes()
+    }
+
+    fn inputs() -> Vec<Vec<u8>> {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let random: Vec<u8> = (0..16_384).map(|_| rng.next() as u8).collect();
+        // Blocks repeated at distances beyond the small dictionaries
+        let mut repeats = Vec::new();
+        for _ in 0..10 {
+            let start = rng.below(random.len() as u64 - 3000) as usize;
+            repeats.extend_from_slice(&random[start..start + 1000 + rng.below(2000) as usize]);
+        }
+        vec![
+            Vec::new(),
+            b"0".to_vec(),
+            b"abababababababababababababababababababababababababababab".to_vec(),
+            vec![0; 5_000],
+            random,
+            repeats,
+            frame_text(&mut rng, 40_000),
+        ]
+    }
+
+    /// Random settings within the ranges of the `.lzma` format.
+    fn random_settings(rng: &mut Rng) -> Settings {
+        Settings {
+            lc: rng.below(9) as u32,
+            lp: rng.below(5) as u32,
+            pb: rng.below(5) as u32,
+            dict_size: 1 << (12 + rng.below(11)),
+            nice_len: MIN_MATCH_LEN + rng.below((MAX_MATCH_LEN - MIN_MATCH_LEN + 1) as u64) as usize,
+            depth: 1 + rng.below(64) as usize,
+        }
+    }
+
+    /// Random data of literals from a random alphabet, runs and copies of earlier data, with
+    /// copies at distances up to the whole data and of lengths around the longest match.
+    fn random_data(rng: &mut Rng, len: usize) -> Vec<u8> {
+        let alphabet = 1 + rng.below(256);
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let run = 1 + rng.below(2 * MAX_MATCH_LEN as u64) as usize;
+            match rng.below(4) {
+                0 => data.extend((0..run).map(|_| rng.below(alphabet) as u8)),
+                1 => data.extend(std::iter::repeat_n(rng.below(alphabet) as u8, run)),
+                _ if !data.is_empty() => {
+                    let start = rng.below(data.len() as u64) as usize;
+                    // Copies may overlap their own output
+                    for i in 0..run {
+                        data.push(data[start + i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        data.truncate(len);
+        data
+    }
+
+    /// Random length up to 16 KiB, short lengths as likely as long ones.
+    fn random_len(rng: &mut Rng) -> usize {
+        let bits = 4 + rng.below(11);
+        rng.below(1 << bits) as usize
+    }
+
+    /// Decompressed frame text of the replay in the assets.
+    fn replay_text() -> Vec<u8> {
+        let replay = crate::Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+        crate::Replay::decompress_lzma(&replay.compressed_data)
+            .expect("Error decompressing replay")
+            .into_bytes()
+    }
+
+    #[test]
+    fn random_round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for case in 0..96 {
+            let len = random_len(&mut rng);
+            let data = if rng.below(2) == 0 {
+                random_data(&mut rng, len)
+            } else {
+                (0..len).map(|_| rng.next() as u8).collect()
+            };
+            let settings = random_settings(&mut rng);
+            assert_eq!(
+                decode(&encode(&data, &settings)),
+                data,
+                "case {}, {:?}, {} bytes",
+                case,
+                settings,
+                data.len()
+            );
+        }
+    }
+
+    #[test]
+    fn random_stream_round_trip() {
+        let mut rng = Rng(0x853c_49e6_748f_ea9b);
+        for case in 0..48 {
+            let len = random_len(&mut rng);
+            let data = random_data(&mut rng, len);
+            let settings = random_settings(&mut rng);
+            let mut encoder = StreamEncoder::new(settings);
+            for chunk in data.chunks(1 + rng.below(2000) as usize) {
+                encoder.write(chunk);
+            }
+            assert_eq!(
+                decode(&encoder.finish()),
+                data,
+                "case {}, {:?}, {} bytes",
+                case,
+                settings,
+                data.len()
+            );
+        }
+    }
+
+    #[test]
+    fn replay_round_trip() {
+        let text = replay_text();
+        for settings in crate::compression::optimize_settings(text.len()) {
+            assert_eq!(decode(&encode(&text, &settings)), text, "{:?}", settings);
+        }
+
+        // Random parts of the frame text, starting and ending within frames
+        let mut rng = Rng(0xda94_2042_e4dd_58b5);
+        for case in 0..16 {
+            let start = rng.below(text.len() as u64) as usize;
+            let end = (start + rng.below(1 << 14) as usize).min(text.len());
+            let data = &text[start..end];
+            let settings = random_settings(&mut rng);
+            assert_eq!(
+                decode(&encode(data, &settings)),
+                data,
+                "case {}, {:?}",
+                case,
+                settings
+            );
+            let mut encoder = StreamEncoder::new(settings);
+            for chunk in data.chunks(1 + rng.below(100) as usize) {
+                encoder.write(chunk);
+            }
+            assert_eq!(decode(&encoder.finish()), data, "case {}, {:?}", case, settings);
+        }
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for data in inputs() {
+            for settings in [
+                settings(3, 0, 2, 1 << 22),
+                settings(0, 0, 0, 1 << 12),
+                settings(0, 2, 2, 1 << 16),
+                settings(8, 4, 4, 1 << 14),
+            ] {
+                let stream = encode(&data, &settings);
+                assert_eq!(
+                    decode(&stream),
+                    data,
+                    "{:?}, {} bytes",
+                    settings,
+                    data.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let mut rng = Rng(42);
+        for data in inputs() {
+            for settings in [settings(3, 0, 2, 1 << 12), settings(0, 2, 2, 1 << 16)] {
+                let mut encoder = StreamEncoder::new(settings);
+                let mut written = 0;
+                while written < data.len() {
+                    let end = (written + 1 + rng.below(5000) as usize).min(data.len());
+                    encoder.write(&data[written..end]);
+                    written = end;
+                    // Finishing does not change the encoder
+                    if rng.below(4) == 0 {
+                        assert_eq!(decode(&encoder.finish()), data[..written]);
+                    }
+                }
+                assert_eq!(
+                    decode(&encoder.finish()),
+                    data,
+                    "{:?}, {} bytes",
+                    settings,
+                    data.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stream_longer_than_dictionary() {
+        // The settings of the recorder with a 2 MiB dictionary, the window moves twice
+        let mut rng = Rng(7);
+        let data = frame_text(&mut rng, 7 << 20);
+        let mut encoder = StreamEncoder::new(Settings {
+            lc: 3,
+            lp: 0,
+            pb: 2,
+            dict_size: 1 << 21,
+            nice_len: 64,
+            depth: 16,
+        });
+        for chunk in data.chunks(25) {
+            encoder.write(chunk);
+        }
+        assert!(encoder.data.len() <= 2 * (1 << 21) + StreamEncoder::LOOKAHEAD + 25);
+        assert_eq!(decode(&encoder.finish()), data);
+    }
+}
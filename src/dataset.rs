@@ -0,0 +1,392 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+#[cfg(feature = "parquet")]
+use parquet::basic::Compression;
+#[cfg(feature = "parquet")]
+use parquet::data_type::{DoubleType, FloatType, Int32Type, Int64Type};
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+#[cfg(feature = "parquet")]
+use parquet::file::writer::SerializedFileWriter;
+#[cfg(feature = "parquet")]
+use parquet::schema::parser::parse_message_type;
+use serde_json::{json, Value};
+
+use crate::batch::{replay_files, Parser};
+use crate::errors::{ParseError, ReplayDataError};
+use crate::frames::FramesExt;
+use crate::parser::ParseOptions;
+use crate::render;
+use crate::replay::{Replay, ReplayData};
+
+/// File name of the frames in the output directory, with the extension of the format.
+pub const FRAMES_FILE: &str = "frames";
+
+/// File name of the metadata sidecar in the output directory.
+pub const METADATA_FILE: &str = "metadata.json";
+
+/// Columnar output format of a dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DatasetFormat {
+    /// Comma separated values with a header row.
+    #[default]
+    Csv,
+    /// Apache Parquet compressed with Snappy, with a row group per replay.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::dataset::{build, DatasetFormat, DatasetOptions};
+    /// use parquet::file::reader::{FileReader, SerializedFileReader};
+    ///
+    /// let output_dir = std::env::temp_dir().join("osu_replay_parser_parquet_example");
+    /// let options = DatasetOptions {
+    ///     output_dir: Some(output_dir.clone()),
+    ///     ..Default::default()
+    /// };
+    /// let summary = build("assets", DatasetFormat::Parquet, &options).expect("Error building dataset");
+    ///
+    /// let file = std::fs::File::open(output_dir.join("frames.parquet")).unwrap();
+    /// let reader = SerializedFileReader::new(file).expect("Error reading Parquet file");
+    /// assert_eq!(reader.metadata().file_metadata().num_rows(), summary.frames as i64);
+    /// # std::fs::remove_dir_all(&output_dir).unwrap();
+    /// ```
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl DatasetFormat {
+    /// The file extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DatasetFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            DatasetFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Schema of the frames written as Parquet.
+#[cfg(feature = "parquet")]
+const PARQUET_SCHEMA: &str = "message frames {
+    required int64 replay;
+    required double time;
+    required float x;
+    required float y;
+    required int32 keys (INTEGER(32, false));
+}";
+
+/// Writer of the frames file in one of the [`DatasetFormat`]s.
+enum FramesWriter {
+    Csv(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(SerializedFileWriter<File>),
+}
+
+impl FramesWriter {
+    fn create(path: &Path, format: DatasetFormat) -> Result<Self, ReplayDataError<'static>> {
+        let file = File::create(path)?;
+        match format {
+            DatasetFormat::Csv => {
+                let mut writer = BufWriter::new(file);
+                writeln!(writer, "replay,time,x,y,keys")?;
+                Ok(FramesWriter::Csv(writer))
+            }
+            #[cfg(feature = "parquet")]
+            DatasetFormat::Parquet => {
+                let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(parquet_error)?);
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build();
+                let writer = SerializedFileWriter::new(file, schema, Arc::new(properties))
+                    .map_err(parquet_error)?;
+                Ok(FramesWriter::Parquet(writer))
+            }
+        }
+    }
+
+    fn write_rows(&mut self, id: usize, rows: &[Row]) -> Result<(), ReplayDataError<'static>> {
+        match self {
+            FramesWriter::Csv(writer) => {
+                for (time, x, y, keys) in rows {
+                    writeln!(writer, "{},{},{},{},{}", id, time, x, y, keys)?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "parquet")]
+            FramesWriter::Parquet(writer) => {
+                write_row_group(writer, id, rows).map_err(parquet_error)
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), ReplayDataError<'static>> {
+        match self {
+            FramesWriter::Csv(mut writer) => Ok(writer.flush()?),
+            #[cfg(feature = "parquet")]
+            FramesWriter::Parquet(writer) => writer.close().map(drop).map_err(parquet_error),
+        }
+    }
+}
+
+/// Write the rows of a replay as a row group, column by column in the order of [`PARQUET_SCHEMA`].
+#[cfg(feature = "parquet")]
+fn write_row_group(
+    writer: &mut SerializedFileWriter<File>,
+    id: usize,
+    rows: &[Row],
+) -> parquet::errors::Result<()> {
+    let mut row_group = writer.next_row_group()?;
+    let missing = || parquet::errors::ParquetError::General("missing column".to_string());
+
+    let mut column = row_group.next_column()?.ok_or_else(missing)?;
+    let ids = vec![id as i64; rows.len()];
+    column.typed::<Int64Type>().write_batch(&ids, None, None)?;
+    column.close()?;
+
+    let mut column = row_group.next_column()?.ok_or_else(missing)?;
+    let times: Vec<f64> = rows.iter().map(|row| row.0).collect();
+    column
+        .typed::<DoubleType>()
+        .write_batch(&times, None, None)?;
+    column.close()?;
+
+    for position in [|row: &Row| row.1, |row: &Row| row.2] {
+        let mut column = row_group.next_column()?.ok_or_else(missing)?;
+        let values: Vec<f32> = rows.iter().map(position).collect();
+        column
+            .typed::<FloatType>()
+            .write_batch(&values, None, None)?;
+        column.close()?;
+    }
+
+    let mut column = row_group.next_column()?.ok_or_else(missing)?;
+    // Stored as unsigned 32 bit integers, the bits are reinterpreted
+    let keys: Vec<i32> = rows.iter().map(|row| row.3 as i32).collect();
+    column.typed::<Int32Type>().write_batch(&keys, None, None)?;
+    column.close()?;
+
+    row_group.close().map(drop)
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_error(e: parquet::errors::ParquetError) -> ReplayDataError<'static> {
+    ReplayDataError::Io(std::io::Error::other(e))
+}
+
+/// Options of [`build`].
+#[derive(Debug, Clone)]
+pub struct DatasetOptions {
+    /// Directory the dataset is written to, `dataset` inside the input directory by default.
+    pub output_dir: Option<PathBuf>,
+    /// Resample the frames to this frame rate, see [`render::timeline`].
+    /// The original frames with absolute times are written if `None`.
+    pub fps: Option<f64>,
+    /// Number of replays parsed and decoded in parallel, `0` to use the available parallelism.
+    pub threads: usize,
+    /// Options used to parse each replay.
+    pub parse_options: ParseOptions,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        DatasetOptions {
+            output_dir: None,
+            fps: Some(60.0),
+            threads: 0,
+            parse_options: ParseOptions::default(),
+        }
+    }
+}
+
+/// Result of [`build`].
+#[derive(Debug)]
+pub struct DatasetSummary {
+    /// Directory the dataset was written to.
+    pub output_dir: PathBuf,
+    /// Number of replays in the dataset.
+    pub replays: usize,
+    /// Number of rows in the frames file.
+    pub frames: usize,
+    /// Replay files that could not be parsed or decoded and were left out.
+    pub failures: Vec<(PathBuf, ReplayDataError<'static>)>,
+}
+
+/// A frame in the dataset: absolute time in milliseconds, position and keys.
+type Row = (f64, f32, f32, u32);
+
+/// Convert all `.osr` files in a directory into a dataset for analysis or machine learning.
+///
+/// The replays are parsed and decoded in parallel and their frames are optionally resampled to a
+/// fixed frame rate. The frames of all replays are written to a single `frames` file with the
+/// columns `replay,time,x,y,keys`, where `replay` is the index of the replay in `metadata.json`.
+/// The sidecar also lists the header fields of every replay and the files that failed.
+/// # Example
+/// ```
+/// use osu_replay_parser::dataset::{build, DatasetFormat, DatasetOptions};
+///
+/// let output_dir = std::env::temp_dir().join("osu_replay_parser_dataset_example");
+/// let options = DatasetOptions {
+///     output_dir: Some(output_dir.clone()),
+///     fps: Some(30.0),
+///     ..Default::default()
+/// };
+/// let summary = build("assets", DatasetFormat::Csv, &options).expect("Error building dataset");
+/// assert_eq!(summary.replays, 1);
+/// assert!(summary.failures.is_empty());
+///
+/// let frames = std::fs::read_to_string(output_dir.join("frames.csv")).unwrap();
+/// assert!(frames.starts_with("replay,time,x,y,keys\n"));
+/// assert_eq!(frames.lines().count(), summary.frames + 1);
+///
+/// let metadata = std::fs::read_to_string(output_dir.join("metadata.json")).unwrap();
+/// assert!(metadata.contains("\"file\":\"replay.osr\""));
+/// # std::fs::remove_dir_all(&output_dir).unwrap();
+///
+/// let options = DatasetOptions { fps: Some(0.0), ..Default::default() };
+/// assert!(build("assets", DatasetFormat::Csv, &options).is_err());
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError::Io` if the input directory cannot be read or the output cannot be written
+/// and `ParseError::InvalidValue` if `fps` is not positive.
+/// Errors of individual replays are part of the summary.
+pub fn build<P: AsRef<Path>>(
+    dir: P,
+    format: DatasetFormat,
+    options: &DatasetOptions,
+) -> Result<DatasetSummary, ReplayDataError<'static>> {
+    if options.fps.is_some_and(|fps| fps.is_nan() || fps <= 0.0) {
+        return Err(ParseError::InvalidValue.into());
+    }
+    let dir = dir.as_ref();
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| dir.join("dataset"));
+    std::fs::create_dir_all(&output_dir)?;
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+
+    let frames_path = output_dir
+        .join(FRAMES_FILE)
+        .with_extension(format.extension());
+    let mut writer = FramesWriter::create(&frames_path, format)?;
+
+    let mut summary = DatasetSummary {
+        output_dir: output_dir.clone(),
+        replays: 0,
+        frames: 0,
+        failures: Vec::new(),
+    };
+    let mut replays = Vec::new();
+    // Files are processed in batches of one file per thread, so only one batch of frames is kept in memory
+    for batch in replay_files(dir)?.chunks(threads) {
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|path| scope.spawn(move || convert(path, options)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Converting a replay panicked"))
+                .collect()
+        });
+
+        for (path, result) in batch.iter().zip(results) {
+            let (replay, rows) = match result {
+                Ok(converted) => converted,
+                Err(e) => {
+                    summary.failures.push((path.clone(), e));
+                    continue;
+                }
+            };
+            let id = replays.len();
+            writer.write_rows(id, &rows)?;
+            summary.frames += rows.len();
+            replays.push(replay_metadata(id, path, &replay, rows.len()));
+        }
+    }
+    writer.finish()?;
+    summary.replays = replays.len();
+
+    let failures: Vec<Value> = summary
+        .failures
+        .iter()
+        .map(|(path, e)| {
+            json!({
+                "file": file_name(path),
+                "error": format!("{:?}", e).trim(),
+            })
+        })
+        .collect();
+    let metadata = json!({
+        "format": format.extension(),
+        "fps": options.fps,
+        "columns": ["replay", "time", "x", "y", "keys"],
+        "replays": replays,
+        "failures": failures,
+    });
+    std::fs::write(output_dir.join(METADATA_FILE), metadata.to_string())?;
+
+    Ok(summary)
+}
+
+/// Parse and decode a replay file into its header and rows.
+fn convert(
+    path: &Path,
+    options: &DatasetOptions,
+) -> Result<(Replay, Vec<Row>), ReplayDataError<'static>> {
+    Parser::new()
+        .options(options.parse_options.clone())
+        .parse_file_frames(path, |replay, frames| {
+            (replay.clone(), rows(frames, options.fps))
+        })
+}
+
+fn rows(frames: &[ReplayData], fps: Option<f64>) -> Vec<Row> {
+    match fps {
+        Some(fps) => render::timeline(frames, fps)
+            .into_iter()
+            .map(|frame| {
+                (
+                    frame.time,
+                    frame.cursor.x,
+                    frame.cursor.y,
+                    frame.cursor.keys,
+                )
+            })
+            .collect(),
+        None => frames
+            .timed()
+            .map(|(time, frame)| (time as f64, frame.x, frame.y, frame.keys))
+            .collect(),
+    }
+}
+
+fn replay_metadata(id: usize, path: &Path, replay: &Replay, frames: usize) -> Value {
+    json!({
+        "replay": id,
+        "file": file_name(path),
+        "gameMode": u8::from(replay.game_mode),
+        "version": replay.version,
+        "beatmapMd5": replay.beatmap_md5,
+        "replayMd5": replay.replay_md5,
+        "playerName": replay.player_name,
+        "mods": replay.mods,
+        "totalScore": replay.total_score,
+        "accuracy": replay.accuracy(),
+        "timeStamp": replay.time_stamp,
+        "onlineScoreId": replay.online_score_id,
+        "frames": frames,
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,730 @@
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+
+use lzma_rs::decompress::{Options, Stream};
+use lzma_rs::error::Error as LzmaError;
+
+use crate::compression::LzmaProperties;
+use crate::errors::{FrameError, InvalidFrame, LimitExceeded, ParseError, ReplayDataError};
+use crate::replay::{Replay, ReplayData};
+use crate::writer::compress_frames;
+
+/// Default limit of the decompressed replay data in bytes, see [`DecodeLimits::untrusted`].
+/// Larger sizes declared in the LZMA header are also reported as
+/// [`Warning::SuspiciousLength`](crate::warnings::Warning::SuspiciousLength), hours of frames
+/// take only a few megabytes.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 64 << 20;
+
+/// A transformation of the compressed replay data applied before it is decompressed,
+/// e.g. to undo the obfuscation some private servers apply to replays.
+///
+/// Implemented for closures taking the data and returning the transformed data.
+pub trait FrameTransform {
+    /// Transform the compressed replay data.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the data cannot be transformed.
+    fn apply(&self, data: &[u8]) -> Result<Vec<u8>, ReplayDataError<'static>>;
+}
+
+impl<F> FrameTransform for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>, ReplayDataError<'static>>,
+{
+    fn apply(&self, data: &[u8]) -> Result<Vec<u8>, ReplayDataError<'static>> {
+        self(data)
+    }
+}
+
+/// Transform XORing the data with a repeating key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorTransform {
+    key: Vec<u8>,
+}
+
+impl XorTransform {
+    /// Create a transform XORing with the given key.
+    ///
+    /// # Panics
+    /// Panics if the key is empty.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        let key = key.into();
+        assert!(!key.is_empty(), "XOR key must not be empty");
+        XorTransform { key }
+    }
+}
+
+impl FrameTransform for XorTransform {
+    fn apply(&self, data: &[u8]) -> Result<Vec<u8>, ReplayDataError<'static>> {
+        Ok(data
+            .iter()
+            .zip(self.key.iter().cycle())
+            .map(|(byte, key)| byte ^ key)
+            .collect())
+    }
+}
+
+/// Whether the data plausibly starts with an LZMA header as written by osu!.
+///
+/// Useful to detect obfuscated replay data before choosing a [`FrameTransform`].
+pub fn looks_like_lzma(data: &[u8]) -> bool {
+    LzmaProperties::from_header(data).is_some_and(|properties| {
+        properties.dict_size >= 1 << 12
+            && properties.dict_size <= 1 << 30
+            && properties.unpacked_size.is_none_or(|size| size < 1 << 40)
+    })
+}
+
+/// A format the frames of a replay can be stored in.
+///
+/// osu!stable and osu!lazer store frames as LZMA compressed text, see [`LzmaTextCodec`].
+/// Other formats, e.g. custom formats of private servers, can be supported by implementing this trait.
+pub trait FrameCodec {
+    /// Name of the format, for diagnostics.
+    fn name(&self) -> &str;
+
+    /// Whether the data appears to be in this format. Used to select a codec automatically.
+    fn detect(&self, data: &[u8]) -> bool;
+
+    /// Decode frames from the data.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the data is not valid in this format.
+    fn decode(&self, data: &[u8]) -> Result<Vec<ReplayData>, ReplayDataError<'static>>;
+
+    /// Encode frames into this format.
+    fn encode(&self, frames: &[ReplayData]) -> Vec<u8>;
+}
+
+/// The LZMA compressed `time|x|y|keys,` text format used by osu!.
+///
+/// [`FrameCodec::decode`] enforces no limits like [`Replay::get_actions`], use
+/// [`Self::decode_with_limits`] for data from untrusted sources.
+/// # Example
+/// ```
+/// use osu_replay_parser::decoder::{FrameCodec, LzmaTextCodec};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// assert!(LzmaTextCodec.detect(&replay.compressed_data));
+///
+/// let frames = LzmaTextCodec.decode(&replay.compressed_data).expect("Error decoding frames");
+/// let encoded = LzmaTextCodec.encode(&frames);
+/// assert_eq!(LzmaTextCodec.decode(&encoded).unwrap(), frames);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LzmaTextCodec;
+
+impl LzmaTextCodec {
+    /// Decode frames from the data like [`FrameCodec::decode`], failing as soon as a limit is
+    /// exceeded.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the data cannot be decompressed, a frame cannot be parsed
+    /// or a limit is exceeded.
+    pub fn decode_with_limits(
+        &self,
+        data: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
+        let mut frames = Vec::new();
+        decode_frames_with_limits(data, limits, |frame| {
+            frames.push(frame);
+            ControlFlow::<()>::Continue(())
+        })?;
+        Ok(frames)
+    }
+}
+
+impl FrameCodec for LzmaTextCodec {
+    fn name(&self) -> &str {
+        "lzma-text"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        looks_like_lzma(data)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
+        Replay::decode_actions(data, |_, _| {})
+    }
+
+    fn encode(&self, frames: &[ReplayData]) -> Vec<u8> {
+        compress_frames(frames)
+    }
+}
+
+/// Decoder for the compressed replay data, applying registered [`FrameTransform`]s before decompressing.
+///
+/// The frame format is chosen explicitly with [`Self::codec`] or detected among the registered
+/// codecs, which by default only contain [`LzmaTextCodec`].
+/// # Example
+/// ```
+/// use osu_replay_parser::decoder::{FrameDecoder, FrameTransform, XorTransform};
+/// use osu_replay_parser::Replay;
+///
+/// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let expected = replay.get_actions_with_progress(|_, _| {}).expect("Error getting actions");
+///
+/// // Obfuscate the data, then decode it with the matching transform
+/// let xor = XorTransform::new(*b"key");
+/// replay.compressed_data = xor.apply(&replay.compressed_data).unwrap();
+/// let actions = FrameDecoder::new()
+///     .with_transform(xor)
+///     .decode(&replay)
+///     .expect("Error decoding actions");
+/// assert_eq!(actions, expected);
+/// ```
+pub struct FrameDecoder {
+    transforms: Vec<Box<dyn FrameTransform>>,
+    codec: Option<Box<dyn FrameCodec>>,
+    codecs: Vec<Box<dyn FrameCodec>>,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        FrameDecoder {
+            transforms: Vec::new(),
+            codec: None,
+            codecs: vec![Box::new(LzmaTextCodec)],
+        }
+    }
+}
+
+impl FrameDecoder {
+    /// Create a decoder without transforms, decoding the same frames as [`Replay::get_actions`],
+    /// including the seed frame.
+    ///
+    /// Only the error differs for data that is not LZMA compressed: no codec detects it, so
+    /// [`Self::decode`] returns `ParseError::InvalidValue` instead of `FrameError::Lzma`.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::decoder::FrameDecoder;
+    /// use osu_replay_parser::errors::{FrameError, ParseError, ReplayDataError};
+    /// use osu_replay_parser::{writer, Replay};
+    ///
+    /// let replay = Replay {
+    ///     compressed_data: writer::compress(b"0|256|192|0,-1|256|192|0,16|260|190|1,-12345|0|0|42,"),
+    ///     ..Default::default()
+    /// };
+    /// let expected = replay.get_actions_with_progress(|_, _| {}).expect("Error getting actions");
+    /// assert_eq!(FrameDecoder::new().decode(&replay).unwrap(), expected);
+    ///
+    /// let invalid = Replay { compressed_data: vec![0; 3], ..Default::default() };
+    /// assert!(matches!(
+    ///     invalid.get_actions_with_progress(|_, _| {}),
+    ///     Err(ReplayDataError::Frame(FrameError::Lzma(_)))
+    /// ));
+    /// assert!(matches!(
+    ///     FrameDecoder::new().decode(&invalid),
+    ///     Err(ReplayDataError::Parse(ParseError::InvalidValue))
+    /// ));
+    /// ```
+    pub fn new() -> Self {
+        FrameDecoder::default()
+    }
+
+    /// Always decode with the given codec instead of detecting the format.
+    pub fn codec<C: FrameCodec + 'static>(mut self, codec: C) -> Self {
+        self.codec = Some(Box::new(codec));
+        self
+    }
+
+    /// Register a codec for format detection. Codecs registered later take precedence.
+    pub fn register_codec<C: FrameCodec + 'static>(mut self, codec: C) -> Self {
+        self.codecs.insert(0, Box::new(codec));
+        self
+    }
+
+    /// Register a transform, transforms are applied in the order they were registered.
+    pub fn with_transform<T: FrameTransform + 'static>(mut self, transform: T) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Apply all transforms to the data.
+    /// # Errors
+    /// Returns the error of the first failing transform.
+    pub fn transform(&self, data: &[u8]) -> Result<Vec<u8>, ReplayDataError<'static>> {
+        let mut data = data.to_vec();
+        for transform in &self.transforms {
+            data = transform.apply(&data)?;
+        }
+        Ok(data)
+    }
+
+    /// The codec used for the (transformed) data, `None` if no registered codec detects the format.
+    pub fn select_codec(&self, data: &[u8]) -> Option<&dyn FrameCodec> {
+        match &self.codec {
+            Some(codec) => Some(codec.as_ref()),
+            None => self
+                .codecs
+                .iter()
+                .find(|codec| codec.detect(data))
+                .map(|codec| codec.as_ref()),
+        }
+    }
+
+    /// Decode the frames of a replay.
+    /// # Errors
+    /// Returns a `ReplayDataError` if a transform fails, no codec matches the data or the data cannot be decoded.
+    pub fn decode(&self, replay: &Replay) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
+        let data = self.transform(&replay.compressed_data)?;
+        self.select_codec(&data)
+            .ok_or(ParseError::InvalidValue)?
+            .decode(&data)
+    }
+}
+
+/// Limits enforced while decoding the frames of a replay, protecting servers from replays
+/// crafted to exhaust memory or time. `None` disables a limit.
+///
+/// The default is [`DecodeLimits::untrusted`], used by [`decode_frames_with`] and
+/// [`decode_frames_pipelined`]. [`Replay::get_actions`] and the other methods of [`Replay`],
+/// [`LzmaTextCodec`] and [`recover_frames`](crate::compression::recover_frames) enforce no
+/// limits. Use their `_with_limits` variants, e.g. [`Replay::get_actions_with_limits`], for
+/// untrusted replays.
+/// Exceeding a limit fails with [`FrameError::LimitExceeded`] as soon as it is detected.
+///
+/// The decompressed size is checked before decompressing against the size and dictionary size
+/// declared in the LZMA header, and while decompressing a dictionary at a time. Streams whose
+/// dictionary is larger than [`Self::max_decompressed_size`] are rejected, so at most twice the
+/// limit is decompressed before a stream exceeding it fails.
+/// # Example
+/// ```
+/// use osu_replay_parser::decoder::DecodeLimits;
+/// use osu_replay_parser::errors::{FrameError, LimitExceeded, ReplayDataError};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay
+///     .get_actions_with_limits(&DecodeLimits::untrusted())
+///     .expect("Error getting actions");
+///
+/// let limits = DecodeLimits {
+///     max_frames: Some(100),
+///     ..Default::default()
+/// };
+/// let Err(ReplayDataError::Frame(FrameError::LimitExceeded(exceeded))) =
+///     replay.get_actions_with_limits(&limits)
+/// else {
+///     panic!("Expected the frame limit to be exceeded");
+/// };
+/// assert!(actions.len() > 100);
+/// assert_eq!(exceeded, LimitExceeded::Frames { limit: 100 });
+///
+/// // A header declaring 1 GiB of frames is rejected without decompressing
+/// let mut compressed_data = osu_replay_parser::writer::compress(b"0|256|192|0,");
+/// compressed_data[5..13].copy_from_slice(&(1u64 << 30).to_le_bytes());
+/// let bomb = Replay { compressed_data, ..Default::default() };
+/// assert!(matches!(
+///     bomb.get_actions_with_limits(&DecodeLimits::untrusted()),
+///     Err(ReplayDataError::Frame(FrameError::LimitExceeded(LimitExceeded::DecompressedSize { .. })))
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of frames.
+    pub max_frames: Option<usize>,
+    /// Maximum length of a single `time|x|y|keys` frame in bytes.
+    pub max_frame_length: Option<usize>,
+    /// Maximum size of the decompressed replay data in bytes. Replays declaring a larger size or
+    /// dictionary size in their LZMA header are rejected before decompressing.
+    pub max_decompressed_size: Option<u64>,
+}
+
+impl DecodeLimits {
+    /// Limits for replays from untrusted sources, generous enough for marathon maps: a million
+    /// frames, 256 bytes per frame and [`MAX_DECOMPRESSED_SIZE`] bytes of decompressed data.
+    pub fn untrusted() -> Self {
+        DecodeLimits {
+            max_frames: Some(1_000_000),
+            max_frame_length: Some(256),
+            max_decompressed_size: Some(MAX_DECOMPRESSED_SIZE),
+        }
+    }
+
+    /// No limits, for replays from trusted sources exceeding [`DecodeLimits::untrusted`].
+    pub fn unlimited() -> Self {
+        DecodeLimits {
+            max_frames: None,
+            max_frame_length: None,
+            max_decompressed_size: None,
+        }
+    }
+
+    /// Fail if the LZMA header of the data declares a decompressed size or a dictionary size
+    /// above the limit.
+    ///
+    /// The decoder only passes on the decompressed data once its dictionary is full, so a
+    /// dictionary above the limit would let a stream of unknown size decompress unchecked.
+    pub(crate) fn check_declared_size(&self, compressed_data: &[u8]) -> Result<(), ReplayDataError<'static>> {
+        let (Some(limit), Some(properties)) = (
+            self.max_decompressed_size,
+            LzmaProperties::from_header(compressed_data),
+        ) else {
+            return Ok(());
+        };
+        if u64::from(properties.dict_size) > limit
+            || properties.unpacked_size.is_some_and(|size| size > limit)
+        {
+            return Err(FrameError::LimitExceeded(LimitExceeded::DecompressedSize { limit }).into());
+        }
+        Ok(())
+    }
+
+    /// Options of the LZMA decoder, bounding its dictionary buffer by the decompressed size limit.
+    pub(crate) fn lzma_options(&self) -> Options {
+        Options {
+            memlimit: self
+                .max_decompressed_size
+                .map(|limit| usize::try_from(limit).unwrap_or(usize::MAX)),
+            ..Options::default()
+        }
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits::untrusted()
+    }
+}
+
+impl Replay {
+    /// Decode the compressed replay data like [`Self::get_actions`], failing as soon as a limit
+    /// is exceeded.
+    ///
+    /// See [`DecodeLimits`] for an example.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the data cannot be decompressed, a frame cannot be parsed
+    /// or a limit is exceeded.
+    pub fn get_actions_with_limits(
+        &self,
+        limits: &DecodeLimits,
+    ) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
+        LzmaTextCodec.decode_with_limits(&self.compressed_data, limits)
+    }
+}
+
+/// Number of compressed bytes fed to the LZMA decoder at once by [`decode_frames_with`], so
+/// decoding stops soon after the dictionary in which the callback breaks.
+const STREAM_CHUNK_SIZE: usize = 4096;
+
+/// Receives the decompressed text and passes every complete frame to the callback.
+struct FrameSink<B, F> {
+    on_frame: F,
+    limits: DecodeLimits,
+    /// Bytes of the frame cut off at the end of the last write.
+    partial: Vec<u8>,
+    index: usize,
+    decompressed: u64,
+    outcome: Option<Result<B, ReplayDataError<'static>>>,
+}
+
+impl<B, F> FrameSink<B, F>
+where
+    F: FnMut(ReplayData) -> ControlFlow<B>,
+{
+    fn new(on_frame: F, limits: DecodeLimits) -> Self {
+        FrameSink {
+            on_frame,
+            limits,
+            partial: Vec::new(),
+            index: 0,
+            decompressed: 0,
+            outcome: None,
+        }
+    }
+
+    /// The result of decoding after all data was written or decoding stopped.
+    fn finish(
+        mut self,
+        decompressed: Result<(), LzmaError>,
+    ) -> Result<Option<B>, ReplayDataError<'static>> {
+        match (self.outcome.take(), decompressed) {
+            (Some(outcome), _) => return outcome.map(Some),
+            // The dictionary buffer reached the memory limit of the LZMA decoder
+            (None, Err(e)) if e.to_string().contains("exceeded memory limit") => {
+                let limit = self.limits.max_decompressed_size.unwrap_or_default();
+                return Err(FrameError::LimitExceeded(LimitExceeded::DecompressedSize { limit }).into());
+            }
+            (None, Err(e)) => return Err(FrameError::Lzma(e).into()),
+            (None, Ok(())) => {}
+        }
+
+        // The last frame may not be followed by a comma
+        if !self.partial.is_empty() {
+            let partial = std::mem::take(&mut self.partial);
+            self.frame(&partial);
+        }
+        self.outcome.transpose()
+    }
+
+    /// Stop decoding with an exceeded limit.
+    fn exceeded(&mut self, limit: LimitExceeded) {
+        self.outcome = Some(Err(FrameError::LimitExceeded(limit).into()));
+    }
+
+    /// Whether the frame being decoded, `len` bytes long so far, exceeds the length limit.
+    fn check_length(&mut self, len: usize) -> bool {
+        match self.limits.max_frame_length {
+            Some(limit) if len > limit => {
+                let index = self.index;
+                self.exceeded(LimitExceeded::FrameLength { index, limit });
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Parse and pass on a frame, `false` if decoding has to stop.
+    fn frame(&mut self, bytes: &[u8]) -> bool {
+        if !self.check_length(bytes.len()) {
+            return false;
+        }
+        if let Some(limit) = self.limits.max_frames.filter(|&limit| self.index >= limit) {
+            self.exceeded(LimitExceeded::Frames { limit });
+            return false;
+        }
+        let index = self.index;
+        self.index += 1;
+        let Ok(data) = std::str::from_utf8(bytes) else {
+            self.outcome = Some(Err(ReplayDataError::Frame(FrameError::InvalidUtf)));
+            return false;
+        };
+        let frame = match Replay::parse_frame(data) {
+            Ok(frame) => frame,
+            Err(_) => {
+                self.outcome = Some(Err(ReplayDataError::Frame(FrameError::InvalidFrame(
+                    InvalidFrame {
+                        index,
+                        raw: data.to_string(),
+                    },
+                ))));
+                return false;
+            }
+        };
+        match (self.on_frame)(frame) {
+            ControlFlow::Continue(()) => true,
+            ControlFlow::Break(value) => {
+                self.outcome = Some(Ok(value));
+                false
+            }
+        }
+    }
+}
+
+impl<B, F> io::Write for FrameSink<B, F>
+where
+    F: FnMut(ReplayData) -> ControlFlow<B>,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.outcome.is_some() {
+            return Err(io::Error::other("decoding stopped"));
+        }
+        self.decompressed += buf.len() as u64;
+        if let Some(limit) = self
+            .limits
+            .max_decompressed_size
+            .filter(|&limit| self.decompressed > limit)
+        {
+            self.exceeded(LimitExceeded::DecompressedSize { limit });
+            return Err(io::Error::other("decoding stopped"));
+        }
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|&byte| byte == b',') {
+            let keep_going = if self.partial.is_empty() {
+                self.frame(&rest[..end])
+            } else {
+                let mut bytes = std::mem::take(&mut self.partial);
+                bytes.extend_from_slice(&rest[..end]);
+                self.frame(&bytes)
+            };
+            if !keep_going {
+                return Err(io::Error::other("decoding stopped"));
+            }
+            rest = &rest[end + 1..];
+        }
+        if !self.check_length(self.partial.len() + rest.len()) {
+            return Err(io::Error::other("decoding stopped"));
+        }
+        self.partial.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decode compressed replay data frame by frame, passing every frame to `on_frame` without
+/// collecting them, e.g. to search marathon replays for a pattern.
+///
+/// The LZMA decoder passes on the decompressed text a dictionary at a time, so frames reach
+/// `on_frame` in batches and memory use is bounded by the dictionary size in the LZMA header
+/// rather than by the number of frames. Replays written by osu! have a dictionary larger than
+/// their text, so the whole text is decompressed before the first frame is visited. Only
+/// streams decompressing to several dictionaries are decoded incrementally.
+///
+/// Decoding stops once `on_frame` returns [`ControlFlow::Break`], whose value is returned,
+/// without decompressing the dictionaries after the current one. Returns `Ok(None)` if all
+/// frames were visited. Frames are decoded as with
+/// [`Replay::get_actions`], so the times are the deltas stored in the replay. The default
+/// [`DecodeLimits`] apply, use [`decode_frames_with_limits`] to change them.
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// use osu_replay_parser::decoder::decode_frames_with;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+///
+/// // Time of the first frame with both mouse buttons held
+/// let mut time = 0;
+/// let found = decode_frames_with(&replay.compressed_data, |frame| {
+///     time += frame.time;
+///     if frame.keys & 3 == 3 {
+///         ControlFlow::Break(time)
+///     } else {
+///         ControlFlow::Continue(())
+///     }
+/// })
+/// .expect("Error decoding frames");
+///
+/// let actions = replay.get_actions_with_progress(|_, _| {}).unwrap();
+/// let index = actions.iter().position(|frame| frame.keys & 3 == 3).unwrap();
+/// assert_eq!(found, Some(actions[..=index].iter().map(|frame| frame.time).sum()));
+///
+/// let mut count = 0;
+/// let all = decode_frames_with(&replay.compressed_data, |_| {
+///     count += 1;
+///     ControlFlow::<()>::Continue(())
+/// })
+/// .expect("Error decoding frames");
+/// assert_eq!((all, count), (None, actions.len()));
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the data cannot be decompressed, a frame visited before
+/// stopping cannot be parsed or a limit of [`DecodeLimits::default`] is exceeded.
+pub fn decode_frames_with<B, F>(
+    compressed_data: &[u8],
+    on_frame: F,
+) -> Result<Option<B>, ReplayDataError<'static>>
+where
+    F: FnMut(ReplayData) -> ControlFlow<B>,
+{
+    decode_frames_with_limits(compressed_data, &DecodeLimits::default(), on_frame)
+}
+
+/// Decode compressed replay data frame by frame like [`decode_frames_with`], failing as soon as
+/// a limit is exceeded.
+/// # Errors
+/// Returns a `ReplayDataError` if the data cannot be decompressed, a frame visited before
+/// stopping cannot be parsed or a limit is exceeded.
+pub fn decode_frames_with_limits<B, F>(
+    compressed_data: &[u8],
+    limits: &DecodeLimits,
+    on_frame: F,
+) -> Result<Option<B>, ReplayDataError<'static>>
+where
+    F: FnMut(ReplayData) -> ControlFlow<B>,
+{
+    limits.check_declared_size(compressed_data)?;
+    let mut sink = FrameSink::new(on_frame, *limits);
+    let decompressed = decompress_into(compressed_data, &limits.lzma_options(), &mut sink);
+    sink.finish(decompressed)
+}
+
+/// Decode compressed replay data frame by frame like [`decode_frames_with`], decompressing on a
+/// second thread while the frames are parsed on the calling thread.
+///
+/// An LZMA stream cannot be split and decompressed in parallel, but overlapping the
+/// decompression with parsing and `on_frame` cuts the wall-clock time of large replays, e.g. of
+/// marathon maps. The decompressed text is handed over a dictionary at a time, 2 MiB for replays
+/// written by osu!, so only replays decompressing to several dictionaries benefit. For smaller
+/// replays the cost of the thread outweighs the gain.
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// use osu_replay_parser::decoder::decode_frames_pipelined;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let mut actions = Vec::new();
+/// decode_frames_pipelined(&replay.compressed_data, |frame| {
+///     actions.push(frame);
+///     ControlFlow::<()>::Continue(())
+/// })
+/// .expect("Error decoding frames");
+/// assert_eq!(actions, replay.get_actions().unwrap());
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the data cannot be decompressed, a frame visited before
+/// stopping cannot be parsed or a limit of [`DecodeLimits::default`] is exceeded.
+pub fn decode_frames_pipelined<B, F>(
+    compressed_data: &[u8],
+    on_frame: F,
+) -> Result<Option<B>, ReplayDataError<'static>>
+where
+    F: FnMut(ReplayData) -> ControlFlow<B>,
+{
+    let limits = DecodeLimits::default();
+    limits.check_declared_size(compressed_data)?;
+    let mut sink = FrameSink::new(on_frame, limits);
+    let (sender, receiver) = mpsc::sync_channel(PIPELINE_DEPTH);
+    let decompressed = thread::scope(|scope| {
+        let decompressor = scope.spawn(move || {
+            lzma_rs::lzma_decompress_with_options(
+                &mut &compressed_data[..],
+                &mut ChannelWriter { sender },
+                &limits.lzma_options(),
+            )
+        });
+        for chunk in &receiver {
+            if sink.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+        // Stops the decompressing thread if the sink stopped early
+        drop(receiver);
+        decompressor
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    });
+    sink.finish(decompressed)
+}
+
+/// Number of decompressed chunks buffered between the threads of [`decode_frames_pipelined`].
+const PIPELINE_DEPTH: usize = 4;
+
+/// Sends the decompressed text to the parsing thread of [`decode_frames_pipelined`].
+struct ChannelWriter {
+    sender: SyncSender<Vec<u8>>,
+}
+
+impl io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::other("decoding stopped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decompress the data in chunks of [`STREAM_CHUNK_SIZE`] into the writer.
+fn decompress_into<W: Write>(
+    compressed_data: &[u8],
+    options: &Options,
+    writer: W,
+) -> Result<(), LzmaError> {
+    let mut stream = Stream::new_with_options(options, writer);
+    for chunk in compressed_data.chunks(STREAM_CHUNK_SIZE) {
+        stream.write_all(chunk).map_err(LzmaError::IoError)?;
+    }
+    stream.finish().map(drop)
+}
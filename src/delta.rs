@@ -0,0 +1,144 @@
+#[cfg(feature = "postcard")]
+use crate::errors::{ParseError, ReplayDataError};
+use crate::replay::ReplayData;
+
+/// Largest number of decimal places cursor positions can be stored with.
+pub const MAX_DELTA_PRECISION: u8 = 6;
+
+/// A frame with its cursor position stored relative to the previous frame, see [`DeltaFrames`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameDelta {
+    /// Time since the previous frame in milliseconds, like [`ReplayData::time`].
+    pub time: i64,
+    /// Change of the x coordinate in units of the precision.
+    pub dx: i64,
+    /// Change of the y coordinate in units of the precision.
+    pub dy: i64,
+    /// The pressed keys.
+    pub keys: u32,
+}
+
+/// Frames with fixed-point cursor positions stored as differences between frames, e.g. for
+/// storing large numbers of frames in a database.
+///
+/// Positions are rounded to `precision` decimal places, the differences are taken between the
+/// rounded positions so rounding errors do not add up. Positions that do not fit into 64 bit
+/// fixed-point numbers, only found in corrupted replays, are clamped and `NaN` becomes 0.
+///
+/// With the `serde` feature the frames can be stored in any serde format. The `postcard` feature
+/// adds [`Self::to_bytes`], whose variable length integers make the small differences several
+/// times smaller than the text format.
+/// # Example
+/// ```
+/// use osu_replay_parser::delta::DeltaFrames;
+/// use osu_replay_parser::frames::FramesExt;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+///
+/// let deltas = actions.delta_encode(2);
+/// let decoded = deltas.delta_decode();
+/// assert!(actions
+///     .iter()
+///     .zip(&decoded)
+///     .all(|(a, b)| a.time == b.time && (a.x - b.x).abs() <= 0.005 && (a.y - b.y).abs() <= 0.005));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeltaFrames {
+    /// Number of decimal places of the positions.
+    pub precision: u8,
+    /// The frames.
+    pub deltas: Vec<FrameDelta>,
+}
+
+impl DeltaFrames {
+    /// Delta encode frames with positions rounded to `precision` decimal places.
+    /// # Panics
+    /// Panics if `precision` is larger than [`MAX_DELTA_PRECISION`].
+    pub fn encode(frames: &[ReplayData], precision: u8) -> Self {
+        assert!(
+            precision <= MAX_DELTA_PRECISION,
+            "precision must be at most {}",
+            MAX_DELTA_PRECISION
+        );
+        let scale = 10f64.powi(i32::from(precision));
+        let (mut x, mut y) = (0, 0);
+        let deltas = frames
+            .iter()
+            .map(|frame| {
+                let (next_x, next_y) = (
+                    (f64::from(frame.x) * scale).round() as i64,
+                    (f64::from(frame.y) * scale).round() as i64,
+                );
+                let delta = FrameDelta {
+                    time: frame.time,
+                    dx: next_x.wrapping_sub(x),
+                    dy: next_y.wrapping_sub(y),
+                    keys: frame.keys,
+                };
+                (x, y) = (next_x, next_y);
+                delta
+            })
+            .collect();
+
+        DeltaFrames { precision, deltas }
+    }
+
+    /// Restore the frames, with the positions rounded to the precision.
+    pub fn delta_decode(&self) -> Vec<ReplayData> {
+        let scale = 10f64.powi(i32::from(self.precision));
+        let (mut x, mut y) = (0i64, 0i64);
+        self.deltas
+            .iter()
+            .map(|delta| {
+                x = x.wrapping_add(delta.dx);
+                y = y.wrapping_add(delta.dy);
+                ReplayData {
+                    time: delta.time,
+                    x: (x as f64 / scale) as f32,
+                    y: (y as f64 / scale) as f32,
+                    keys: delta.keys,
+                }
+            })
+            .collect()
+    }
+
+    /// Serialize the frames with [`postcard`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::delta::DeltaFrames;
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let actions = replay.get_actions().expect("Error getting actions");
+    /// let deltas = actions.delta_encode(2);
+    ///
+    /// // Several times smaller than the text format
+    /// let bytes = deltas.to_bytes();
+    /// let text = osu_replay_parser::writer::encode_frames(&actions, None);
+    /// assert!(bytes.len() * 4 < text.len());
+    /// assert_eq!(DeltaFrames::from_bytes(&bytes).unwrap(), deltas);
+    /// ```
+    #[cfg(feature = "postcard")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("Serializing into memory does not fail")
+    }
+
+    /// Deserialize frames written by [`Self::to_bytes`].
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if the data is not valid, has an invalid precision or
+    /// trailing bytes.
+    #[cfg(feature = "postcard")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayDataError<'static>> {
+        let (frames, rest): (DeltaFrames, _) =
+            postcard::take_from_bytes(bytes).map_err(|_| ParseError::InvalidValue)?;
+        if frames.precision > MAX_DELTA_PRECISION || !rest.is_empty() {
+            return Err(ParseError::InvalidValue.into());
+        }
+        Ok(frames)
+    }
+}
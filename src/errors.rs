@@ -1,23 +1,131 @@
+use std::fmt;
+
 use lzma_rs::error::Error as LzmaError;
 use nom::error::{VerboseError, VerboseErrorKind};
 
 /// Error type for parsing replay data
-/// 
-/// This error type is used for all errors that occur during parsing of replay data.
-
-// TODO: Refactor error into parsing error and lzma error
+///
+/// Every stage of reading a replay has its own error type: [`ParseError`] for the replay file
+/// and its values, [`FrameError`] for the compressed frames and [`std::io::Error`] for reading
+/// files. Functions spanning several stages return this error, which wraps the error of the
+/// stage that failed.
+/// # Example
+/// ```
+/// use osu_replay_parser::errors::{ParseError, ReplayDataError};
+/// use osu_replay_parser::Replay;
+///
+/// match Replay::parse(b"not a replay") {
+///     Err(ReplayDataError::Parse(ParseError::Nom(_))) => {}
+///     other => panic!("Expected a parse error, got {:?}", other),
+/// }
+/// ```
+#[non_exhaustive]
 pub enum ReplayDataError<'a> {
+    /// The replay file or a value in it is invalid
+    Parse(ParseError<'a>),
+    /// The compressed replay data cannot be decoded
+    Frame(FrameError),
+    /// Error reading or writing a file
+    Io(std::io::Error),
+}
+
+/// Error parsing the replay file or a value of it.
+#[non_exhaustive]
+pub enum ParseError<'a> {
     /// Error parsing replay data
     /// This variant includes a trace of all the parsers that led to the error
-    NomParsingError(VerboseError<&'a [u8]>),
+    Nom(VerboseError<&'a [u8]>),
     /// Expected value in replay data not found
-    MissingValueError,
+    MissingValue,
     /// Value in replay data is invalid
-    InvalidValueError,
-    /// Decompressed replay data is not valid UTF-8
-    InvalidUtfError,
+    InvalidValue,
+}
+
+/// Error decoding the compressed frames of a replay.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FrameError {
     /// Error decompressing replay data
-    LzmaError(LzmaError)
+    Lzma(LzmaError),
+    /// Decompressed replay data is not valid UTF-8
+    InvalidUtf,
+    /// A frame of the decompressed replay data cannot be parsed
+    InvalidFrame(InvalidFrame),
+    /// The replay data exceeds a limit of [`DecodeLimits`](crate::decoder::DecodeLimits)
+    LimitExceeded(LimitExceeded),
+}
+
+/// The limit of [`DecodeLimits`](crate::decoder::DecodeLimits) a replay exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LimitExceeded {
+    /// The replay has more frames than allowed.
+    Frames {
+        /// The maximum number of frames.
+        limit: usize,
+    },
+    /// A frame is longer than allowed.
+    FrameLength {
+        /// Index of the frame in the decompressed replay data.
+        index: usize,
+        /// The maximum length of a frame in bytes.
+        limit: usize,
+    },
+    /// The decompressed replay data is larger than allowed.
+    DecompressedSize {
+        /// The maximum decompressed size in bytes.
+        limit: u64,
+    },
+}
+
+/// Position and raw text of a frame that cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFrame {
+    /// Index of the frame in the decompressed replay data.
+    pub index: usize,
+    /// The raw text of the frame.
+    pub raw: String,
+}
+
+/// A byte sequence of the decompressed replay data that is not valid UTF-8 and was replaced by
+/// U+FFFD, see [`Utf8Handling::Lossy`](crate::replay::Utf8Handling::Lossy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    /// Offset of the sequence in the decompressed replay data.
+    pub offset: usize,
+    /// Length of the sequence in bytes.
+    pub len: usize,
+}
+
+impl ReplayDataError<'_> {
+    /// Detach the error from the input it was parsed from.
+    ///
+    /// The parser trace only keeps its contexts, the referenced input slices are dropped.
+    /// This is needed when the input buffer does not outlive the error, e.g. in [`crate::Replay::from_file`].
+    pub fn into_owned(self) -> ReplayDataError<'static> {
+        match self {
+            ReplayDataError::Parse(e) => ReplayDataError::Parse(e.into_owned()),
+            ReplayDataError::Frame(e) => ReplayDataError::Frame(e),
+            ReplayDataError::Io(e) => ReplayDataError::Io(e),
+        }
+    }
+}
+
+impl ParseError<'_> {
+    /// Detach the error from the input it was parsed from, see [`ReplayDataError::into_owned`].
+    pub fn into_owned(self) -> ParseError<'static> {
+        match self {
+            ParseError::Nom(e) => ParseError::Nom(VerboseError {
+                errors: e
+                    .errors
+                    .into_iter()
+                    .map(|(_, kind)| (&[][..], kind))
+                    .collect(),
+            }),
+            ParseError::MissingValue => ParseError::MissingValue,
+            ParseError::InvalidValue => ParseError::InvalidValue,
+        }
+    }
 }
 
 impl<'a> From<ReplayDataError<'a>> for nom::Err<ReplayDataError<'a>> {
@@ -33,7 +141,7 @@ pub(crate) fn from_context<I>(input: I, context: &'static str) -> VerboseError<I
 }
 
 // Take error trace and print human readable
-fn convert_error(e: VerboseError<&[u8]>) -> String {
+fn convert_error(e: &VerboseError<&[u8]>) -> String {
     let mut error = String::new();
     for (i, chunk) in e.errors.iter().rev().enumerate() {
         match chunk {
@@ -49,38 +157,150 @@ fn convert_error(e: VerboseError<&[u8]>) -> String {
     error
 }
 
+impl<'a> From<ParseError<'a>> for ReplayDataError<'a> {
+    fn from(e: ParseError<'a>) -> Self {
+        ReplayDataError::Parse(e)
+    }
+}
+
+impl From<FrameError> for ReplayDataError<'_> {
+    fn from(e: FrameError) -> Self {
+        ReplayDataError::Frame(e)
+    }
+}
+
 impl From<std::num::ParseIntError> for ReplayDataError<'_> {
     fn from(_: std::num::ParseIntError) -> Self {
-        ReplayDataError::InvalidValueError
+        ReplayDataError::Parse(ParseError::InvalidValue)
     }
 }
 
 impl From<std::num::ParseFloatError> for ReplayDataError<'_> {
     fn from(_: std::num::ParseFloatError) -> Self {
-        ReplayDataError::InvalidValueError
+        ReplayDataError::Parse(ParseError::InvalidValue)
+    }
+}
+
+impl From<LzmaError> for FrameError {
+    fn from(lzma_error: LzmaError) -> Self {
+        FrameError::Lzma(lzma_error)
     }
 }
 
 impl From<LzmaError> for ReplayDataError<'_> {
-    fn from(lzma_error: lzma_rs::error::Error) -> Self {
-        ReplayDataError::LzmaError(lzma_error)
+    fn from(lzma_error: LzmaError) -> Self {
+        ReplayDataError::Frame(FrameError::Lzma(lzma_error))
     }
 }
 
-impl<'a> std::fmt::Debug for ReplayDataError<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ReplayDataError::NomParsingError(e) => write!(f, "\n{}", convert_error(e.clone())),
-            ReplayDataError::MissingValueError => write!(f, "MissingValueError: Expected value in replay data not found"),
-            ReplayDataError::InvalidValueError => write!(f, "InvalidValueError: Value in replay data is invalid"),
-            ReplayDataError::InvalidUtfError => write!(f, "InvalidUtfError: Decompressed replay data is not valid UTF-8"),
-            ReplayDataError::LzmaError(e) => write!(f, "LzmaError: Error decompressing replay data\n\n{}", e),
-        }
+impl From<std::io::Error> for ReplayDataError<'_> {
+    fn from(io_error: std::io::Error) -> Self {
+        ReplayDataError::Io(io_error)
+    }
+}
+
+impl<'a> From<VerboseError<&'a [u8]>> for ParseError<'a> {
+    fn from(e: VerboseError<&'a [u8]>) -> Self {
+        ParseError::Nom(e)
     }
 }
 
 impl<'a> From<VerboseError<&'a [u8]>> for ReplayDataError<'a> {
     fn from(e: VerboseError<&'a [u8]>) -> Self {
-        ReplayDataError::NomParsingError(e)
+        ReplayDataError::Parse(ParseError::Nom(e))
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for ReplayDataError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayDataError::Parse(e) => write!(f, "{}", e),
+            ReplayDataError::Frame(e) => write!(f, "{}", e),
+            ReplayDataError::Io(e) => write!(f, "Error reading replay file: {}", e),
+        }
+    }
+}
+
+impl fmt::Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Nom(e) => write!(f, "Error parsing replay file\n{}", convert_error(e)),
+            ParseError::MissingValue => write!(f, "Expected value in replay data not found"),
+            ParseError::InvalidValue => write!(f, "Value in replay data is invalid"),
+        }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Lzma(e) => write!(f, "Error decompressing replay data: {}", e),
+            FrameError::InvalidUtf => write!(f, "Decompressed replay data is not valid UTF-8"),
+            FrameError::InvalidFrame(e) => {
+                write!(f, "Frame {} in replay data is invalid: {:?}", e.index, e.raw)
+            }
+            FrameError::LimitExceeded(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Frames { limit } => {
+                write!(f, "Replay data has more than {} frames", limit)
+            }
+            LimitExceeded::FrameLength { index, limit } => {
+                write!(f, "Frame {} in replay data is longer than {} bytes", index, limit)
+            }
+            LimitExceeded::DecompressedSize { limit } => {
+                write!(f, "Decompressed replay data is larger than {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayDataError<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // The parser trace borrows the input, its contexts are part of the message
+            ReplayDataError::Parse(_) => None,
+            ReplayDataError::Frame(e) => Some(e),
+            ReplayDataError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl std::error::Error for ParseError<'_> {}
+
+impl std::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrameError::Lzma(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for ReplayDataError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayDataError::Parse(e) => write!(f, "{:?}", e),
+            ReplayDataError::Frame(FrameError::Lzma(e)) => write!(f, "LzmaError: Error decompressing replay data\n\n{}", e),
+            ReplayDataError::Frame(FrameError::InvalidUtf) => write!(f, "InvalidUtfError: Decompressed replay data is not valid UTF-8"),
+            ReplayDataError::Frame(FrameError::InvalidFrame(e)) => write!(f, "InvalidFrameError: Frame {} in replay data is invalid: {:?}", e.index, e.raw),
+            ReplayDataError::Frame(FrameError::LimitExceeded(e)) => write!(f, "LimitExceededError: {}", e),
+            ReplayDataError::Io(e) => write!(f, "IoError: Error reading replay file\n\n{}", e),
+        }
+    }
+}
+
+impl fmt::Debug for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Nom(e) => write!(f, "\n{}", convert_error(e)),
+            ParseError::MissingValue => write!(f, "MissingValueError: Expected value in replay data not found"),
+            ParseError::InvalidValue => write!(f, "InvalidValueError: Value in replay data is invalid"),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+mod bookmarks;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use serde_json::json;
+
+use crate::replay::Replay;
+
+pub use bookmarks::{
+    editor_bookmarks, editor_timestamp, finding_bookmarks, frame_issue_bookmarks, miss_bookmarks,
+    pause_bookmarks, slider_break_bookmarks, timestamp_list, Bookmark,
+};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{to_sqlite, SqliteOptions};
+
+/// Settings for a render submission to o!rdr, which renders replays with danser.
+///
+/// Only the commonly changed settings are exposed, o!rdr uses its defaults for all others.
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    /// Name of the user requesting the render.
+    pub username: String,
+    /// Output resolution, e.g. `1280x720`.
+    pub resolution: String,
+    /// Name or ID of the skin to use.
+    pub skin: String,
+    /// Whether `skin` refers to a custom skin ID instead of a skin name.
+    pub custom_skin: bool,
+    /// URL the replay file can be downloaded from, if it is not uploaded with the request.
+    pub replay_url: Option<String>,
+    /// Whether the intro should be skipped.
+    pub skip: bool,
+    /// Whether the hit error meter is shown.
+    pub show_hit_error_meter: bool,
+    /// Whether the key overlay is shown.
+    pub show_key_overlay: bool,
+    /// Whether the result screen is shown at the end.
+    pub show_result_screen: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            username: String::new(),
+            resolution: "1280x720".to_string(),
+            skin: "default".to_string(),
+            custom_skin: false,
+            replay_url: None,
+            skip: true,
+            show_hit_error_meter: true,
+            show_key_overlay: true,
+            show_result_screen: true,
+        }
+    }
+}
+
+/// Create the JSON payload of an o!rdr render submission for a replay.
+///
+/// Besides the render settings the payload contains the replay and beatmap hashes and the mods
+/// of the replay, so submissions can be matched to replays afterwards.
+/// # Example
+/// ```
+/// use osu_replay_parser::export::{ordr_payload, RenderSettings};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let payload = ordr_payload(&replay, &RenderSettings::default());
+/// assert!(payload.contains("\"replayHash\":\"4ceccf534227945cac1fa270d921508c\""));
+/// ```
+pub fn ordr_payload(replay: &Replay, settings: &RenderSettings) -> String {
+    let mut entries = json!({
+        "username": settings.username,
+        "resolution": settings.resolution,
+        "skin": settings.skin,
+        "customSkin": settings.custom_skin,
+        "skip": settings.skip,
+        "showHitErrorMeter": settings.show_hit_error_meter,
+        "showKeyOverlay": settings.show_key_overlay,
+        "showResultScreen": settings.show_result_screen,
+        "replayHash": replay.replay_md5,
+        "beatmapHash": replay.beatmap_md5,
+        "playerName": replay.player_name,
+        "mods": replay.mods,
+    });
+    if let Some(url) = &settings.replay_url {
+        entries["replayURL"] = url.as_str().into();
+    }
+
+    entries.to_string()
+}
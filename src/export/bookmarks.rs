@@ -0,0 +1,208 @@
+use std::fmt::Write as _;
+
+use crate::analysis::{FrameIssue, HitError, Judgement, Report, SliderBreak};
+use crate::beatmap::Beatmap;
+use crate::frames::{FramesExt, MAX_INTERPOLATION_GAP};
+use crate::replay::ReplayData;
+
+/// A point of interest in a replay, e.g. a miss, to jump to in the osu! editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    /// Time in milliseconds.
+    pub time: i64,
+    /// Description of the point, e.g. `Miss`.
+    pub label: String,
+}
+
+impl Bookmark {
+    /// Create a bookmark.
+    pub fn new(time: i64, label: impl Into<String>) -> Self {
+        Bookmark {
+            time,
+            label: label.into(),
+        }
+    }
+}
+
+/// Bookmarks at the hit objects that were missed, see [`hit_errors`](crate::analysis::hit_errors).
+pub fn miss_bookmarks(hit_errors: &[HitError]) -> Vec<Bookmark> {
+    hit_errors
+        .iter()
+        .filter(|error| error.judgement == Judgement::Miss)
+        .map(|error| Bookmark::new(error.time, "Miss"))
+        .collect()
+}
+
+/// Bookmarks at the missed slider ticks and repeats, see [`slider_breaks`](crate::analysis::slider_breaks).
+pub fn slider_break_bookmarks(breaks: &[SliderBreak]) -> Vec<Bookmark> {
+    breaks
+        .iter()
+        .map(|slider_break| Bookmark::new(slider_break.time, "Slider break"))
+        .collect()
+}
+
+/// Bookmarks at the frames with problems, e.g. cursor snaps, see
+/// [`validate_frames`](crate::analysis::validate_frames).
+///
+/// Problems of the whole replay are left out, as they have no time.
+pub fn frame_issue_bookmarks(frames: &[ReplayData], issues: &[FrameIssue]) -> Vec<Bookmark> {
+    let times = absolute_times(frames);
+    issues
+        .iter()
+        .filter_map(|issue| {
+            let time = (*times.get(issue.index?)?)?;
+            Some(Bookmark::new(time, format!("{:?}", issue.kind)))
+        })
+        .collect()
+}
+
+/// Bookmarks at the pauses of a replay, gaps between frames longer than
+/// [`MAX_INTERPOLATION_GAP`] after the first key press.
+///
+/// The skipped intro comes before the first key press and is not reported.
+/// # Example
+/// ```
+/// use osu_replay_parser::export::pause_bookmarks;
+/// use osu_replay_parser::ReplayData;
+///
+/// let frames = vec![
+///     ReplayData { time: 1000, x: 0.0, y: 0.0, keys: 1 },
+///     ReplayData { time: 16, x: 0.0, y: 0.0, keys: 0 },
+///     ReplayData { time: 5000, x: 0.0, y: 0.0, keys: 0 },
+/// ];
+/// let pauses = pause_bookmarks(&frames);
+/// assert_eq!(pauses.len(), 1);
+/// assert_eq!((pauses[0].time, pauses[0].label.as_str()), (1016, "Pause (5000ms)"));
+/// ```
+pub fn pause_bookmarks(frames: &[ReplayData]) -> Vec<Bookmark> {
+    let Some((first_press, _, _)) = frames.key_presses().next() else {
+        return Vec::new();
+    };
+    let timed: Vec<(i64, &ReplayData)> = frames.timed().collect();
+    timed
+        .windows(2)
+        .filter(|pair| pair[0].0 >= first_press && pair[1].0 - pair[0].0 > MAX_INTERPOLATION_GAP)
+        .map(|pair| Bookmark::new(pair[0].0, format!("Pause ({}ms)", pair[1].0 - pair[0].0)))
+        .collect()
+}
+
+/// Bookmarks at the findings of an [`Analyzer`](crate::analysis::Analyzer) report.
+///
+/// Findings concerning the whole replay are left out, as they have no time.
+pub fn finding_bookmarks(report: &Report) -> Vec<Bookmark> {
+    report
+        .findings
+        .iter()
+        .filter_map(|finding| Some(Bookmark::new(finding.time?, finding.message.clone())))
+        .collect()
+}
+
+/// Format a time as an osu! editor timestamp, `mm:ss:mmm (combo) - `.
+///
+/// With a beatmap the combo number of the first hit object at or after the time is added, so
+/// the link selects it in the editor. Pasting the timestamp into the osu! chat creates a link
+/// that opens the editor at the time.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::export::editor_timestamp;
+///
+/// let beatmap = Beatmap::parse(
+///     "[HitObjects]\n100,100,1000,5,0\n200,100,1500,1,0\n300,100,62345,1,0\n",
+/// )
+/// .unwrap();
+/// assert_eq!(editor_timestamp(62345, None), "01:02:345 - ");
+/// assert_eq!(editor_timestamp(62345, Some(&beatmap)), "01:02:345 (3) - ");
+/// ```
+pub fn editor_timestamp(time: i64, beatmap: Option<&Beatmap>) -> String {
+    let time = time.max(0);
+    let mut timestamp = format!(
+        "{:02}:{:02}:{:03}",
+        time / 60_000,
+        time / 1000 % 60,
+        time % 1000
+    );
+    if let Some(combo) = beatmap.and_then(|beatmap| combo_number_at(beatmap, time)) {
+        let _ = write!(timestamp, " ({})", combo);
+    }
+    timestamp.push_str(" - ");
+    timestamp
+}
+
+/// One editor timestamp per line followed by the label of the bookmark, sorted by time.
+/// # Example
+/// ```
+/// use osu_replay_parser::export::{timestamp_list, Bookmark};
+///
+/// let bookmarks = vec![Bookmark::new(2500, "Slider break"), Bookmark::new(1000, "Miss")];
+/// assert_eq!(
+///     timestamp_list(&bookmarks, None),
+///     "00:01:000 - Miss\n00:02:500 - Slider break\n"
+/// );
+/// ```
+pub fn timestamp_list(bookmarks: &[Bookmark], beatmap: Option<&Beatmap>) -> String {
+    let mut sorted: Vec<&Bookmark> = bookmarks.iter().collect();
+    sorted.sort_by_key(|bookmark| bookmark.time);
+
+    let mut list = String::new();
+    for bookmark in sorted {
+        list.push_str(&editor_timestamp(bookmark.time, beatmap));
+        list.push_str(&bookmark.label);
+        list.push('\n');
+    }
+    list
+}
+
+/// The `Bookmarks` line of the `[Editor]` section of an `.osu` file, showing the bookmarks on
+/// the timeline of the editor.
+///
+/// The times are sorted and duplicates removed, labels are not stored by osu!.
+/// # Example
+/// ```
+/// use osu_replay_parser::export::{editor_bookmarks, Bookmark};
+///
+/// let bookmarks = vec![Bookmark::new(2500, "Miss"), Bookmark::new(1000, "Miss"), Bookmark::new(2500, "Pause")];
+/// assert_eq!(editor_bookmarks(&bookmarks), "Bookmarks: 1000,2500");
+/// ```
+pub fn editor_bookmarks(bookmarks: &[Bookmark]) -> String {
+    let mut times: Vec<i64> = bookmarks
+        .iter()
+        .map(|bookmark| bookmark.time.max(0))
+        .collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let times: Vec<String> = times.iter().map(i64::to_string).collect();
+    format!("Bookmarks: {}", times.join(","))
+}
+
+/// Absolute time of every frame, `None` for the seed frame.
+fn absolute_times(frames: &[ReplayData]) -> Vec<Option<i64>> {
+    let mut time = 0;
+    frames
+        .iter()
+        .map(|frame| {
+            if frame.is_seed_frame() {
+                return None;
+            }
+            time += frame.time;
+            Some(time)
+        })
+        .collect()
+}
+
+/// Combo number of the first hit object at or after the time.
+fn combo_number_at(beatmap: &Beatmap, time: i64) -> Option<u32> {
+    let mut combo = 0;
+    for (i, object) in beatmap.hit_objects.iter().enumerate() {
+        combo = if i == 0 || object.new_combo {
+            1
+        } else {
+            combo + 1
+        };
+        if object.time >= time {
+            return Some(combo);
+        }
+    }
+    None
+}
@@ -0,0 +1,183 @@
+use std::io;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::analysis::Analyzers;
+use crate::errors::ReplayDataError;
+use crate::frames::FramesExt;
+use crate::replay::Replay;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS replays (
+    id INTEGER PRIMARY KEY,
+    replay_md5 TEXT NOT NULL,
+    beatmap_md5 TEXT NOT NULL,
+    player_name TEXT NOT NULL,
+    game_mode INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    mods INTEGER NOT NULL,
+    n300 INTEGER NOT NULL,
+    n100 INTEGER NOT NULL,
+    n50 INTEGER NOT NULL,
+    n_geki INTEGER NOT NULL,
+    n_katu INTEGER NOT NULL,
+    n_miss INTEGER NOT NULL,
+    total_score INTEGER NOT NULL,
+    greatest_combo INTEGER NOT NULL,
+    perfect INTEGER NOT NULL,
+    accuracy REAL NOT NULL,
+    time_stamp INTEGER NOT NULL,
+    online_score_id INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS frames (
+    replay_id INTEGER NOT NULL REFERENCES replays (id),
+    frame_index INTEGER NOT NULL,
+    time INTEGER NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    keys INTEGER NOT NULL,
+    PRIMARY KEY (replay_id, frame_index)
+);
+CREATE TABLE IF NOT EXISTS metrics (
+    replay_id INTEGER NOT NULL REFERENCES replays (id),
+    analyzer TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS findings (
+    replay_id INTEGER NOT NULL REFERENCES replays (id),
+    analyzer TEXT NOT NULL,
+    time INTEGER,
+    message TEXT NOT NULL
+);
+";
+
+/// Options for [`to_sqlite`].
+#[derive(Default)]
+pub struct SqliteOptions {
+    /// Also write the decoded frames of every replay, with absolute times.
+    pub include_frames: bool,
+    /// Analyzers whose metrics and findings are written for every replay.
+    pub analyzers: Analyzers,
+}
+
+/// Write replays into an SQLite database, so a replay library can be queried with SQL.
+///
+/// The tables are created if they do not exist, the replays are appended:
+/// - `replays`: one row per replay with the header fields and the accuracy,
+/// - `frames`: the frames of each replay if [`SqliteOptions::include_frames`] is set,
+/// - `metrics` and `findings`: the reports of [`SqliteOptions::analyzers`].
+///
+/// All rows reference the `id` of their replay. The replays are written in a single transaction,
+/// so the database is unchanged if writing fails. SQLite is compiled into the library.
+/// # Example
+/// ```
+/// use osu_replay_parser::export::{to_sqlite, SqliteOptions};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let path = std::env::temp_dir().join("osu_replay_parser_export.sqlite");
+/// # let _ = std::fs::remove_file(&path);
+/// let options = SqliteOptions { include_frames: true, ..SqliteOptions::default() };
+/// to_sqlite(&path, &[replay], &options).expect("Error exporting replays");
+/// assert!(path.is_file());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the database cannot be written or frames cannot be decoded.
+pub fn to_sqlite<P: AsRef<Path>>(
+    db_path: P,
+    replays: &[Replay],
+    options: &SqliteOptions,
+) -> Result<(), ReplayDataError<'static>> {
+    let mut connection = Connection::open(db_path).map_err(sql_error)?;
+    connection.execute_batch(SCHEMA).map_err(sql_error)?;
+    // The transaction is rolled back when dropped without committing
+    let transaction = connection.transaction().map_err(sql_error)?;
+    write_replays(&transaction, replays, options)?;
+    transaction.commit().map_err(sql_error)
+}
+
+fn sql_error(error: rusqlite::Error) -> ReplayDataError<'static> {
+    ReplayDataError::Io(io::Error::other(error))
+}
+
+fn write_replays(
+    connection: &Connection,
+    replays: &[Replay],
+    options: &SqliteOptions,
+) -> Result<(), ReplayDataError<'static>> {
+    let mut insert_replay = connection
+        .prepare(
+            "INSERT INTO replays (replay_md5, beatmap_md5, player_name, game_mode, version, mods, \
+             n300, n100, n50, n_geki, n_katu, n_miss, total_score, greatest_combo, perfect, \
+             accuracy, time_stamp, online_score_id) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .map_err(sql_error)?;
+    let mut insert_frame = connection
+        .prepare(
+            "INSERT INTO frames (replay_id, frame_index, time, x, y, keys) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .map_err(sql_error)?;
+    let mut insert_metric = connection
+        .prepare("INSERT INTO metrics (replay_id, analyzer, name, value) VALUES (?, ?, ?, ?)")
+        .map_err(sql_error)?;
+    let mut insert_finding = connection
+        .prepare("INSERT INTO findings (replay_id, analyzer, time, message) VALUES (?, ?, ?, ?)")
+        .map_err(sql_error)?;
+
+    for replay in replays {
+        insert_replay
+            .execute(params![
+                replay.replay_md5,
+                replay.beatmap_md5,
+                replay.player_name,
+                u8::from(replay.game_mode),
+                replay.version,
+                replay.mods,
+                replay.n300,
+                replay.n100,
+                replay.n50,
+                replay.n_geki,
+                replay.n_katu,
+                replay.n_miss,
+                replay.total_score,
+                replay.greatest_combo,
+                replay.perfect_byte(),
+                replay.accuracy(),
+                replay.time_stamp,
+                replay.online_score_id,
+            ])
+            .map_err(sql_error)?;
+        let id = connection.last_insert_rowid();
+
+        if !options.include_frames && options.analyzers.is_empty() {
+            continue;
+        }
+        let frames = replay.get_actions_with_progress(|_, _| {})?;
+        if options.include_frames {
+            for (index, (time, frame)) in frames.timed().enumerate() {
+                insert_frame
+                    .execute(params![id, index, time, frame.x, frame.y, frame.keys])
+                    .map_err(sql_error)?;
+            }
+        }
+        for (analyzer, report) in options.analyzers.run(replay, &frames) {
+            for (name, value) in &report.metrics {
+                insert_metric
+                    .execute(params![id, analyzer, name, value])
+                    .map_err(sql_error)?;
+            }
+            for finding in &report.findings {
+                insert_finding
+                    .execute(params![id, analyzer, finding.time, finding.message])
+                    .map_err(sql_error)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,489 @@
+use std::fmt::Write;
+
+use crate::beatmap::Beatmap;
+use crate::export::editor_timestamp;
+use crate::replay::{GameMode, Replay, ReplayFormat};
+
+/// Windows ticks, the unit of [`Replay::time_stamp`](crate::Replay::time_stamp), per millisecond.
+pub const TICKS_PER_MILLISECOND: i64 = 10_000;
+
+/// Windows ticks between 0001-01-01 and the Unix epoch.
+pub const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+/// Shown in place of NaN and infinite numbers, e.g. the accuracy of a replay without hits.
+pub const NOT_A_NUMBER: &str = "-";
+
+/// Order of the day, month and year in formatted dates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    /// 2024-03-09, used in ISO 8601 and East Asian locales.
+    #[default]
+    YearMonthDay,
+    /// 09.03.2024, used in most of Europe.
+    DayMonthYear,
+    /// 03/09/2024, used in the United States.
+    MonthDayYear,
+}
+
+/// Options for formatting numbers and dates for display, e.g. by chat bots presenting replays.
+///
+/// The default formats like `1,234,567`, `98.76%` and `2024-03-09 14:05` in UTC.
+/// The crate cannot read the timezone of the system, pass the offset of the user with
+/// [`Self::with_utc_offset`].
+/// # Example
+/// ```
+/// use osu_replay_parser::format::FormatOptions;
+///
+/// let options = FormatOptions::for_locale("de-DE").unwrap().with_utc_offset(60);
+/// assert_eq!(options.format_integer(12345678), "12.345.678");
+/// assert_eq!(options.format_accuracy(0.98765), "98,77%");
+/// // 2024-03-09 13:05 UTC
+/// assert_eq!(options.format_timestamp(638_455_863_000_000_000), "09.03.2024 14:05");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Separator between groups of three digits, `None` to not group digits.
+    pub thousands_separator: Option<char>,
+    /// Separator between the integer and the fractional part.
+    pub decimal_separator: char,
+    /// Order of the date components.
+    pub date_order: DateOrder,
+    /// Separator between the date components.
+    pub date_separator: char,
+    /// Offset of the displayed times from UTC in minutes.
+    pub utc_offset: i32,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+            date_order: DateOrder::YearMonthDay,
+            date_separator: '-',
+            utc_offset: 0,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Options for a BCP 47 language tag such as `en-US`, `de` or `pt-BR`, `None` for unknown languages.
+    ///
+    /// Only the separators and the date order are set, the times stay in UTC.
+    pub fn for_locale(tag: &str) -> Option<Self> {
+        let tag = tag.replace('_', "-").to_lowercase();
+        let (language, region) = tag.split_once('-').unwrap_or((&tag, ""));
+        let european = |thousands| FormatOptions {
+            thousands_separator: Some(thousands),
+            decimal_separator: ',',
+            date_order: DateOrder::DayMonthYear,
+            date_separator: '.',
+            utc_offset: 0,
+        };
+
+        let options = match language {
+            "en" if region == "us" || region.is_empty() => FormatOptions {
+                date_order: DateOrder::MonthDayYear,
+                date_separator: '/',
+                ..FormatOptions::default()
+            },
+            "en" => FormatOptions {
+                date_order: DateOrder::DayMonthYear,
+                date_separator: '/',
+                ..FormatOptions::default()
+            },
+            "de" | "nl" | "da" | "id" | "tr" => european('.'),
+            "es" | "it" | "pt" => FormatOptions {
+                date_separator: '/',
+                ..european('.')
+            },
+            "fr" => FormatOptions {
+                date_separator: '/',
+                ..european('\u{202f}')
+            },
+            "ru" | "pl" | "uk" | "cs" | "fi" | "sv" | "nb" | "no" => european('\u{a0}'),
+            "ja" | "zh" | "ko" => FormatOptions {
+                date_separator: '/',
+                ..FormatOptions::default()
+            },
+            _ => return None,
+        };
+        Some(options)
+    }
+
+    /// Display times with an offset from UTC in minutes, e.g. `-300` for US Eastern Standard Time.
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// Format an integer with the thousands separator, e.g. a score.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::format::FormatOptions;
+    ///
+    /// let options = FormatOptions::default();
+    /// assert_eq!(options.format_integer(999), "999");
+    /// assert_eq!(options.format_integer(-1234567), "-1,234,567");
+    /// ```
+    pub fn format_integer(&self, value: i64) -> String {
+        let mut formatted = String::new();
+        if value < 0 {
+            formatted.push('-');
+        }
+        self.push_grouped(&mut formatted, &value.unsigned_abs().to_string());
+        formatted
+    }
+
+    /// Append ASCII digits with the thousands separator between each group of three.
+    fn push_grouped(&self, formatted: &mut String, digits: &str) {
+        formatted.reserve(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                formatted.extend(self.thousands_separator);
+            }
+            formatted.push(digit);
+        }
+    }
+
+    /// Format a number with a fixed number of decimals. NaN and infinite values are shown as
+    /// [`NOT_A_NUMBER`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::format::{FormatOptions, NOT_A_NUMBER};
+    ///
+    /// let options = FormatOptions::for_locale("fr").unwrap();
+    /// assert_eq!(options.format_decimal(1234.5, 2), "1\u{202f}234,50");
+    ///
+    /// let options = FormatOptions::default();
+    /// assert_eq!(options.format_decimal(-1e20, 1), "-100,000,000,000,000,000,000.0");
+    /// assert_eq!(options.format_decimal(f64::NAN, 2), NOT_A_NUMBER);
+    /// assert_eq!(options.format_decimal(f64::INFINITY, 2), NOT_A_NUMBER);
+    /// ```
+    pub fn format_decimal(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return NOT_A_NUMBER.to_string();
+        }
+        let fixed = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let mut formatted = if value < 0.0 && fixed.bytes().any(|b| b.is_ascii_digit() && b != b'0')
+        {
+            "-".to_string()
+        } else {
+            String::new()
+        };
+        self.push_grouped(&mut formatted, integer);
+        if !fraction.is_empty() {
+            formatted.push(self.decimal_separator);
+            formatted += fraction;
+        }
+        formatted
+    }
+
+    /// Format an accuracy in the range `0.0..=1.0` as a percentage with two decimals, or
+    /// [`NOT_A_NUMBER`] if it is NaN or infinite.
+    pub fn format_accuracy(&self, accuracy: f64) -> String {
+        if !accuracy.is_finite() {
+            return NOT_A_NUMBER.to_string();
+        }
+        self.format_decimal(accuracy * 100.0, 2) + "%"
+    }
+
+    /// Format a time stamp in Windows ticks, see [`Replay::time_stamp`](crate::Replay::time_stamp),
+    /// as date and time in minutes, shifted by the UTC offset.
+    pub fn format_timestamp(&self, ticks: i64) -> String {
+        let minutes = ticks.div_euclid(TICKS_PER_MILLISECOND * 60_000) + i64::from(self.utc_offset);
+        let (year, month, day) = civil_from_days(minutes.div_euclid(24 * 60));
+        let minute_of_day = minutes.rem_euclid(24 * 60);
+
+        let s = self.date_separator;
+        let mut formatted = match self.date_order {
+            DateOrder::YearMonthDay => format!("{:04}{}{:02}{}{:02}", year, s, month, s, day),
+            DateOrder::DayMonthYear => format!("{:02}{}{:02}{}{:04}", day, s, month, s, year),
+            DateOrder::MonthDayYear => format!("{:02}{}{:02}{}{:04}", month, s, day, s, year),
+        };
+        let _ = write!(
+            formatted,
+            " {:02}:{:02}",
+            minute_of_day / 60,
+            minute_of_day % 60
+        );
+        formatted
+    }
+}
+
+/// Convert Windows ticks to milliseconds since the Unix epoch.
+///
+/// Time stamps are read from the replay file unchecked, so the difference to the Unix epoch
+/// saturates instead of overflowing for crafted values near `i64::MIN`.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::ticks_to_unix_millis;
+///
+/// assert_eq!(ticks_to_unix_millis(621_355_968_000_000_000), 0);
+/// assert_eq!(ticks_to_unix_millis(i64::MIN), i64::MIN.div_euclid(10_000));
+/// ```
+pub fn ticks_to_unix_millis(ticks: i64) -> i64 {
+    ticks
+        .saturating_sub(UNIX_EPOCH_TICKS)
+        .div_euclid(TICKS_PER_MILLISECOND)
+}
+
+/// How Discord displays a timestamp, in the time zone and locale of the reader.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DiscordTimestampStyle {
+    /// `14:05`
+    ShortTime,
+    /// `14:05:30`
+    LongTime,
+    /// `09/03/2024`
+    ShortDate,
+    /// `9 March 2024`
+    LongDate,
+    /// `9 March 2024 14:05`
+    #[default]
+    ShortDateTime,
+    /// `Saturday, 9 March 2024 14:05`
+    LongDateTime,
+    /// `2 months ago`
+    Relative,
+}
+
+/// Format a time stamp in Windows ticks as a Discord timestamp, e.g. `<t:1709989500:f>`.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::{discord_timestamp, DiscordTimestampStyle};
+///
+/// // 2024-03-09 13:05 UTC
+/// let ticks = 638_455_863_000_000_000;
+/// assert_eq!(discord_timestamp(ticks, DiscordTimestampStyle::Relative), "<t:1709989500:R>");
+/// ```
+pub fn discord_timestamp(ticks: i64, style: DiscordTimestampStyle) -> String {
+    let style = match style {
+        DiscordTimestampStyle::ShortTime => 't',
+        DiscordTimestampStyle::LongTime => 'T',
+        DiscordTimestampStyle::ShortDate => 'd',
+        DiscordTimestampStyle::LongDate => 'D',
+        DiscordTimestampStyle::ShortDateTime => 'f',
+        DiscordTimestampStyle::LongDateTime => 'F',
+        DiscordTimestampStyle::Relative => 'R',
+    };
+    format!(
+        "<t:{}:{}>",
+        ticks_to_unix_millis(ticks).div_euclid(1000),
+        style
+    )
+}
+
+/// Link to the score of a replay on the osu! website, `None` if the score was not submitted.
+///
+/// osu!stable scores are linked per game mode, osu!lazer scores share one ID space. Scores of
+/// [`GameMode::Unknown`] game modes have no page on the website.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::score_url;
+/// use osu_replay_parser::replay::GameMode;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay {
+///     game_mode: GameMode::Mania,
+///     version: 20240101,
+///     online_score_id: 123456,
+///     ..Replay::default()
+/// };
+/// assert_eq!(score_url(&replay).unwrap(), "https://osu.ppy.sh/scores/mania/123456");
+/// assert_eq!(score_url(&Replay::default()), None);
+/// ```
+pub fn score_url(replay: &Replay) -> Option<String> {
+    if replay.online_score_id <= 0 {
+        return None;
+    }
+    if replay.format() == ReplayFormat::Lazer {
+        return Some(format!(
+            "https://osu.ppy.sh/scores/{}",
+            replay.online_score_id
+        ));
+    }
+
+    let mode = match replay.game_mode {
+        GameMode::Osu => "osu",
+        GameMode::Taiko => "taiko",
+        GameMode::CatchTheBeat => "fruits",
+        GameMode::Mania => "mania",
+        GameMode::Unknown(_) => return None,
+    };
+    Some(format!(
+        "https://osu.ppy.sh/scores/{}/{}",
+        mode, replay.online_score_id
+    ))
+}
+
+/// Link to the profile of a player on the osu! website.
+///
+/// Profiles are looked up by name, so the link leads to another player if the name was changed
+/// and taken by someone else.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::user_url;
+///
+/// assert_eq!(user_url("Some Player"), "https://osu.ppy.sh/users/Some%20Player");
+/// ```
+pub fn user_url(player_name: &str) -> String {
+    let mut url = String::from("https://osu.ppy.sh/users/");
+    percent_encode(&mut url, player_name.trim());
+    url
+}
+
+/// `osu://` link spectating the player of a replay in the running game client.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::spectate_link;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay {
+///     player_name: "Some Player".to_string(),
+///     ..Replay::default()
+/// };
+/// assert_eq!(spectate_link(&replay), "osu://spectate/Some%20Player");
+/// ```
+pub fn spectate_link(replay: &Replay) -> String {
+    let mut link = String::from("osu://spectate/");
+    percent_encode(&mut link, replay.player_name.trim());
+    link
+}
+
+/// `osu://` link opening a beatmap in the game client, `None` if the beatmap has no online ID.
+///
+/// Beatmaps that are not installed are offered for download with osu!direct.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::format::beatmap_link;
+///
+/// let beatmap = Beatmap::parse("[Metadata]\nBeatmapID:129891\nBeatmapSetID:39804\n").unwrap();
+/// assert_eq!(beatmap_link(&beatmap).unwrap(), "osu://b/129891");
+/// assert_eq!(beatmap_link(&Beatmap::default()), None);
+/// ```
+pub fn beatmap_link(beatmap: &Beatmap) -> Option<String> {
+    (beatmap.beatmap_id != 0).then(|| format!("osu://b/{}", beatmap.beatmap_id))
+}
+
+/// `osu://` link downloading the beatmap set of a beatmap with osu!direct, `None` if the set has
+/// no online ID.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::format::download_link;
+///
+/// let beatmap = Beatmap::parse("[Metadata]\nBeatmapID:129891\nBeatmapSetID:39804\n").unwrap();
+/// assert_eq!(download_link(&beatmap).unwrap(), "osu://dl/39804");
+/// ```
+pub fn download_link(beatmap: &Beatmap) -> Option<String> {
+    (beatmap.beatmap_set_id != 0).then(|| format!("osu://dl/{}", beatmap.beatmap_set_id))
+}
+
+/// `osu://` link opening the editor at a time, see [`editor_timestamp`]. The beatmap has to be
+/// open in the game client already.
+///
+/// Only the spaces of the timestamp are escaped, osu! expects the colons and combo numbers as is.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::edit_link;
+///
+/// assert_eq!(edit_link(62345, None), "osu://edit/01:02:345%20-%20");
+/// ```
+pub fn edit_link(time: i64, beatmap: Option<&Beatmap>) -> String {
+    format!(
+        "osu://edit/{}",
+        editor_timestamp(time, beatmap).replace(' ', "%20")
+    )
+}
+
+/// Escape the characters Discord interprets as markdown, e.g. the underscores in player names.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::escape_discord_markdown;
+///
+/// assert_eq!(escape_discord_markdown("_cool_*player*"), "\\_cool\\_\\*player\\*");
+/// ```
+pub fn escape_discord_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '[' | ']' | '(' | ')') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A short Discord message describing a replay, with the player, score, accuracy, combo, misses,
+/// mods, the time it was played and a link to the score if it was submitted.
+/// # Example
+/// ```
+/// use osu_replay_parser::format::{discord_summary, FormatOptions};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let summary = discord_summary(&replay, &FormatOptions::default());
+/// assert!(summary.starts_with(&format!("**{}**", replay.player_name)));
+/// assert!(summary.contains("7 ❌"));
+///
+/// // Crafted time stamps do not overflow
+/// let crafted = Replay { time_stamp: i64::MIN, ..replay };
+/// assert!(discord_summary(&crafted, &FormatOptions::default()).contains("<t:"));
+/// ```
+pub fn discord_summary(replay: &Replay, options: &FormatOptions) -> String {
+    let mods = replay.mods();
+    let mods = if mods.is_empty() {
+        "NM".to_string()
+    } else {
+        mods.acronyms()
+    };
+
+    let mut summary = format!(
+        "**{}** +{} | {} | {} | {}x | {} ❌\nPlayed {}",
+        escape_discord_markdown(&replay.player_name),
+        mods,
+        options.format_integer(replay.total_score.into()),
+        options.format_accuracy(replay.hit_statistics().accuracy()),
+        options.format_integer(replay.greatest_combo.into()),
+        options.format_integer(replay.n_miss.into()),
+        discord_timestamp(replay.time_stamp, DiscordTimestampStyle::Relative)
+    );
+    if let Some(url) = score_url(replay) {
+        let _ = write!(summary, "\n<{}>", url);
+    }
+    summary
+}
+
+/// Append the text to a URL, escaping all bytes but unreserved characters.
+fn percent_encode(url: &mut String, text: &str) {
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(char::from(byte));
+        } else {
+            let _ = write!(url, "%{:02X}", byte);
+        }
+    }
+}
+
+/// Year, month and day of a day counted from 0001-01-01 in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Shift to 0000-03-01 so leap days are at the end of the 400 year era
+    let days = days + 306;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,757 @@
+use bitflags::bitflags;
+
+use crate::delta::DeltaFrames;
+use crate::game_math::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::merkle::MerkleTree;
+use crate::replay::ReplayData;
+
+/// Time delta of the frame osu! appends to the end of replays to store the RNG seed.
+pub const SEED_FRAME_TIME: i64 = -12345;
+
+bitflags! {
+    /// Keys pressed in a frame of an osu!standard replay.
+    ///
+    /// Keyboard keys also set the bit of the corresponding mouse button.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Keys: u32 {
+        /// Left mouse button.
+        const M1 = 1 << 0;
+        /// Right mouse button.
+        const M2 = 1 << 1;
+        /// First keyboard key.
+        const K1 = 1 << 2;
+        /// Second keyboard key.
+        const K2 = 1 << 3;
+        /// Smoke key.
+        const SMOKE = 1 << 4;
+    }
+}
+
+impl Keys {
+    /// Keys that can be used to hit objects.
+    pub const HIT: Keys = Keys::M1.union(Keys::M2);
+
+    /// Split the keys into the individual input sources, keyboard keys are not additionally reported as mouse buttons.
+    pub fn sources(self) -> impl Iterator<Item = Keys> {
+        let first = if self.contains(Keys::K1) {
+            Keys::K1
+        } else {
+            self.intersection(Keys::M1)
+        };
+        let second = if self.contains(Keys::K2) {
+            Keys::K2
+        } else {
+            self.intersection(Keys::M2)
+        };
+
+        [first, second].into_iter().filter(|key| !key.is_empty())
+    }
+}
+
+impl ReplayData {
+    /// The keys pressed in this frame, interpreted as osu!standard keys.
+    pub fn pressed_keys(&self) -> Keys {
+        Keys::from_bits_truncate(self.keys)
+    }
+
+    /// Whether this is the frame storing the RNG seed, see [`SEED_FRAME_TIME`].
+    pub fn is_seed_frame(&self) -> bool {
+        self.time == SEED_FRAME_TIME
+    }
+}
+
+/// Extension methods for a list of [`ReplayData`] frames as returned by [`Replay::get_actions`](crate::Replay::get_actions).
+///
+/// The `time` of the frames is the delta to the previous frame, as stored in the replay.
+pub trait FramesExt {
+    /// Iterate over the frames together with their absolute time in milliseconds.
+    ///
+    /// The seed frame is skipped.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::{frames::FramesExt, Replay};
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let actions = replay.get_actions().expect("Error getting actions");
+    /// let (end, _) = actions.timed().last().unwrap();
+    /// assert!(end > 0);
+    /// ```
+    fn timed(&self) -> impl Iterator<Item = (i64, &ReplayData)>;
+
+    /// The seed frame of the replay, if present.
+    fn seed_frame(&self) -> Option<&ReplayData>;
+
+    /// Iterate over the frames in which at least one key was pressed down, together with
+    /// their absolute time and the keys that were newly pressed.
+    fn key_presses(&self) -> impl Iterator<Item = (i64, &ReplayData, Keys)>;
+
+    /// Time in milliseconds between the first and the last frame, 0 if there are fewer than two frames.
+    ///
+    /// Times are accumulated as 64-bit integers, so replays lasting days do not overflow.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// // A 30 hour marathon with a frame every 16ms
+    /// let frames = vec![ReplayData { time: 16, x: 256.0, y: 192.0, keys: 0 }; 30 * 60 * 60 * 1000 / 16];
+    /// assert_eq!(frames.duration(), 30 * 60 * 60 * 1000 - 16);
+    /// ```
+    fn duration(&self) -> i64;
+
+    /// Time in milliseconds between frames, excluding gaps longer than [`MAX_INTERPOLATION_GAP`]
+    /// such as the skipped intro.
+    fn active_time(&self) -> i64;
+
+    /// Length in milliseconds of the intro skipped by the player, 0 if the intro was not skipped.
+    ///
+    /// A skip is a gap longer than [`MAX_INTERPOLATION_GAP`] between two frames before the first key press.
+    fn skipped_intro(&self) -> i64;
+
+    /// Simplify the cursor path with the Ramer–Douglas–Peucker algorithm.
+    ///
+    /// Frames are dropped as long as the path stays within `epsilon` osu!pixels of the original path.
+    /// The first and last frame, frames where the pressed keys change and the seed frame are always kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames: Vec<ReplayData> = (0..100)
+    ///     .map(|i| ReplayData { time: 16, x: i as f32, y: i as f32, keys: 0 })
+    ///     .collect();
+    /// assert_eq!(frames.simplify(0.5).len(), 2);
+    /// ```
+    fn simplify(&self, epsilon: f32) -> Vec<ReplayData>;
+
+    /// Start a query selecting frames by time, keys and cursor speed.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::{FramesExt, Keys};
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let actions = replay.get_actions().expect("Error getting actions");
+    /// let fast_k1 = actions
+    ///     .query()
+    ///     .between(10_000, 20_000)
+    ///     .with_key(Keys::K1)
+    ///     .min_speed(800.0)
+    ///     .collect();
+    /// assert!(fast_k1.iter().all(|(time, _)| (10_000..=20_000).contains(time)));
+    /// ```
+    fn query(&self) -> FrameQuery<'_>;
+
+    /// Hash the frames in chunks of `chunk_size` frames, see [`MerkleTree`].
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0.
+    fn merkle_hash(&self, chunk_size: usize) -> MerkleTree;
+
+    /// Delta encode the frames with positions rounded to `precision` decimal places, see
+    /// [`DeltaFrames`].
+    ///
+    /// # Panics
+    /// Panics if `precision` is larger than [`MAX_DELTA_PRECISION`](crate::delta::MAX_DELTA_PRECISION).
+    fn delta_encode(&self, precision: u8) -> DeltaFrames;
+
+    /// Resample the frames onto a grid with a frame every `ms_grid` milliseconds, starting at the first frame.
+    ///
+    /// Positions are interpolated and keys taken from the last frame at or before each grid time,
+    /// see [`Timeline::state_at`]. Key presses shorter than the grid can be lost. The seed frame is kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames: Vec<ReplayData> = [(0, 0.0), (7, 7.0), (9, 16.0)]
+    ///     .into_iter()
+    ///     .map(|(time, x)| ReplayData { time, x, y: 0.0, keys: 0 })
+    ///     .collect();
+    /// let grid = frames.requantize(8);
+    /// assert_eq!(grid.iter().map(|f| f.time).collect::<Vec<_>>(), vec![0, 8, 8]);
+    /// assert_eq!(grid[1].x, 8.0);
+    /// ```
+    /// # Panics
+    /// Panics if `ms_grid` is 0.
+    fn requantize(&self, ms_grid: i64) -> Vec<ReplayData>;
+
+    /// Smooth the cursor positions with the given filter, for cleaning noisy tablet data.
+    ///
+    /// Times and keys are unchanged. The filters weigh neighbouring frames equally regardless of
+    /// their time, so frames should be evenly spaced, e.g. by [`Self::requantize`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::{FramesExt, Smoothing};
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames: Vec<ReplayData> = [0.0, 0.0, 9.0, 0.0, 0.0]
+    ///     .into_iter()
+    ///     .map(|x| ReplayData { time: 16, x, y: 0.0, keys: 0 })
+    ///     .collect();
+    /// let smoothed = frames.smooth(Smoothing::MovingAverage { window: 3 });
+    /// assert_eq!(smoothed[2].x, 3.0);
+    /// ```
+    fn smooth(&self, filter: Smoothing) -> Vec<ReplayData>;
+
+    /// Shift the frames after the skipped intro by `ms` milliseconds, e.g. to correct a replay
+    /// recorded with a wrong local offset.
+    ///
+    /// Frames before and at the start of the skip stay in place, only the skip gap is lengthened or
+    /// shortened. Without a skip all frames are shifted. The seed frame is kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames: Vec<ReplayData> = [0, 20, 5000, 16, 16]
+    ///     .into_iter()
+    ///     .map(|time| ReplayData { time, x: 256.0, y: 192.0, keys: 0 })
+    ///     .collect();
+    /// let shifted = frames.apply_offset(-25);
+    /// let times: Vec<i64> = shifted.timed().map(|(time, _)| time).collect();
+    /// assert_eq!(times, vec![0, 20, 4995, 5011, 5027]);
+    /// ```
+    /// # Panics
+    /// Panics if a negative offset is larger than the skip gap, which would move the frames after
+    /// the skip before the frames of the intro.
+    fn apply_offset(&self, ms: i64) -> Vec<ReplayData>;
+
+    /// Scale the times of the frames by `factor`, e.g. `1.0 / 1.5` to play a replay 1.5 times as
+    /// fast.
+    ///
+    /// The absolute times are scaled and rounded, so rounding errors do not add up over long
+    /// replays. The seed frame is kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames = vec![ReplayData { time: 16, x: 256.0, y: 192.0, keys: 0 }; 4];
+    /// let faster = frames.scale_time(1.0 / 1.5);
+    /// let times: Vec<i64> = faster.timed().map(|(time, _)| time).collect();
+    /// assert_eq!(times, vec![11, 21, 32, 43]);
+    /// ```
+    /// # Panics
+    /// Panics if `factor` is not finite and positive.
+    fn scale_time(&self, factor: f64) -> Vec<ReplayData>;
+
+    /// Flip the cursor positions vertically like Hard Rock flips the beatmap, e.g. to compare a
+    /// Hard Rock replay with the unmodified beatmap.
+    ///
+    /// Flipping twice restores the frames. The seed frame is kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames = vec![ReplayData { time: 16, x: 100.0, y: 50.0, keys: 0 }];
+    /// let flipped = frames.flip_vertical();
+    /// assert_eq!((flipped[0].x, flipped[0].y), (100.0, 334.0));
+    /// assert_eq!(flipped.flip_vertical(), frames);
+    /// ```
+    fn flip_vertical(&self) -> Vec<ReplayData>;
+
+    /// Mirror the cursor positions horizontally, as the osu!standard Mirror mod of osu!lazer does.
+    ///
+    /// osu!mania replays store the pressed columns in the x coordinate, see
+    /// [`mania::mirror`](crate::mania::mirror) for the osu!mania Mirror mod. The seed frame is kept.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames = vec![ReplayData { time: 16, x: 100.0, y: 50.0, keys: 0 }];
+    /// let mirrored = frames.mirror();
+    /// assert_eq!((mirrored[0].x, mirrored[0].y), (412.0, 50.0));
+    /// ```
+    fn mirror(&self) -> Vec<ReplayData>;
+}
+
+impl FramesExt for [ReplayData] {
+    fn timed(&self) -> impl Iterator<Item = (i64, &ReplayData)> {
+        self.iter()
+            .filter(|frame| !frame.is_seed_frame())
+            .scan(0i64, |time, frame| {
+                *time += frame.time;
+                Some((*time, frame))
+            })
+    }
+
+    fn seed_frame(&self) -> Option<&ReplayData> {
+        self.iter().rev().find(|frame| frame.is_seed_frame())
+    }
+
+    fn key_presses(&self) -> impl Iterator<Item = (i64, &ReplayData, Keys)> {
+        self.timed()
+            .scan(Keys::empty(), |previous, (time, frame)| {
+                let keys = frame.pressed_keys();
+                let pressed = keys.difference(*previous);
+                *previous = keys;
+                Some((time, frame, pressed))
+            })
+            .filter(|(_, _, pressed)| !pressed.is_empty())
+    }
+
+    fn duration(&self) -> i64 {
+        let mut times = self.timed().map(|(time, _)| time);
+        let Some(first) = times.next() else {
+            return 0;
+        };
+        times.last().map_or(0, |last| (last - first).max(0))
+    }
+
+    fn active_time(&self) -> i64 {
+        self.timed()
+            .map(|(time, _)| time)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|&gap| (0..=MAX_INTERPOLATION_GAP).contains(&gap))
+            .sum()
+    }
+
+    fn skipped_intro(&self) -> i64 {
+        intro_skip(self).map_or(0, |(_, gap)| gap)
+    }
+
+    fn simplify(&self, epsilon: f32) -> Vec<ReplayData> {
+        let frames: Vec<ReplayData> = self
+            .timed()
+            .map(|(time, frame)| ReplayData { time, ..*frame })
+            .collect();
+        let mut keep = vec![false; frames.len()];
+        let mut anchors = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            if i == 0 || i == frames.len() - 1 || frame.keys != frames[i - 1].keys {
+                anchors.push(i);
+            }
+        }
+        for pair in anchors.windows(2) {
+            ramer_douglas_peucker(&frames, pair[0], pair[1], epsilon, &mut keep);
+        }
+        for anchor in anchors {
+            keep[anchor] = true;
+        }
+
+        let simplified = frames
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(frame, keep)| keep.then_some(frame));
+        from_timed(simplified, self.seed_frame())
+    }
+
+    fn query(&self) -> FrameQuery<'_> {
+        FrameQuery {
+            frames: self,
+            start: None,
+            end: None,
+            keys: Keys::empty(),
+            min_speed: None,
+            max_speed: None,
+        }
+    }
+
+    fn merkle_hash(&self, chunk_size: usize) -> MerkleTree {
+        MerkleTree::new(self, chunk_size)
+    }
+
+    fn delta_encode(&self, precision: u8) -> DeltaFrames {
+        DeltaFrames::encode(self, precision)
+    }
+
+    fn requantize(&self, ms_grid: i64) -> Vec<ReplayData> {
+        assert!(ms_grid > 0, "grid must be greater than 0");
+
+        let timeline = Timeline::new(self);
+        let (Some(start), Some(end)) = (timeline.start_time(), timeline.end_time()) else {
+            return self.to_vec();
+        };
+        let resampled = (start..=end).step_by(ms_grid as usize).filter_map(|time| {
+            let state = timeline.state_at(time as f64)?;
+            Some(ReplayData {
+                time,
+                x: state.x,
+                y: state.y,
+                keys: state.keys,
+            })
+        });
+        from_timed(resampled, self.seed_frame())
+    }
+
+    fn apply_offset(&self, ms: i64) -> Vec<ReplayData> {
+        assert!(
+            offset_keeps_order(self, ms),
+            "offset must not be larger than the skip gap"
+        );
+
+        let mut frames = self.to_vec();
+        // Times are deltas, so shifting a single frame shifts all frames after it
+        let start = match intro_skip(self) {
+            Some((index, _)) => Some(index),
+            None => self.iter().position(|frame| !frame.is_seed_frame()),
+        };
+        if let Some(start) = start {
+            frames[start].time += ms;
+        }
+        frames
+    }
+
+    fn scale_time(&self, factor: f64) -> Vec<ReplayData> {
+        assert!(
+            factor.is_finite() && factor > 0.0,
+            "factor must be finite and positive"
+        );
+
+        let scaled = self.timed().map(|(time, frame)| ReplayData {
+            time: (time as f64 * factor).round() as i64,
+            ..*frame
+        });
+        from_timed(scaled, self.seed_frame())
+    }
+
+    fn flip_vertical(&self) -> Vec<ReplayData> {
+        map_positions(self, |frame| (frame.x, PLAYFIELD_HEIGHT - frame.y))
+    }
+
+    fn mirror(&self) -> Vec<ReplayData> {
+        map_positions(self, |frame| (PLAYFIELD_WIDTH - frame.x, frame.y))
+    }
+
+    fn smooth(&self, filter: Smoothing) -> Vec<ReplayData> {
+        let half_window = match filter {
+            Smoothing::MovingAverage { window } | Smoothing::SavitzkyGolay { window } => window / 2,
+        };
+        let positions: Vec<(usize, f32, f32)> = self
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| !frame.is_seed_frame())
+            .map(|(i, frame)| (i, frame.x, frame.y))
+            .collect();
+
+        let mut smoothed = self.to_vec();
+        for (n, &(i, _, _)) in positions.iter().enumerate() {
+            // The window shrinks at the ends to stay centered
+            let m = half_window.min(n).min(positions.len() - 1 - n);
+            let weights = smoothing_weights(filter, m);
+            let neighbours = &positions[n - m..=n + m];
+            let (x, y, total) = neighbours
+                .iter()
+                .zip(&weights)
+                .fold((0.0, 0.0, 0.0), |(x, y, total), (&(_, px, py), &w)| {
+                    (x + px * w, y + py * w, total + w)
+                });
+            smoothed[i].x = x / total;
+            smoothed[i].y = y / total;
+        }
+        smoothed
+    }
+}
+
+/// Filter used by [`FramesExt::smooth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Smoothing {
+    /// Average of the frames in a centered window of `window` frames.
+    MovingAverage {
+        /// Number of frames, rounded up to the next odd number.
+        window: usize,
+    },
+    /// Savitzky-Golay filter fitting a quadratic polynomial to a centered window of `window` frames.
+    ///
+    /// Keeps the peaks of the cursor path better than the moving average.
+    SavitzkyGolay {
+        /// Number of frames, rounded up to the next odd number.
+        window: usize,
+    },
+}
+
+/// Index of the first frame after the skipped intro together with the length of the skip.
+fn intro_skip(frames: &[ReplayData]) -> Option<(usize, i64)> {
+    let mut previous = None;
+    let mut time = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.is_seed_frame() {
+            continue;
+        }
+        time += frame.time;
+        if let Some(previous) = previous {
+            if time - previous > MAX_INTERPOLATION_GAP {
+                return Some((index, time - previous));
+            }
+        }
+        if frame.pressed_keys().intersects(Keys::HIT) {
+            break;
+        }
+        previous = Some(time);
+    }
+    None
+}
+
+/// Whether [`FramesExt::apply_offset`] can shift the frames by `ms` without moving the frames
+/// after the skip before the frames of the intro.
+pub(crate) fn offset_keeps_order(frames: &[ReplayData], ms: i64) -> bool {
+    intro_skip(frames).is_none_or(|(_, gap)| gap + ms >= 0)
+}
+
+/// Weights of the `2m + 1` frames of a centered smoothing window.
+fn smoothing_weights(filter: Smoothing, m: usize) -> Vec<f32> {
+    let m = m as i64;
+    (-m..=m)
+        .map(|i| match filter {
+            Smoothing::MovingAverage { .. } => 1.0,
+            Smoothing::SavitzkyGolay { .. } => (3 * m * m + 3 * m - 1 - 5 * i * i) as f32,
+        })
+        .collect()
+}
+
+fn distance_to_segment(point: &ReplayData, start: &ReplayData, end: &ReplayData) -> f32 {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return (point.x - start.x).hypot(point.y - start.y);
+    }
+    let t =
+        (((point.x - start.x) * dx + (point.y - start.y) * dy) / length_squared).clamp(0.0, 1.0);
+    (point.x - (start.x + t * dx)).hypot(point.y - (start.y + t * dy))
+}
+
+fn ramer_douglas_peucker(
+    frames: &[ReplayData],
+    start: usize,
+    end: usize,
+    epsilon: f32,
+    keep: &mut [bool],
+) {
+    // Iterative to avoid deep recursion on replays with hundreds of thousands of frames
+    let mut stack = vec![(start, end)];
+    while let Some((start, end)) = stack.pop() {
+        keep[start] = true;
+        keep[end] = true;
+        let farthest = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    distance_to_segment(&frames[i], &frames[start], &frames[end]),
+                )
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, distance)) = farthest {
+            if distance > epsilon {
+                stack.push((start, index));
+                stack.push((index, end));
+            }
+        }
+    }
+}
+
+/// Query over frames, created with [`FramesExt::query`].
+///
+/// All conditions must hold for a frame to match.
+#[derive(Debug, Clone)]
+pub struct FrameQuery<'a> {
+    frames: &'a [ReplayData],
+    start: Option<i64>,
+    end: Option<i64>,
+    keys: Keys,
+    min_speed: Option<f32>,
+    max_speed: Option<f32>,
+}
+
+impl<'a> FrameQuery<'a> {
+    /// Only match frames with an absolute time in `start..=end` milliseconds.
+    pub fn between(mut self, start: i64, end: i64) -> Self {
+        self.start = Some(start);
+        self.end = Some(end);
+        self
+    }
+
+    /// Only match frames at or after `start` milliseconds.
+    pub fn after(mut self, start: i64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Only match frames at or before `end` milliseconds.
+    pub fn before(mut self, end: i64) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Only match frames in which the given keys are held. Can be called multiple times to require several keys.
+    pub fn with_key(mut self, key: Keys) -> Self {
+        self.keys |= key;
+        self
+    }
+
+    /// Only match frames where the cursor moved at least `speed` osu!pixels per second since the previous frame.
+    pub fn min_speed(mut self, speed: f32) -> Self {
+        self.min_speed = Some(speed);
+        self
+    }
+
+    /// Only match frames where the cursor moved at most `speed` osu!pixels per second since the previous frame.
+    pub fn max_speed(mut self, speed: f32) -> Self {
+        self.max_speed = Some(speed);
+        self
+    }
+
+    /// Iterate over the matching frames together with their absolute time.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &'a ReplayData)> + '_ {
+        let frames: &'a [ReplayData] = self.frames;
+        frames
+            .timed()
+            .scan(
+                None,
+                |previous: &mut Option<(i64, &ReplayData)>, (time, frame)| {
+                    let speed = previous.and_then(|(previous_time, previous_frame)| {
+                        let delta = time - previous_time;
+                        (delta > 0).then(|| {
+                            let distance =
+                                (frame.x - previous_frame.x).hypot(frame.y - previous_frame.y);
+                            distance / delta as f32 * 1000.0
+                        })
+                    });
+                    *previous = Some((time, frame));
+                    Some((time, frame, speed))
+                },
+            )
+            .filter(move |(time, frame, speed)| {
+                self.start.is_none_or(|start| *time >= start)
+                    && self.end.is_none_or(|end| *time <= end)
+                    && frame.pressed_keys().contains(self.keys)
+                    && self
+                        .min_speed
+                        .is_none_or(|min| speed.is_some_and(|speed| speed >= min))
+                    && self
+                        .max_speed
+                        .is_none_or(|max| speed.is_some_and(|speed| speed <= max))
+            })
+            .map(|(time, frame, _)| (time, frame))
+    }
+
+    /// Collect the matching frames together with their absolute time.
+    pub fn collect(&self) -> Vec<(i64, &'a ReplayData)> {
+        self.iter().collect()
+    }
+
+    /// Number of matching frames.
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+/// Move the cursor of every frame except the seed frame.
+fn map_positions<F>(frames: &[ReplayData], position: F) -> Vec<ReplayData>
+where
+    F: Fn(&ReplayData) -> (f32, f32),
+{
+    frames
+        .iter()
+        .map(|frame| {
+            if frame.is_seed_frame() {
+                return *frame;
+            }
+            let (x, y) = position(frame);
+            ReplayData { x, y, ..*frame }
+        })
+        .collect()
+}
+
+/// Gaps between frames longer than this, in milliseconds, are not interpolated, e.g. the skipped intro of a replay.
+pub const MAX_INTERPOLATION_GAP: i64 = 1000;
+
+/// Cursor position and keys at an arbitrary point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CursorState {
+    /// The x-coordinate of the cursor.
+    pub x: f32,
+    /// The y-coordinate of the cursor.
+    pub y: f32,
+    /// The keys pressed.
+    pub keys: u32,
+    /// Whether the position was interpolated between two frames instead of taken from a frame.
+    pub interpolated: bool,
+}
+
+/// Frames with absolute times, supporting lookups of the cursor state at any point in time.
+#[derive(Debug, Default, Clone)]
+pub struct Timeline {
+    times: Vec<i64>,
+    frames: Vec<ReplayData>,
+}
+
+impl Timeline {
+    /// Create a timeline from frames with delta times. The seed frame is skipped.
+    pub fn new(frames: &[ReplayData]) -> Self {
+        let (times, frames) = frames.timed().map(|(time, frame)| (time, *frame)).unzip();
+        Timeline { times, frames }
+    }
+
+    /// Absolute time of the first frame, `None` if there are no frames.
+    pub fn start_time(&self) -> Option<i64> {
+        self.times.first().copied()
+    }
+
+    /// Absolute time of the last frame, `None` if there are no frames.
+    pub fn end_time(&self) -> Option<i64> {
+        self.times.last().copied()
+    }
+
+    /// Cursor state at the given time.
+    ///
+    /// Keys are taken from the last frame at or before `time`, the position is linearly
+    /// interpolated towards the next frame unless the gap exceeds [`MAX_INTERPOLATION_GAP`].
+    /// Times outside of the recorded range are clamped. Returns `None` if there are no frames.
+    pub fn state_at(&self, time: f64) -> Option<CursorState> {
+        let next = self.times.partition_point(|&t| t as f64 <= time);
+        let previous = next.checked_sub(1);
+
+        let (frame, interpolated) = match (previous, self.frames.get(next)) {
+            (None, next_frame) => (*next_frame?, false),
+            (Some(previous), None) => (self.frames[previous], false),
+            (Some(previous), Some(next_frame)) => {
+                let (start, end) = (self.times[previous], self.times[next]);
+                let frame = self.frames[previous];
+                if time == start as f64 || end - start > MAX_INTERPOLATION_GAP {
+                    (frame, false)
+                } else {
+                    let t = ((time - start as f64) / (end - start) as f64) as f32;
+                    let x = frame.x + (next_frame.x - frame.x) * t;
+                    let y = frame.y + (next_frame.y - frame.y) * t;
+                    (ReplayData { x, y, ..frame }, true)
+                }
+            }
+        };
+
+        Some(CursorState {
+            x: frame.x,
+            y: frame.y,
+            keys: frame.keys,
+            interpolated,
+        })
+    }
+}
+
+/// Build frames with delta times from frames with absolute times.
+///
+/// The `time` of the given frames is interpreted as absolute. If a seed frame is given, it is appended to the end.
+pub fn from_timed<I>(frames: I, seed_frame: Option<&ReplayData>) -> Vec<ReplayData>
+where
+    I: IntoIterator<Item = ReplayData>,
+{
+    let mut previous = 0;
+    let mut result: Vec<ReplayData> = frames
+        .into_iter()
+        .map(|frame| {
+            let delta = frame.time - previous;
+            previous = frame.time;
+            ReplayData {
+                time: delta,
+                ..frame
+            }
+        })
+        .collect();
+    result.extend(seed_frame.copied());
+
+    result
+}
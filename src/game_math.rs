@@ -0,0 +1,121 @@
+use crate::beatmap::Difficulty;
+use crate::replay::Mods;
+
+/// Width of the osu! playfield in osu!pixels.
+pub const PLAYFIELD_WIDTH: f32 = 512.0;
+/// Height of the osu! playfield in osu!pixels.
+pub const PLAYFIELD_HEIGHT: f32 = 384.0;
+/// Center of the osu! playfield in osu!pixels.
+pub const PLAYFIELD_CENTER: (f32, f32) = (PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+
+/// Offset of each stacked hit object relative to the object below, as a fraction of the circle radius.
+pub const STACK_OFFSET_FACTOR: f32 = 0.1;
+/// Default stack leniency used when a beatmap does not specify one.
+pub const DEFAULT_STACK_LENIENCY: f32 = 0.7;
+
+/// Map a difficulty value in the range `0..=10` to a value between `min`, `mid` and `max`,
+/// the way osu! does for all difficulty settings.
+pub fn difficulty_range(difficulty: f32, min: f32, mid: f32, max: f32) -> f32 {
+    if difficulty > 5.0 {
+        mid + (max - mid) * (difficulty - 5.0) / 5.0
+    } else if difficulty < 5.0 {
+        mid - (mid - min) * (5.0 - difficulty) / 5.0
+    } else {
+        mid
+    }
+}
+
+/// Scale of hit objects for the given circle size, relative to a 64 osu!pixel wide sprite.
+pub fn circle_scale(cs: f32) -> f32 {
+    (1.0 - 0.7 * (cs - 5.0) / 5.0) / 2.0
+}
+
+/// Radius of a hit circle in osu!pixels for the given circle size.
+/// # Example
+/// ```
+/// use osu_replay_parser::game_math::circle_radius;
+///
+/// assert!((circle_radius(4.0) - 36.48).abs() < 0.01);
+/// ```
+pub fn circle_radius(cs: f32) -> f32 {
+    64.0 * circle_scale(cs)
+}
+
+/// Offset in osu!pixels applied to both coordinates of a hit object at the given stack height.
+///
+/// Stacks grow towards the top left, so the offset is negative for positive stack heights.
+pub fn stack_offset(cs: f32, stack_height: i32) -> f32 {
+    -(stack_height as f32) * circle_radius(cs) * STACK_OFFSET_FACTOR
+}
+
+/// Time in milliseconds between the approach circle appearing and the hit object's time,
+/// for the given approach rate.
+pub fn approach_time(ar: f32) -> f32 {
+    difficulty_range(ar, 1800.0, 1200.0, 450.0)
+}
+
+/// Approach rate for the given approach time in milliseconds, the inverse of [`approach_time`].
+///
+/// The result is not clamped, so very short approach times yield approach rates above 10.
+pub fn approach_rate(approach_time: f32) -> f32 {
+    if approach_time > 1200.0 {
+        (1800.0 - approach_time) / 120.0
+    } else {
+        5.0 + (1200.0 - approach_time) / 150.0
+    }
+}
+
+/// Hit window in milliseconds after which an early hit on a hit circle is not ignored anymore but counted as a miss.
+pub const MISS_WINDOW: f32 = 400.0;
+
+/// Hit windows of osu!standard in milliseconds, measured from the hit object's time in both directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitWindows {
+    /// Window for a 300.
+    pub great: f32,
+    /// Window for a 100.
+    pub ok: f32,
+    /// Window for a 50.
+    pub meh: f32,
+}
+
+impl HitWindows {
+    /// Hit windows for the given overall difficulty.
+    pub fn from_od(od: f32) -> Self {
+        HitWindows {
+            great: difficulty_range(od, 80.0, 50.0, 20.0),
+            ok: difficulty_range(od, 140.0, 100.0, 60.0),
+            meh: difficulty_range(od, 200.0, 150.0, 100.0),
+        }
+    }
+
+    /// Hit windows in real time for the given overall difficulty of the beatmap and mods.
+    ///
+    /// Hard Rock and Easy change the overall difficulty, Double Time and Half Time scale the
+    /// windows by the playback speed. Timing errors classified with these windows have to be
+    /// measured in real time as well, i.e. divided by [`Mods::speed_multiplier`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::game_math::HitWindows;
+    /// use osu_replay_parser::replay::Mods;
+    ///
+    /// let windows = HitWindows::for_mods(8.0, Mods::DOUBLE_TIME);
+    /// assert_eq!(windows.great, HitWindows::from_od(8.0).great / 1.5);
+    /// ```
+    pub fn for_mods(od: f32, mods: Mods) -> Self {
+        let od = Difficulty {
+            overall_difficulty: od,
+            ..Difficulty::default()
+        }
+        .with_mods(mods)
+        .overall_difficulty;
+        let windows = HitWindows::from_od(od);
+        let speed = mods.speed_multiplier() as f32;
+
+        HitWindows {
+            great: windows.great / speed,
+            ok: windows.ok / speed,
+            meh: windows.meh / speed,
+        }
+    }
+}
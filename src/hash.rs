@@ -0,0 +1,135 @@
+use sha2::{Digest, Sha256};
+
+use crate::replay::Replay;
+
+/// Compute the MD5 digest of the data, as used by osu! for beatmap and replay hashes.
+/// # Example
+/// ```
+/// use osu_replay_parser::hash::{md5, to_hex};
+///
+/// assert_eq!(to_hex(&md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+/// ```
+#[cfg(feature = "md5")]
+pub fn md5(data: &[u8]) -> [u8; 16] {
+    ::md5::Md5::digest(data).into()
+}
+
+/// Format bytes as a lowercase hexadecimal string.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Compute the MD5 digest of the data as a lowercase hexadecimal string.
+#[cfg(feature = "md5")]
+pub fn md5_hex(data: &[u8]) -> String {
+    to_hex(&md5(data))
+}
+
+/// Compute the SHA-256 digest of the data, used where hashes must resist deliberate collisions
+/// instead of matching osu!.
+/// # Example
+/// ```
+/// use osu_replay_parser::hash::{sha256, to_hex};
+///
+/// assert_eq!(
+///     to_hex(&sha256(b"")),
+///     "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+/// );
+/// ```
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// An MD5 implementation used wherever this crate hashes data with MD5, e.g. to use a hardware
+/// accelerated implementation instead of [`BuiltinMd5`].
+///
+/// Functions hashing with MD5 have a `_with_hasher` variant taking a `&dyn Hasher`. Without the
+/// default `md5` feature no MD5 implementation is compiled in and only these variants exist.
+/// # Example
+/// ```
+/// use osu_replay_parser::hash::{md5, Hasher};
+/// use osu_replay_parser::ids::BeatmapMd5;
+///
+/// struct External;
+///
+/// impl Hasher for External {
+///     fn md5(&self, data: &[u8]) -> [u8; 16] {
+///         // Call into the implementation of the platform here
+///         md5(data)
+///     }
+/// }
+///
+/// let beatmap = b"osu file format v14";
+/// assert_eq!(BeatmapMd5::of_with_hasher(beatmap, &External), BeatmapMd5::of(beatmap));
+/// ```
+pub trait Hasher {
+    /// Compute the MD5 digest of the data.
+    fn md5(&self, data: &[u8]) -> [u8; 16];
+
+    /// Compute the MD5 digest of the data as a lowercase hexadecimal string.
+    fn md5_hex(&self, data: &[u8]) -> String {
+        to_hex(&self.md5(data))
+    }
+}
+
+/// The MD5 implementation of the `md-5` crate, see [`md5`].
+#[cfg(feature = "md5")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinMd5;
+
+#[cfg(feature = "md5")]
+impl Hasher for BuiltinMd5 {
+    fn md5(&self, data: &[u8]) -> [u8; 16] {
+        md5(data)
+    }
+}
+
+impl Replay {
+    /// A 128 bit key identifying the replay, e.g. for caching analysis results.
+    ///
+    /// The key is the MD5 hash of the beatmap hash, player name, timestamp, game mode, mods and
+    /// score of the replay, so the frame data does not need to be hashed. It is stable across
+    /// platforms and versions of this crate. Replays whose frames were edited keep their key.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    ///
+    /// let input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    /// let replay = Replay::parse(&input).expect("Error parsing replay");
+    /// let header = Replay::parse_with_options(&input, &ParseOptions::header_only()).unwrap();
+    /// assert_eq!(replay.cache_key(), header.cache_key());
+    ///
+    /// let other = Replay { total_score: replay.total_score + 1, ..replay.clone() };
+    /// assert_ne!(replay.cache_key(), other.cache_key());
+    /// // The key does not change between versions
+    /// let key = format!("{:032x}", Replay::default().cache_key());
+    /// assert_eq!(key, "f5cfd73023c1eedb6b9569736073f1dd");
+    /// ```
+    #[cfg(feature = "md5")]
+    pub fn cache_key(&self) -> u128 {
+        self.cache_key_with_hasher(&BuiltinMd5)
+    }
+
+    /// Like [`Self::cache_key`], hashing with another MD5 implementation.
+    pub fn cache_key_with_hasher(&self, hasher: &dyn Hasher) -> u128 {
+        let player_name = self
+            .player_name_raw
+            .as_deref()
+            .unwrap_or(self.player_name.as_bytes());
+        let beatmap_md5 = self.beatmap_md5.to_ascii_lowercase();
+
+        let mut data = Vec::with_capacity(64 + player_name.len() + beatmap_md5.len());
+        // Strings are prefixed with their length so fields cannot run into each other
+        for field in [beatmap_md5.as_bytes(), player_name] {
+            data.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            data.extend_from_slice(field);
+        }
+        data.extend_from_slice(&self.time_stamp.to_le_bytes());
+        data.push(self.game_mode.into());
+        data.extend_from_slice(&self.mods.to_le_bytes());
+        data.extend_from_slice(&self.total_score.to_le_bytes());
+        data.extend_from_slice(&self.online_score_id.to_le_bytes());
+
+        u128::from_be_bytes(hasher.md5(&data))
+    }
+}
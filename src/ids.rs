@@ -0,0 +1,314 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{ParseError, ReplayDataError};
+#[cfg(feature = "md5")]
+use crate::hash::BuiltinMd5;
+use crate::hash::{to_hex, Hasher};
+use crate::replay::{Replay, ReplayFormat};
+
+/// The MD5 hash of a beatmap file, identifying the beatmap a replay was played on.
+///
+/// Parsed from the 32 hexadecimal characters stored in replays and beatmap databases,
+/// upper and lower case digits are accepted. Displayed in lowercase.
+/// # Example
+/// ```
+/// use osu_replay_parser::ids::BeatmapMd5;
+///
+/// let hash: BeatmapMd5 = "D41D8CD98F00B204E9800998ECF8427E".parse().unwrap();
+/// assert_eq!(hash, BeatmapMd5::of(b""));
+/// assert_eq!(hash.to_string(), "d41d8cd98f00b204e9800998ecf8427e");
+///
+/// assert!("".parse::<BeatmapMd5>().is_err());
+/// assert!("not a hash".parse::<BeatmapMd5>().is_err());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BeatmapMd5([u8; 16]);
+
+impl BeatmapMd5 {
+    /// Wrap the raw bytes of a digest.
+    pub const fn new(bytes: [u8; 16]) -> Self {
+        BeatmapMd5(bytes)
+    }
+
+    /// Compute the hash of the contents of a beatmap file.
+    #[cfg(feature = "md5")]
+    pub fn of(data: &[u8]) -> Self {
+        BeatmapMd5::of_with_hasher(data, &BuiltinMd5)
+    }
+
+    /// Compute the hash of the contents of a beatmap file with another MD5 implementation.
+    pub fn of_with_hasher(data: &[u8], hasher: &dyn Hasher) -> Self {
+        BeatmapMd5(hasher.md5(data))
+    }
+
+    /// The raw bytes of the digest.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl From<[u8; 16]> for BeatmapMd5 {
+    fn from(bytes: [u8; 16]) -> Self {
+        BeatmapMd5(bytes)
+    }
+}
+
+impl FromStr for BeatmapMd5 {
+    type Err = ReplayDataError<'static>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !is_md5_hex(s) {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+        let mut bytes = [0; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)?;
+        }
+        Ok(BeatmapMd5(bytes))
+    }
+}
+
+impl fmt::Display for BeatmapMd5 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+/// Whether the string is an MD5 hash of 32 hexadecimal characters.
+pub(crate) fn is_md5_hex(s: &str) -> bool {
+    s.len() == 32 && s.bytes().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The ID of a score submitted to the osu! servers.
+///
+/// Replays of unsubmitted scores store `0` instead, so only positive IDs are valid.
+/// # Example
+/// ```
+/// use osu_replay_parser::ids::OnlineScoreId;
+///
+/// let id = OnlineScoreId::new(4_200_000_000).unwrap();
+/// assert_eq!(id.get(), 4_200_000_000);
+/// assert_eq!(id.to_string(), "4200000000");
+///
+/// assert_eq!(OnlineScoreId::new(0), None);
+/// assert!(OnlineScoreId::try_from(-1).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OnlineScoreId(i64);
+
+impl OnlineScoreId {
+    /// Wrap a score ID, returns `None` if it is not positive.
+    pub const fn new(id: i64) -> Option<Self> {
+        if id > 0 {
+            Some(OnlineScoreId(id))
+        } else {
+            None
+        }
+    }
+
+    /// The raw score ID.
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl TryFrom<i64> for OnlineScoreId {
+    type Error = ReplayDataError<'static>;
+
+    fn try_from(id: i64) -> Result<Self, Self::Error> {
+        OnlineScoreId::new(id).ok_or(ParseError::InvalidValue.into())
+    }
+}
+
+impl From<OnlineScoreId> for i64 {
+    fn from(id: OnlineScoreId) -> Self {
+        id.0
+    }
+}
+
+impl FromStr for OnlineScoreId {
+    type Err = ReplayDataError<'static>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OnlineScoreId::try_from(s.parse::<i64>()?)
+    }
+}
+
+impl fmt::Display for OnlineScoreId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The release date of the osu!stable client a replay was recorded with, encoded as `yyyymmdd`
+/// in the version of the replay.
+///
+/// Dates are ordered chronologically, so the format changes of osu! can be checked by comparing
+/// them. osu!lazer versions are not dates.
+/// # Example
+/// ```
+/// use osu_replay_parser::ids::ReleaseDate;
+///
+/// let date = ReleaseDate::from_version(20140721).unwrap();
+/// assert_eq!((date.year(), date.month(), date.day()), (2014, 7, 21));
+/// assert_eq!(date.to_string(), "2014-07-21");
+/// assert!(date.is_after(2014, 7, 20));
+/// assert!(!date.is_after(2014, 7, 21));
+/// assert!(date < ReleaseDate::new(2015, 1, 1).unwrap());
+///
+/// assert_eq!(ReleaseDate::from_version(30000001), None);
+/// assert_eq!(ReleaseDate::new(2023, 2, 29), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ReleaseDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl ReleaseDate {
+    /// A date of the Gregorian calendar, returns `None` if the month or day does not exist.
+    pub const fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(ReleaseDate { year, month, day })
+    }
+
+    /// The date encoded in a replay version, returns `None` if it is not a valid date.
+    pub const fn from_version(version: u32) -> Option<Self> {
+        let year = version / 10000;
+        if year > u16::MAX as u32 {
+            return None;
+        }
+        ReleaseDate::new(
+            year as u16,
+            (version / 100 % 100) as u8,
+            (version % 100) as u8,
+        )
+    }
+
+    /// The date encoded as a replay version, `yyyymmdd`.
+    pub const fn version(self) -> u32 {
+        self.year as u32 * 10000 + self.month as u32 * 100 + self.day as u32
+    }
+
+    /// The year of the date.
+    pub const fn year(self) -> u16 {
+        self.year
+    }
+
+    /// The month of the date, from 1 to 12.
+    pub const fn month(self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, starting at 1.
+    pub const fn day(self) -> u8 {
+        self.day
+    }
+
+    /// Whether the date is later than the given day.
+    pub fn is_after(self, year: u16, month: u8, day: u8) -> bool {
+        (self.year, self.month, self.day) > (year, month, day)
+    }
+
+    /// Whether the date is earlier than the given day.
+    pub fn is_before(self, year: u16, month: u8, day: u8) -> bool {
+        (self.year, self.month, self.day) < (year, month, day)
+    }
+}
+
+impl TryFrom<u32> for ReleaseDate {
+    type Error = ReplayDataError<'static>;
+
+    fn try_from(version: u32) -> Result<Self, Self::Error> {
+        ReleaseDate::from_version(version).ok_or(ParseError::InvalidValue.into())
+    }
+}
+
+impl From<ReleaseDate> for u32 {
+    fn from(date: ReleaseDate) -> Self {
+        date.version()
+    }
+}
+
+impl fmt::Display for ReleaseDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Replay {
+    /// The MD5 hash of the beatmap, parsed from `beatmap_md5`.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let hash = replay.beatmap_hash().expect("Invalid beatmap hash");
+    /// assert_eq!(hash.to_string(), replay.beatmap_md5.to_lowercase());
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if `beatmap_md5` is not 32 hexadecimal characters,
+    /// e.g. if it is empty in replays created by third-party tools.
+    pub fn beatmap_hash(&self) -> Result<BeatmapMd5, ReplayDataError<'static>> {
+        self.beatmap_md5.parse()
+    }
+
+    /// Whether `beatmap_md5` and `replay_md5` are 32 hexadecimal characters.
+    ///
+    /// Replays with malformed hashes fail to parse with [`HashValidation::Strict`](crate::parser::HashValidation::Strict).
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    ///
+    /// let replay = Replay::default();
+    /// let input = replay.to_bytes().expect("Error writing replay");
+    /// assert!(Replay::parse_with_options(&input, &ParseOptions::strict_hashes()).is_err());
+    ///
+    /// let replay = Replay::parse(&input).expect("Error parsing replay");
+    /// assert!(!replay.has_valid_hashes());
+    /// ```
+    pub fn has_valid_hashes(&self) -> bool {
+        is_md5_hex(&self.beatmap_md5) && is_md5_hex(&self.replay_md5)
+    }
+
+    /// The online score ID of the replay, `None` if the score was not submitted.
+    pub fn score_id(&self) -> Option<OnlineScoreId> {
+        OnlineScoreId::new(self.online_score_id)
+    }
+
+    /// The release date of the osu!stable client that recorded the replay, parsed from
+    /// `version`. `None` for osu!lazer replays, whose versions are not dates.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay { version: 20150414, ..Replay::default() };
+    /// let date = replay.client_release_date().expect("Version is not a date");
+    /// assert_eq!(date.version(), replay.version);
+    /// assert!(date.is_after(2014, 7, 21));
+    ///
+    /// let lazer = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// assert_eq!(lazer.client_release_date(), None);
+    /// ```
+    pub fn client_release_date(&self) -> Option<ReleaseDate> {
+        if self.format() == ReplayFormat::Lazer {
+            return None;
+        }
+        ReleaseDate::from_version(self.version)
+    }
+}
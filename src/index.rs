@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[cfg(feature = "md5")]
+use crate::hash::BuiltinMd5;
+use crate::hash::Hasher;
+use crate::replay::Replay;
+
+/// Name of the cache file [`SongsIndex::build`] stores inside the cache folder.
+pub const CACHE_FILE_NAME: &str = "osu_replay_parser_index";
+
+/// First line of the cache file, files with a different first line are ignored.
+const CACHE_HEADER: &str = "osu_replay_parser songs index 1";
+
+/// A hashed `.osu` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// Path relative to the Songs folder.
+    path: PathBuf,
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    modified: u128,
+    md5: String,
+}
+
+/// Number of files handled by [`SongsIndex::refresh`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshStats {
+    /// Files that were new or changed and had to be hashed.
+    pub hashed: usize,
+    /// Unchanged files whose hash was taken from the index.
+    pub reused: usize,
+    /// Files that were removed from the folder.
+    pub removed: usize,
+}
+
+/// Index of the MD5 hashes of all `.osu` files in a Songs folder, to find the beatmap of a
+/// replay without hashing the folder every time like [`find_beatmap`](crate::pairing::find_beatmap).
+///
+/// The index is cached in a file and refreshed incrementally, only files whose size or
+/// modification time changed are hashed again.
+/// # Example
+/// ```
+/// use osu_replay_parser::hash::md5_hex;
+/// use osu_replay_parser::index::SongsIndex;
+///
+/// let dir = std::env::temp_dir().join("osu_replay_parser_index_example");
+/// let (songs_dir, cache_dir) = (dir.join("Songs"), dir.join("cache"));
+/// std::fs::create_dir_all(songs_dir.join("1 Artist - Title")).unwrap();
+/// std::fs::create_dir_all(&cache_dir).unwrap();
+/// let beatmap = "osu file format v14\n\n[HitObjects]\n256,192,1000,1,0\n";
+/// std::fs::write(songs_dir.join("1 Artist - Title/Normal.osu"), beatmap).unwrap();
+/// // Symbolic links to folders are not followed, so loops are harmless
+/// #[cfg(unix)]
+/// std::os::unix::fs::symlink(&songs_dir, songs_dir.join("1 Artist - Title/loop")).unwrap();
+///
+/// let index = SongsIndex::build(&songs_dir, &cache_dir).expect("Error indexing songs");
+/// assert_eq!(index.len(), 1);
+/// let path = index.find(&md5_hex(beatmap.as_bytes())).unwrap();
+/// assert!(path.ends_with("Normal.osu"));
+///
+/// // The second build reads the hashes from the cache file
+/// let mut index = SongsIndex::build(&songs_dir, &cache_dir).expect("Error indexing songs");
+/// assert_eq!(index.refresh().unwrap().reused, 1);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SongsIndex {
+    songs_dir: PathBuf,
+    cache_path: PathBuf,
+    entries: Vec<Entry>,
+    by_md5: HashMap<String, usize>,
+}
+
+impl SongsIndex {
+    /// Index a Songs folder, with the cache stored in [`CACHE_FILE_NAME`] inside `cache_dir`,
+    /// e.g. the cache folder of the application. The Songs folder itself is not written to.
+    ///
+    /// The cache is read if present, the index is refreshed and the cache is written back.
+    /// # Errors
+    /// Returns an error if the folder cannot be read or the cache cannot be written.
+    #[cfg(feature = "md5")]
+    pub fn build<P: AsRef<Path>, C: AsRef<Path>>(songs_dir: P, cache_dir: C) -> io::Result<Self> {
+        SongsIndex::build_with_cache(songs_dir, cache_dir.as_ref().join(CACHE_FILE_NAME))
+    }
+
+    /// Index a Songs folder with the cache stored in the given file.
+    /// # Errors
+    /// Returns an error if the folder cannot be read or the cache cannot be written.
+    #[cfg(feature = "md5")]
+    pub fn build_with_cache<P: AsRef<Path>, C: AsRef<Path>>(
+        songs_dir: P,
+        cache_path: C,
+    ) -> io::Result<Self> {
+        SongsIndex::build_with_hasher(songs_dir, cache_path, &BuiltinMd5)
+    }
+
+    /// Like [`Self::build_with_cache`], hashing the files with another MD5 implementation.
+    /// # Errors
+    /// Returns an error if the folder cannot be read or the cache cannot be written.
+    pub fn build_with_hasher<P: AsRef<Path>, C: AsRef<Path>>(
+        songs_dir: P,
+        cache_path: C,
+        hasher: &dyn Hasher,
+    ) -> io::Result<Self> {
+        let mut index = SongsIndex {
+            songs_dir: songs_dir.as_ref().to_path_buf(),
+            cache_path: cache_path.as_ref().to_path_buf(),
+            entries: Vec::new(),
+            by_md5: HashMap::new(),
+        };
+        // A missing or unreadable cache only means every file is hashed
+        if let Ok(cache) = std::fs::read_to_string(&index.cache_path) {
+            index.entries = parse_cache(&cache);
+        }
+        index.refresh_with_hasher(hasher)?;
+        index.save()?;
+
+        Ok(index)
+    }
+
+    /// Hash the files that were added or changed since the index was built and drop removed
+    /// files. The cache file is not written, see [`Self::save`].
+    /// # Errors
+    /// Returns an error if the folder or one of the files cannot be read.
+    #[cfg(feature = "md5")]
+    pub fn refresh(&mut self) -> io::Result<RefreshStats> {
+        self.refresh_with_hasher(&BuiltinMd5)
+    }
+
+    /// Like [`Self::refresh`], hashing the files with another MD5 implementation.
+    /// # Errors
+    /// Returns an error if the folder or one of the files cannot be read.
+    pub fn refresh_with_hasher(&mut self, hasher: &dyn Hasher) -> io::Result<RefreshStats> {
+        let mut files = Vec::new();
+        collect_beatmap_files(&self.songs_dir, &mut files)?;
+        files.sort();
+
+        let mut cached: HashMap<PathBuf, Entry> = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        let mut stats = RefreshStats::default();
+        for path in files {
+            let metadata = std::fs::metadata(&path)?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_nanos());
+            let relative = path
+                .strip_prefix(&self.songs_dir)
+                .unwrap_or(&path)
+                .to_path_buf();
+
+            let entry = match cached.remove(&relative) {
+                Some(entry) if entry.size == metadata.len() && entry.modified == modified => {
+                    stats.reused += 1;
+                    entry
+                }
+                _ => {
+                    stats.hashed += 1;
+                    Entry {
+                        md5: hasher.md5_hex(&std::fs::read(&path)?),
+                        path: relative,
+                        size: metadata.len(),
+                        modified,
+                    }
+                }
+            };
+            self.entries.push(entry);
+        }
+        stats.removed = cached.len();
+
+        self.by_md5 = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.md5.clone(), i))
+            .collect();
+        Ok(stats)
+    }
+
+    /// Write the index to its cache file.
+    ///
+    /// Files whose path is not valid UTF-8 or contains a line break are left out and hashed again
+    /// by the next build.
+    /// # Errors
+    /// Returns an error if the cache file cannot be written.
+    pub fn save(&self) -> io::Result<()> {
+        let mut cache = String::from(CACHE_HEADER);
+        cache.push('\n');
+        for entry in &self.entries {
+            let Some(path) = entry.path.to_str().filter(|path| !path.contains('\n')) else {
+                continue;
+            };
+            // Writing to a String cannot fail
+            let _ = writeln!(
+                cache,
+                "{}\t{}\t{}\t{}",
+                entry.md5, entry.size, entry.modified, path
+            );
+        }
+        std::fs::write(&self.cache_path, cache)
+    }
+
+    /// Path of the `.osu` file with the given MD5 hash.
+    pub fn find(&self, md5: &str) -> Option<PathBuf> {
+        self.by_md5
+            .get(&md5.to_ascii_lowercase())
+            .map(|&i| self.songs_dir.join(&self.entries[i].path))
+    }
+
+    /// Path of the `.osu` file of the beatmap a replay was played on.
+    pub fn find_replay_beatmap(&self, replay: &Replay) -> Option<PathBuf> {
+        self.find(&replay.beatmap_md5)
+    }
+
+    /// Number of indexed `.osu` files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no `.osu` files were found.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Read the entries of a cache file, malformed lines are skipped.
+fn parse_cache(cache: &str) -> Vec<Entry> {
+    let mut lines = cache.lines();
+    if lines.next() != Some(CACHE_HEADER) {
+        return Vec::new();
+    }
+
+    lines
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            Some(Entry {
+                md5: fields.next()?.to_string(),
+                size: fields.next()?.parse().ok()?,
+                modified: fields.next()?.parse().ok()?,
+                path: PathBuf::from(fields.next()?),
+            })
+        })
+        .collect()
+}
+
+/// Collect the `.osu` files in a folder and its subfolders, without following symbolic links to
+/// folders, which may form loops.
+fn collect_beatmap_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_beatmap_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("osu"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+//! Decoded replays are played back as operating system input: cursor movements and key presses
+//! are sent to an [`InputDevice`] in real time, driven by a [`Clock`]. This is meant for testing
+//! input setups, e.g. measuring the latency of a tablet driver, and for research in offline
+//! environments.
+//!
+//! **Playing back replays on the osu! servers is cheating and gets accounts restricted.**
+//! Only use this with offline clients or private servers that allow it.
+//!
+//! [`EnigoDevice`] sends the input to the operating system with [enigo](https://docs.rs/enigo)
+//! on Windows, macOS and Linux with X11. Implement [`InputDevice`] to use another input library,
+//! e.g. a Linux `uinput` device for Wayland.
+
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use enigo::{Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+
+use crate::frames::Keys;
+use crate::game_math::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::playback::Clock;
+use crate::replay::ReplayData;
+
+/// Target of the emitted input, implemented with the input API of the operating system.
+pub trait InputDevice {
+    /// Move the cursor to the screen position in pixels.
+    fn move_cursor(&mut self, x: f32, y: f32) -> io::Result<()>;
+
+    /// Press a key, one of [`Keys::M1`], [`Keys::M2`], [`Keys::K1`], [`Keys::K2`] or [`Keys::SMOKE`].
+    fn press(&mut self, key: Keys) -> io::Result<()>;
+
+    /// Release a key pressed with [`Self::press`].
+    fn release(&mut self, key: Keys) -> io::Result<()>;
+}
+
+/// An [`InputDevice`] moving the system cursor and pressing keys with enigo.
+///
+/// [`Keys::M1`] and [`Keys::M2`] press the left and right mouse button, [`Keys::K1`],
+/// [`Keys::K2`] and [`Keys::SMOKE`] the keys bound to them, `z`, `x` and `c` like in osu! by
+/// default.
+/// # Example
+/// ```no_run
+/// use osu_replay_parser::input::{EnigoDevice, Emitter, ScreenMapping};
+/// use osu_replay_parser::Replay;
+/// use std::time::Duration;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let frames = replay.get_actions().expect("Error getting actions");
+/// let device = EnigoDevice::new().expect("Error connecting to the display");
+/// let mapping = ScreenMapping::for_window(1920.0, 1080.0);
+/// Emitter::new(&frames, device, mapping)
+///     .run(Duration::from_millis(1))
+///     .expect("Error emitting input");
+/// ```
+pub struct EnigoDevice {
+    enigo: Enigo,
+    k1: char,
+    k2: char,
+    smoke: char,
+}
+
+impl EnigoDevice {
+    /// Connect to the input system of the operating system.
+    /// # Errors
+    /// Returns an error if the connection fails, e.g. if no X11 display is available.
+    pub fn new() -> io::Result<Self> {
+        Ok(EnigoDevice {
+            enigo: Enigo::new(&Settings::default()).map_err(io::Error::other)?,
+            k1: 'z',
+            k2: 'x',
+            smoke: 'c',
+        })
+    }
+
+    /// Set the keys pressed for [`Keys::K1`] and [`Keys::K2`].
+    pub fn with_keys(mut self, k1: char, k2: char) -> Self {
+        self.k1 = k1;
+        self.k2 = k2;
+        self
+    }
+
+    /// Set the key pressed for [`Keys::SMOKE`].
+    pub fn with_smoke_key(mut self, smoke: char) -> Self {
+        self.smoke = smoke;
+        self
+    }
+
+    fn key(&mut self, key: Keys, direction: Direction) -> io::Result<()> {
+        let result = if key == Keys::M1 {
+            self.enigo.button(Button::Left, direction)
+        } else if key == Keys::M2 {
+            self.enigo.button(Button::Right, direction)
+        } else if key == Keys::K1 {
+            self.enigo.key(Key::Unicode(self.k1), direction)
+        } else if key == Keys::K2 {
+            self.enigo.key(Key::Unicode(self.k2), direction)
+        } else if key == Keys::SMOKE {
+            self.enigo.key(Key::Unicode(self.smoke), direction)
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported key",
+            ));
+        };
+        result.map_err(io::Error::other)
+    }
+}
+
+impl InputDevice for EnigoDevice {
+    fn move_cursor(&mut self, x: f32, y: f32) -> io::Result<()> {
+        self.enigo
+            .move_mouse(x.round() as i32, y.round() as i32, Coordinate::Abs)
+            .map_err(io::Error::other)
+    }
+
+    fn press(&mut self, key: Keys) -> io::Result<()> {
+        self.key(key, Direction::Press)
+    }
+
+    fn release(&mut self, key: Keys) -> io::Result<()> {
+        self.key(key, Direction::Release)
+    }
+}
+
+/// Mapping of osu! pixels to screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenMapping {
+    /// Screen x coordinate of the left edge of the playfield.
+    pub left: f32,
+    /// Screen y coordinate of the top edge of the playfield.
+    pub top: f32,
+    /// Screen pixels per osu! pixel.
+    pub scale: f32,
+}
+
+impl ScreenMapping {
+    /// Mapping for a playfield centered in a window, scaled to 80% of its height like in osu!.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::input::ScreenMapping;
+    ///
+    /// let mapping = ScreenMapping::for_window(1920.0, 1080.0);
+    /// assert_eq!(mapping.to_screen(256.0, 192.0), (960.0, 540.0));
+    /// assert_eq!(mapping.to_screen(0.0, 0.0), (384.0, 108.0));
+    /// ```
+    pub fn for_window(width: f32, height: f32) -> Self {
+        let scale = height * 0.8 / PLAYFIELD_HEIGHT;
+        ScreenMapping {
+            left: (width - PLAYFIELD_WIDTH * scale) / 2.0,
+            top: (height - PLAYFIELD_HEIGHT * scale) / 2.0,
+            scale,
+        }
+    }
+
+    /// Convert a position in osu! pixels to screen pixels.
+    pub fn to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.left + x * self.scale, self.top + y * self.scale)
+    }
+}
+
+/// Plays back the frames of a replay on an [`InputDevice`].
+/// # Example
+/// ```
+/// use osu_replay_parser::frames::Keys;
+/// use osu_replay_parser::input::{Emitter, InputDevice, ScreenMapping};
+/// use osu_replay_parser::ReplayData;
+///
+/// #[derive(Default)]
+/// struct Recorder(Vec<String>);
+///
+/// impl InputDevice for Recorder {
+///     fn move_cursor(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+///         self.0.push(format!("move {} {}", x, y));
+///         Ok(())
+///     }
+///     fn press(&mut self, key: Keys) -> std::io::Result<()> {
+///         self.0.push(format!("press {:?}", key));
+///         Ok(())
+///     }
+///     fn release(&mut self, key: Keys) -> std::io::Result<()> {
+///         self.0.push(format!("release {:?}", key));
+///         Ok(())
+///     }
+/// }
+///
+/// let frames = vec![
+///     ReplayData { time: 0, x: 0.0, y: 0.0, keys: 0 },
+///     ReplayData { time: 10, x: 10.0, y: 0.0, keys: 5 },
+///     ReplayData { time: 10, x: 10.0, y: 0.0, keys: 0 },
+/// ];
+/// let mapping = ScreenMapping { left: 0.0, top: 0.0, scale: 1.0 };
+/// let mut emitter = Emitter::new(&frames, Recorder::default(), mapping);
+/// while emitter.step(10.0).unwrap() {}
+///
+/// let events = emitter.into_device().0;
+/// assert_eq!(events, ["move 0 0", "move 10 0", "press Keys(K1)", "move 10 0", "release Keys(K1)"]);
+/// ```
+#[derive(Debug)]
+pub struct Emitter<D> {
+    clock: Clock,
+    device: D,
+    mapping: ScreenMapping,
+    pressed: Keys,
+    started: bool,
+}
+
+impl<D: InputDevice> Emitter<D> {
+    /// Create an emitter positioned at the first frame.
+    pub fn new(frames: &[ReplayData], device: D, mapping: ScreenMapping) -> Self {
+        Emitter {
+            clock: Clock::new(frames),
+            device,
+            mapping,
+            pressed: Keys::empty(),
+            started: false,
+        }
+    }
+
+    /// Set the playback rate, see [`Clock::with_rate`].
+    /// # Panics
+    /// Panics if `rate` is not positive.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.clock = self.clock.with_rate(rate);
+        self
+    }
+
+    /// The clock driving the playback.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Release the device, keys that are still pressed stay pressed.
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
+    /// Move forward by `dt` milliseconds and emit the cursor position and changed keys.
+    ///
+    /// The first call emits the state at the first frame without moving forward. When the end is
+    /// reached all keys are released and `false` is returned.
+    /// # Errors
+    /// Returns the errors of the device.
+    pub fn step(&mut self, dt: f64) -> io::Result<bool> {
+        let state = if self.started {
+            self.clock.advance(dt)
+        } else {
+            self.started = true;
+            self.clock.state()
+        };
+        let Some(state) = state else {
+            return Ok(false);
+        };
+
+        let (x, y) = self.mapping.to_screen(state.x, state.y);
+        self.device.move_cursor(x, y)?;
+        let keys = Keys::from_bits_truncate(state.keys)
+            .sources()
+            .chain(Some(Keys::from_bits_truncate(state.keys) & Keys::SMOKE))
+            .fold(Keys::empty(), |keys, key| keys | key);
+        self.set_keys(keys)?;
+
+        if self.clock.is_finished() {
+            self.release_all()?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Play back the remaining frames in real time, emitting the state every `tick`.
+    ///
+    /// Blocks until the end of the replay. The position follows the elapsed wall clock time,
+    /// so slow devices skip states instead of falling behind.
+    /// # Errors
+    /// Returns the errors of the device. Pressed keys are released before returning.
+    pub fn run(&mut self, tick: Duration) -> io::Result<()> {
+        let start = Instant::now();
+        let mut emitted = 0.0;
+        loop {
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            match self.step(elapsed - emitted) {
+                Ok(true) => emitted = elapsed,
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    // Do not leave keys stuck
+                    let _ = self.release_all();
+                    return Err(e);
+                }
+            }
+            thread::sleep(tick);
+        }
+    }
+
+    /// Release all pressed keys.
+    /// # Errors
+    /// Returns the errors of the device.
+    pub fn release_all(&mut self) -> io::Result<()> {
+        self.set_keys(Keys::empty())
+    }
+
+    fn set_keys(&mut self, keys: Keys) -> io::Result<()> {
+        for key in (self.pressed - keys).iter() {
+            self.device.release(key)?;
+            self.pressed.remove(key);
+        }
+        for key in (keys - self.pressed).iter() {
+            self.device.press(key)?;
+            self.pressed.insert(key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::replay::{GameMode, Replay};
+
+/// Value of a customised osu!lazer mod setting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModSetting {
+    /// A toggle, e.g. `adjust_pitch`.
+    Bool(bool),
+    /// A number, e.g. `speed_change`.
+    Number(f64),
+    /// Any other value, e.g. an enum name.
+    String(String),
+}
+
+/// A mod as stored by osu!lazer.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct LazerMod {
+    /// The acronym of the mod, e.g. `DT`.
+    pub acronym: String,
+    /// Settings that differ from the defaults of the mod.
+    #[serde(default, deserialize_with = "mod_settings")]
+    pub settings: BTreeMap<String, ModSetting>,
+}
+
+/// Additional score information appended to replays by osu!lazer.
+///
+/// Fields missing in the trailer are `None` or empty.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LazerScoreInfo {
+    /// Version of the client that set the score, e.g. `2024.1009.1-lazer`.
+    pub client_version: Option<String>,
+    /// Rank of the score, e.g. `A`.
+    pub rank: Option<String>,
+    /// Online ID of the player.
+    pub user_id: Option<i64>,
+    /// Online ID of the score.
+    pub online_id: Option<i64>,
+    /// Mods of the score, including their settings.
+    #[serde(deserialize_with = "null_as_default")]
+    pub mods: Vec<LazerMod>,
+    /// Hit results of the score by their osu!lazer name, e.g. `great`.
+    #[serde(deserialize_with = "null_as_default")]
+    pub statistics: BTreeMap<String, u32>,
+    /// Hit results of a perfect play of the beatmap.
+    #[serde(deserialize_with = "null_as_default")]
+    pub maximum_statistics: BTreeMap<String, u32>,
+    /// Total score without the mod multiplier.
+    pub total_score_without_mods: Option<i64>,
+}
+
+/// Hit results of an osu!lazer score, see [`LazerScoreInfo::hit_statistics`].
+///
+/// Which results a game mode uses differs, e.g. slider ticks are large ticks in osu!standard,
+/// while droplets are large ticks in osu!catch.
+/// # Example
+/// ```
+/// use osu_replay_parser::replay::GameMode;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let info = replay.lazer_score_info().expect("Error parsing trailer").unwrap();
+/// let statistics = info.hit_statistics();
+/// assert_eq!((statistics.great, statistics.ok, statistics.miss), (774, 70, 7));
+/// assert_eq!(statistics.slider_tail_hit, 282);
+/// assert_eq!(info.maximum_hit_statistics().slider_tail_hit, 296);
+///
+/// let accuracy = info.accuracy(GameMode::Osu).unwrap();
+/// assert!((accuracy - 0.9393).abs() < 1e-4);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LazerStatistics {
+    /// Perfect hits, the MAX judgement of osu!mania.
+    pub perfect: u32,
+    /// Great hits, 300s.
+    pub great: u32,
+    /// Good hits, the 200s of osu!mania.
+    pub good: u32,
+    /// Ok hits, 100s.
+    pub ok: u32,
+    /// Meh hits, 50s.
+    pub meh: u32,
+    /// Misses of hit objects.
+    pub miss: u32,
+    /// Hit large ticks, e.g. slider ticks and repeats or droplets.
+    pub large_tick_hit: u32,
+    /// Missed large ticks.
+    pub large_tick_miss: u32,
+    /// Hit small ticks, e.g. tiny droplets or slider ends with the Classic mod.
+    pub small_tick_hit: u32,
+    /// Missed small ticks.
+    pub small_tick_miss: u32,
+    /// Hit slider ends.
+    pub slider_tail_hit: u32,
+    /// Small bonus results, e.g. spinner spins.
+    pub small_bonus: u32,
+    /// Large bonus results, e.g. spinner bonus spins or bananas.
+    pub large_bonus: u32,
+    /// Hits of objects that do not affect the score, e.g. slider heads with the Classic mod.
+    pub ignore_hit: u32,
+    /// Misses of objects that do not affect the score.
+    pub ignore_miss: u32,
+    /// Combo breaks without another judgement.
+    pub combo_break: u32,
+    /// Combo given to match the maximum combo of osu!stable.
+    pub legacy_combo_increase: u32,
+}
+
+impl LazerStatistics {
+    /// The statistics of hit results named as in osu!lazer, e.g. `great`. Unknown names are
+    /// ignored.
+    pub fn from_map(statistics: &BTreeMap<String, u32>) -> Self {
+        let mut typed = LazerStatistics::default();
+        for (name, &count) in statistics {
+            let field = match name.as_str() {
+                "perfect" => &mut typed.perfect,
+                "great" => &mut typed.great,
+                "good" => &mut typed.good,
+                "ok" => &mut typed.ok,
+                "meh" => &mut typed.meh,
+                "miss" => &mut typed.miss,
+                "large_tick_hit" => &mut typed.large_tick_hit,
+                "large_tick_miss" => &mut typed.large_tick_miss,
+                "small_tick_hit" => &mut typed.small_tick_hit,
+                "small_tick_miss" => &mut typed.small_tick_miss,
+                "slider_tail_hit" => &mut typed.slider_tail_hit,
+                "small_bonus" => &mut typed.small_bonus,
+                "large_bonus" => &mut typed.large_bonus,
+                "ignore_hit" => &mut typed.ignore_hit,
+                "ignore_miss" => &mut typed.ignore_miss,
+                "combo_break" => &mut typed.combo_break,
+                "legacy_combo_increase" => &mut typed.legacy_combo_increase,
+                _ => continue,
+            };
+            *field = count;
+        }
+        typed
+    }
+
+    /// Sum of the base scores of the results that affect accuracy, as weighted by osu!lazer.
+    fn accuracy_score(&self, mode: GameMode) -> u64 {
+        // osu!mania weighs perfect hits like osu!stable, the other modes give them a bonus
+        let perfect = if mode == GameMode::Mania { 305 } else { 315 };
+        [
+            (self.perfect, perfect),
+            (self.great, 300),
+            (self.good, 200),
+            (self.ok, 100),
+            (self.meh, 50),
+            (self.slider_tail_hit, 150),
+            (self.large_tick_hit, 30),
+            (self.small_tick_hit, 10),
+        ]
+        .into_iter()
+        .map(|(count, score)| u64::from(count) * score)
+        .sum()
+    }
+
+    /// Accuracy in the range `0.0..=1.0` as computed by osu!lazer, relative to the statistics of
+    /// a perfect play, `None` if the beatmap has no results that affect accuracy.
+    ///
+    /// Unlike the accuracy of osu!stable, slider ticks and ends count towards it.
+    pub fn accuracy(&self, maximum: &LazerStatistics, mode: GameMode) -> Option<f64> {
+        let maximum = maximum.accuracy_score(mode);
+        if maximum == 0 {
+            return None;
+        }
+        Some(self.accuracy_score(mode) as f64 / maximum as f64)
+    }
+}
+
+/// Read `null` like a missing field.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Read the settings of a mod, skipping settings that are not a toggle, number or string.
+fn mod_settings<'de, D>(deserializer: D) -> Result<BTreeMap<String, ModSetting>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let settings: BTreeMap<String, Value> = null_as_default(deserializer)?;
+    Ok(settings
+        .into_iter()
+        .filter_map(|(name, setting)| {
+            let setting = match setting {
+                Value::Bool(b) => ModSetting::Bool(b),
+                Value::Number(n) => ModSetting::Number(n.as_f64()?),
+                Value::String(s) => ModSetting::String(s),
+                _ => return None,
+            };
+            Some((name, setting))
+        })
+        .collect())
+}
+
+impl LazerScoreInfo {
+    /// Parse the decompressed JSON of the osu!lazer trailer.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::lazer::LazerScoreInfo;
+    ///
+    /// let info = LazerScoreInfo::parse(r#"{"rank": "S", "statistics": {"great": 3}}"#).unwrap();
+    /// assert_eq!(info.rank.as_deref(), Some("S"));
+    ///
+    /// // Deeply nested JSON is rejected instead of overflowing the stack
+    /// let nested = format!("{{\"mods\": {}{}}}", "[".repeat(100_000), "]".repeat(100_000));
+    /// assert!(LazerScoreInfo::parse(&nested).is_err());
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the JSON is invalid, nested more than 128 levels deep or a
+    /// field has an unexpected type.
+    pub fn parse(json: &str) -> Result<Self, ReplayDataError<'static>> {
+        serde_json::from_str(json).map_err(|_| ParseError::InvalidValue.into())
+    }
+
+    /// Decompress and parse the osu!lazer trailer as stored in [`Replay::lazer_data`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the data cannot be decompressed or parsed.
+    pub fn from_compressed(data: &[u8]) -> Result<Self, ReplayDataError<'static>> {
+        LazerScoreInfo::parse(&Replay::decompress_lzma(data)?)
+    }
+
+    /// The hit results of the score, see [`LazerStatistics`].
+    pub fn hit_statistics(&self) -> LazerStatistics {
+        LazerStatistics::from_map(&self.statistics)
+    }
+
+    /// The hit results of a perfect play of the beatmap.
+    pub fn maximum_hit_statistics(&self) -> LazerStatistics {
+        LazerStatistics::from_map(&self.maximum_statistics)
+    }
+
+    /// Accuracy of the score as computed by osu!lazer, see [`LazerStatistics::accuracy`].
+    pub fn accuracy(&self, mode: GameMode) -> Option<f64> {
+        self.hit_statistics()
+            .accuracy(&self.maximum_hit_statistics(), mode)
+    }
+}
+
+impl Replay {
+    /// The additional score information of osu!lazer replays, `None` for replays without a trailer.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let info = replay.lazer_score_info().expect("Error parsing trailer").unwrap();
+    /// assert_eq!(info.client_version.as_deref(), Some("2024.1009.1-lazer"));
+    /// assert_eq!(info.statistics["great"], 774);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the trailer cannot be decompressed or parsed.
+    pub fn lazer_score_info(&self) -> Result<Option<LazerScoreInfo>, ReplayDataError<'static>> {
+        self.lazer_data
+            .as_deref()
+            .map(LazerScoreInfo::from_compressed)
+            .transpose()
+    }
+}
+
+/// A score exported from osu!lazer: the legacy format replay and the osu!lazer score information.
+#[derive(Debug, Clone)]
+pub struct LazerScore {
+    /// The replay, with frames in the same format as osu!stable.
+    pub replay: Replay,
+    /// The additional score information, `None` if the export has no osu!lazer trailer.
+    pub info: Option<LazerScoreInfo>,
+}
+
+impl LazerScore {
+    /// Parse an exported `.osr` file.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the replay or its trailer cannot be parsed.
+    pub fn parse(input: &[u8]) -> Result<Self, ReplayDataError<'static>> {
+        let replay = Replay::parse(input).map_err(ReplayDataError::into_owned)?;
+        let info = replay.lazer_score_info()?;
+
+        Ok(LazerScore { replay, info })
+    }
+}
+
+/// Read the scores of an osu!lazer export.
+///
+/// Single `.osr` exports are supported, and with the `archive` feature zip containers of them.
+/// Scores stored in the realm database of osu!lazer are not supported, they have to be exported first.
+/// # Example
+/// ```
+/// use osu_replay_parser::lazer::read_export;
+///
+/// let scores = read_export("assets/replay.osr").expect("Error reading export");
+/// assert_eq!(scores[0].replay.player_name, "RenLephy");
+/// assert!(scores[0].info.is_some());
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the file cannot be read or a score cannot be parsed.
+pub fn read_export<P: AsRef<Path>>(path: P) -> Result<Vec<LazerScore>, ReplayDataError<'static>> {
+    let data = std::fs::read(path)?;
+
+    #[cfg(feature = "archive")]
+    if data.starts_with(b"PK") {
+        let archive = crate::archive::ReplayArchive::from_bytes(data)?;
+        return archive
+            .entries()
+            .iter()
+            .filter(|entry| entry.name.to_ascii_lowercase().ends_with(".osr"))
+            .map(|entry| LazerScore::parse(&archive.read(entry)?))
+            .collect();
+    }
+
+    Ok(vec![LazerScore::parse(&data)?])
+}
@@ -3,12 +3,112 @@
 //! A library for parsing and analyzing osu! replay files.
 #![doc = include_str!("../README.md")]
 
+/// The prelude module contains re-exports of the types needed by most users of the library.
+pub mod prelude;
 /// The parser module contains the functions and types for parsing osu! replay files.
 pub mod parser;
 /// The errors module contains the error types for the library.
 pub mod errors;
+/// The warnings module contains the soft problems of replays that do not prevent parsing.
+pub mod warnings;
 /// The replay module contains the types for representing osu! replay files and handling of the compressed replay data.
 pub mod replay;
+/// The statistics module contains the hit counts of replays interpreted by game mode.
+pub mod statistics;
+/// The writer module contains functions for encoding replay data.
+pub mod writer;
+/// The patch module contains functions for editing single header fields of raw replay files in place.
+pub mod patch;
+/// The recorder module contains a writer for recording replays while they are played.
+pub mod recorder;
+/// The compression module contains types describing the LZMA compressed replay data.
+pub mod compression;
+/// The archive module contains support for reading replays from zip and tar archives.
+#[cfg(feature = "archive")]
+pub mod archive;
+/// The lazer module contains types for the additional score information of osu!lazer replays.
+pub mod lazer;
+/// The redact module contains functions for removing personally identifying information from replays.
+pub mod redact;
+/// The hash module contains the hash functions used by osu!, SHA-256 and a trait for other MD5 implementations.
+pub mod hash;
+/// The ids module contains typed wrappers for the hashes, IDs and release dates stored in replays.
+pub mod ids;
+/// The osu_db module contains a parser for the beatmap index of osu!stable.
+pub mod osu_db;
+/// The pairing module contains functions for finding the beatmap of a replay.
+pub mod pairing;
+/// The index module contains a cached MD5 index of the beatmaps in a Songs folder.
+pub mod index;
+/// The decoder module contains the configurable decoder for the compressed replay data.
+pub mod decoder;
+/// The batch module contains functions for working with many replays at once.
+pub mod batch;
+/// The scan module contains functions for finding replays by their metadata.
+pub mod scan;
+/// The dataset module contains a pipeline converting replay folders into datasets for analysis and machine learning.
+pub mod dataset;
+/// The pack module contains manifests of replay folders for verifying archives.
+pub mod pack;
+/// The game_math module contains constants and formulas of the osu! playfield and difficulty settings.
+pub mod game_math;
+/// The beatmap module contains a parser for the parts of osu! beatmap files needed for replay analysis.
+pub mod beatmap;
+/// The frames module contains types and helpers for working with decoded replay frames.
+pub mod frames;
+/// The mania module contains helpers for osu!mania replays.
+pub mod mania;
+/// The merkle module contains chunked hashes of frame streams for proving that replays were not modified.
+pub mod merkle;
+/// The delta module contains the delta encoding and compact binary serialization of frames.
+pub mod delta;
+/// The analysis module contains functions for analyzing replay frames.
+pub mod analysis;
+/// The compare module contains functions for comparing multiple replays of the same beatmap.
+pub mod compare;
+/// The render module contains helpers for rendering replays to video.
+pub mod render;
+/// The playback module contains a clock for playing back replays in viewers.
+pub mod playback;
+/// The export module contains functions for exporting replays to other tools and formats.
+pub mod export;
+/// The format module contains locale-aware formatting of numbers and dates, osu! website and Discord links and `osu://` protocol links for displaying replays.
+pub mod format;
+/// The summary module contains owned summary types of replays for storing in application state.
+#[cfg(feature = "summary")]
+pub mod summary;
+/// The testing module contains a generator of synthetic replays for benchmarks and tests.
+#[cfg(feature = "testing")]
+pub mod testing;
+/// The test_data module contains a conformance suite run over a corpus of real replays.
+#[cfg(feature = "test-data")]
+pub mod test_data;
+/// The ffi module contains the C ABI of the parser.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// The node module contains the Node.js bindings of the parser.
+#[cfg(feature = "node")]
+pub mod node;
+/// The arrow module contains the export of frames as Arrow record batches.
+#[cfg(feature = "arrow")]
+pub mod arrow;
+/// The sign module contains Ed25519 signatures of replays for tournament submissions.
+#[cfg(feature = "sign")]
+pub mod sign;
+/// The input module contains the playback of replays as operating system input.
+#[cfg(feature = "input")]
+pub mod input;
+/// The api module contains the verification of replays against their online scores on the osu! API.
+#[cfg(feature = "api")]
+pub mod api;
+/// The watch module contains a watcher of replay folders reporting new replays.
+#[cfg(feature = "watch")]
+pub mod watch;
+/// The plot module contains the plotting of cursor paths and key presses as SVG and PNG images.
+#[cfg(feature = "plot")]
+pub mod plot;
+mod unicode;
 
 pub use replay::{Replay, ReplayData};
-pub use errors::ReplayDataError;
\ No newline at end of file
+pub use errors::ReplayDataError;
+pub use parser::ParseOptions;
\ No newline at end of file
@@ -0,0 +1,223 @@
+use crate::beatmap::{Beatmap, HitObjectKind};
+use crate::errors::ReplayDataError;
+use crate::frames::FramesExt;
+use crate::replay::{GameMode, Mods, Replay, ReplayData};
+
+/// Largest key count of osu!mania, 9 keys per stage with co-op.
+pub const MAX_KEY_COUNT: usize = 18;
+
+/// Key count set by the key mods, `None` if no key mod is set.
+///
+/// Co-op is not taken into account, see [`key_count`].
+/// # Example
+/// ```
+/// use osu_replay_parser::mania::key_count_from_mods;
+/// use osu_replay_parser::replay::Mods;
+///
+/// assert_eq!(key_count_from_mods(Mods::KEY7 | Mods::RANDOM), Some(7));
+/// assert_eq!(key_count_from_mods(Mods::NONE), None);
+/// ```
+pub fn key_count_from_mods(mods: Mods) -> Option<usize> {
+    [
+        Mods::KEY1,
+        Mods::KEY2,
+        Mods::KEY3,
+        Mods::KEY4,
+        Mods::KEY5,
+        Mods::KEY6,
+        Mods::KEY7,
+        Mods::KEY8,
+        Mods::KEY9,
+    ]
+    .iter()
+    .position(|&key| mods.contains(key))
+    .map(|index| index + 1)
+}
+
+/// Key count of a replay played on a native osu!mania beatmap, whose circle size is the key count.
+///
+/// Only covers native osu!mania beatmaps: osu! applies key mods to converted beatmaps only, so
+/// they are ignored here, while co-op doubles the keys. Use [`key_count_for_beatmap`], which
+/// honours key mods, for beatmaps that may be converted.
+/// # Example
+/// ```
+/// use osu_replay_parser::mania::key_count;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::Replay;
+///
+/// let mut replay = Replay { mods: Mods::KEY4.bits(), ..Replay::default() };
+/// assert_eq!(key_count(&replay, 7.0), 7);
+///
+/// replay.mods = Mods::COOP.bits();
+/// assert_eq!(key_count(&replay, 7.0), 14);
+/// ```
+pub fn key_count(replay: &Replay, beatmap_cs: f32) -> usize {
+    let keys = (beatmap_cs.round().max(1.0) as usize).min(MAX_KEY_COUNT);
+    with_coop(keys, replay.mods())
+}
+
+/// Key count of a replay on a beatmap, converting beatmaps of other game modes like osu! does.
+///
+/// Converted beatmaps get between 4 and 7 keys depending on their circle size, overall
+/// difficulty and share of sliders and spinners, unless a key mod is set.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::mania::key_count_for_beatmap;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::Replay;
+///
+/// // An osu!standard beatmap with only circles
+/// let beatmap = Beatmap::parse(
+///     "[Difficulty]\nCircleSize:4\nOverallDifficulty:8\n[HitObjects]\n256,192,1000,1,0\n",
+/// )
+/// .unwrap();
+/// let mut replay = Replay::default();
+/// assert_eq!(key_count_for_beatmap(&replay, &beatmap), 7);
+///
+/// replay.mods = (Mods::KEY4 | Mods::COOP).bits();
+/// assert_eq!(key_count_for_beatmap(&replay, &beatmap), 8);
+/// ```
+pub fn key_count_for_beatmap(replay: &Replay, beatmap: &Beatmap) -> usize {
+    if beatmap.game_mode == GameMode::Mania {
+        return key_count(replay, beatmap.difficulty.circle_size);
+    }
+
+    let keys = key_count_from_mods(replay.mods()).unwrap_or_else(|| converted_key_count(beatmap));
+    with_coop(keys, replay.mods())
+}
+
+/// Key count osu! chooses for a beatmap of another game mode.
+fn converted_key_count(beatmap: &Beatmap) -> usize {
+    let circle_size = beatmap.difficulty.circle_size.round();
+    let overall_difficulty = beatmap.difficulty.overall_difficulty.round();
+    let long_objects = beatmap
+        .hit_objects
+        .iter()
+        .filter(|object| !matches!(object.kind, HitObjectKind::Circle))
+        .count();
+    let long_share = long_objects as f32 / beatmap.hit_objects.len().max(1) as f32;
+
+    if long_share < 0.2 {
+        7
+    } else if long_share < 0.3 || circle_size >= 5.0 {
+        if overall_difficulty > 5.0 {
+            7
+        } else {
+            6
+        }
+    } else if long_share > 0.6 {
+        if overall_difficulty > 4.0 {
+            5
+        } else {
+            4
+        }
+    } else {
+        (overall_difficulty as usize + 1).clamp(4, 7)
+    }
+}
+
+fn with_coop(keys: usize, mods: Mods) -> usize {
+    if mods.contains(Mods::COOP) {
+        (keys * 2).min(MAX_KEY_COUNT)
+    } else {
+        keys
+    }
+}
+
+/// Mirror the pressed columns of osu!mania frames like the Mirror mod, so the first column
+/// becomes the last.
+///
+/// Columns beyond `key_count` are dropped, see [`key_count_for_beatmap`] for the key count of a
+/// replay. The seed frame is kept.
+/// # Example
+/// ```
+/// use osu_replay_parser::mania::mirror;
+/// use osu_replay_parser::ReplayData;
+///
+/// let frames = vec![ReplayData { time: 16, x: 0b0011 as f32, y: 0.0, keys: 0 }];
+/// assert_eq!(mirror(&frames, 4)[0].x, 0b1100 as f32);
+/// assert_eq!(mirror(&frames, 7)[0].x, 0b1100000 as f32);
+/// ```
+pub fn mirror(frames: &[ReplayData], key_count: usize) -> Vec<ReplayData> {
+    let key_count = key_count.min(32);
+    frames
+        .iter()
+        .map(|frame| {
+            if frame.is_seed_frame() {
+                return *frame;
+            }
+            let mirrored = pressed_columns(frame, key_count)
+                .into_iter()
+                .fold(0u32, |mask, column| mask | 1 << (key_count - 1 - column));
+            ReplayData {
+                x: mirrored as f32,
+                ..*frame
+            }
+        })
+        .collect()
+}
+
+/// The columns pressed in a frame of an osu!mania replay, stored as a bit mask in the x
+/// coordinate. Columns beyond `key_count` are dropped.
+///
+/// [`columns`] splits all frames of a replay with the key count of its beatmap.
+/// # Example
+/// ```
+/// use osu_replay_parser::mania::pressed_columns;
+/// use osu_replay_parser::ReplayData;
+///
+/// let frame = ReplayData { time: 16, x: 0b1010 as f32, y: 0.0, keys: 0 };
+/// assert_eq!(pressed_columns(&frame, 4), vec![1, 3]);
+/// assert_eq!(pressed_columns(&frame, 2), vec![1]);
+/// ```
+pub fn pressed_columns(frame: &ReplayData, key_count: usize) -> Vec<usize> {
+    let mask = frame.x.max(0.0) as u32;
+    (0..key_count.min(32))
+        .filter(|&column| mask & (1 << column) != 0)
+        .collect()
+}
+
+/// The pressed columns of every frame of an osu!mania replay with its absolute time, split into
+/// as many columns as the replay has keys on the beatmap, see [`key_count_for_beatmap`].
+///
+/// The seed frame is skipped.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::mania::columns;
+/// use osu_replay_parser::replay::Mods;
+/// use osu_replay_parser::{writer, Replay, ReplayData};
+///
+/// let frames = vec![
+///     ReplayData { time: 0, x: 0.0, y: 0.0, keys: 0 },
+///     ReplayData { time: 16, x: 0b1001 as f32, y: 0.0, keys: 0 },
+///     ReplayData { time: 16, x: 0b110000 as f32, y: 0.0, keys: 0 },
+/// ];
+/// let mut replay = Replay {
+///     compressed_data: writer::compress_frames(&frames),
+///     ..Replay::default()
+/// };
+/// let mania = Beatmap::parse("[General]\nMode:3\n[Difficulty]\nCircleSize:4\n").unwrap();
+/// let columns_4k = columns(&replay, &mania).unwrap();
+/// assert_eq!(columns_4k, vec![(0, vec![]), (16, vec![0, 3]), (32, vec![])]);
+///
+/// // Key mods set the key count of converted beatmaps
+/// replay.mods = Mods::KEY6.bits();
+/// let converted = Beatmap::parse("[Difficulty]\nCircleSize:4\n").unwrap();
+/// assert_eq!(columns(&replay, &converted).unwrap()[2], (32, vec![4, 5]));
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the compressed replay data cannot be decoded.
+pub fn columns(
+    replay: &Replay,
+    beatmap: &Beatmap,
+) -> Result<Vec<(i64, Vec<usize>)>, ReplayDataError<'static>> {
+    let key_count = key_count_for_beatmap(replay, beatmap);
+    let frames = replay.get_actions_with_progress(|_, _| {})?;
+
+    Ok(frames
+        .timed()
+        .map(|(time, frame)| (time, pressed_columns(frame, key_count)))
+        .collect())
+}
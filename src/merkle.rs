@@ -0,0 +1,172 @@
+use crate::hash::sha256;
+use crate::replay::ReplayData;
+
+/// A hash of the frame stream or of a node in a [`MerkleTree`].
+pub type Digest = [u8; 32];
+
+fn hash_chunk(frames: &[ReplayData]) -> Digest {
+    let mut bytes = Vec::with_capacity(frames.len() * 20);
+    for frame in frames {
+        bytes.extend_from_slice(&frame.time.to_le_bytes());
+        bytes.extend_from_slice(&frame.x.to_le_bytes());
+        bytes.extend_from_slice(&frame.y.to_le_bytes());
+        bytes.extend_from_slice(&frame.keys.to_le_bytes());
+    }
+    sha256(&bytes)
+}
+
+fn hash_chunks(frames: &[ReplayData], chunk_size: usize) -> Vec<Digest> {
+    let mut hashes: Vec<Digest> = frames.chunks(chunk_size).map(hash_chunk).collect();
+    if hashes.is_empty() {
+        hashes.push(hash_chunk(&[]));
+    }
+    hashes
+}
+
+fn hash_pair(left: &Digest, right: &Digest) -> Digest {
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(left);
+    bytes[32..].copy_from_slice(right);
+    sha256(&bytes)
+}
+
+/// Position of a sibling hash in a [`MerkleProof`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The sibling is hashed before the current node.
+    Left,
+    /// The sibling is hashed after the current node.
+    Right,
+}
+
+/// Proof that a chunk of frames is part of a [`MerkleTree`] with a given root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// Index of the chunk.
+    pub index: usize,
+    /// Sibling hashes from the chunk up to the root.
+    pub siblings: Vec<(Side, Digest)>,
+}
+
+impl MerkleProof {
+    /// Check that the frames of a chunk hash to the given root.
+    pub fn verify(&self, chunk: &[ReplayData], root: &Digest) -> bool {
+        let hash =
+            self.siblings
+                .iter()
+                .fold(hash_chunk(chunk), |hash, (side, sibling)| match side {
+                    Side::Left => hash_pair(sibling, &hash),
+                    Side::Right => hash_pair(&hash, sibling),
+                });
+        &hash == root
+    }
+}
+
+/// Chunked hashes of a frame stream, combined pairwise into a single root hash.
+///
+/// Publishing the root lets anyone prove later that a segment of the replay was not modified,
+/// by handing out the segment together with its [`MerkleProof`].
+/// Hashes use SHA-256, so modified frames cannot be crafted to keep the root.
+/// # Example
+/// ```
+/// use osu_replay_parser::frames::FramesExt;
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let mut actions = replay.get_actions().expect("Error getting actions");
+/// let tree = actions.merkle_hash(1000);
+///
+/// let proof = tree.proof(3).unwrap();
+/// assert!(proof.verify(&actions[3000..4000], &tree.root()));
+///
+/// actions[3500].x += 1.0;
+/// assert_eq!(tree.modified_chunks(&actions), vec![3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    chunk_size: usize,
+    // Levels from the chunk hashes up to the root
+    levels: Vec<Vec<Digest>>,
+}
+
+impl MerkleTree {
+    /// Hash the frames in chunks of `chunk_size` frames.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0.
+    pub fn new(frames: &[ReplayData], chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than 0");
+
+        let mut levels = vec![hash_chunks(frames, chunk_size)];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_pair(left, right),
+                    // An unpaired node is promoted to the next level unchanged
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        MerkleTree { chunk_size, levels }
+    }
+
+    /// Number of frames per chunk.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// The root hash identifying the whole frame stream.
+    pub fn root(&self) -> Digest {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// The hashes of the individual chunks.
+    pub fn chunk_hashes(&self) -> &[Digest] {
+        &self.levels[0]
+    }
+
+    /// Proof for the chunk with the given index, `None` if there is no such chunk.
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = position ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                let side = if sibling < position {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                siblings.push((side, *hash));
+            }
+            position /= 2;
+        }
+
+        Some(MerkleProof { index, siblings })
+    }
+
+    /// Whether the frames hash to the same root.
+    pub fn verify(&self, frames: &[ReplayData]) -> bool {
+        MerkleTree::new(frames, self.chunk_size).root() == self.root()
+    }
+
+    /// Indices of the chunks whose frames differ from the hashed frames.
+    ///
+    /// Chunks that were added or removed are reported as well.
+    pub fn modified_chunks(&self, frames: &[ReplayData]) -> Vec<usize> {
+        let hashes = hash_chunks(frames, self.chunk_size);
+        let count = hashes.len().max(self.levels[0].len());
+
+        (0..count)
+            .filter(|&i| hashes.get(i) != self.levels[0].get(i))
+            .collect()
+    }
+}
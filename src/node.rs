@@ -0,0 +1,124 @@
+//! The bindings are built with [napi-rs](https://napi.rs). Build the addon with
+//! `cargo rustc --release --features node --crate-type cdylib` and copy the library to
+//! `osu_replay_parser.node`. TypeScript declarations are in `node/osu_replay_parser.d.ts`.
+//!
+//! ```js
+//! const osr = require("./osu_replay_parser.node");
+//! const data = fs.readFileSync("replay.osr");
+//! const replay = osr.parse(data);
+//! const frames = osr.decodeFrames(data);
+//! ```
+//!
+//! Errors are thrown as JavaScript exceptions, panics are caught and thrown as well instead of
+//! aborting the Node.js process.
+
+use napi::bindgen_prelude::Buffer;
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use crate::replay::Replay;
+
+/// Header of a replay, as returned by [`parse`].
+#[napi(object, js_name = "Replay")]
+pub struct JsReplay {
+    /// Game mode as its number in the replay file.
+    pub game_mode: u32,
+    /// Version of the game the replay was created with.
+    pub version: u32,
+    /// MD5 hash of the beatmap.
+    pub beatmap_md5: String,
+    /// Name of the player.
+    pub player_name: String,
+    /// MD5 hash of the replay.
+    pub replay_md5: String,
+    /// Number of 300s.
+    pub n300: u32,
+    /// Number of 100s.
+    pub n100: u32,
+    /// Number of 50s.
+    pub n50: u32,
+    /// Number of gekis.
+    pub n_geki: u32,
+    /// Number of katus.
+    pub n_katu: u32,
+    /// Number of misses.
+    pub n_miss: u32,
+    /// Total score.
+    pub total_score: u32,
+    /// Greatest combo.
+    pub greatest_combo: u32,
+    /// Perfect combo byte as stored in the replay file.
+    pub perfect: u32,
+    /// Bits of the mods.
+    pub mods: u32,
+    /// Life bar graph.
+    pub life_bar: String,
+    /// Time stamp in Windows ticks.
+    pub time_stamp: f64,
+    /// Online score ID, passed as a string as score IDs exceed the exact integer range of
+    /// doubles.
+    pub online_score_id: String,
+    /// Accuracy in the range `0.0..=1.0`.
+    pub accuracy: f64,
+}
+
+/// A frame of the replay, `time` is the delta to the previous frame in milliseconds.
+#[napi(object, js_name = "Frame")]
+pub struct JsFrame {
+    /// Time since the previous frame in milliseconds.
+    pub time: i64,
+    /// X position of the cursor.
+    pub x: f64,
+    /// Y position of the cursor.
+    pub y: f64,
+    /// Bits of the pressed keys.
+    pub keys: u32,
+}
+
+fn parse_replay(data: &[u8]) -> Result<Replay> {
+    Replay::parse(data).map_err(|e| Error::from_reason(format!("Error parsing replay: {:?}", e)))
+}
+
+/// `parse(buffer)`: the header of the replay as an object.
+#[napi(catch_unwind)]
+pub fn parse(data: Buffer) -> Result<JsReplay> {
+    let replay = parse_replay(&data)?;
+    Ok(JsReplay {
+        game_mode: u8::from(replay.game_mode).into(),
+        version: replay.version,
+        perfect: replay.perfect_byte().into(),
+        n300: replay.n300.into(),
+        n100: replay.n100.into(),
+        n50: replay.n50.into(),
+        n_geki: replay.n_geki.into(),
+        n_katu: replay.n_katu.into(),
+        n_miss: replay.n_miss.into(),
+        total_score: replay.total_score,
+        greatest_combo: replay.greatest_combo.into(),
+        mods: replay.mods,
+        time_stamp: replay.time_stamp as f64,
+        online_score_id: replay.online_score_id.to_string(),
+        accuracy: replay.accuracy(),
+        beatmap_md5: replay.beatmap_md5,
+        player_name: replay.player_name,
+        replay_md5: replay.replay_md5,
+        life_bar: replay.life_bar,
+    })
+}
+
+/// `decodeFrames(buffer)`: the frames of the replay as an array of objects.
+#[napi(catch_unwind)]
+pub fn decode_frames(data: Buffer) -> Result<Vec<JsFrame>> {
+    let frames = parse_replay(&data)?
+        .get_actions_with_progress(|_, _| {})
+        .map_err(|e| Error::from_reason(format!("Error decoding frames: {:?}", e)))?;
+    Ok(frames
+        .iter()
+        .map(|frame| JsFrame {
+            time: frame.time,
+            x: frame.x.into(),
+            y: frame.y.into(),
+            keys: frame.keys,
+        })
+        .collect())
+}
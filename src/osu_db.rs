@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use nom::bytes::complete::take;
+use nom::error::context;
+use nom::multi::count;
+use nom::number::complete::{le_f32, le_f64, le_i16, le_i32, le_i64, le_u8};
+use nom::Finish;
+
+use crate::errors::ReplayDataError;
+use crate::parser::{osr_string, ParseResult};
+
+/// First version of osu!.db storing difficulty settings as floats and star ratings per mod combination.
+const VERSION_FLOAT_DIFFICULTY: i32 = 20140609;
+/// First version of osu!.db without the size prefix of each beatmap entry.
+const VERSION_NO_ENTRY_SIZE: i32 = 20191106;
+/// First version of osu!.db storing star ratings as floats instead of doubles.
+const VERSION_FLOAT_STAR_RATING: i32 = 20250107;
+
+/// A beatmap entry of the osu!.db file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OsuDbBeatmap {
+    /// The MD5 hash of the `.osu` file.
+    pub md5: String,
+    /// Name of the beatmap's folder inside the Songs folder.
+    pub folder_name: String,
+    /// File name of the `.osu` file inside the beatmap's folder.
+    pub file_name: String,
+    /// Online ID of the beatmap.
+    pub beatmap_id: i32,
+    /// Online ID of the beatmap set.
+    pub beatmap_set_id: i32,
+}
+
+/// The beatmap index osu!stable stores in its `osu!.db` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OsuDb {
+    /// Version of the osu! client that wrote the file.
+    pub version: i32,
+    /// Name of the player.
+    pub player_name: String,
+    /// All beatmaps known to the client.
+    pub beatmaps: Vec<OsuDbBeatmap>,
+}
+
+fn string(input: &[u8]) -> ParseResult<&[u8], String> {
+    let (input, value) = osr_string(input)?;
+    Ok((input, value.to_string()))
+}
+
+fn star_ratings(version: i32) -> impl Fn(&[u8]) -> ParseResult<&[u8], ()> {
+    move |input| {
+        let (input, pairs) = le_i32(input)?;
+        // Each pair is a type byte and int mods followed by a type byte and the rating
+        let pair_size = if version >= VERSION_FLOAT_STAR_RATING {
+            10
+        } else {
+            14
+        };
+        let (input, _) = take(pairs.max(0) as usize * pair_size)(input)?;
+        Ok((input, ()))
+    }
+}
+
+fn beatmap(version: i32) -> impl Fn(&[u8]) -> ParseResult<&[u8], OsuDbBeatmap> {
+    move |input| {
+        let input = if version < VERSION_NO_ENTRY_SIZE {
+            le_i32(input)?.0
+        } else {
+            input
+        };
+        // Artist, unicode artist, title, unicode title, creator, difficulty and audio file name
+        let (input, _) = count(string, 7)(input)?;
+        let (input, md5) = string(input)?;
+        let (input, file_name) = string(input)?;
+        // Ranked status, object counts and modification date
+        let (input, _) = take(1usize + 2 * 3 + 8)(input)?;
+        let (input, _) = if version >= VERSION_FLOAT_DIFFICULTY {
+            count(le_f32, 4)(input).map(|(input, _)| (input, ()))?
+        } else {
+            count(le_u8, 4)(input).map(|(input, _)| (input, ()))?
+        };
+        let (input, _slider_velocity) = le_f64(input)?;
+        let (input, _) = if version >= VERSION_FLOAT_DIFFICULTY {
+            count(star_ratings(version), 4)(input).map(|(input, _)| (input, ()))?
+        } else {
+            (input, ())
+        };
+        // Drain time, total time and preview time
+        let (input, _) = take(4usize * 3)(input)?;
+        let (input, timing_points) = le_i32(input)?;
+        let (input, _) = take(timing_points.max(0) as usize * 17)(input)?;
+        let (input, beatmap_id) = le_i32(input)?;
+        let (input, beatmap_set_id) = le_i32(input)?;
+        // Thread ID, grades, local offset, stack leniency and mode
+        let (input, _) = take(4usize + 4 + 2 + 4 + 1)(input)?;
+        let (input, _source) = string(input)?;
+        let (input, _tags) = string(input)?;
+        let (input, _online_offset) = le_i16(input)?;
+        let (input, _font) = string(input)?;
+        // Unplayed, last played and osz2 flag
+        let (input, _) = take(1usize + 8 + 1)(input)?;
+        let (input, folder_name) = string(input)?;
+        // Last checked date, five override flags, an unknown short in old versions,
+        // the last modification time and the mania scroll speed
+        let unknown = if version < VERSION_FLOAT_DIFFICULTY {
+            2
+        } else {
+            0
+        };
+        let (input, _) = take(8usize + 5 + unknown + 4 + 1)(input)?;
+
+        let beatmap = OsuDbBeatmap {
+            md5,
+            folder_name,
+            file_name,
+            beatmap_id,
+            beatmap_set_id,
+        };
+        Ok((input, beatmap))
+    }
+}
+
+fn osu_db(input: &[u8]) -> ParseResult<&[u8], OsuDb> {
+    let (input, version) = context("Error parsing osu!.db version", le_i32)(input)?;
+    let (input, _folder_count) = le_i32(input)?;
+    let (input, _account_unlocked) = le_u8(input)?;
+    let (input, _unlock_date) = le_i64(input)?;
+    let (input, player_name) = context("Error parsing player name", string)(input)?;
+    let (input, beatmap_count) = context("Error parsing beatmap count", le_i32)(input)?;
+    let (input, beatmaps) = context(
+        "Error parsing beatmap entry",
+        count(beatmap(version), beatmap_count.max(0) as usize),
+    )(input)?;
+
+    let db = OsuDb {
+        version,
+        player_name,
+        beatmaps,
+    };
+    Ok((input, db))
+}
+
+impl OsuDb {
+    /// Parse the contents of an `osu!.db` file.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file is truncated or malformed.
+    pub fn parse(input: &[u8]) -> Result<Self, ReplayDataError<'_>> {
+        let (_, db) = context("Error parsing osu!.db", osu_db)(input).finish()?;
+        Ok(db)
+    }
+
+    /// Read and parse an `osu!.db` file from disk.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read or parsed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ReplayDataError<'static>> {
+        let input = std::fs::read(path)?;
+        OsuDb::parse(&input).map_err(ReplayDataError::into_owned)
+    }
+
+    /// Find the beatmap with the given MD5 hash.
+    pub fn find(&self, md5: &str) -> Option<&OsuDbBeatmap> {
+        self.beatmaps
+            .iter()
+            .find(|beatmap| beatmap.md5.eq_ignore_ascii_case(md5))
+    }
+}
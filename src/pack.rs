@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::batch::replay_files;
+use crate::errors::{ParseError, ReplayDataError};
+use crate::hash::{sha256, to_hex};
+use crate::parser::{HashValidation, ParseOptions};
+use crate::replay::Replay;
+
+/// Conventional file name of a manifest stored next to the replays it describes.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Version written to manifests, manifests with another version are rejected.
+const MANIFEST_VERSION: i64 = 2;
+
+/// A replay file of a [`Manifest`].
+///
+/// The header fields are empty if the header of the replay could not be parsed, see
+/// [`Self::error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// File name of the replay inside the folder.
+    pub file_name: String,
+    /// Name of the player.
+    pub player_name: String,
+    /// MD5 hash of the beatmap the replay was played on.
+    pub beatmap_md5: String,
+    /// Bitwise representation of the mods used.
+    pub mods: u32,
+    /// Total score displayed on the score report.
+    pub total_score: u32,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// SHA-256 hash of the whole file.
+    pub file_sha256: String,
+    /// Why the header of the replay could not be parsed, `None` if it was parsed.
+    pub error: Option<String>,
+}
+
+/// A description of the replays in a folder, e.g. of a tournament archive, to detect replays
+/// that were added, removed or modified afterwards.
+/// # Example
+/// ```
+/// use osu_replay_parser::pack::{self, Manifest, Mismatch};
+///
+/// let dir = std::env::temp_dir().join("osu_replay_parser_pack_example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::copy("assets/replay.osr", dir.join("round1.osr")).unwrap();
+///
+/// let manifest = pack::manifest(&dir).expect("Error creating manifest");
+/// manifest.save(&dir).expect("Error writing manifest");
+/// assert_eq!(Manifest::load(&dir).unwrap(), manifest);
+///
+/// std::fs::copy("assets/replay.osr", dir.join("round2.osr")).unwrap();
+/// let mismatches = pack::verify(&dir, &manifest).expect("Error verifying manifest");
+/// assert_eq!(mismatches, vec![Mismatch::Unexpected("round2.osr".to_string())]);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// The replays, sorted by file name.
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Serialize the manifest as JSON.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut fields = json!({
+                    "fileName": entry.file_name,
+                    "playerName": entry.player_name,
+                    "beatmapMd5": entry.beatmap_md5,
+                    "mods": entry.mods,
+                    "totalScore": entry.total_score,
+                    "size": entry.size,
+                    "fileSha256": entry.file_sha256,
+                });
+                if let Some(error) = &entry.error {
+                    fields["error"] = error.as_str().into();
+                }
+                fields
+            })
+            .collect();
+
+        json!({
+            "version": MANIFEST_VERSION,
+            "replays": Value::Array(entries),
+        })
+        .to_string()
+    }
+
+    /// Write the manifest as [`MANIFEST_FILE_NAME`] into a folder.
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        std::fs::write(dir.as_ref().join(MANIFEST_FILE_NAME), self.to_json())
+    }
+
+    /// Read the manifest stored as [`MANIFEST_FILE_NAME`] in a folder.
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read or parsed.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self, ReplayDataError<'static>> {
+        Manifest::parse(&std::fs::read_to_string(
+            dir.as_ref().join(MANIFEST_FILE_NAME),
+        )?)
+    }
+
+    /// Parse a manifest written by [`Self::to_json`].
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if the JSON is invalid or has another version and
+    /// `ParseError::MissingValue` if a field is missing.
+    pub fn parse(json: &str) -> Result<Self, ReplayDataError<'static>> {
+        let value: Value = serde_json::from_str(json).map_err(|_| ParseError::InvalidValue)?;
+        if value.get("version").and_then(Value::as_i64) != Some(MANIFEST_VERSION) {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+        let replays = value
+            .get("replays")
+            .and_then(Value::as_array)
+            .ok_or(ParseError::MissingValue)?;
+
+        let entries = replays
+            .iter()
+            .map(|replay| {
+                let string = |key| {
+                    replay
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .ok_or(ParseError::MissingValue)
+                };
+                let int = |key| {
+                    replay
+                        .get(key)
+                        .and_then(Value::as_i64)
+                        .ok_or(ParseError::MissingValue)
+                };
+                let convert = |value: i64| value.try_into().map_err(|_| ParseError::InvalidValue);
+                Ok(ManifestEntry {
+                    file_name: string("fileName")?,
+                    player_name: string("playerName")?,
+                    beatmap_md5: string("beatmapMd5")?,
+                    mods: int("mods").and_then(convert)?,
+                    total_score: int("totalScore").and_then(convert)?,
+                    size: int("size")?
+                        .try_into()
+                        .map_err(|_| ParseError::InvalidValue)?,
+                    file_sha256: string("fileSha256")?,
+                    error: replay
+                        .get("error")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                })
+            })
+            .collect::<Result<_, ReplayDataError<'static>>>()?;
+
+        Ok(Manifest { entries })
+    }
+}
+
+/// A difference between the replays in a folder and a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The replay is in the manifest but not in the folder.
+    Missing(String),
+    /// The replay is in the folder but not in the manifest.
+    Unexpected(String),
+    /// The contents of the replay file differ from the manifest.
+    Modified(String),
+}
+
+/// Create the manifest of the `.osr` files in a folder, see [`replay_files`].
+///
+/// Only the headers of the replays are parsed, so replays with damaged frame data are listed.
+/// Replays whose header cannot be parsed are listed with their hash and the error, see
+/// [`ManifestEntry::error`].
+/// # Example
+/// ```
+/// use osu_replay_parser::pack;
+///
+/// let dir = std::env::temp_dir().join("osu_replay_parser_pack_error_example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::copy("assets/replay.osr", dir.join("good.osr")).unwrap();
+/// std::fs::write(dir.join("broken.osr"), b"not a replay").unwrap();
+///
+/// let manifest = pack::manifest(&dir).expect("Error creating manifest");
+/// assert_eq!(manifest.entries.len(), 2);
+/// assert_eq!(manifest.entries[0].file_name, "broken.osr");
+/// assert!(manifest.entries[0].error.is_some());
+/// assert_eq!(manifest.entries[1].player_name, "RenLephy");
+/// assert!(pack::verify(&dir, &manifest).unwrap().is_empty());
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+/// # Errors
+/// Returns an error if the folder or a file cannot be read.
+pub fn manifest<P: AsRef<Path>>(dir: P) -> io::Result<Manifest> {
+    let options = ParseOptions {
+        header_only: true,
+        hash_validation: HashValidation::Lenient,
+        unknown_game_modes: true,
+    };
+
+    let mut entries = Vec::new();
+    for path in replay_files(dir.as_ref())? {
+        let bytes = std::fs::read(&path)?;
+        let (replay, error) = match Replay::parse_with_options(&bytes, &options) {
+            Ok(replay) => (replay, None),
+            Err(e) => (Replay::default(), Some(e.to_string())),
+        };
+        entries.push(ManifestEntry {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            player_name: replay.player_name,
+            beatmap_md5: replay.beatmap_md5,
+            mods: replay.mods,
+            total_score: replay.total_score,
+            size: bytes.len() as u64,
+            file_sha256: to_hex(&sha256(&bytes)),
+            error,
+        });
+    }
+
+    Ok(Manifest { entries })
+}
+
+/// Compare the replays in a folder with a manifest, e.g. one created by [`manifest`] when the
+/// folder was archived.
+///
+/// Files are compared by their size and hash, so any change, including to the frames or a
+/// damaged header, is reported as [`Mismatch::Modified`]. The mismatches are sorted by file name.
+/// # Errors
+/// Returns an error if the folder or a file cannot be read.
+pub fn verify<P: AsRef<Path>>(dir: P, expected: &Manifest) -> io::Result<Vec<Mismatch>> {
+    let mut expected: BTreeMap<&str, &ManifestEntry> = expected
+        .entries
+        .iter()
+        .map(|entry| (entry.file_name.as_str(), entry))
+        .collect();
+
+    let mut mismatches = Vec::new();
+    for path in replay_files(dir.as_ref())? {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match expected.remove(file_name.as_str()) {
+            None => mismatches.push(Mismatch::Unexpected(file_name)),
+            Some(entry) => {
+                let bytes = std::fs::read(&path)?;
+                if entry.size != bytes.len() as u64
+                    || !entry
+                        .file_sha256
+                        .eq_ignore_ascii_case(&to_hex(&sha256(&bytes)))
+                {
+                    mismatches.push(Mismatch::Modified(file_name));
+                }
+            }
+        }
+    }
+    mismatches.extend(
+        expected
+            .into_keys()
+            .map(|file_name| Mismatch::Missing(file_name.to_string())),
+    );
+    mismatches.sort_by(|a, b| mismatch_file_name(a).cmp(mismatch_file_name(b)));
+
+    Ok(mismatches)
+}
+
+fn mismatch_file_name(mismatch: &Mismatch) -> &str {
+    match mismatch {
+        Mismatch::Missing(file_name)
+        | Mismatch::Unexpected(file_name)
+        | Mismatch::Modified(file_name) => file_name,
+    }
+}
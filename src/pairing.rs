@@ -0,0 +1,75 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "md5")]
+use crate::hash::BuiltinMd5;
+use crate::hash::Hasher;
+use crate::osu_db::OsuDb;
+use crate::replay::Replay;
+
+/// Search a folder recursively, without following symbolic links to folders, which may form loops.
+fn find_in_dir(dir: &Path, md5: &str, hasher: &dyn Hasher) -> io::Result<Option<PathBuf>> {
+    let mut entries: Vec<(PathBuf, bool)> = std::fs::read_dir(dir)?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.path(), entry.file_type()?.is_dir()))
+        })
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for (path, is_dir) in entries {
+        if is_dir {
+            if let Some(found) = find_in_dir(&path, md5, hasher)? {
+                return Ok(Some(found));
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("osu"))
+            && hasher
+                .md5_hex(&std::fs::read(&path)?)
+                .eq_ignore_ascii_case(md5)
+        {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find the `.osu` file of the beatmap a replay was played on by hashing the files in a Songs folder.
+///
+/// The folder is searched recursively, symbolic links to folders are skipped. Returns `Ok(None)` if no file matches the replay's `beatmap_md5`.
+/// Use [`find_beatmap_in_db`] or a [`SongsIndex`](crate::index::SongsIndex) to avoid hashing every file.
+/// # Errors
+/// Returns an error if the folder or one of the files cannot be read.
+#[cfg(feature = "md5")]
+pub fn find_beatmap<P: AsRef<Path>>(replay: &Replay, songs_dir: P) -> io::Result<Option<PathBuf>> {
+    find_beatmap_with_hasher(replay, songs_dir, &BuiltinMd5)
+}
+
+/// Like [`find_beatmap`], hashing the files with another MD5 implementation.
+/// # Errors
+/// Returns an error if the folder or one of the files cannot be read.
+pub fn find_beatmap_with_hasher<P: AsRef<Path>>(
+    replay: &Replay,
+    songs_dir: P,
+    hasher: &dyn Hasher,
+) -> io::Result<Option<PathBuf>> {
+    find_in_dir(songs_dir.as_ref(), &replay.beatmap_md5, hasher)
+}
+
+/// Find the `.osu` file of the beatmap a replay was played on using osu!stable's `osu!.db` index.
+///
+/// Returns the path inside `songs_dir` stored in the index, or `None` if the beatmap is not in the index.
+pub fn find_beatmap_in_db<P: AsRef<Path>>(
+    replay: &Replay,
+    db: &OsuDb,
+    songs_dir: P,
+) -> Option<PathBuf> {
+    db.find(&replay.beatmap_md5).map(|beatmap| {
+        songs_dir
+            .as_ref()
+            .join(&beatmap.folder_name)
+            .join(&beatmap.file_name)
+    })
+}
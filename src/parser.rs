@@ -1,22 +1,144 @@
-use crate::errors::{from_context, ReplayDataError};
-use crate::replay::{GameMode, Replay};
+use crate::compression::complete_stream_length;
+use crate::decoder::DecodeLimits;
+use crate::errors::{from_context, ParseError, ReplayDataError};
+use crate::ids::is_md5_hex;
+use crate::replay::{
+    GameMode, Mods, Replay, FIRST_LAZER_VERSION, FIRST_SCORE_ID_VERSION, LONG_SCORE_ID_VERSION,
+};
+use crate::writer::osr_string_bytes;
 
-use nom::bytes::complete::take;
+use std::ops::Range;
+use std::path::Path;
 
-use nom::error::{context, ParseError, VerboseError};
+use nom::error::{context, ParseError as _, VerboseError};
+use nom::number::{complete, streaming};
+use nom::{bytes, Finish, IResult, Needed};
+
+pub(crate) type ParseResult<I, O> = IResult<I, O, VerboseError<I>>;
+
+// Primitive parsers, returning `Incomplete` instead of an error on missing input if `STREAMING` is set.
 // Naming conventions taken from the osu! wiki
-use nom::number::complete::{le_i64, le_u16 as short, le_u32 as integer, le_u8 as byte};
-use nom::{Finish, IResult};
+macro_rules! primitive {
+    ($name:ident, $parser:ident, $output:ty) => {
+        fn $name<const STREAMING: bool>(input: &[u8]) -> ParseResult<&[u8], $output> {
+            if STREAMING {
+                streaming::$parser(input)
+            } else {
+                complete::$parser(input)
+            }
+        }
+    };
+}
+
+primitive!(byte, le_u8, u8);
+primitive!(short, le_u16, u16);
+primitive!(integer, le_u32, u32);
+primitive!(le_i32, le_i32, i32);
+primitive!(le_i64, le_i64, i64);
+primitive!(le_f64, le_f64, f64);
+
+fn take<const STREAMING: bool>(count: u32) -> impl Fn(&[u8]) -> ParseResult<&[u8], &[u8]> {
+    move |input| {
+        if STREAMING {
+            bytes::streaming::take(count)(input)
+        } else {
+            bytes::complete::take(count)(input)
+        }
+    }
+}
 
-type ParseResult<I, O> = IResult<I, O, VerboseError<I>>;
+/// How the beatmap and replay MD5 hashes are checked while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum HashValidation {
+    /// Fail parsing if a hash is not 32 hexadecimal characters.
+    Strict,
+    /// Keep malformed hashes, they can be detected with [`Replay::has_valid_hashes`].
+    ///
+    /// Some replays generated by third-party tools have empty or garbage hashes.
+    #[default]
+    Lenient,
+}
 
-fn uleb128(input: &[u8]) -> ParseResult<&[u8], u32> {
+/// Options controlling how a replay file is parsed.
+///
+/// Use with [`Replay::parse_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Only parse the header, the compressed replay data is skipped and not stored in the [`Replay`].
+    ///
+    /// Useful for scanning large numbers of replays where only the metadata is of interest.
+    pub header_only: bool,
+    /// How the beatmap and replay MD5 hashes are checked.
+    pub hash_validation: HashValidation,
+    /// Keep game mode bytes other than 0 to 3 as [`GameMode::Unknown`] instead of failing.
+    ///
+    /// Some private servers use custom values for their own game modes.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::GameMode;
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    ///
+    /// let mut input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    /// input[0] = 7;
+    /// assert!(Replay::parse(&input).is_err());
+    ///
+    /// let options = ParseOptions { unknown_game_modes: true, ..Default::default() };
+    /// let replay = Replay::parse_with_options(&input, &options).expect("Error parsing replay");
+    /// assert_eq!(replay.game_mode, GameMode::Unknown(7));
+    /// assert_eq!(replay.to_bytes().unwrap(), input);
+    /// ```
+    pub unknown_game_modes: bool,
+}
+
+impl ParseOptions {
+    /// Options for parsing only the header of a replay, see [`Self::header_only`].
+    pub fn header_only() -> Self {
+        ParseOptions {
+            header_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// Options accepting malformed hashes, the default, see [`HashValidation::Lenient`].
+    pub fn lenient_hashes() -> Self {
+        ParseOptions {
+            hash_validation: HashValidation::Lenient,
+            ..Default::default()
+        }
+    }
+
+    /// Options rejecting malformed hashes, see [`HashValidation::Strict`].
+    pub fn strict_hashes() -> Self {
+        ParseOptions {
+            hash_validation: HashValidation::Strict,
+            ..Default::default()
+        }
+    }
+}
+
+fn md5_hash<'a, const STREAMING: bool>(
+    options: &ParseOptions,
+) -> impl Fn(&'a [u8]) -> ParseResult<&'a [u8], &'a str> + '_ {
+    move |input| {
+        let (rest, hash) = string::<STREAMING>(input)?;
+        if options.hash_validation == HashValidation::Strict && !is_md5_hex(hash) {
+            return Err(nom::Err::Error(from_context(input, "Invalid MD5 hash")));
+        }
+        Ok((rest, hash))
+    }
+}
+
+fn uleb128<const STREAMING: bool>(input: &[u8]) -> ParseResult<&[u8], u32> {
     let mut result = 0;
     let mut shift = 0;
 
     for (bytes, byte) in input.iter().enumerate() {
-        let byte = *byte as u32;
-        result |= (byte & 0x7F) << shift;
+        let low = u32::from(byte & 0x7F);
+        result |= low
+            .checked_shl(shift)
+            .filter(|value| value >> shift == low)
+            .ok_or_else(|| nom::Err::Error(from_context(input, "ULEB128 overflows 32 bits")))?;
         shift += 7;
 
         if byte & 0x80 == 0 {
@@ -24,6 +146,9 @@ fn uleb128(input: &[u8]) -> ParseResult<&[u8], u32> {
         }
     }
 
+    if STREAMING {
+        return Err(nom::Err::Incomplete(Needed::new(1)));
+    }
     Err(nom::Err::Error(VerboseError::from_error_kind(
         input,
         nom::error::ErrorKind::Eof,
@@ -37,61 +162,133 @@ fn utf8_string(input: &[u8]) -> ParseResult<&[u8], &str> {
     Ok((b"", str))
 }
 
-fn osr_string(input: &[u8]) -> ParseResult<&[u8], &str> {
-    let (input, is_present) = byte(input)?;
+pub(crate) fn osr_string(input: &[u8]) -> ParseResult<&[u8], &str> {
+    string::<false>(input)
+}
+
+fn string<const STREAMING: bool>(input: &[u8]) -> ParseResult<&[u8], &str> {
+    let (input, is_present) = byte::<STREAMING>(input)?;
     if is_present == 0x00 {
         return Ok((input, ""));
     }
 
-    let (input, length) = context("Error parsing ULEB128 for String length", uleb128)(input)?;
-    let (input, value) = context("Expected more bytes for string", take(length))(input)?;
+    let (input, length) =
+        context("Error parsing ULEB128 for String length", uleb128::<STREAMING>)(input)?;
+    let (input, value) =
+        context("Expected more bytes for string", take::<STREAMING>(length))(input)?;
     let (_, string) = utf8_string(value)?;
 
     Ok((input, string))
 }
 
-fn game_mode(input: &[u8]) -> ParseResult<&[u8], GameMode> {
-    let (input, game_mode_int) = byte(input)?;
-
-    let game_mode = match game_mode_int {
-        0 => GameMode::Osu,
-        1 => GameMode::Taiko,
-        2 => GameMode::CatchTheBeat,
-        3 => GameMode::Mania,
-        _ => return Err(nom::Err::Error(from_context(input, "Invalid Game Mode"))),
+/// The player name, decoded lossily, with the raw bytes of the field if they differ from the encoding of the name.
+pub(crate) fn player_name<const STREAMING: bool>(
+    input: &[u8],
+) -> ParseResult<&[u8], (String, Option<Vec<u8>>)> {
+    let (rest, is_present) = byte::<STREAMING>(input)?;
+    let (rest, value) = if is_present == 0x00 {
+        (rest, &b""[..])
+    } else {
+        let (rest, length) = context(
+            "Error parsing ULEB128 for String length",
+            uleb128::<STREAMING>,
+        )(rest)?;
+        context("Expected more bytes for string", take::<STREAMING>(length))(rest)?
     };
 
-    Ok((input, game_mode))
-}
-
-fn replay_parser(input: &[u8]) -> ParseResult<&[u8], Replay> {
-    let (input, game_mode) = context("Error parsing game mode", game_mode)(input)?;
-    let (input, version) = context("Error parsing game version", integer)(input)?;
-    let (input, beatmap_md5) = context("Error parsing beatmap MD5", osr_string)(input)?;
-    let (input, player_name) = context("Error parsing player name", osr_string)(input)?;
-    let (input, replay_md5) = context("Error parsing replay MD5", osr_string)(input)?;
-    let (input, n300) = context("Error parsing 300s count", short)(input)?;
-    let (input, n100) = context("Error parsing 100s count", short)(input)?;
-    let (input, n50) = context("Error parsing 50s count", short)(input)?;
-    let (input, n_geki) = context("Error parsing gekis count", short)(input)?;
-    let (input, n_katu) = context("Error parsing katus count", short)(input)?;
-    let (input, n_miss) = context("Error parsing misses count", short)(input)?;
-    let (input, total_score) = context("Error parsing total score", integer)(input)?;
-    let (input, greatest_combo) = context("Error parsing greatest combo", short)(input)?;
-    let (input, perfect) = context("Error parsing perfect", byte)(input)?;
-    let (input, mods) = context("Error parsing mods", integer)(input)?;
-    let (input, life_bar) = context("Error parsing life bar", osr_string)(input)?;
-    let (input, time_stamp) = context("Error parsing time stamp", le_i64)(input)?;
-    let (input, compressed_length) = context("Error parsing compressed length", integer)(input)?;
-    let (input, compressed_data) =
-        context("Error parsing compressed data", take(compressed_length))(input)?;
-    let (input, online_score_id) = context("Error parsing online score ID", le_i64)(input)?;
+    let name = String::from_utf8_lossy(value).into_owned();
+    let field = &input[..input.len() - rest.len()];
+    let raw = (field != osr_string_bytes(&name)).then(|| field.to_vec());
+
+    Ok((rest, (name, raw)))
+}
+
+fn game_mode<const STREAMING: bool>(
+    options: &ParseOptions,
+) -> impl Fn(&[u8]) -> ParseResult<&[u8], GameMode> + '_ {
+    move |input| {
+        let (input, game_mode_int) = byte::<STREAMING>(input)?;
+
+        let game_mode = match game_mode_int {
+            0 => GameMode::Osu,
+            1 => GameMode::Taiko,
+            2 => GameMode::CatchTheBeat,
+            3 => GameMode::Mania,
+            _ if options.unknown_game_modes => GameMode::Unknown(game_mode_int),
+            _ => return Err(nom::Err::Error(from_context(input, "Invalid Game Mode"))),
+        };
+
+        Ok((input, game_mode))
+    }
+}
+
+fn replay_parser<'a, const S: bool>(
+    input: &'a [u8],
+    options: &ParseOptions,
+) -> ParseResult<&'a [u8], Replay> {
+    let (input, game_mode) = context("Error parsing game mode", game_mode::<S>(options))(input)?;
+    let (input, version) = context("Error parsing game version", integer::<S>)(input)?;
+    let beatmap_md5_field = input;
+    let (input, beatmap_md5) = context("Error parsing beatmap MD5", md5_hash::<S>(options))(input)?;
+    let (input, (player_name, player_name_raw)) =
+        context("Error parsing player name", player_name::<S>)(input)?;
+    let replay_md5_field = input;
+    let (input, replay_md5) = context("Error parsing replay MD5", md5_hash::<S>(options))(input)?;
+    let (input, n300) = context("Error parsing 300s count", short::<S>)(input)?;
+    let (input, n100) = context("Error parsing 100s count", short::<S>)(input)?;
+    let (input, n50) = context("Error parsing 50s count", short::<S>)(input)?;
+    let (input, n_geki) = context("Error parsing gekis count", short::<S>)(input)?;
+    let (input, n_katu) = context("Error parsing katus count", short::<S>)(input)?;
+    let (input, n_miss) = context("Error parsing misses count", short::<S>)(input)?;
+    let (input, total_score) = context("Error parsing total score", integer::<S>)(input)?;
+    let (input, greatest_combo) = context("Error parsing greatest combo", short::<S>)(input)?;
+    let (input, perfect) = context("Error parsing perfect", byte::<S>)(input)?;
+    let (input, mods) = context("Error parsing mods", integer::<S>)(input)?;
+    let life_bar_field = input;
+    let (input, life_bar) = context("Error parsing life bar", string::<S>)(input)?;
+    let empty_strings_present = [
+        (beatmap_md5_field, beatmap_md5),
+        (replay_md5_field, replay_md5),
+        (life_bar_field, life_bar),
+    ]
+    .iter()
+    .any(|(field, value)| value.is_empty() && field[0] != 0x00);
+    let (input, time_stamp) = context("Error parsing time stamp", le_i64::<S>)(input)?;
+    let (input, compressed_length) =
+        context("Error parsing compressed length", integer::<S>)(input)?;
+    let (input, compressed_data) = context(
+        "Error parsing compressed data",
+        take::<S>(compressed_length),
+    )(input)?;
+    let (input, online_score_id) = if version >= LONG_SCORE_ID_VERSION {
+        context("Error parsing online score ID", le_i64::<S>)(input)?
+    } else if version < FIRST_SCORE_ID_VERSION {
+        (input, 0)
+    } else {
+        let (input, id) = context("Error parsing online score ID", le_i32::<S>)(input)?;
+        (input, i64::from(id))
+    };
+    let (input, additional_mod_info) = if mods & Mods::TARGET_PRACTICE.bits() != 0 {
+        let (input, accuracy) = context("Error parsing additional mod info", le_f64::<S>)(input)?;
+        (input, Some(accuracy))
+    } else {
+        (input, None)
+    };
+    // osu!lazer replays without the trailer are accepted, so a streamed one is incomplete
+    // until its trailer has been received
+    let (input, lazer_data) = if version >= FIRST_LAZER_VERSION && (S || !input.is_empty()) {
+        let (input, length) = context("Error parsing lazer data length", integer::<S>)(input)?;
+        let (input, data) = context("Error parsing lazer data", take::<S>(length))(input)?;
+        (input, Some(data.to_vec()))
+    } else {
+        (input, None)
+    };
 
     let replay = Replay {
         game_mode,
         version,
         beatmap_md5: beatmap_md5.to_string(),
-        player_name: player_name.to_string(),
+        player_name,
         replay_md5: replay_md5.to_string(),
         n300,
         n100,
@@ -101,17 +298,86 @@ fn replay_parser(input: &[u8]) -> ParseResult<&[u8], Replay> {
         n_miss,
         total_score,
         greatest_combo,
-        perfect,
+        perfect: perfect != 0,
         mods,
         life_bar: life_bar.to_string(),
         time_stamp,
-        compressed_data: compressed_data.to_vec(),
+        compressed_data: if options.header_only {
+            Vec::new()
+        } else {
+            compressed_data.to_vec()
+        },
         online_score_id,
+        additional_mod_info,
+        lazer_data,
+        player_name_raw,
+        perfect_raw: (perfect > 1).then_some(perfect),
+        empty_strings_present,
     };
 
     Ok((input, replay))
 }
 
+/// Streaming variant of the replay parser for input that is received in chunks.
+///
+/// Returns `nom::Err::Incomplete` if more bytes are needed and `nom::Err::Error` if the input is
+/// malformed. osu!lazer replays are only complete once their trailer has been received, replays
+/// of osu!lazer versions without a trailer have to be parsed again once the input ends.
+/// See [`Replay::parse_streaming`] for a non-nom interface.
+pub fn streaming_replay<'a>(
+    input: &'a [u8],
+    options: &ParseOptions,
+) -> IResult<&'a [u8], Replay, VerboseError<&'a [u8]>> {
+    context("Error parsing replay file", |input| {
+        replay_parser::<true>(input, options)
+    })(input)
+}
+
+/// Replay versions outside of this range are rejected by [`peek_header`], from the first
+/// release of osu! to far beyond the current osu!lazer versions.
+const PLAUSIBLE_VERSIONS: Range<u32> = 20070101..40000000;
+
+/// Read the game mode and version from the first 5 bytes of a replay file without parsing the
+/// rest, e.g. to skip replays of other game modes before parsing them.
+/// # Example
+/// ```
+/// use osu_replay_parser::parser::peek_header;
+/// use osu_replay_parser::replay::GameMode;
+///
+/// let input = std::fs::read("assets/replay.osr").expect("Error reading file");
+/// let (game_mode, version) = peek_header(&input).expect("Error reading header");
+/// assert_eq!(game_mode, GameMode::Osu);
+/// assert!(version > 20070101);
+///
+/// assert!(peek_header(&input[..4]).is_err());
+/// assert!(peek_header(b"\x07\x00\x00\x00\x00").is_err());
+/// ```
+/// # Errors
+/// Returns `ParseError::MissingValue` if there are fewer than 5 bytes and
+/// `ParseError::InvalidValue` if the game mode is unknown or the version is implausible.
+pub fn peek_header(input: &[u8]) -> Result<(GameMode, u32), ReplayDataError<'static>> {
+    let Some(&[mode, a, b, c, d]) = input.get(..5) else {
+        return Err(ParseError::MissingValue.into());
+    };
+    let game_mode = GameMode::try_from(mode)?;
+    let version = u32::from_le_bytes([a, b, c, d]);
+    if !PLAUSIBLE_VERSIONS.contains(&version) {
+        return Err(ParseError::InvalidValue.into());
+    }
+
+    Ok((game_mode, version))
+}
+
+/// Outcome of parsing a possibly incomplete replay file with [`Replay::parse_streaming`].
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum ParseStatus {
+    /// The replay was parsed completely.
+    Complete(Replay),
+    /// The input ended before the replay was complete.
+    Incomplete(Needed),
+}
+
 impl Replay {
     /// Parse an osu! replay file into a `Replay` struct.
     /// # Example
@@ -129,8 +395,361 @@ impl Replay {
     /// Returns a `ReplayDataError` if the replay file is invalid or cannot be parsed.
     ///
     pub fn parse(input: &[u8]) -> Result<Self, ReplayDataError<'_>> {
-        let (_, replay) = context("Error parsing replay file", replay_parser)(input).finish()?;
+        Replay::parse_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parse an osu! replay file into a `Replay` struct using the given [`ParseOptions`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    /// use std::fs;
+    ///
+    /// let input = fs::read("assets/replay.osr").expect("Error reading file");
+    /// let replay = Replay::parse_with_options(&input, &ParseOptions::header_only())
+    ///     .expect("Error parsing replay");
+    /// assert!(replay.compressed_data.is_empty());
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the replay file is invalid or cannot be parsed.
+    pub fn parse_with_options<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Self, ReplayDataError<'a>> {
+        let (_, replay) = context("Error parsing replay file", |input| {
+            replay_parser::<false>(input, options)
+        })(input)
+        .finish()?;
 
         Ok(replay)
     }
+
+    /// Parse an osu! replay file that may not have been received completely yet.
+    ///
+    /// Distinguishes input that needs more bytes from malformed input, see [`streaming_replay`].
+    /// A replay of an osu!lazer version is incomplete until its trailer has been received.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::parser::ParseStatus;
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    /// use std::fs;
+    ///
+    /// let input = fs::read("assets/replay.osr").expect("Error reading file");
+    /// let options = ParseOptions::default();
+    ///
+    /// let partial = Replay::parse_streaming(&input[..1000], &options).expect("Malformed replay");
+    /// assert!(matches!(partial, ParseStatus::Incomplete(_)));
+    ///
+    /// let complete = Replay::parse_streaming(&input, &options).expect("Malformed replay");
+    /// assert!(matches!(complete, ParseStatus::Complete(_)));
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the received part of the replay file is invalid.
+    pub fn parse_streaming<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<ParseStatus, ReplayDataError<'a>> {
+        match streaming_replay(input, options) {
+            Ok((_, replay)) => Ok(ParseStatus::Complete(replay)),
+            Err(nom::Err::Incomplete(needed)) => Ok(ParseStatus::Incomplete(needed)),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(e.into()),
+        }
+    }
+
+    /// Read and parse an osu! replay file from disk.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the file cannot be read or the replay cannot be parsed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ReplayDataError<'static>> {
+        let input = std::fs::read(path)?;
+
+        Replay::parse(&input).map_err(ReplayDataError::into_owned)
+    }
+}
+
+/// Largest difference between the declared and actual length of the compressed data that
+/// [`Replay::parse_with_length_recovery`] corrects.
+pub const MAX_LENGTH_CORRECTION: u32 = 64;
+
+/// Largest number of candidate lengths whose LZMA stream [`Replay::parse_with_length_recovery`]
+/// decompresses before giving up.
+const MAX_RECOVERY_DECODES: usize = 8;
+
+/// Correction of a wrong compressed length field, see [`Replay::parse_with_length_recovery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthCorrection {
+    /// Length of the compressed data stored in the file.
+    pub declared: u32,
+    /// Length of the compressed data the replay was parsed with.
+    pub actual: u32,
+}
+
+impl LengthCorrection {
+    /// Number of bytes the declared length was off by, negative if it was too short.
+    pub fn difference(&self) -> i64 {
+        i64::from(self.declared) - i64::from(self.actual)
+    }
+}
+
+/// Offset of the compressed length field, `None` if the fields before it cannot be parsed.
+fn compressed_length_offset(input: &[u8]) -> Option<usize> {
+    let fields = |input| -> ParseResult<&[u8], ()> {
+        let (input, _) = byte::<false>(input)?;
+        let (input, _) = integer::<false>(input)?;
+        let (input, _) = string::<false>(input)?;
+        let (input, _) = player_name::<false>(input)?;
+        let (input, _) = string::<false>(input)?;
+        // Hit counts, total score, greatest combo, perfect and mods
+        let (input, _) = take::<false>(6 * 2 + 4 + 2 + 1 + 4)(input)?;
+        let (input, _) = string::<false>(input)?;
+        let (input, _) = le_i64::<false>(input)?;
+        Ok((input, ()))
+    };
+    let (rest, ()) = fields(input).ok()?;
+
+    Some(input.len() - rest.len())
+}
+
+impl Replay {
+    /// Parse a replay file whose compressed length field is off by a few bytes, as written by
+    /// some third-party tools.
+    ///
+    /// A wrong length makes parsing fail or silently misaligns the online score ID and the
+    /// fields after it. If the replay does not parse to the exact end of the input, the end of the
+    /// LZMA stream is located by decompressing it, and its length is used if it is at most
+    /// [`MAX_LENGTH_CORRECTION`] bytes away from the declared one and parses to the end. Otherwise,
+    /// e.g. if the stream is padded, the lengths in that range are tried, closest first, and the
+    /// first one that parses to the end with a complete LZMA stream is used. Streams are
+    /// decompressed within [`DecodeLimits::untrusted`] and replays that parse normally are not
+    /// decompressed.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    /// let (replay, correction) = Replay::parse_with_length_recovery(&input, &Default::default())
+    ///     .expect("Error parsing replay");
+    /// assert!(correction.is_none());
+    ///
+    /// // Find the length field and make it 3 bytes too short
+    /// let length = (replay.compressed_data.len() as u32).to_le_bytes();
+    /// let offset = input.windows(4).position(|bytes| bytes == length).unwrap();
+    /// let mut broken = input.clone();
+    /// broken[offset..offset + 4].copy_from_slice(&(replay.compressed_data.len() as u32 - 3).to_le_bytes());
+    ///
+    /// let (recovered, correction) = Replay::parse_with_length_recovery(&broken, &Default::default())
+    ///     .expect("Error parsing replay");
+    /// assert_eq!(correction.unwrap().difference(), -3);
+    /// assert_eq!(recovered.online_score_id, replay.online_score_id);
+    /// assert_eq!(recovered.compressed_data, replay.compressed_data);
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::MissingValue` if the input ends within the length field and the
+    /// error of [`Replay::parse_with_options`] if no length works.
+    pub fn parse_with_length_recovery<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, Option<LengthCorrection>), ReplayDataError<'a>> {
+        let decode_options = ParseOptions {
+            header_only: false,
+            ..options.clone()
+        };
+        let parse_exact = |input: &[u8], options: &ParseOptions| {
+            let (rest, replay) = replay_parser::<false>(input, options).ok()?;
+            rest.is_empty().then_some(replay)
+        };
+
+        if let Some(replay) = parse_exact(input, options) {
+            return Ok((replay, None));
+        }
+        if let Some(offset) = compressed_length_offset(input) {
+            let field = input
+                .get(offset..offset + 4)
+                .ok_or(ParseError::MissingValue)?;
+            let declared = u32::from_le_bytes(field.try_into().expect("Field has 4 bytes"));
+            let limits = DecodeLimits::untrusted();
+            let mut patched = input.to_vec();
+
+            // The end of the LZMA stream gives the actual length unless the data is padded
+            let located = complete_stream_length(&input[offset + 4..], &limits)
+                .and_then(|length| u32::try_from(length).ok())
+                .filter(|&actual| {
+                    actual != declared && actual.abs_diff(declared) <= MAX_LENGTH_CORRECTION
+                });
+            if let Some(actual) = located {
+                patched[offset..offset + 4].copy_from_slice(&actual.to_le_bytes());
+                if let Some(replay) = parse_exact(&patched, options) {
+                    return Ok((replay, Some(LengthCorrection { declared, actual })));
+                }
+            }
+
+            let candidates = (1..=MAX_LENGTH_CORRECTION).flat_map(|delta| {
+                [declared.checked_sub(delta), declared.checked_add(delta)]
+            });
+            let mut decodes = 0;
+            for actual in candidates.flatten() {
+                patched[offset..offset + 4].copy_from_slice(&actual.to_le_bytes());
+                let Some(mut replay) = parse_exact(&patched, &decode_options) else {
+                    continue;
+                };
+                if complete_stream_length(&replay.compressed_data, &limits).is_some() {
+                    if options.header_only {
+                        replay.compressed_data = Vec::new();
+                    }
+                    return Ok((replay, Some(LengthCorrection { declared, actual })));
+                }
+                decodes += 1;
+                if decodes == MAX_RECOVERY_DECODES {
+                    break;
+                }
+            }
+        }
+
+        Replay::parse_with_options(input, options).map(|replay| (replay, None))
+    }
+}
+
+/// Byte ranges of the fields of a replay file in the parsed input, see [`Replay::parse_with_spans`].
+///
+/// Fields are named like the fields of [`Replay`] and listed in file order. The length prefix of
+/// the compressed data and the osu!lazer data are listed as `compressed_length` and
+/// `lazer_data_length`, optional fields missing from the file are not listed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FieldSpans {
+    spans: Vec<(&'static str, Range<usize>)>,
+}
+
+impl FieldSpans {
+    /// Byte range of the field, `None` if it is not in the file.
+    pub fn get(&self, field: &str) -> Option<Range<usize>> {
+        self.spans
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, span)| span.clone())
+    }
+
+    /// Name of the field containing the byte at the offset.
+    pub fn field_at(&self, offset: usize) -> Option<&'static str> {
+        self.spans
+            .iter()
+            .find(|(_, span)| span.contains(&offset))
+            .map(|(name, _)| *name)
+    }
+
+    /// Iterate over the fields and their byte ranges in file order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Range<usize>)> + '_ {
+        self.spans.iter().cloned()
+    }
+}
+
+/// Size of a field in the replay file.
+enum FieldSize {
+    Fixed(u32),
+    OsrString,
+    /// Length given by the previous field, a 4 byte integer.
+    Prefixed,
+}
+
+/// Record the byte range of every field of a replay that was already parsed from the input.
+fn field_spans<'a>(input: &'a [u8], replay: &Replay) -> ParseResult<&'a [u8], FieldSpans> {
+    use FieldSize::*;
+
+    let mut layout = vec![
+        ("game_mode", Fixed(1)),
+        ("version", Fixed(4)),
+        ("beatmap_md5", OsrString),
+        ("player_name", OsrString),
+        ("replay_md5", OsrString),
+        ("n300", Fixed(2)),
+        ("n100", Fixed(2)),
+        ("n50", Fixed(2)),
+        ("n_geki", Fixed(2)),
+        ("n_katu", Fixed(2)),
+        ("n_miss", Fixed(2)),
+        ("total_score", Fixed(4)),
+        ("greatest_combo", Fixed(2)),
+        ("perfect", Fixed(1)),
+        ("mods", Fixed(4)),
+        ("life_bar", OsrString),
+        ("time_stamp", Fixed(8)),
+        ("compressed_length", Fixed(4)),
+        ("compressed_data", Prefixed),
+    ];
+    if replay.version >= LONG_SCORE_ID_VERSION {
+        layout.push(("online_score_id", Fixed(8)));
+    } else if replay.version >= FIRST_SCORE_ID_VERSION {
+        layout.push(("online_score_id", Fixed(4)));
+    }
+    if replay.additional_mod_info.is_some() {
+        layout.push(("additional_mod_info", Fixed(8)));
+    }
+    if replay.lazer_data.is_some() {
+        layout.push(("lazer_data_length", Fixed(4)));
+        layout.push(("lazer_data", Prefixed));
+    }
+
+    let mut spans = FieldSpans::default();
+    let mut rest = input;
+    let mut previous: &[u8] = &[];
+    for (name, size) in layout {
+        let (remaining, value) = match size {
+            Fixed(length) => take::<false>(length)(rest)?,
+            OsrString => {
+                let (remaining, _) = player_name::<false>(rest)?;
+                (remaining, &rest[..rest.len() - remaining.len()])
+            }
+            Prefixed => {
+                let (_, length) = integer::<false>(previous)?;
+                take::<false>(length)(rest)?
+            }
+        };
+        let start = input.len() - rest.len();
+        spans.spans.push((name, start..start + value.len()));
+        previous = value;
+        rest = remaining;
+    }
+
+    Ok((rest, spans))
+}
+
+impl Replay {
+    /// Parse a replay file like [`Replay::parse_with_options`] and additionally return the byte
+    /// range of every field in the input, e.g. for replay inspectors or patching single fields.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    /// let (replay, spans) = Replay::parse_with_spans(&input, &Default::default()).expect("Error parsing replay");
+    ///
+    /// assert_eq!(spans.get("game_mode"), Some(0..1));
+    /// let score = spans.get("total_score").unwrap();
+    /// assert_eq!(input[score.clone()], replay.total_score.to_le_bytes());
+    /// assert_eq!(spans.field_at(score.start + 1), Some("total_score"));
+    /// assert_eq!(spans.get("compressed_data").unwrap().len(), replay.compressed_data.len());
+    /// assert_eq!(spans.iter().last().unwrap().1.end, input.len());
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the replay file is invalid or cannot be parsed.
+    pub fn parse_with_spans<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, FieldSpans), ReplayDataError<'a>> {
+        let replay = Replay::parse_with_options(input, options)?;
+        let (_, spans) = field_spans(input, &replay).finish()?;
+
+        Ok((replay, spans))
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Replay {
+    type Error = ReplayDataError<'a>;
+
+    fn try_from(input: &'a [u8]) -> Result<Self, Self::Error> {
+        Replay::parse(input)
+    }
 }
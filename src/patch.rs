@@ -0,0 +1,120 @@
+use crate::errors::{ParseError, ReplayDataError};
+use crate::parser::{HashValidation, ParseOptions};
+use crate::replay::{Mods, Replay, LONG_SCORE_ID_VERSION};
+use crate::writer::osr_string_bytes;
+
+/// Replace the bytes of a field in the raw replay file, see [`Replay::parse_with_spans`].
+///
+/// Only the header is parsed, the compressed replay data is moved but not decoded.
+fn set_field(
+    bytes: &mut Vec<u8>,
+    field: &str,
+    value: impl FnOnce(&Replay) -> Result<Vec<u8>, ReplayDataError<'static>>,
+) -> Result<(), ReplayDataError<'static>> {
+    let options = ParseOptions {
+        header_only: true,
+        hash_validation: HashValidation::Lenient,
+        unknown_game_modes: true,
+    };
+    let (replay, spans) =
+        Replay::parse_with_spans(bytes, &options).map_err(ReplayDataError::into_owned)?;
+    let span = spans.get(field).ok_or(ParseError::MissingValue)?;
+    let value = value(&replay)?;
+    bytes.splice(span, value);
+
+    Ok(())
+}
+
+/// Set the player name in a raw replay file, shifting the bytes after it if its length changes.
+///
+/// The replay MD5 hash is not updated, so osu! may reject the edited replay as modified.
+/// # Example
+/// ```
+/// use osu_replay_parser::{patch, Replay};
+///
+/// let mut bytes = std::fs::read("assets/replay.osr").expect("Error reading file");
+/// let original = Replay::parse(&bytes).expect("Error parsing replay");
+///
+/// patch::set_player_name(&mut bytes, "A much longer player name than before").expect("Error patching replay");
+/// let patched = Replay::parse(&bytes).expect("Error parsing replay");
+/// assert_eq!(patched.player_name, "A much longer player name than before");
+/// assert_eq!(patched.compressed_data, original.compressed_data);
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_player_name(bytes: &mut Vec<u8>, name: &str) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "player_name", |_| Ok(osr_string_bytes(name)))
+}
+
+/// Set the beatmap MD5 hash in a raw replay file.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_beatmap_md5(bytes: &mut Vec<u8>, md5: &str) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "beatmap_md5", |_| Ok(osr_string_bytes(md5)))
+}
+
+/// Set the replay MD5 hash in a raw replay file.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_replay_md5(bytes: &mut Vec<u8>, md5: &str) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "replay_md5", |_| Ok(osr_string_bytes(md5)))
+}
+
+/// Set the life bar graph in a raw replay file.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_life_bar(bytes: &mut Vec<u8>, life_bar: &str) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "life_bar", |_| Ok(osr_string_bytes(life_bar)))
+}
+
+/// Set the total score in a raw replay file.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_total_score(bytes: &mut Vec<u8>, score: u32) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "total_score", |_| Ok(score.to_le_bytes().to_vec()))
+}
+
+/// Set the mods in a raw replay file.
+///
+/// Target Practice adds a field after the compressed data, so it cannot be toggled in place.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed and
+/// `ParseError::InvalidValue` if the change would add or remove Target Practice.
+pub fn set_mods(bytes: &mut Vec<u8>, mods: u32) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "mods", |replay| {
+        if (replay.mods ^ mods) & Mods::TARGET_PRACTICE.bits() != 0 {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+        Ok(mods.to_le_bytes().to_vec())
+    })
+}
+
+/// Set the time stamp, in Windows ticks, in a raw replay file.
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed.
+pub fn set_time_stamp(bytes: &mut Vec<u8>, ticks: i64) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "time_stamp", |_| Ok(ticks.to_le_bytes().to_vec()))
+}
+
+/// Set the online score ID in a raw replay file.
+/// # Example
+/// ```
+/// use osu_replay_parser::{patch, Replay};
+///
+/// let mut bytes = std::fs::read("assets/replay.osr").expect("Error reading file");
+/// patch::set_online_score_id(&mut bytes, 4_000_000_000).expect("Error patching replay");
+/// assert_eq!(Replay::parse(&bytes).unwrap().online_score_id, 4_000_000_000);
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError` if the header of the replay cannot be parsed, `ParseError::MissingValue`
+/// if the replay version has no online score ID and `ParseError::InvalidValue` if the ID does not fit
+/// into the field of the replay version.
+pub fn set_online_score_id(bytes: &mut Vec<u8>, id: i64) -> Result<(), ReplayDataError<'static>> {
+    set_field(bytes, "online_score_id", |replay| {
+        if replay.version >= LONG_SCORE_ID_VERSION {
+            return Ok(id.to_le_bytes().to_vec());
+        }
+        let id = i32::try_from(id).map_err(|_| ParseError::InvalidValue)?;
+        Ok(id.to_le_bytes().to_vec())
+    })
+}
@@ -0,0 +1,130 @@
+use crate::frames::{CursorState, Timeline};
+use crate::replay::ReplayData;
+
+/// Playback position in a replay, the core loop of replay viewers.
+///
+/// The clock starts at the first frame and moves forward with [`Self::advance`] or jumps with
+/// [`Self::seek`], returning the interpolated cursor state, see [`Timeline::state_at`].
+/// The position is clamped to the recorded range. The clock does not read the system time,
+/// so the same calls always produce the same states.
+/// # Example
+/// ```
+/// use osu_replay_parser::playback::Clock;
+/// use osu_replay_parser::ReplayData;
+///
+/// let frames = vec![
+///     ReplayData { time: 1000, x: 0.0, y: 0.0, keys: 0 },
+///     ReplayData { time: 100, x: 100.0, y: 50.0, keys: 1 },
+///     ReplayData { time: 100, x: 100.0, y: 50.0, keys: 0 },
+/// ];
+/// let mut clock = Clock::new(&frames);
+/// assert_eq!(clock.time(), 1000.0);
+///
+/// let state = clock.advance(50.0).unwrap();
+/// assert_eq!((state.x, state.y, state.keys), (50.0, 25.0, 0));
+/// assert!(state.interpolated);
+///
+/// let state = clock.advance(50.0).unwrap();
+/// assert_eq!((state.x, state.keys), (100.0, 1));
+///
+/// // Seeking clamps to the recorded range
+/// clock.seek(5000.0);
+/// assert_eq!(clock.time(), 1200.0);
+/// assert!(clock.is_finished());
+/// clock.seek(0.0);
+/// assert_eq!(clock.time(), 1000.0);
+///
+/// // Double Time plays back 1.5 times as fast
+/// let mut clock = Clock::new(&frames).with_rate(1.5);
+/// clock.advance(100.0);
+/// assert_eq!(clock.time(), 1150.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Clock {
+    timeline: Timeline,
+    time: f64,
+    rate: f64,
+}
+
+impl Clock {
+    /// Create a clock positioned at the first frame, playing back at normal speed.
+    ///
+    /// The frames have delta times, the seed frame is skipped.
+    pub fn new(frames: &[ReplayData]) -> Self {
+        let timeline = Timeline::new(frames);
+        let time = timeline.start_time().unwrap_or(0) as f64;
+        Clock {
+            timeline,
+            time,
+            rate: 1.0,
+        }
+    }
+
+    /// Set the playback rate, the beatmap time passing per millisecond of [`Self::advance`],
+    /// e.g. [`Mods::speed_multiplier`](crate::replay::Mods::speed_multiplier).
+    /// # Panics
+    /// Panics if `rate` is not positive.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        self.rate = rate;
+        self
+    }
+
+    /// The playback rate.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// The current position in milliseconds of beatmap time.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Absolute time of the first frame, the start of the playback.
+    pub fn start_time(&self) -> f64 {
+        self.timeline.start_time().unwrap_or(0) as f64
+    }
+
+    /// Absolute time of the last frame, the end of the playback.
+    pub fn end_time(&self) -> f64 {
+        self.timeline.end_time().unwrap_or(0) as f64
+    }
+
+    /// Whether the clock reached the last frame.
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.end_time()
+    }
+
+    /// Cursor state at the current position, `None` if there are no frames.
+    pub fn state(&self) -> Option<CursorState> {
+        self.timeline.state_at(self.time)
+    }
+
+    /// Jump to an absolute time in milliseconds and return the cursor state there.
+    ///
+    /// Frames ending before they start, caused by negative delta times, stay at the start.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::playback::Clock;
+    /// use osu_replay_parser::ReplayData;
+    ///
+    /// let frames = vec![
+    ///     ReplayData { time: 1000, x: 0.0, y: 0.0, keys: 0 },
+    ///     ReplayData { time: -500, x: 100.0, y: 50.0, keys: 0 },
+    /// ];
+    /// let mut clock = Clock::new(&frames);
+    /// clock.seek(700.0);
+    /// assert_eq!(clock.time(), 1000.0);
+    /// assert!(clock.is_finished());
+    /// ```
+    pub fn seek(&mut self, time: f64) -> Option<CursorState> {
+        self.time = time.min(self.end_time()).max(self.start_time());
+        self.state()
+    }
+
+    /// Move forward by `dt` milliseconds of playback time, scaled by the rate, and return the
+    /// cursor state at the new position. Negative values move backwards.
+    pub fn advance(&mut self, dt: f64) -> Option<CursorState> {
+        self.seek(self.time + dt * self.rate)
+    }
+}
@@ -0,0 +1,457 @@
+//! The cursor path and key presses of a replay, or a heatmap of the cursor positions, are plotted
+//! onto the playfield, e.g. for reports or chat bots.
+//!
+//! The plot is available as SVG and as PNG, which is rendered from the SVG with [`resvg`].
+
+use std::fmt::Write;
+
+use resvg::{tiny_skia, usvg};
+
+use crate::beatmap::{Beatmap, HitObjectKind};
+use crate::errors::{ParseError, ReplayDataError};
+use crate::frames::{FramesExt, Keys, MAX_INTERPOLATION_GAP};
+use crate::game_math::{circle_radius, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::replay::ReplayData;
+
+/// What is plotted by [`svg`] and [`png`].
+/// # Example
+/// ```
+/// use osu_replay_parser::plot::{self, PlotOptions, PlotStyle};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let options = PlotOptions { style: PlotStyle::Heatmap, ..PlotOptions::default() };
+///
+/// let svg = plot::svg(&actions, None, &options).expect("Error plotting replay");
+/// assert!(svg.matches("<rect").count() > 100);
+/// assert!(!svg.contains("<circle"));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PlotStyle {
+    /// The cursor path and the key presses.
+    #[default]
+    Path,
+    /// The time the cursor spent in each area of the playfield, from purple to yellow.
+    Heatmap,
+}
+
+/// Options of [`svg`] and [`png`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotOptions {
+    /// What is plotted.
+    pub style: PlotStyle,
+    /// Width of the image in pixels, the height follows from the aspect ratio of the playfield
+    /// and the margin.
+    pub width: u32,
+    /// Space around the playfield in osu!pixels, so cursor movements outside of it are visible.
+    pub margin: f32,
+    /// Only plot frames and hit objects in `start..=end` milliseconds.
+    pub time_range: Option<(i64, i64)>,
+    /// Mark the positions at which keys were pressed with [`PlotStyle::Path`], the first key in
+    /// orange and the second one in teal.
+    pub key_presses: bool,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions {
+            style: PlotStyle::Path,
+            width: 1024,
+            margin: 64.0,
+            time_range: None,
+            key_presses: true,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// Size of the image in pixels, `None` if `width` is 0.
+    pub fn size(&self) -> Option<(u32, u32)> {
+        if self.width == 0 {
+            return None;
+        }
+        let height = (PLAYFIELD_HEIGHT + 2.0 * self.margin) * self.scale();
+        Some((self.width, (height.round() as u32).max(1)))
+    }
+
+    /// Image pixels per osu!pixel.
+    fn scale(&self) -> f32 {
+        self.width as f32 / (PLAYFIELD_WIDTH + 2.0 * self.margin)
+    }
+
+    /// Whether the position of the frame is inside the image.
+    fn is_visible(&self, frame: &ReplayData) -> bool {
+        (-self.margin..=PLAYFIELD_WIDTH + self.margin).contains(&frame.x)
+            && (-self.margin..=PLAYFIELD_HEIGHT + self.margin).contains(&frame.y)
+    }
+
+    fn contains_time(&self, start: i64, end: i64) -> bool {
+        self.time_range
+            .is_none_or(|(range_start, range_end)| start <= range_end && end >= range_start)
+    }
+}
+
+/// Plot replay frames as an SVG image, see [`PlotStyle`].
+///
+/// Hit objects of the beatmap are drawn below the cursor, pass the beatmap with
+/// [`Beatmap::with_mods`] applied for Hard Rock replays. Frames outside of the image and gaps
+/// longer than [`MAX_INTERPOLATION_GAP`] split the path.
+/// # Example
+/// ```
+/// use osu_replay_parser::beatmap::Beatmap;
+/// use osu_replay_parser::plot::{self, PlotOptions};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let beatmap = Beatmap::parse(
+///     "[Difficulty]\nCircleSize:4\n\n[HitObjects]\n100,100,1000,1,0\n\
+///      200,200,2000,2,0,L|300:200,1,100\n",
+/// )
+/// .unwrap();
+/// let options = PlotOptions { time_range: Some((0, 10_000)), ..PlotOptions::default() };
+///
+/// let svg = plot::svg(&actions, Some(&beatmap), &options).expect("Error plotting replay");
+/// assert!(svg.starts_with("<svg"));
+/// assert_eq!(svg.matches("<circle cx=\"100.0\" cy=\"100.0\"").count(), 1);
+/// assert!(svg.contains("<polyline points=\"200.0,200.0"));
+///
+/// let options = PlotOptions { width: 0, ..PlotOptions::default() };
+/// assert!(plot::svg(&actions, None, &options).is_err());
+/// ```
+/// # Errors
+/// Returns `ParseError::InvalidValue` if the width of the options is 0.
+pub fn svg(
+    frames: &[ReplayData],
+    beatmap: Option<&Beatmap>,
+    options: &PlotOptions,
+) -> Result<String, ReplayDataError<'static>> {
+    let (width, height) = options.size().ok_or(ParseError::InvalidValue)?;
+    let margin = options.margin;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">",
+        width,
+        height,
+        -margin,
+        -margin,
+        PLAYFIELD_WIDTH + 2.0 * margin,
+        PLAYFIELD_HEIGHT + 2.0 * margin
+    );
+    let _ = write!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        -margin, -margin, BACKGROUND
+    );
+
+    for shape in scene(frames, beatmap, options) {
+        match shape {
+            Shape::Path {
+                points,
+                width,
+                color,
+            } => {
+                svg.push_str("<polyline points=\"");
+                for (i, (x, y)) in points.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { " " };
+                    let _ = write!(svg, "{}{:.1},{:.1}", separator, x, y);
+                }
+                let _ = write!(
+                    svg,
+                    "\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+                    color, width
+                );
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let _ = write!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"",
+                    center.0,
+                    center.1,
+                    radius,
+                    fill.map_or("none".to_string(), |color| color.to_string())
+                );
+                if let Some((color, width)) = stroke {
+                    let _ = write!(svg, " stroke=\"{}\" stroke-width=\"{}\"", color, width);
+                }
+                svg.push_str("/>");
+            }
+            Shape::Rect { min, max, color } => {
+                let _ = write!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                    min.0,
+                    min.1,
+                    max.0 - min.0,
+                    max.1 - min.1,
+                    color
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+/// Plot replay frames as a PNG image, see [`svg`].
+/// # Example
+/// ```
+/// use osu_replay_parser::plot::{self, PlotOptions};
+/// use osu_replay_parser::Replay;
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let options = PlotOptions { width: 320, ..PlotOptions::default() };
+///
+/// let png = plot::png(&actions, None, &options).expect("Error plotting replay");
+/// assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+/// // Width and height in the header
+/// assert_eq!(&png[16..24], &[0, 0, 1, 64, 0, 0, 1, 0]);
+/// ```
+/// # Errors
+/// Returns `ParseError::InvalidValue` if the width of the options is 0 or the image is too large
+/// to be allocated.
+pub fn png(
+    frames: &[ReplayData],
+    beatmap: Option<&Beatmap>,
+    options: &PlotOptions,
+) -> Result<Vec<u8>, ReplayDataError<'static>> {
+    let svg = svg(frames, beatmap, options)?;
+    let (width, height) = options.size().ok_or(ParseError::InvalidValue)?;
+    let tree =
+        usvg::Tree::from_str(&svg, &usvg::Options::default()).expect("The plot is valid SVG");
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or(ParseError::InvalidValue)?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    Ok(pixmap
+        .encode_png()
+        .expect("Encoding into memory does not fail"))
+}
+
+const BACKGROUND: Color = Color(0x10, 0x10, 0x14);
+const PLAYFIELD_BORDER: Color = Color(0x50, 0x50, 0x60);
+const SLIDER_BODY: Color = Color(0x1d, 0x35, 0x57);
+const HIT_OBJECT: Color = Color(0x4a, 0x90, 0xd9);
+const CURSOR_PATH: Color = Color(0xe0, 0xe0, 0xe0);
+const FIRST_KEY: Color = Color(0xff, 0x9f, 0x1c);
+const SECOND_KEY: Color = Color(0x2e, 0xc4, 0xb6);
+
+/// Width of the cursor path in osu!pixels.
+const CURSOR_PATH_WIDTH: f32 = 1.5;
+/// Width of the outlines of hit objects in osu!pixels.
+const OUTLINE_WIDTH: f32 = 3.0;
+/// Radius of the key press markers in osu!pixels.
+const KEY_PRESS_RADIUS: f32 = 3.0;
+/// Distance between the sampled points of slider paths in osu!pixels.
+const SLIDER_STEP: f32 = 4.0;
+/// Largest number of points sampled from a slider path, longer sliders are sampled less densely.
+const MAX_SLIDER_STEPS: usize = 4096;
+/// Size of the cells of heatmaps in osu!pixels.
+const HEATMAP_CELL: f32 = 8.0;
+/// Colors of heatmaps from the least to the most visited cells.
+const HEATMAP_COLORS: [Color; 4] = [
+    Color(0x2a, 0x0a, 0x4a),
+    Color(0x9c, 0x1f, 0x6e),
+    Color(0xf0, 0x6a, 0x2a),
+    Color(0xff, 0xf0, 0x90),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Color(u8, u8, u8);
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// A shape of the plot in osu!pixels.
+enum Shape {
+    Path {
+        points: Vec<(f32, f32)>,
+        width: f32,
+        color: Color,
+    },
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        fill: Option<Color>,
+        stroke: Option<(Color, f32)>,
+    },
+    Rect {
+        min: (f32, f32),
+        max: (f32, f32),
+        color: Color,
+    },
+}
+
+/// The shapes of the plot, from bottom to top.
+fn scene(frames: &[ReplayData], beatmap: Option<&Beatmap>, options: &PlotOptions) -> Vec<Shape> {
+    let mut shapes = vec![Shape::Path {
+        points: vec![
+            (0.0, 0.0),
+            (PLAYFIELD_WIDTH, 0.0),
+            (PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+            (0.0, PLAYFIELD_HEIGHT),
+            (0.0, 0.0),
+        ],
+        width: 1.0,
+        color: PLAYFIELD_BORDER,
+    }];
+
+    if let Some(beatmap) = beatmap {
+        let radius = circle_radius(beatmap.difficulty.circle_size);
+        for object in beatmap
+            .hit_objects
+            .iter()
+            .filter(|object| options.contains_time(object.time, object.end_time))
+        {
+            // Lengths of hit objects built by hand are not validated like parsed ones
+            let path = object
+                .slider_path()
+                .filter(|path| path.length().is_finite() && path.length() > 0.0);
+            if let Some(path) = path {
+                let steps = ((path.length() / SLIDER_STEP).ceil() as usize).min(MAX_SLIDER_STEPS);
+                let points = (0..=steps)
+                    .map(|step| path.position_at(path.length() * step as f32 / steps as f32))
+                    .collect();
+                shapes.push(Shape::Path {
+                    points,
+                    width: 2.0 * radius,
+                    color: SLIDER_BODY,
+                });
+            }
+            if matches!(
+                object.kind,
+                HitObjectKind::Circle | HitObjectKind::Slider { .. }
+            ) {
+                shapes.push(Shape::Circle {
+                    center: (object.x, object.y),
+                    radius: radius - OUTLINE_WIDTH / 2.0,
+                    fill: None,
+                    stroke: Some((HIT_OBJECT, OUTLINE_WIDTH)),
+                });
+            }
+        }
+    }
+
+    match options.style {
+        PlotStyle::Path => cursor_path(frames, options, &mut shapes),
+        PlotStyle::Heatmap => heatmap(frames, options, &mut shapes),
+    }
+
+    shapes
+}
+
+/// The cursor path split at gaps and frames outside of the image, and the key presses.
+fn cursor_path(frames: &[ReplayData], options: &PlotOptions, shapes: &mut Vec<Shape>) {
+    let mut paths = vec![Vec::new()];
+    let mut previous_time = None;
+    for (time, frame) in frames
+        .timed()
+        .filter(|&(time, _)| options.contains_time(time, time))
+    {
+        let gap = previous_time.is_some_and(|previous| time - previous > MAX_INTERPOLATION_GAP);
+        if gap || !options.is_visible(frame) {
+            paths.push(Vec::new());
+        }
+        if options.is_visible(frame) {
+            paths.last_mut().unwrap().push((frame.x, frame.y));
+        }
+        previous_time = Some(time);
+    }
+    shapes.extend(
+        paths
+            .into_iter()
+            .filter(|points| points.len() > 1)
+            .map(|points| Shape::Path {
+                points,
+                width: CURSOR_PATH_WIDTH,
+                color: CURSOR_PATH,
+            }),
+    );
+
+    if options.key_presses {
+        for (time, frame, pressed) in frames.key_presses() {
+            if !options.contains_time(time, time) || !options.is_visible(frame) {
+                continue;
+            }
+            for key in pressed.sources() {
+                let color = if key.intersects(Keys::M1) {
+                    FIRST_KEY
+                } else {
+                    SECOND_KEY
+                };
+                shapes.push(Shape::Circle {
+                    center: (frame.x, frame.y),
+                    radius: KEY_PRESS_RADIUS,
+                    fill: Some(color),
+                    stroke: None,
+                });
+            }
+        }
+    }
+}
+
+/// Cells of a grid over the image colored by the time the cursor spent in them.
+fn heatmap(frames: &[ReplayData], options: &PlotOptions, shapes: &mut Vec<Shape>) {
+    let margin = options.margin;
+    let columns = ((PLAYFIELD_WIDTH + 2.0 * margin) / HEATMAP_CELL).ceil() as usize;
+    let rows = ((PLAYFIELD_HEIGHT + 2.0 * margin) / HEATMAP_CELL).ceil() as usize;
+    let mut heat = vec![0.0; columns * rows];
+
+    let timed: Vec<(i64, &ReplayData)> = frames
+        .timed()
+        .filter(|&(time, _)| options.contains_time(time, time))
+        .collect();
+    for pair in timed.windows(2) {
+        let ((time, frame), (next_time, _)) = (pair[0], pair[1]);
+        if !options.is_visible(frame) {
+            continue;
+        }
+        let column = (((frame.x + margin) / HEATMAP_CELL) as usize).min(columns - 1);
+        let row = (((frame.y + margin) / HEATMAP_CELL) as usize).min(rows - 1);
+        heat[row * columns + column] += (next_time - time).clamp(0, MAX_INTERPOLATION_GAP) as f32;
+    }
+
+    // A few cells where the cursor rested, e.g. during breaks, would outshine all others, so the
+    // scale ends at the 99th percentile
+    let mut visited: Vec<f32> = heat.iter().copied().filter(|&value| value > 0.0).collect();
+    if visited.is_empty() {
+        return;
+    }
+    visited.sort_unstable_by(f32::total_cmp);
+    let max = visited[(visited.len() - 1) * 99 / 100];
+    for (i, &value) in heat.iter().enumerate() {
+        if value <= 0.0 {
+            continue;
+        }
+        let min = (
+            (i % columns) as f32 * HEATMAP_CELL - margin,
+            (i / columns) as f32 * HEATMAP_CELL - margin,
+        );
+        shapes.push(Shape::Rect {
+            min,
+            max: (min.0 + HEATMAP_CELL, min.1 + HEATMAP_CELL),
+            // The square root keeps rarely visited cells visible
+            color: heat_color((value / max).min(1.0).sqrt()),
+        });
+    }
+}
+
+/// Color of a heatmap cell with a heat in the range `0.0..=1.0`.
+fn heat_color(heat: f32) -> Color {
+    let position = heat * (HEATMAP_COLORS.len() - 1) as f32;
+    let index = (position as usize).min(HEATMAP_COLORS.len() - 2);
+    let t = position - index as f32;
+    let (from, to) = (HEATMAP_COLORS[index], HEATMAP_COLORS[index + 1]);
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    Color(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
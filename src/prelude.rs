@@ -0,0 +1,21 @@
+//! The types needed by most users of the library, to be glob imported.
+//!
+//! Only items covered by the stability policy of the crate are re-exported here, so
+//! `use osu_replay_parser::prelude::*;` keeps compiling across minor versions.
+//! # Example
+//! ```
+//! use osu_replay_parser::prelude::*;
+//!
+//! let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+//! assert_eq!(replay.game_mode, GameMode::Osu);
+//! let actions: Vec<ReplayData> = replay.get_actions().expect("Error getting actions");
+//! assert!(actions.duration() > 0);
+//! ```
+
+pub use crate::beatmap::Beatmap;
+pub use crate::errors::{FrameError, ParseError, ReplayDataError};
+pub use crate::frames::{FramesExt, Keys};
+pub use crate::parser::{HashValidation, ParseOptions};
+pub use crate::replay::{GameMode, Mods, Replay, ReplayData, ReplayFormat};
+pub use crate::statistics::HitStatistics;
+pub use crate::warnings::Warning;
@@ -0,0 +1,125 @@
+use std::io;
+
+use crate::compression::encoder::{Settings, StreamEncoder};
+use crate::replay::{Replay, ReplayData};
+use crate::writer::encode_frames;
+
+/// Records frames as they are played and compresses them on the fly, so a replay can be
+/// written at any moment, e.g. by private server clients or practice tools.
+///
+/// The frame text is compressed greedily while frames arrive, only the last few bytes are
+/// compressed when finalizing. Besides the compressed data only the last 2 MiB dictionary of
+/// frame text is kept, so long sessions don't hold the whole recording.
+/// # Example
+/// ```
+/// use osu_replay_parser::recorder::ReplayRecorder;
+/// use osu_replay_parser::Replay;
+///
+/// let header = Replay {
+///     player_name: "Player".to_string(),
+///     version: 20240101,
+///     ..Replay::default()
+/// };
+/// let mut recorder = ReplayRecorder::new(header);
+/// recorder.record(1000, 256.0, 192.0, 0);
+/// recorder.record(1016, 260.0, 190.0, 5);
+///
+/// recorder.header_mut().total_score = 1234;
+/// let replay = recorder.finalize();
+/// assert_eq!(replay.total_score, 1234);
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// assert_eq!((actions[1].time, actions[1].x, actions[1].keys), (16, 260.0, 5));
+///
+/// // Recording continues after finalizing
+/// recorder.record(1040, 270.0, 185.0, 0);
+/// let actions = recorder.finalize().get_actions().expect("Error getting actions");
+/// assert_eq!((actions.len(), actions[2].time), (3, 24));
+/// ```
+pub struct ReplayRecorder {
+    header: Replay,
+    encoder: StreamEncoder,
+    frames: usize,
+    time: i64,
+}
+
+impl ReplayRecorder {
+    /// Start recording a replay, the compressed data of `header` is replaced by the recorded frames.
+    pub fn new(header: Replay) -> Self {
+        ReplayRecorder {
+            header,
+            encoder: StreamEncoder::new(Settings {
+                lc: 3,
+                lp: 0,
+                pb: 2,
+                dict_size: 1 << 21,
+                nice_len: 64,
+                depth: 16,
+            }),
+            frames: 0,
+            time: 0,
+        }
+    }
+
+    /// The header of the replay, e.g. to set the score once the play is over.
+    pub fn header(&self) -> &Replay {
+        &self.header
+    }
+
+    /// Mutable access to the header of the replay.
+    pub fn header_mut(&mut self) -> &mut Replay {
+        &mut self.header
+    }
+
+    /// Append a frame, its `time` is the delta to the previous frame as stored in replays.
+    pub fn push(&mut self, frame: ReplayData) {
+        if !frame.is_seed_frame() {
+            self.time += frame.time;
+        }
+        self.encoder
+            .write(encode_frames(std::slice::from_ref(&frame), None).as_bytes());
+        self.frames += 1;
+    }
+
+    /// Append a frame at an absolute time in milliseconds, e.g. the time of the playing audio.
+    ///
+    /// The time is relative to the sum of the deltas of all frames pushed before, ignoring the
+    /// seed frame.
+    pub fn record(&mut self, time: i64, x: f32, y: f32, keys: u32) {
+        self.push(ReplayData {
+            time: time - self.time,
+            x,
+            y,
+            keys,
+        });
+    }
+
+    /// Number of frames recorded.
+    pub fn frame_count(&self) -> usize {
+        self.frames
+    }
+
+    /// Absolute time of the last frame in milliseconds.
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+
+    /// Number of compressed bytes produced so far.
+    pub fn compressed_len(&self) -> usize {
+        self.encoder.compressed_len()
+    }
+
+    /// The replay with all frames recorded so far, recording can continue afterwards.
+    pub fn finalize(&self) -> Replay {
+        Replay {
+            compressed_data: self.encoder.finish(),
+            ..self.header.clone()
+        }
+    }
+
+    /// Write the replay with all frames recorded so far in the `.osr` format, see [`Replay::write`].
+    /// # Errors
+    /// Returns an error if writing fails or the online score ID does not fit into the field of the replay's version.
+    pub fn write<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.finalize().write(writer)
+    }
+}
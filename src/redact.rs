@@ -0,0 +1,81 @@
+use serde_json::Value;
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::replay::Replay;
+use crate::writer::compress;
+
+/// Fields of the osu!lazer trailer identifying the player or the score.
+const LAZER_IDENTIFYING_KEYS: [&str; 2] = ["user_id", "online_id"];
+
+/// Options for removing personally identifying information from a replay.
+///
+/// The player name is replaced, the replay hash, the online score ID and the time stamp are
+/// cleared, and the user and score IDs are removed from the osu!lazer trailer. The replay hash
+/// is derived from the player name and identifies the score online.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    /// Name replacing the player name.
+    pub player_name: String,
+    /// Keep the time the replay was set, e.g. for tournament records.
+    pub keep_time_stamp: bool,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction {
+            player_name: "Anonymous".to_string(),
+            keep_time_stamp: false,
+        }
+    }
+}
+
+impl Replay {
+    /// A copy of the replay with personally identifying fields removed, see [`Redaction`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the osu!lazer trailer cannot be decompressed or parsed.
+    pub fn redacted(&self, redaction: &Redaction) -> Result<Replay, ReplayDataError<'static>> {
+        let mut replay = self.clone();
+        replay.player_name = redaction.player_name.clone();
+        replay.replay_md5.clear();
+        replay.online_score_id = 0;
+        if !redaction.keep_time_stamp {
+            replay.time_stamp = 0;
+        }
+        if let Some(data) = &self.lazer_data {
+            let json = Replay::decompress_lzma(data)?;
+            let Ok(Value::Object(entries)) = serde_json::from_str(&json) else {
+                return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+            };
+            let redacted = Value::Object(
+                entries
+                    .into_iter()
+                    .filter(|(key, _)| !LAZER_IDENTIFYING_KEYS.contains(&key.as_str()))
+                    .collect(),
+            );
+            replay.lazer_data = Some(compress(redacted.to_string().as_bytes()));
+        }
+
+        Ok(replay)
+    }
+
+    /// Encode the replay with personally identifying fields removed, ready to be shared.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::redact::Redaction;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let bytes = replay.redact(&Redaction::default()).expect("Error redacting replay");
+    ///
+    /// let redacted = Replay::parse(&bytes).expect("Error parsing redacted replay");
+    /// assert_eq!(redacted.player_name, "Anonymous");
+    /// assert!(redacted.replay_md5.is_empty());
+    /// let info = redacted.lazer_score_info().unwrap().unwrap();
+    /// assert_eq!((info.user_id, info.online_id), (None, None));
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the osu!lazer trailer cannot be decompressed or parsed.
+    pub fn redact(&self, redaction: &Redaction) -> Result<Vec<u8>, ReplayDataError<'static>> {
+        Ok(self.redacted(redaction)?.to_bytes()?)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::frames::{CursorState, Timeline};
+use crate::replay::ReplayData;
+
+/// State of the replay at a single frame of the output video.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RenderFrame {
+    /// Index of the video frame.
+    pub index: usize,
+    /// Time of the video frame in milliseconds of beatmap time.
+    pub time: f64,
+    /// Cursor position and pressed keys, including whether the position was interpolated.
+    pub cursor: CursorState,
+}
+
+/// Sample the cursor state for every frame of a video with the given frame rate.
+///
+/// The video starts at the first frame of the replay and ends at the last one. Frame times are in
+/// beatmap time, for Double Time or Half Time replays divide the `fps` by
+/// [`Mods::speed_multiplier`](crate::replay::Mods::speed_multiplier) to render at playback speed.
+///
+/// # Panics
+/// Panics if `fps` is not positive.
+/// # Example
+/// ```
+/// use osu_replay_parser::{render, Replay};
+///
+/// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// let video_frames = render::timeline(&actions, 60.0);
+/// assert!(!video_frames.is_empty());
+/// ```
+pub fn timeline(frames: &[ReplayData], fps: f64) -> Vec<RenderFrame> {
+    assert!(fps > 0.0, "fps must be positive");
+
+    let timeline = Timeline::new(frames);
+    let (Some(start), Some(end)) = (timeline.start_time(), timeline.end_time()) else {
+        return Vec::new();
+    };
+
+    let frame_duration = 1000.0 / fps;
+    let count = ((end - start) as f64 / frame_duration).floor() as usize + 1;
+
+    (0..count)
+        .filter_map(|index| {
+            let time = start as f64 + index as f64 * frame_duration;
+            let cursor = timeline.state_at(time)?;
+            Some(RenderFrame {
+                index,
+                time,
+                cursor,
+            })
+        })
+        .collect()
+}
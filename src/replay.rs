@@ -1,12 +1,23 @@
 use std::fmt::{self, Formatter, Debug};
+use std::time::Duration;
 
 use bitflags::bitflags;
 use lzma_rs::lzma_decompress;
 
-use crate::errors::ReplayDataError;
+use crate::errors::{FrameError, InvalidFrame, InvalidUtf8, ParseError, ReplayDataError};
+use crate::frames::FramesExt;
+use crate::unicode::compose_nfc;
+
+/// First replay version storing the online score ID, older replays lack the field.
+pub const FIRST_SCORE_ID_VERSION: u32 = 20121008;
+/// First replay version storing the online score ID as a long instead of an integer.
+pub const LONG_SCORE_ID_VERSION: u32 = 20140721;
+/// First replay version written by osu!lazer, which appends additional score information to the replay.
+pub const FIRST_LAZER_VERSION: u32 = 30000001;
 
 /// Game mode of the replay.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum GameMode {
     #[default]
     /// The default osu! game mode.
@@ -17,6 +28,9 @@ pub enum GameMode {
     CatchTheBeat,
     /// The Mania game mode.
     Mania,
+    /// A game mode byte unknown to osu!, e.g. a custom mode of a private server. Only parsed with
+    /// [`ParseOptions::unknown_game_modes`](crate::parser::ParseOptions::unknown_game_modes).
+    Unknown(u8),
 }
 
 impl TryFrom<u8> for GameMode {
@@ -28,51 +42,351 @@ impl TryFrom<u8> for GameMode {
             1 => Ok(GameMode::Taiko),
             2 => Ok(GameMode::CatchTheBeat),
             3 => Ok(GameMode::Mania),
-            _ => Err(ReplayDataError::InvalidValueError),
+            _ => Err(ReplayDataError::Parse(ParseError::InvalidValue)),
+        }
+    }
+}
+
+impl From<GameMode> for u8 {
+    fn from(mode: GameMode) -> Self {
+        match mode {
+            GameMode::Osu => 0,
+            GameMode::Taiko => 1,
+            GameMode::CatchTheBeat => 2,
+            GameMode::Mania => 3,
+            GameMode::Unknown(byte) => byte,
+        }
+    }
+}
+
+/// Generation of the replay format, determined by the version of the replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ReplayFormat {
+    /// Replays before [`FIRST_SCORE_ID_VERSION`] without the online score ID.
+    LegacyFormat,
+    /// Replays written by osu!stable.
+    Stable,
+    /// Replays written by osu!lazer, starting with [`FIRST_LAZER_VERSION`].
+    Lazer,
+}
+
+impl ReplayFormat {
+    /// The format of replays with the version.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::ReplayFormat;
+    ///
+    /// assert_eq!(ReplayFormat::from_version(20110526), ReplayFormat::LegacyFormat);
+    /// assert_eq!(ReplayFormat::from_version(20210520), ReplayFormat::Stable);
+    /// assert_eq!(ReplayFormat::from_version(30000016), ReplayFormat::Lazer);
+    /// ```
+    pub fn from_version(version: u32) -> Self {
+        if version < FIRST_SCORE_ID_VERSION {
+            ReplayFormat::LegacyFormat
+        } else if version < FIRST_LAZER_VERSION {
+            ReplayFormat::Stable
+        } else {
+            ReplayFormat::Lazer
         }
     }
 }
 
 bitflags! {
     /// Flags for the mods used in the replay.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct Mods: u32 {
+        /// No mods.
         const NONE = 0;
+        /// No Fail (NF).
         const NO_FAIL = 1 << 0;
+        /// Easy (EZ).
         const EASY = 1 << 1;
+        /// Touch Device (TD).
         const TOUCH_DEVICE = 1 << 2;
+        /// Hidden (HD).
         const HIDDEN = 1 << 3;
+        /// Hard Rock (HR).
         const HARD_ROCK = 1 << 4;
+        /// Sudden Death (SD).
         const SUDDEN_DEATH = 1 << 5;
+        /// Double Time (DT).
         const DOUBLE_TIME = 1 << 6;
+        /// Relax (RX).
         const RELAX = 1 << 7;
+        /// Half Time (HT).
         const HALF_TIME = 1 << 8;
+        /// Nightcore (NC), always set together with Double Time.
         const NIGHTCORE = 1 << 9;
+        /// Flashlight (FL).
         const FLASHLIGHT = 1 << 10;
+        /// Autoplay (AT).
         const AUTOPLAY = 1 << 11;
+        /// Spun Out (SO).
         const SPUN_OUT = 1 << 12;
+        /// Autopilot (AP).
         const RELAX2 = 1 << 13;
+        /// Perfect (PF), always set together with Sudden Death.
         const PERFECT = 1 << 14;
+        /// Mania 4K.
         const KEY4 = 1 << 15;
+        /// Mania 5K.
         const KEY5 = 1 << 16;
+        /// Mania 6K.
         const KEY6 = 1 << 17;
+        /// Mania 7K.
         const KEY7 = 1 << 18;
+        /// Mania 8K.
         const KEY8 = 1 << 19;
+        /// Mania Fade In (FI).
         const FADE_IN = 1 << 20;
+        /// Random (RD).
         const RANDOM = 1 << 21;
+        /// Cinema (CN).
         const LAST_MOD = 1 << 22;
+        /// Target Practice (TP).
         const TARGET_PRACTICE = 1 << 23;
+        /// Mania 9K.
         const KEY9 = 1 << 24;
+        /// Mania Co-op.
         const COOP = 1 << 25;
+        /// Mania 1K.
         const KEY1 = 1 << 26;
+        /// Mania 3K.
         const KEY3 = 1 << 27;
+        /// Mania 2K.
         const KEY2 = 1 << 28;
+        /// Score V2 (V2).
         const SCORE_V2 = 1 << 29;
+        /// Mania Mirror (MR).
         const MIRROR = 1 << 30;
     }
 }
 
+/// Acronyms and names of all mods in the order of their bits.
+const MOD_NAMES: [(Mods, &str, &str); 31] = [
+    (Mods::NO_FAIL, "NF", "NoFail"),
+    (Mods::EASY, "EZ", "Easy"),
+    (Mods::TOUCH_DEVICE, "TD", "TouchDevice"),
+    (Mods::HIDDEN, "HD", "Hidden"),
+    (Mods::HARD_ROCK, "HR", "HardRock"),
+    (Mods::SUDDEN_DEATH, "SD", "SuddenDeath"),
+    (Mods::DOUBLE_TIME, "DT", "DoubleTime"),
+    (Mods::RELAX, "RX", "Relax"),
+    (Mods::HALF_TIME, "HT", "HalfTime"),
+    (Mods::NIGHTCORE, "NC", "Nightcore"),
+    (Mods::FLASHLIGHT, "FL", "Flashlight"),
+    (Mods::AUTOPLAY, "AT", "Autoplay"),
+    (Mods::SPUN_OUT, "SO", "SpunOut"),
+    (Mods::RELAX2, "AP", "Autopilot"),
+    (Mods::PERFECT, "PF", "Perfect"),
+    (Mods::KEY4, "4K", "Key4"),
+    (Mods::KEY5, "5K", "Key5"),
+    (Mods::KEY6, "6K", "Key6"),
+    (Mods::KEY7, "7K", "Key7"),
+    (Mods::KEY8, "8K", "Key8"),
+    (Mods::FADE_IN, "FI", "FadeIn"),
+    (Mods::RANDOM, "RD", "Random"),
+    (Mods::LAST_MOD, "CN", "Cinema"),
+    (Mods::TARGET_PRACTICE, "TP", "TargetPractice"),
+    (Mods::KEY9, "9K", "Key9"),
+    (Mods::COOP, "CO", "KeyCoop"),
+    (Mods::KEY1, "1K", "Key1"),
+    (Mods::KEY3, "3K", "Key3"),
+    (Mods::KEY2, "2K", "Key2"),
+    (Mods::SCORE_V2, "V2", "ScoreV2"),
+    (Mods::MIRROR, "MR", "Mirror"),
+];
+
+impl Mods {
+    /// Mods which prevent a score from being submitted.
+    pub const UNRANKED: Mods = Mods::RELAX
+        .union(Mods::RELAX2)
+        .union(Mods::AUTOPLAY)
+        .union(Mods::LAST_MOD)
+        .union(Mods::TARGET_PRACTICE)
+        .union(Mods::SCORE_V2);
+
+    /// Score multiplier of the mod combination in the given game mode, as used by osu!stable's ScoreV1.
+    ///
+    /// Mods which prevent a score from being submitted, see [`Self::UNRANKED`], do not change the multiplier.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::{GameMode, Mods};
+    ///
+    /// let mods = Mods::HIDDEN | Mods::DOUBLE_TIME;
+    /// assert!((mods.score_multiplier(GameMode::Osu) - 1.06 * 1.12).abs() < 1e-9);
+    /// assert_eq!(Mods::NO_FAIL.score_multiplier(GameMode::Mania), 0.5);
+    /// ```
+    pub fn score_multiplier(self, mode: GameMode) -> f64 {
+        let (half_time, hard_rock, double_time, hidden, flashlight) = match mode {
+            GameMode::Osu | GameMode::Taiko | GameMode::Unknown(_) => (0.3, 1.06, 1.12, 1.06, 1.12),
+            GameMode::CatchTheBeat => (0.3, 1.12, 1.06, 1.06, 1.12),
+            GameMode::Mania => (0.5, 1.0, 1.0, 1.0, 1.0),
+        };
+
+        [
+            (Mods::NO_FAIL, 0.5),
+            (Mods::EASY, 0.5),
+            (Mods::HALF_TIME, half_time),
+            (Mods::HARD_ROCK, hard_rock),
+            (Mods::DOUBLE_TIME, double_time),
+            (Mods::HIDDEN, hidden),
+            (Mods::FLASHLIGHT, flashlight),
+            (Mods::SPUN_OUT, 0.9),
+        ]
+        .into_iter()
+        .filter(|(m, _)| self.contains(*m))
+        .map(|(_, multiplier)| multiplier)
+        .product()
+    }
+
+    /// Whether a score with this mod combination can be submitted to the osu! servers.
+    ///
+    /// Returns `false` if any of the [`Self::UNRANKED`] mods is set or the combination contains
+    /// mutually exclusive mods, like Easy and Hard Rock.
+    pub fn is_submittable(self) -> bool {
+        const EXCLUSIVE: [(Mods, Mods); 4] = [
+            (Mods::EASY, Mods::HARD_ROCK),
+            (Mods::HALF_TIME, Mods::DOUBLE_TIME),
+            (Mods::NO_FAIL, Mods::SUDDEN_DEATH),
+            (Mods::FADE_IN, Mods::HIDDEN),
+        ];
+
+        !self.intersects(Mods::UNRANKED)
+            && !EXCLUSIVE
+                .iter()
+                .any(|(a, b)| self.contains(*a) && self.contains(*b))
+            && (!self.contains(Mods::NIGHTCORE) || self.contains(Mods::DOUBLE_TIME))
+            && (!self.contains(Mods::PERFECT) || self.contains(Mods::SUDDEN_DEATH))
+    }
+
+    /// Parse mods from their acronyms, e.g. `HDDT` or `+HD,HR`.
+    ///
+    /// Acronyms are case insensitive and may be separated by any non-alphanumeric characters.
+    /// `NM` stands for no mods. Nightcore and Perfect also set Double Time and Sudden Death,
+    /// as they do in replays.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::Mods;
+    ///
+    /// assert_eq!(Mods::from_acronyms("HDDT").unwrap(), Mods::HIDDEN | Mods::DOUBLE_TIME);
+    /// assert_eq!(Mods::from_acronyms("+hd, nc").unwrap(), Mods::HIDDEN | Mods::DOUBLE_TIME | Mods::NIGHTCORE);
+    /// assert_eq!(Mods::from_acronyms("NM").unwrap(), Mods::NONE);
+    /// assert!(Mods::from_acronyms("HDXX").is_err());
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if an acronym is unknown.
+    pub fn from_acronyms(acronyms: &str) -> Result<Mods, ReplayDataError<'static>> {
+        let letters: Vec<char> = acronyms
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if !letters.len().is_multiple_of(2) {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+
+        letters.chunks_exact(2).try_fold(Mods::NONE, |mods, acronym| {
+            let acronym: String = acronym.iter().collect();
+            if acronym == "NM" {
+                return Ok(mods);
+            }
+            MOD_NAMES
+                .iter()
+                .find(|(_, a, _)| *a == acronym)
+                .map(|(m, _, _)| mods | m.with_implied())
+                .ok_or(ParseError::InvalidValue.into())
+        })
+    }
+
+    /// Parse mods from their full names, e.g. `Hidden` and `DoubleTime`.
+    ///
+    /// Names are case insensitive and whitespace is ignored, so `Double Time` is also accepted.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::Mods;
+    ///
+    /// let mods = Mods::from_names(&["Hidden", "Double Time"]).unwrap();
+    /// assert_eq!(mods, Mods::HIDDEN | Mods::DOUBLE_TIME);
+    /// assert!(Mods::from_names(&["Hiden"]).is_err());
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if a name is unknown.
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Mods, ReplayDataError<'static>> {
+        names.iter().try_fold(Mods::NONE, |mods, name| {
+            let name: String = name.as_ref().chars().filter(|c| !c.is_whitespace()).collect();
+            MOD_NAMES
+                .iter()
+                .find(|(_, _, n)| n.eq_ignore_ascii_case(&name))
+                .map(|(m, _, _)| mods | m.with_implied())
+                .ok_or(ParseError::InvalidValue.into())
+        })
+    }
+
+    /// The acronyms of the mods in the order of their bits, as shown by osu!, e.g. `HDDT`.
+    ///
+    /// Double Time and Sudden Death are left out if Nightcore and Perfect are set.
+    /// Returns an empty string for no mods. Unknown bits are ignored.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::Mods;
+    ///
+    /// assert_eq!((Mods::DOUBLE_TIME | Mods::HIDDEN).acronyms(), "HDDT");
+    /// assert_eq!((Mods::DOUBLE_TIME | Mods::NIGHTCORE).acronyms(), "NC");
+    /// assert_eq!(Mods::NONE.acronyms(), "");
+    /// ```
+    pub fn acronyms(self) -> String {
+        let mut mods = self;
+        if mods.contains(Mods::NIGHTCORE) {
+            mods.remove(Mods::DOUBLE_TIME);
+        }
+        if mods.contains(Mods::PERFECT) {
+            mods.remove(Mods::SUDDEN_DEATH);
+        }
+
+        MOD_NAMES
+            .iter()
+            .filter(|(m, _, _)| mods.contains(*m))
+            .map(|(_, acronym, _)| *acronym)
+            .collect()
+    }
+
+    /// The mod together with the mods it is always set with.
+    fn with_implied(self) -> Mods {
+        match self {
+            Mods::NIGHTCORE => Mods::NIGHTCORE | Mods::DOUBLE_TIME,
+            Mods::PERFECT => Mods::PERFECT | Mods::SUDDEN_DEATH,
+            mods => mods,
+        }
+    }
+
+    /// Factor by which the playback speed is changed, 1.5 for Double Time and 0.75 for Half Time.
+    pub fn speed_multiplier(self) -> f64 {
+        if self.contains(Mods::DOUBLE_TIME) {
+            1.5
+        } else if self.contains(Mods::HALF_TIME) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+}
+
+/// How invalid UTF-8 in the decompressed replay data is handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Utf8Handling {
+    /// Fail with `FrameError::InvalidUtf`.
+    #[default]
+    Strict,
+    /// Replace invalid byte sequences with U+FFFD and report them. Frames containing invalid
+    /// bytes are dropped, the other frames are kept.
+    Lossy,
+}
+
 /// Struct representing a single action in the replay.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct ReplayData {
     /// The time the action was performed.
     pub time: i64,
@@ -87,7 +401,7 @@ pub struct ReplayData {
 /// Struct representing a replay file.
 /// 
 /// Use [Self::parse] to parse a replay.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Replay {
     /// The game mode of the replay.
     pub game_mode: GameMode,
@@ -115,8 +429,8 @@ pub struct Replay {
     pub total_score: u32,
     /// Greatest combo displayed on the score report.
     pub greatest_combo: u16,
-    /// Perfect/full combo
-    pub perfect: u8,
+    /// Perfect/full combo as stored by osu!, see [`Replay::is_full_combo`] for a reliable check
+    pub perfect: bool,
     /// Bitwise representation of the mods used.
     pub mods: u32,
     /// Life bar graph
@@ -127,6 +441,18 @@ pub struct Replay {
     pub compressed_data: Vec<u8>,
     /// Online score ID
     pub online_score_id: i64,
+    /// Additional mod information, the accuracy of Target Practice replays
+    pub additional_mod_info: Option<f64>,
+    /// LZMA compressed JSON with additional score information, appended by osu!lazer
+    pub lazer_data: Option<Vec<u8>>,
+    /// The exact bytes of the player name field, kept if they differ from the encoding of `player_name`,
+    /// e.g. for names that are not valid UTF-8
+    pub player_name_raw: Option<Vec<u8>>,
+    /// The byte of the perfect field, kept if it is neither 0 nor 1
+    pub perfect_raw: Option<u8>,
+    /// Whether empty strings were stored as present strings of length 0 instead of absent, as
+    /// osu!lazer does for the life bar. Empty strings are written the same way.
+    pub empty_strings_present: bool,
 }
 
 impl fmt::Display for Replay {
@@ -156,16 +482,180 @@ impl fmt::Display for Replay {
 }
 
 impl Replay {
-    fn decompress_lzma(self) -> Result<String, ReplayDataError<'static>> {
+    /// The format generation of the replay, see [`ReplayFormat::from_version`].
+    ///
+    /// Replays in the [`ReplayFormat::LegacyFormat`] have no online score ID, it is set to 0
+    /// when parsing and not written.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::ReplayFormat;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// assert_eq!(replay.format(), ReplayFormat::Lazer);
+    ///
+    /// let legacy = replay.convert_to_version(20110526).expect("Error converting replay");
+    /// let bytes = legacy.to_bytes().expect("Error writing replay");
+    /// let parsed = Replay::parse(&bytes).expect("Error parsing replay");
+    /// assert_eq!(parsed.format(), ReplayFormat::LegacyFormat);
+    /// assert_eq!(parsed.online_score_id, 0);
+    /// assert_eq!(parsed.compressed_data, replay.compressed_data);
+    /// ```
+    pub fn format(&self) -> ReplayFormat {
+        ReplayFormat::from_version(self.version)
+    }
+
+    /// The mods used in the replay.
+    ///
+    /// Unknown bits are retained.
+    pub fn mods(&self) -> Mods {
+        Mods::from_bits_retain(self.mods)
+    }
+
+    /// The byte of the perfect field as written to the replay file.
+    ///
+    /// Bytes other than 0 and 1 read from a replay are kept in `perfect_raw` as long as they
+    /// agree with `perfect`.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay { perfect: true, perfect_raw: Some(2), ..Replay::default() };
+    /// assert_eq!(replay.perfect_byte(), 2);
+    ///
+    /// replay.perfect = false;
+    /// assert_eq!(replay.perfect_byte(), 0);
+    /// ```
+    pub fn perfect_byte(&self) -> u8 {
+        match self.perfect_raw {
+            Some(raw) if self.perfect && raw > 1 => raw,
+            _ => u8::from(self.perfect),
+        }
+    }
+
+    /// The player name with surrounding whitespace removed and combining characters composed.
+    ///
+    /// Old player names may have trailing spaces and names entered on some systems use combining
+    /// characters, so this should be used when comparing or grouping by player name.
+    /// The original name is kept unchanged in `player_name`.
+    ///
+    /// With the `nfc` feature the name is normalized to Unicode normalization form C. Without it
+    /// only base characters directly followed by a mark are composed, which covers the Latin
+    /// scripts up to Latin Extended-B and the voiced kana.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay {
+    ///     player_name: "Rene\u{301} ".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(replay.normalized_player_name(), "René");
+    /// ```
+    pub fn normalized_player_name(&self) -> String {
+        compose_nfc(self.player_name.trim())
+    }
+
+    /// Accuracy of the replay in the range `0.0..=1.0`, calculated from the hit counts
+    /// using the formula of the replay's game mode.
+    ///
+    /// Returns `1.0` if the replay contains no judgements.
+    pub fn accuracy(&self) -> f64 {
+        self.hit_statistics().accuracy()
+    }
+
+    /// Total duration of the replay, the time between its first and last frame.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::{writer, Replay, ReplayData};
+    ///
+    /// // A synthetic 26 hour replay
+    /// let frames = [
+    ///     ReplayData { time: 0, x: 0.0, y: 0.0, keys: 0 },
+    ///     ReplayData { time: 26 * 60 * 60 * 1000, x: 0.0, y: 0.0, keys: 0 },
+    /// ];
+    /// let replay = Replay {
+    ///     compressed_data: writer::compress_frames(&frames),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(replay.duration().unwrap().as_secs(), 26 * 60 * 60);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded.
+    pub fn duration(&self) -> Result<Duration, ReplayDataError<'static>> {
+        let frames = self.get_actions_with_progress(|_, _| {})?;
+        Ok(Duration::from_millis(frames.duration() as u64))
+    }
+
+    /// Time actually played, the duration without the skipped intro and other gaps in the frame
+    /// stream, see [`FramesExt::active_time`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded.
+    pub fn active_time(&self) -> Result<Duration, ReplayDataError<'static>> {
+        let frames = self.get_actions_with_progress(|_, _| {})?;
+        Ok(Duration::from_millis(frames.active_time() as u64))
+    }
+
+    /// Length of the skipped intro in milliseconds, 0 if the player did not skip, see [`FramesExt::skipped_intro`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let duration = replay.duration().expect("Error decoding frames");
+    /// let active_time = replay.active_time().expect("Error decoding frames");
+    /// let skipped = replay.skipped_intro_ms().expect("Error decoding frames");
+    /// assert!(active_time.as_millis() as i64 + skipped <= duration.as_millis() as i64);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded.
+    pub fn skipped_intro_ms(&self) -> Result<i64, ReplayDataError<'static>> {
+        let frames = self.get_actions_with_progress(|_, _| {})?;
+        Ok(frames.skipped_intro())
+    }
+
+    pub(crate) fn decompress_lzma(compressed_data: &[u8]) -> Result<String, ReplayDataError<'static>> {
+        Replay::decompress_lzma_with(compressed_data, Utf8Handling::Strict, &mut Vec::new())
+    }
+
+    /// Decompress the replay data, adding the replaced byte sequences to `invalid` if `handling` is lossy.
+    pub(crate) fn decompress_lzma_with(
+        compressed_data: &[u8],
+        handling: Utf8Handling,
+        invalid: &mut Vec<InvalidUtf8>,
+    ) -> Result<String, ReplayDataError<'static>> {
         let mut decompressed_data = Vec::new();
-        lzma_decompress(&mut self.compressed_data.as_slice(), &mut decompressed_data)?;
-        let decompressed_data =
-            String::from_utf8(decompressed_data).map_err(|_| ReplayDataError::InvalidUtfError)?;
+        lzma_decompress(&mut &compressed_data[..], &mut decompressed_data)?;
+        let decompressed_data = match (String::from_utf8(decompressed_data), handling) {
+            (Ok(text), _) => text,
+            (Err(_), Utf8Handling::Strict) => return Err(ReplayDataError::Frame(FrameError::InvalidUtf)),
+            (Err(e), Utf8Handling::Lossy) => {
+                let bytes = e.into_bytes();
+                let mut text = String::with_capacity(bytes.len());
+                let mut offset = 0;
+                for chunk in bytes.utf8_chunks() {
+                    text.push_str(chunk.valid());
+                    offset += chunk.valid().len();
+                    if !chunk.invalid().is_empty() {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        invalid.push(InvalidUtf8 {
+                            offset,
+                            len: chunk.invalid().len(),
+                        });
+                        offset += chunk.invalid().len();
+                    }
+                }
+                text
+            }
+        };
 
         Ok(decompressed_data)
     }
 
     /// Get a vector of [`ReplayData`](struct.ReplayData.html) from the compressed replay data.
+    ///
+    /// No limits are enforced, use [`Self::get_actions_with_limits`] for replays from untrusted
+    /// sources.
     /// # Example
     /// ```
     /// use osu_replay_parser::{Replay, ReplayData};
@@ -176,32 +666,226 @@ impl Replay {
     /// let actions = replay.get_actions().expect("Error getting actions");
     /// 
     pub fn get_actions(self) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
-        let decompressed_data = self.decompress_lzma()?;
-        let replay_data: Result<Vec<ReplayData>, ReplayDataError<'_>> = decompressed_data
-            .split_terminator(',')
-            .map(|data| {
-                let mut split = data.split('|');
-                let time: i64 = split
-                    .next()
-                    .ok_or(ReplayDataError::MissingValueError)?
-                    .parse()?;
-                let x: f32 = split
-                    .next()
-                    .ok_or(ReplayDataError::MissingValueError)?
-                    .parse()?;
-                let y: f32 = split
-                    .next()
-                    .ok_or(ReplayDataError::MissingValueError)?
-                    .parse()?;
-                let keys: u32 = split
-                    .next()
-                    .ok_or(ReplayDataError::MissingValueError)?
-                    .parse()?;
-
-                Ok(ReplayData { time, x, y, keys })
-            })
-            .collect();
+        Replay::decode_actions(&self.compressed_data, |_, _| {})
+    }
+
+    /// Decode the compressed replay data like [`Self::get_actions`] without consuming the replay,
+    /// reporting the progress to `on_progress`.
+    ///
+    /// `on_progress` is called after every frame with the number of decompressed bytes processed
+    /// so far and the total number of decompressed bytes.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let mut last = (0, 0);
+    /// let actions = replay
+    ///     .get_actions_with_progress(|processed, total| last = (processed, total))
+    ///     .expect("Error getting actions");
+    /// assert_eq!(last.0, last.1);
+    /// ```
+    pub fn get_actions_with_progress<F>(
+        &self,
+        on_progress: F,
+    ) -> Result<Vec<ReplayData>, ReplayDataError<'static>>
+    where
+        F: FnMut(usize, usize),
+    {
+        Replay::decode_actions(&self.compressed_data, on_progress)
+    }
 
-        replay_data
+    /// Decode the compressed replay data like [`Self::get_actions`] with the given handling of
+    /// invalid UTF-8, e.g. for replays with a few corrupted bytes.
+    ///
+    /// Returns the frames together with the replaced byte sequences, which are always empty with
+    /// [`Utf8Handling::Strict`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::errors::InvalidUtf8;
+    /// use osu_replay_parser::replay::Utf8Handling;
+    /// use osu_replay_parser::{writer, Replay};
+    ///
+    /// let replay = Replay {
+    ///     compressed_data: writer::compress(b"0|256|192|0,16|260|190|1,\xff"),
+    ///     ..Default::default()
+    /// };
+    /// assert!(replay.get_actions_with_progress(|_, _| {}).is_err());
+    ///
+    /// let (actions, invalid) = replay.get_actions_with_utf8_handling(Utf8Handling::Lossy).unwrap();
+    /// assert_eq!(actions.len(), 2);
+    /// assert_eq!(invalid, vec![InvalidUtf8 { offset: 25, len: 1 }]);
+    ///
+    /// // Only the frame with the invalid byte is dropped
+    /// let replay = Replay {
+    ///     compressed_data: writer::compress(b"0|256|192|0,16|26\xff0|190|1,16|270|180|0,"),
+    ///     ..Default::default()
+    /// };
+    /// let (actions, invalid) = replay.get_actions_with_utf8_handling(Utf8Handling::Lossy).unwrap();
+    /// assert_eq!(actions.len(), 2);
+    /// assert_eq!((actions[1].x, invalid.len()), (270.0, 1));
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded, is not valid
+    /// UTF-8 with [`Utf8Handling::Strict`] or a frame cannot be parsed.
+    pub fn get_actions_with_utf8_handling(
+        &self,
+        handling: Utf8Handling,
+    ) -> Result<(Vec<ReplayData>, Vec<InvalidUtf8>), ReplayDataError<'static>> {
+        let mut invalid = Vec::new();
+        let mut decompressed_data =
+            Replay::decompress_lzma_with(&self.compressed_data, handling, &mut invalid)?;
+        if !invalid.is_empty() {
+            // Frames with invalid bytes cannot be parsed, the others are kept
+            decompressed_data = decompressed_data
+                .split_inclusive(',')
+                .filter(|frame| !frame.contains(char::REPLACEMENT_CHARACTER))
+                .collect();
+        }
+        let mut frames = Vec::new();
+        Replay::parse_frames_into(&decompressed_data, &mut frames, |_, _| {})?;
+
+        Ok((frames, invalid))
+    }
+
+    /// Decode the compressed replay data and drop it from the replay afterwards.
+    ///
+    /// Unlike [`Self::get_actions`] the header of the replay is kept, while the memory
+    /// used by the compressed data is released. Calling this a second time returns an error,
+    /// as the compressed data is gone.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let actions = replay.take_actions().expect("Error getting actions");
+    /// assert!(replay.compressed_data.is_empty());
+    /// ```
+    pub fn take_actions(&mut self) -> Result<Vec<ReplayData>, ReplayDataError<'static>> {
+        let compressed_data = std::mem::take(&mut self.compressed_data);
+        Replay::decode_actions(&compressed_data, |_, _| {})
+    }
+
+    /// Decode the compressed replay data, skipping frames that cannot be parsed instead of failing.
+    ///
+    /// Returns the valid frames together with the position and raw text of every skipped frame.
+    /// Invalid UTF-8 is replaced with U+FFFD, see [`Utf8Handling::Lossy`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::errors::{FrameError, InvalidFrame, ReplayDataError};
+    /// use osu_replay_parser::{writer, Replay};
+    ///
+    /// let replay = Replay {
+    ///     compressed_data: writer::compress(b"0|256|192|0,16|oops|192|1,16|260|190|1,"),
+    ///     ..Default::default()
+    /// };
+    /// let Err(ReplayDataError::Frame(FrameError::InvalidFrame(invalid))) = replay.get_actions_with_progress(|_, _| {}) else {
+    ///     panic!("Expected an invalid frame");
+    /// };
+    /// assert_eq!(invalid, InvalidFrame { index: 1, raw: "16|oops|192|1".to_string() });
+    ///
+    /// let (actions, skipped) = replay.get_actions_lenient().expect("Error decompressing actions");
+    /// assert_eq!(actions.len(), 2);
+    /// assert_eq!(skipped, vec![invalid]);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decompressed.
+    pub fn get_actions_lenient(
+        &self,
+    ) -> Result<(Vec<ReplayData>, Vec<InvalidFrame>), ReplayDataError<'static>> {
+        let decompressed_data =
+            Replay::decompress_lzma_with(&self.compressed_data, Utf8Handling::Lossy, &mut Vec::new())?;
+        let mut frames = Vec::new();
+        let mut skipped = Vec::new();
+        for (index, data) in decompressed_data.split_terminator(',').enumerate() {
+            match Replay::parse_frame(data) {
+                Ok(frame) => frames.push(frame),
+                Err(_) => skipped.push(InvalidFrame {
+                    index,
+                    raw: data.to_string(),
+                }),
+            }
+        }
+
+        Ok((frames, skipped))
+    }
+
+    pub(crate) fn decode_actions<F>(
+        compressed_data: &[u8],
+        on_progress: F,
+    ) -> Result<Vec<ReplayData>, ReplayDataError<'static>>
+    where
+        F: FnMut(usize, usize),
+    {
+        let mut frames = Vec::new();
+        Replay::decode_actions_into(compressed_data, &mut frames, on_progress)?;
+
+        Ok(frames)
+    }
+
+    pub(crate) fn parse_frame(data: &str) -> Result<ReplayData, ReplayDataError<'static>> {
+        let mut split = data.split('|');
+        let time: i64 = split
+            .next()
+            .ok_or(ParseError::MissingValue)?
+            .parse()?;
+        let x: f32 = split
+            .next()
+            .ok_or(ParseError::MissingValue)?
+            .parse()?;
+        let y: f32 = split
+            .next()
+            .ok_or(ParseError::MissingValue)?
+            .parse()?;
+        let keys: u32 = split
+            .next()
+            .ok_or(ParseError::MissingValue)?
+            .parse()?;
+
+        Ok(ReplayData { time, x, y, keys })
+    }
+
+    /// Decode the compressed replay data into `frames`, replacing its content but reusing its allocation.
+    pub(crate) fn decode_actions_into<F>(
+        compressed_data: &[u8],
+        frames: &mut Vec<ReplayData>,
+        on_progress: F,
+    ) -> Result<(), ReplayDataError<'static>>
+    where
+        F: FnMut(usize, usize),
+    {
+        frames.clear();
+        let decompressed_data = Replay::decompress_lzma(compressed_data)?;
+        Replay::parse_frames_into(&decompressed_data, frames, on_progress)
+    }
+
+    /// Parse the frames of the decompressed replay data into `frames`, which must be empty.
+    pub(crate) fn parse_frames_into<F>(
+        decompressed_data: &str,
+        frames: &mut Vec<ReplayData>,
+        mut on_progress: F,
+    ) -> Result<(), ReplayDataError<'static>>
+    where
+        F: FnMut(usize, usize),
+    {
+        let total = decompressed_data.len();
+        let mut processed = 0;
+        for (index, data) in decompressed_data.split_terminator(',').enumerate() {
+            processed = (processed + data.len() + 1).min(total);
+            on_progress(processed, total);
+
+            match Replay::parse_frame(data) {
+                Ok(frame) => frames.push(frame),
+                Err(_) => {
+                    frames.clear();
+                    return Err(ReplayDataError::Frame(FrameError::InvalidFrame(InvalidFrame {
+                        index,
+                        raw: data.to_string(),
+                    })));
+                }
+            }
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,143 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::batch::replay_files;
+use crate::errors::ReplayDataError;
+use crate::parser::{peek_header, ParseOptions};
+use crate::replay::{GameMode, Mods, Replay};
+
+/// Predicate over the header of a replay, used by [`filter`].
+///
+/// All conditions must hold for a replay to match.
+/// # Example
+/// ```
+/// use osu_replay_parser::replay::{GameMode, Mods};
+/// use osu_replay_parser::scan::Filter;
+///
+/// let filter = Filter::new()
+///     .mode(GameMode::Osu)
+///     .mods_contain(Mods::HIDDEN | Mods::DOUBLE_TIME)
+///     .min_accuracy(0.98);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    mode: Option<GameMode>,
+    mods_contain: Mods,
+    mods_exclude: Mods,
+    min_accuracy: Option<f64>,
+    player: Option<String>,
+    beatmap_md5: Option<String>,
+}
+
+impl Filter {
+    /// Create a filter matching all replays.
+    pub fn new() -> Self {
+        Filter::default()
+    }
+
+    /// Only match replays of the game mode.
+    pub fn mode(mut self, mode: GameMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Only match replays with all of the mods set.
+    pub fn mods_contain(mut self, mods: Mods) -> Self {
+        self.mods_contain |= mods;
+        self
+    }
+
+    /// Only match replays with none of the mods set.
+    pub fn mods_exclude(mut self, mods: Mods) -> Self {
+        self.mods_exclude |= mods;
+        self
+    }
+
+    /// Only match replays with an accuracy of at least `accuracy`, in the range `0.0..=1.0`.
+    pub fn min_accuracy(mut self, accuracy: f64) -> Self {
+        self.min_accuracy = Some(accuracy);
+        self
+    }
+
+    /// Only match replays of the player, compared case insensitively after
+    /// [`Replay::normalized_player_name`].
+    pub fn player(mut self, name: &str) -> Self {
+        self.player = Some(name.trim().to_lowercase());
+        self
+    }
+
+    /// Only match replays of the beatmap with the MD5 hash, compared case insensitively.
+    pub fn beatmap_md5(mut self, md5: &str) -> Self {
+        self.beatmap_md5 = Some(md5.to_lowercase());
+        self
+    }
+
+    /// Whether the replay matches all conditions. Only header fields are used.
+    pub fn matches(&self, replay: &Replay) -> bool {
+        let mods = replay.mods();
+        self.mode.is_none_or(|mode| replay.game_mode == mode)
+            && mods.contains(self.mods_contain)
+            && !mods.intersects(self.mods_exclude)
+            && self
+                .min_accuracy
+                .is_none_or(|accuracy| replay.accuracy() >= accuracy)
+            && self
+                .player
+                .as_ref()
+                .is_none_or(|player| replay.normalized_player_name().to_lowercase() == *player)
+            && self
+                .beatmap_md5
+                .as_ref()
+                .is_none_or(|md5| replay.beatmap_md5.eq_ignore_ascii_case(md5))
+    }
+}
+
+/// Find the replays in a directory matching the filter, sorted by path.
+///
+/// Each file is first parsed with [`ParseOptions::header_only`] and only fully parsed if its
+/// header matches, so scanning a large replay folder for a few plays stays fast.
+/// Files whose header cannot be parsed are returned with their error.
+/// Subdirectories are not searched.
+/// # Example
+/// ```
+/// use osu_replay_parser::replay::{GameMode, Mods};
+/// use osu_replay_parser::scan::{filter, Filter};
+///
+/// let matching = filter("assets", &Filter::new().mode(GameMode::Osu)).expect("Error reading directory");
+/// assert_eq!(matching.len(), 1);
+/// assert!(!matching[0].1.as_ref().unwrap().compressed_data.is_empty());
+///
+/// let hidden = filter("assets", &Filter::new().mods_contain(Mods::HIDDEN)).expect("Error reading directory");
+/// assert!(hidden.is_empty());
+/// ```
+/// # Errors
+/// Returns an error if the directory cannot be read. Errors of individual files are part of the result.
+pub fn filter<P: AsRef<Path>>(
+    dir: P,
+    filter: &Filter,
+) -> io::Result<Vec<(PathBuf, Result<Replay, ReplayDataError<'static>>)>> {
+    let mut matching = Vec::new();
+    for path in replay_files(dir.as_ref())? {
+        let input = match std::fs::read(&path) {
+            Ok(input) => input,
+            Err(e) => {
+                matching.push((path, Err(e.into())));
+                continue;
+            }
+        };
+        // Replays of other game modes are skipped before parsing the header
+        if let (Some(mode), Ok((game_mode, _))) = (filter.mode, peek_header(&input)) {
+            if game_mode != mode {
+                continue;
+            }
+        }
+        let result = match Replay::parse_with_options(&input, &ParseOptions::header_only()) {
+            Ok(header) if filter.matches(&header) => Replay::parse(&input),
+            Ok(_) => continue,
+            Err(e) => Err(e),
+        };
+        matching.push((path, result.map_err(ReplayDataError::into_owned)));
+    }
+
+    Ok(matching)
+}
@@ -0,0 +1,207 @@
+//! Replays are signed with Ed25519 ([RFC 8032]), so tournament organizers can check that a
+//! submitted replay was not modified after it was signed by a trusted client or referee.
+//! The keys are provided by the application, this module does not generate or store them.
+//! The cryptography is done by [`ed25519_dalek`], which signs in constant time and zeroes
+//! secret keys when they are dropped.
+//!
+//! Signatures are either kept separately with [`Replay::sign`] and [`Replay::verify_signature`],
+//! or written to a detached `.sig` file next to the replay file with [`sign_file`] and [`verify_file`].
+//!
+//! [RFC 8032]: https://www.rfc-editor.org/rfc/rfc8032
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signer, Verifier};
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::replay::Replay;
+
+/// Length of a signature in bytes.
+pub const SIGNATURE_LENGTH: usize = 64;
+
+/// Extension appended to the path of a replay file for its detached signature.
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// An Ed25519 private key, derived from a 32 byte secret seed.
+#[derive(Clone)]
+pub struct SigningKey(ed25519_dalek::SigningKey);
+
+/// An Ed25519 public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VerifyingKey(ed25519_dalek::VerifyingKey);
+
+/// An Ed25519 signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; SIGNATURE_LENGTH]);
+
+impl SigningKey {
+    /// Derive the key from its secret seed.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::hash::to_hex;
+    /// use osu_replay_parser::sign::SigningKey;
+    ///
+    /// // Test vector 1 of RFC 8032
+    /// let seed = [
+    ///     0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4,
+    ///     0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60,
+    /// ];
+    /// let key = SigningKey::from_bytes(&seed);
+    /// assert_eq!(
+    ///     to_hex(key.verifying_key().as_bytes()),
+    ///     "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+    /// );
+    /// assert_eq!(
+    ///     to_hex(key.sign(b"").as_bytes()),
+    ///     "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+    /// );
+    /// ```
+    pub fn from_bytes(seed: &[u8; 32]) -> Self {
+        SigningKey(ed25519_dalek::SigningKey::from_bytes(seed))
+    }
+
+    /// The secret seed of the key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// The public key for verifying signatures of this key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(self.0.verifying_key())
+    }
+
+    /// Sign the message.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        Signature(self.0.sign(message).to_bytes())
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The seed is secret
+        f.debug_struct("SigningKey")
+            .field("public", &self.verifying_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl VerifyingKey {
+    /// Read an encoded public key, returns `None` if it is not a valid point.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        ed25519_dalek::VerifyingKey::from_bytes(bytes)
+            .ok()
+            .map(VerifyingKey)
+    }
+
+    /// The encoded public key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.0.as_bytes()
+    }
+
+    /// Check that the signature of the message was created by the private key of this key.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.0);
+        self.0.verify(message, &signature).is_ok()
+    }
+}
+
+impl Signature {
+    /// Wrap the raw bytes of a signature.
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Self {
+        Signature(*bytes)
+    }
+
+    /// The raw bytes of the signature.
+    pub fn as_bytes(&self) -> &[u8; SIGNATURE_LENGTH] {
+        &self.0
+    }
+}
+
+impl Replay {
+    /// Sign the replay as encoded by [`Replay::write`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::sign::SigningKey;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let key = SigningKey::from_bytes(&[7; 32]);
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let signature = replay.sign(&key).expect("Error encoding replay");
+    /// assert!(replay.verify_signature(&key.verifying_key(), &signature));
+    ///
+    /// replay.total_score += 1;
+    /// assert!(!replay.verify_signature(&key.verifying_key(), &signature));
+    /// ```
+    /// # Errors
+    /// Returns an error if the replay cannot be encoded, see [`Replay::write`].
+    pub fn sign(&self, key: &SigningKey) -> io::Result<Signature> {
+        Ok(key.sign(&self.to_bytes()?))
+    }
+
+    /// Check that the replay was signed by the private key of `key` and not modified since.
+    ///
+    /// Returns `false` if the replay cannot be encoded.
+    pub fn verify_signature(&self, key: &VerifyingKey, signature: &Signature) -> bool {
+        self.to_bytes()
+            .map(|bytes| key.verify(&bytes, signature))
+            .unwrap_or(false)
+    }
+}
+
+/// Path of the detached signature of a replay file, the path with `.sig` appended.
+pub fn signature_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut path = path.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Sign the bytes of a replay file and write the signature to [`signature_path`].
+///
+/// Returns the path of the signature file.
+/// # Example
+/// ```
+/// use osu_replay_parser::sign::{sign_file, verify_file, SigningKey};
+///
+/// let dir = std::env::temp_dir().join("osu_replay_parser_sign_example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("replay.osr");
+/// std::fs::copy("assets/replay.osr", &path).unwrap();
+///
+/// let key = SigningKey::from_bytes(&[7; 32]);
+/// let signature_path = sign_file(&path, &key).expect("Error signing replay");
+/// assert!(signature_path.ends_with("replay.osr.sig"));
+/// assert!(verify_file(&path, &key.verifying_key()).expect("Error verifying replay"));
+///
+/// let other = SigningKey::from_bytes(&[8; 32]);
+/// assert!(!verify_file(&path, &other.verifying_key()).expect("Error verifying replay"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+/// # Errors
+/// Returns a `ReplayDataError::Io` if the replay file cannot be read or the signature cannot be written.
+pub fn sign_file<P: AsRef<Path>>(
+    path: P,
+    key: &SigningKey,
+) -> Result<PathBuf, ReplayDataError<'static>> {
+    let signature = key.sign(&std::fs::read(&path)?);
+    let signature_path = signature_path(path);
+    std::fs::write(&signature_path, signature.as_bytes())?;
+    Ok(signature_path)
+}
+
+/// Check a replay file against its detached signature at [`signature_path`].
+/// # Errors
+/// Returns a `ReplayDataError::Io` if the replay or signature file cannot be read and a `ParseError::InvalidValue`
+/// if the signature file does not have 64 bytes.
+pub fn verify_file<P: AsRef<Path>>(
+    path: P,
+    key: &VerifyingKey,
+) -> Result<bool, ReplayDataError<'static>> {
+    let data = std::fs::read(&path)?;
+    let signature: [u8; SIGNATURE_LENGTH] = std::fs::read(signature_path(path))?
+        .try_into()
+        .map_err(|_| ParseError::InvalidValue)?;
+    Ok(key.verify(&data, &Signature(signature)))
+}
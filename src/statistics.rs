@@ -0,0 +1,354 @@
+use crate::analysis::slider_breaks;
+use crate::beatmap::Beatmap;
+use crate::replay::{GameMode, Replay, ReplayData};
+
+/// Hit counts of a replay, named as in the game mode of the replay.
+///
+/// The replay format stores the hit counts of all game modes in the same six fields, whose
+/// meaning depends on the game mode. Fields not used by a game mode are dropped.
+/// # Example
+/// ```
+/// use osu_replay_parser::statistics::HitStatistics;
+/// use osu_replay_parser::Replay;
+///
+/// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+/// let HitStatistics::Osu { n300, n_miss, .. } = replay.hit_statistics() else {
+///     panic!("Expected an osu!standard replay");
+/// };
+/// assert_eq!((n300, n_miss), (774, 7));
+///
+/// HitStatistics::Mania { max: 10, n300: 5, n200: 0, n100: 0, n50: 0, miss: 1 }.apply_to(&mut replay);
+/// assert_eq!((replay.n_geki, replay.n300, replay.n_miss), (10, 5, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HitStatistics {
+    /// Hit counts of an osu!standard replay.
+    Osu {
+        /// Number of 300s.
+        n300: u16,
+        /// Number of 100s.
+        n100: u16,
+        /// Number of 50s.
+        n50: u16,
+        /// Number of combos completed with only 300s.
+        n_geki: u16,
+        /// Number of combos completed with at least one 100 and no 50s or misses.
+        n_katu: u16,
+        /// Number of misses.
+        n_miss: u16,
+    },
+    /// Hit counts of an osu!taiko replay.
+    Taiko {
+        /// Number of great hits.
+        great: u16,
+        /// Number of ok hits.
+        ok: u16,
+        /// Number of great hits on big notes hit with both keys.
+        large_great: u16,
+        /// Number of ok hits on big notes hit with both keys.
+        large_ok: u16,
+        /// Number of misses.
+        miss: u16,
+    },
+    /// Hit counts of an osu!catch replay.
+    Catch {
+        /// Number of caught fruits.
+        fruits: u16,
+        /// Number of caught droplets.
+        droplets: u16,
+        /// Number of caught tiny droplets.
+        tiny_droplets: u16,
+        /// Number of missed tiny droplets.
+        tiny_droplet_misses: u16,
+        /// Number of missed fruits and droplets.
+        misses: u16,
+    },
+    /// Hit counts of an osu!mania replay.
+    Mania {
+        /// Number of MAX (rainbow 300) judgements.
+        max: u16,
+        /// Number of 300s.
+        n300: u16,
+        /// Number of 200s.
+        n200: u16,
+        /// Number of 100s.
+        n100: u16,
+        /// Number of 50s.
+        n50: u16,
+        /// Number of misses.
+        miss: u16,
+    },
+}
+
+impl HitStatistics {
+    /// The game mode the hit counts belong to.
+    pub fn game_mode(&self) -> GameMode {
+        match self {
+            HitStatistics::Osu { .. } => GameMode::Osu,
+            HitStatistics::Taiko { .. } => GameMode::Taiko,
+            HitStatistics::Catch { .. } => GameMode::CatchTheBeat,
+            HitStatistics::Mania { .. } => GameMode::Mania,
+        }
+    }
+
+    /// The hit counts in the order of the replay fields `n300`, `n100`, `n50`, `n_geki`, `n_katu` and `n_miss`.
+    pub fn to_raw(&self) -> [u16; 6] {
+        match *self {
+            HitStatistics::Osu {
+                n300,
+                n100,
+                n50,
+                n_geki,
+                n_katu,
+                n_miss,
+            } => [n300, n100, n50, n_geki, n_katu, n_miss],
+            HitStatistics::Taiko {
+                great,
+                ok,
+                large_great,
+                large_ok,
+                miss,
+            } => [great, ok, 0, large_great, large_ok, miss],
+            HitStatistics::Catch {
+                fruits,
+                droplets,
+                tiny_droplets,
+                tiny_droplet_misses,
+                misses,
+            } => [
+                fruits,
+                droplets,
+                tiny_droplets,
+                0,
+                tiny_droplet_misses,
+                misses,
+            ],
+            HitStatistics::Mania {
+                max,
+                n300,
+                n200,
+                n100,
+                n50,
+                miss,
+            } => [n300, n100, n50, max, n200, miss],
+        }
+    }
+
+    /// Set the game mode and the hit counts of the replay.
+    pub fn apply_to(&self, replay: &mut Replay) {
+        replay.game_mode = self.game_mode();
+        [
+            replay.n300,
+            replay.n100,
+            replay.n50,
+            replay.n_geki,
+            replay.n_katu,
+            replay.n_miss,
+        ] = self.to_raw();
+    }
+
+    /// Accuracy in the range `0.0..=1.0`, using the formula of the game mode.
+    ///
+    /// Returns `1.0` if there are no judgements.
+    pub fn accuracy(&self) -> f64 {
+        let (achieved, total) = match *self {
+            HitStatistics::Osu {
+                n300,
+                n100,
+                n50,
+                n_miss,
+                ..
+            } => {
+                let [n300, n100, n50, n_miss] = [n300, n100, n50, n_miss].map(f64::from);
+                (
+                    300.0 * n300 + 100.0 * n100 + 50.0 * n50,
+                    300.0 * (n300 + n100 + n50 + n_miss),
+                )
+            }
+            HitStatistics::Taiko {
+                great, ok, miss, ..
+            } => {
+                let [great, ok, miss] = [great, ok, miss].map(f64::from);
+                (great + 0.5 * ok, great + ok + miss)
+            }
+            HitStatistics::Catch {
+                fruits,
+                droplets,
+                tiny_droplets,
+                tiny_droplet_misses,
+                misses,
+            } => {
+                let caught = f64::from(fruits) + f64::from(droplets) + f64::from(tiny_droplets);
+                (
+                    caught,
+                    caught + f64::from(tiny_droplet_misses) + f64::from(misses),
+                )
+            }
+            HitStatistics::Mania {
+                max,
+                n300,
+                n200,
+                n100,
+                n50,
+                miss,
+            } => {
+                let [max, n300, n200, n100, n50, miss] =
+                    [max, n300, n200, n100, n50, miss].map(f64::from);
+                (
+                    300.0 * (max + n300) + 200.0 * n200 + 100.0 * n100 + 50.0 * n50,
+                    300.0 * (max + n300 + n200 + n100 + n50 + miss),
+                )
+            }
+        };
+
+        if total == 0.0 {
+            1.0
+        } else {
+            achieved / total
+        }
+    }
+}
+
+impl From<&Replay> for HitStatistics {
+    fn from(replay: &Replay) -> Self {
+        let (n300, n100, n50, n_geki, n_katu, n_miss) = (
+            replay.n300,
+            replay.n100,
+            replay.n50,
+            replay.n_geki,
+            replay.n_katu,
+            replay.n_miss,
+        );
+
+        match replay.game_mode {
+            GameMode::Osu | GameMode::Unknown(_) => HitStatistics::Osu {
+                n300,
+                n100,
+                n50,
+                n_geki,
+                n_katu,
+                n_miss,
+            },
+            GameMode::Taiko => HitStatistics::Taiko {
+                great: n300,
+                ok: n100,
+                large_great: n_geki,
+                large_ok: n_katu,
+                miss: n_miss,
+            },
+            GameMode::CatchTheBeat => HitStatistics::Catch {
+                fruits: n300,
+                droplets: n100,
+                tiny_droplets: n50,
+                tiny_droplet_misses: n_katu,
+                misses: n_miss,
+            },
+            GameMode::Mania => HitStatistics::Mania {
+                max: n_geki,
+                n300,
+                n200: n_katu,
+                n100,
+                n50,
+                miss: n_miss,
+            },
+        }
+    }
+}
+
+impl Replay {
+    /// The hit counts of the replay, interpreted according to its game mode.
+    pub fn hit_statistics(&self) -> HitStatistics {
+        HitStatistics::from(self)
+    }
+
+    /// Whether the replay is a full combo of a beatmap with the given maximum combo.
+    ///
+    /// The `perfect` byte is not used, as osu! sets it inconsistently across versions and game
+    /// modes. In osu!standard and osu!catch the combo must reach `beatmap_max_combo` without
+    /// misses, so slider breaks and dropped slider ends, which break the combo without counting
+    /// as a miss, are not full combos. In osu!taiko and osu!mania only misses break the combo
+    /// and the maximum combo is ignored, as it differs between osu!stable and osu!lazer.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// assert!(!replay.is_full_combo(1000));
+    ///
+    /// replay.n_miss = 0;
+    /// replay.greatest_combo = 1000;
+    /// assert!(replay.is_full_combo(1000));
+    /// ```
+    pub fn is_full_combo(&self, beatmap_max_combo: u16) -> bool {
+        match self.hit_statistics() {
+            HitStatistics::Osu { n_miss: 0, .. } | HitStatistics::Catch { misses: 0, .. } => {
+                self.greatest_combo >= beatmap_max_combo
+            }
+            HitStatistics::Taiko { miss, .. } => miss == 0,
+            HitStatistics::Mania { miss, .. } => miss == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether the `perfect` field agrees with the hit counts and, if given, the maximum combo of
+    /// the beatmap, see [`Self::is_full_combo`].
+    ///
+    /// A perfect play cannot have misses. Without the maximum combo, plays without misses are not
+    /// checked, as slider breaks cannot be told apart from other drops.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay { perfect: true, n_miss: 1, ..Replay::default() };
+    /// assert!(!replay.perfect_matches(None));
+    ///
+    /// let replay = Replay { perfect: false, greatest_combo: 500, ..Replay::default() };
+    /// assert!(replay.perfect_matches(None));
+    /// assert!(!replay.perfect_matches(Some(500)));
+    /// ```
+    pub fn perfect_matches(&self, beatmap_max_combo: Option<u16>) -> bool {
+        match beatmap_max_combo {
+            Some(max_combo) => self.perfect == self.is_full_combo(max_combo),
+            None => !self.perfect || self.is_full_combo(0),
+        }
+    }
+
+    /// Like [`Self::is_full_combo`], additionally checking the frames of osu!standard replays
+    /// for slider breaks, see [`slider_breaks`].
+    ///
+    /// This catches slider breaks in replays whose greatest combo cannot be trusted, e.g. edited
+    /// or converted replays.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::beatmap::Beatmap;
+    /// use osu_replay_parser::{Replay, ReplayData};
+    ///
+    /// // A straight slider lasting two beats with a tick in the middle
+    /// let beatmap = Beatmap::parse(
+    ///     "[Difficulty]\nCircleSize:4\nSliderMultiplier:1\nSliderTickRate:1\n\
+    ///      [TimingPoints]\n0,500,4,2,0,100,1,0\n\
+    ///      [HitObjects]\n100,100,1000,2,0,L|300:100,1,200\n",
+    /// )
+    /// .unwrap();
+    /// let replay = Replay { n300: 1, greatest_combo: 3, ..Replay::default() };
+    ///
+    /// // The key is released before the tick
+    /// let frames = vec![
+    ///     ReplayData { time: 1000, x: 100.0, y: 100.0, keys: 1 },
+    ///     ReplayData { time: 100, x: 120.0, y: 100.0, keys: 0 },
+    /// ];
+    /// assert!(replay.is_full_combo(3));
+    /// assert!(!replay.is_full_combo_with_frames(3, &frames, &beatmap));
+    /// ```
+    pub fn is_full_combo_with_frames(
+        &self,
+        beatmap_max_combo: u16,
+        frames: &[ReplayData],
+        beatmap: &Beatmap,
+    ) -> bool {
+        self.is_full_combo(beatmap_max_combo)
+            && (self.game_mode != GameMode::Osu
+                || slider_breaks(frames, beatmap, self.mods()).is_empty())
+    }
+}
@@ -0,0 +1,165 @@
+use serde_json::json;
+
+use crate::analysis::tapping_report;
+use crate::format::FormatOptions;
+use crate::frames::FramesExt;
+use crate::replay::{GameMode, Mods, Replay, ReplayData};
+
+/// Owned summary of a replay and its frames, cheap to clone and store in application state.
+///
+/// The summary holds no references to the parsed input, so GUI applications can keep it around
+/// without keeping the replay file or the decoded frames in memory.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReplaySummary {
+    /// The game mode of the replay.
+    pub game_mode: GameMode,
+    /// The name of the player.
+    pub player_name: String,
+    /// The MD5 hash of the beatmap.
+    pub beatmap_md5: String,
+    /// The MD5 hash of the replay.
+    pub replay_md5: String,
+    /// The mods used in the replay.
+    pub mods: Mods,
+    /// Accuracy in the range `0.0..=1.0`.
+    pub accuracy: f64,
+    /// Total score displayed on the score report.
+    pub total_score: u32,
+    /// Greatest combo displayed on the score report.
+    pub greatest_combo: u16,
+    /// Number of misses.
+    pub n_miss: u16,
+    /// Time of the replay (Windows ticks).
+    pub time_stamp: i64,
+    /// Online score ID.
+    pub online_score_id: i64,
+    /// Frame statistics, only present if the summary was created with frames.
+    pub frames: Option<FramesSummary>,
+}
+
+/// Owned summary of the decoded frames of a replay.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FramesSummary {
+    /// Number of frames, excluding the seed frame.
+    pub frame_count: usize,
+    /// Time of the last frame in milliseconds.
+    pub end_time: i64,
+    /// Number of key presses.
+    pub key_presses: usize,
+    /// Fraction of consecutive taps that switch between keys.
+    pub alternation: f64,
+}
+
+impl From<&Replay> for ReplaySummary {
+    fn from(replay: &Replay) -> Self {
+        ReplaySummary {
+            game_mode: replay.game_mode,
+            player_name: replay.player_name.clone(),
+            beatmap_md5: replay.beatmap_md5.clone(),
+            replay_md5: replay.replay_md5.clone(),
+            mods: replay.mods(),
+            accuracy: replay.accuracy(),
+            total_score: replay.total_score,
+            greatest_combo: replay.greatest_combo,
+            n_miss: replay.n_miss,
+            time_stamp: replay.time_stamp,
+            online_score_id: replay.online_score_id,
+            frames: None,
+        }
+    }
+}
+
+impl From<&[ReplayData]> for FramesSummary {
+    fn from(frames: &[ReplayData]) -> Self {
+        let tapping = tapping_report(frames);
+
+        FramesSummary {
+            frame_count: frames.timed().count(),
+            end_time: frames.timed().last().map_or(0, |(time, _)| time),
+            key_presses: tapping.keys.iter().map(|key| key.presses).sum(),
+            alternation: tapping.alternation,
+        }
+    }
+}
+
+impl ReplaySummary {
+    /// Create a summary of a replay including statistics of its decoded frames.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::summary::ReplaySummary;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let mut replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let actions = replay.take_actions().expect("Error getting actions");
+    /// let summary = ReplaySummary::with_frames(&replay, &actions);
+    /// assert!(summary.frames.is_some());
+    /// ```
+    pub fn with_frames(replay: &Replay, frames: &[ReplayData]) -> Self {
+        ReplaySummary {
+            frames: Some(FramesSummary::from(frames)),
+            ..ReplaySummary::from(replay)
+        }
+    }
+
+    /// Serialize the summary as a JSON object.
+    pub fn to_json(&self) -> String {
+        let mut entries = json!({
+            "gameMode": u8::from(self.game_mode),
+            "playerName": self.player_name,
+            "beatmapMd5": self.beatmap_md5,
+            "replayMd5": self.replay_md5,
+            "mods": self.mods.bits(),
+            "accuracy": self.accuracy,
+            "totalScore": self.total_score,
+            "greatestCombo": self.greatest_combo,
+            "misses": self.n_miss,
+            "timeStamp": self.time_stamp,
+            "onlineScoreId": self.online_score_id,
+        });
+        if let Some(frames) = &self.frames {
+            entries["frames"] = json!({
+                "frameCount": frames.frame_count,
+                "endTime": frames.end_time,
+                "keyPresses": frames.key_presses,
+                "alternation": frames.alternation,
+            });
+        }
+
+        entries.to_string()
+    }
+
+    /// The displayed fields of the summary as label and value pairs, formatted with the options,
+    /// e.g. for the fields of a chat bot embed.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::format::FormatOptions;
+    /// use osu_replay_parser::summary::ReplaySummary;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let summary = ReplaySummary::from(&replay);
+    /// let fields = summary.formatted(&FormatOptions::for_locale("de").unwrap());
+    /// assert_eq!(fields[0], ("Player", replay.player_name.clone()));
+    /// assert!(fields[1].1.contains('.'));
+    /// ```
+    pub fn formatted(&self, options: &FormatOptions) -> Vec<(&'static str, String)> {
+        let mods = if self.mods.is_empty() {
+            "NM".to_string()
+        } else {
+            self.mods.acronyms()
+        };
+
+        vec![
+            ("Player", self.player_name.clone()),
+            ("Score", options.format_integer(self.total_score.into())),
+            ("Accuracy", options.format_accuracy(self.accuracy)),
+            (
+                "Combo",
+                options.format_integer(self.greatest_combo.into()) + "x",
+            ),
+            ("Misses", options.format_integer(self.n_miss.into())),
+            ("Mods", mods),
+            ("Date", options.format_timestamp(self.time_stamp)),
+        ]
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::analysis::tapping_report;
+use crate::batch::replay_files;
+use crate::frames::FramesExt;
+#[cfg(feature = "md5")]
+use crate::hash::BuiltinMd5;
+use crate::hash::Hasher;
+use crate::ids::is_md5_hex;
+use crate::replay::Replay;
+
+/// Environment variable pointing to a local directory of replays used by [`Corpus::from_env`].
+pub const TEST_DATA_DIR_VAR: &str = "OSU_REPLAY_TEST_DATA";
+
+/// Manifest of the replays in the `assets` folder of the repository, see [`Fixture::parse_manifest`].
+pub const FIXTURE_MANIFEST: &str = include_str!("../assets/fixtures.txt");
+
+/// A replay of a test corpus that can be downloaded, see [`Corpus::fetch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// File name the replay is stored as in the corpus directory.
+    pub name: String,
+    /// URL the replay is downloaded from.
+    pub url: String,
+    /// Expected MD5 hash of the file as lowercase hex string, `None` to skip verification.
+    pub md5: Option<String>,
+}
+
+impl Fixture {
+    /// Parse a manifest with one fixture per line as name, URL and MD5 hash separated by
+    /// whitespace, like [`FIXTURE_MANIFEST`]. A hash of `-` skips verification, empty lines and
+    /// lines starting with `#` are ignored.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::test_data::{Corpus, Fixture, FIXTURE_MANIFEST};
+    ///
+    /// let fixtures = Fixture::parse_manifest(FIXTURE_MANIFEST).unwrap();
+    /// assert_eq!(fixtures[0].name, "replay.osr");
+    ///
+    /// // Serve the downloads from the local assets instead of the network
+    /// let dir = std::env::temp_dir().join("osu_replay_parser_fixtures_example");
+    /// let fetch = |url: &str| std::fs::read(format!("assets/{}", url.rsplit('/').next().unwrap()));
+    /// let corpus = Corpus::fetch(&dir, &fixtures, &fetch).expect("Error fetching corpus");
+    /// assert_eq!(corpus.paths().len(), fixtures.len());
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    /// # Errors
+    /// Returns an `InvalidData` error naming the line if a line does not have three fields or
+    /// the hash is not an MD5 hash.
+    pub fn parse_manifest(manifest: &str) -> io::Result<Vec<Fixture>> {
+        let mut fixtures = Vec::new();
+        for (i, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} of the fixture manifest: {}", i + 1, message),
+                )
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [name, url, md5] = fields[..] else {
+                return Err(invalid("expected name, URL and MD5 hash"));
+            };
+            let md5 = match md5 {
+                "-" => None,
+                md5 if is_md5_hex(md5) => Some(md5.to_ascii_lowercase()),
+                _ => return Err(invalid("invalid MD5 hash")),
+            };
+            fixtures.push(Fixture {
+                name: name.to_string(),
+                url: url.to_string(),
+                md5,
+            });
+        }
+
+        Ok(fixtures)
+    }
+}
+
+/// Downloads fixtures for [`Corpus::fetch`].
+///
+/// The crate does not include an HTTP client, so downloading is left to the implementor, e.g.
+/// with the HTTP client the downstream project already uses. Implemented for closures taking
+/// the URL and returning the downloaded bytes.
+pub trait Fetch {
+    /// Download the data at the URL.
+    /// # Errors
+    /// Returns an error if the download fails.
+    fn fetch(&self, url: &str) -> io::Result<Vec<u8>>;
+}
+
+impl<F> Fetch for F
+where
+    F: Fn(&str) -> io::Result<Vec<u8>>,
+{
+    fn fetch(&self, url: &str) -> io::Result<Vec<u8>> {
+        self(url)
+    }
+}
+
+/// A check of the conformance suite run by [`Corpus::run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// The file parses as a replay.
+    Parse,
+    /// Writing the parsed replay reproduces the file byte for byte.
+    RoundTrip,
+    /// The compressed frames decode.
+    Frames,
+    /// The analyses run on the decoded frames.
+    Analyze,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Check::Parse => "parse",
+            Check::RoundTrip => "round-trip",
+            Check::Frames => "frames",
+            Check::Analyze => "analyze",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A failed check of a corpus file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Path of the replay.
+    pub path: PathBuf,
+    /// The check that failed.
+    pub check: Check,
+    /// Description of the failure.
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} failed: {}",
+            self.path.display(),
+            self.check,
+            self.message
+        )
+    }
+}
+
+/// Result of running the conformance suite over a corpus.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConformanceReport {
+    /// Number of replays checked.
+    pub files: usize,
+    /// Number of checks that passed.
+    pub passed: usize,
+    /// The checks that failed.
+    pub failures: Vec<Failure>,
+}
+
+impl ConformanceReport {
+    /// Whether all checks passed.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A directory of real replays across game modes and versions, used to validate changes to the
+/// parser against the conformance suite.
+/// # Example
+/// ```
+/// use osu_replay_parser::test_data::Corpus;
+///
+/// let corpus = Corpus::from_dir("assets").expect("Error reading corpus");
+/// let report = corpus.run();
+/// assert_eq!(report.files, 1);
+/// assert!(report.is_success(), "{:?}", report.failures);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corpus {
+    paths: Vec<PathBuf>,
+}
+
+impl Corpus {
+    /// Use all `.osr` files in a local directory.
+    /// # Errors
+    /// Returns an error if the directory cannot be read.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Ok(Corpus {
+            paths: replay_files(dir.as_ref())?,
+        })
+    }
+
+    /// Use the directory named by the [`TEST_DATA_DIR_VAR`] environment variable, `None` if it is not set.
+    /// # Errors
+    /// Returns an error if the directory cannot be read.
+    pub fn from_env() -> io::Result<Option<Self>> {
+        match std::env::var_os(TEST_DATA_DIR_VAR) {
+            Some(dir) => Corpus::from_dir(dir).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Download the fixtures missing from `dir` with `fetcher` and use all replays in `dir`.
+    ///
+    /// Fixtures already present are not downloaded again, so `dir` acts as a cache.
+    /// # Errors
+    /// Returns an error if a download fails, a downloaded file does not match its MD5 hash or
+    /// the directory cannot be written.
+    #[cfg(feature = "md5")]
+    pub fn fetch<P: AsRef<Path>, F: Fetch>(
+        dir: P,
+        fixtures: &[Fixture],
+        fetcher: &F,
+    ) -> io::Result<Self> {
+        Corpus::fetch_with_hasher(dir, fixtures, fetcher, &BuiltinMd5)
+    }
+
+    /// Like [`Self::fetch`], verifying the downloads with another MD5 implementation.
+    /// # Errors
+    /// Returns an error if a download fails, a downloaded file does not match its MD5 hash or
+    /// the directory cannot be written.
+    pub fn fetch_with_hasher<P: AsRef<Path>, F: Fetch>(
+        dir: P,
+        fixtures: &[Fixture],
+        fetcher: &F,
+        hasher: &dyn Hasher,
+    ) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for fixture in fixtures {
+            let path = dir.join(&fixture.name);
+            if path.is_file() {
+                continue;
+            }
+            let data = fetcher.fetch(&fixture.url)?;
+            if let Some(md5) = &fixture.md5 {
+                if !hasher.md5_hex(&data).eq_ignore_ascii_case(md5) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("MD5 hash of {} does not match", fixture.name),
+                    ));
+                }
+            }
+            fs::write(path, data)?;
+        }
+
+        Corpus::from_dir(dir)
+    }
+
+    /// Paths of the replays in the corpus, sorted.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Run the conformance suite over all replays of the corpus.
+    ///
+    /// Each replay is parsed, written back and compared to the file, its frames are decoded and
+    /// analyzed. Checks depending on a failed check are skipped.
+    pub fn run(&self) -> ConformanceReport {
+        let mut report = ConformanceReport::default();
+        for path in &self.paths {
+            report.files += 1;
+            let mut fail = |check: Check, message: String| {
+                report.failures.push(Failure {
+                    path: path.clone(),
+                    check,
+                    message,
+                });
+            };
+            let input = match fs::read(path) {
+                Ok(input) => input,
+                Err(e) => {
+                    fail(Check::Parse, e.to_string());
+                    continue;
+                }
+            };
+            let replay = match Replay::parse(&input) {
+                Ok(replay) => replay,
+                Err(e) => {
+                    fail(Check::Parse, format!("{:?}", e));
+                    continue;
+                }
+            };
+            let mut passed = 1;
+
+            match replay.to_bytes() {
+                Ok(bytes) if bytes == input => passed += 1,
+                Ok(_) => fail(
+                    Check::RoundTrip,
+                    "written replay differs from the file".to_string(),
+                ),
+                Err(e) => fail(Check::RoundTrip, e.to_string()),
+            }
+
+            match replay.get_actions_with_progress(|_, _| {}) {
+                Ok(frames) => {
+                    passed += 1;
+                    let tapping = tapping_report(&frames);
+                    let accuracy = replay.accuracy();
+                    let duration = frames.duration();
+                    if (0.0..=1.0).contains(&accuracy)
+                        && (0.0..=1.0).contains(&tapping.alternation)
+                        && duration >= 0
+                    {
+                        passed += 1;
+                    } else {
+                        fail(
+                            Check::Analyze,
+                            format!(
+                                "implausible results: accuracy {}, alternation {}, duration {}ms",
+                                accuracy, tapping.alternation, duration
+                            ),
+                        );
+                    }
+                }
+                Err(e) => fail(Check::Frames, format!("{:?}", e)),
+            }
+            report.passed += passed;
+        }
+
+        report
+    }
+}
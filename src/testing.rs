@@ -0,0 +1,341 @@
+use crate::frames::{from_timed, Keys, SEED_FRAME_TIME};
+use crate::game_math::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+#[cfg(feature = "md5")]
+use crate::hash::BuiltinMd5;
+use crate::hash::Hasher;
+use crate::replay::{GameMode, Mods, Replay, ReplayData};
+use crate::writer::compress_frames;
+
+/// Version written into generated replays.
+pub const GENERATED_VERSION: u32 = 20240101;
+
+/// Mod combinations picked from by [`ModSelection::Random`].
+pub const COMMON_MOD_COMBOS: [Mods; 8] = [
+    Mods::NONE,
+    Mods::HIDDEN,
+    Mods::HARD_ROCK,
+    Mods::DOUBLE_TIME,
+    Mods::HIDDEN.union(Mods::HARD_ROCK),
+    Mods::HIDDEN.union(Mods::DOUBLE_TIME),
+    Mods::HIDDEN.union(Mods::DOUBLE_TIME).union(Mods::HARD_ROCK),
+    Mods::EASY.union(Mods::HALF_TIME),
+];
+
+/// Noise added to the cursor position of generated osu!standard frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorNoise {
+    /// The cursor follows its path exactly.
+    None,
+    /// Independent normally distributed offsets, similar to the jitter of tablets.
+    Gaussian {
+        /// Standard deviation in osu!pixels.
+        std_dev: f32,
+    },
+    /// Slow sinusoidal shaking of the hand.
+    Tremor {
+        /// Maximum offset in osu!pixels.
+        amplitude: f32,
+        /// Frequency in Hz.
+        frequency: f32,
+    },
+}
+
+/// How generated osu!standard replays tap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TappingPattern {
+    /// Alternate between both keys.
+    Alternate,
+    /// Tap with the first key only.
+    SingleTap,
+    /// Alternated bursts of 1/4 notes at the given BPM, with pauses in between.
+    Streams {
+        /// Beats per minute of the streams.
+        bpm: f64,
+    },
+}
+
+/// The mods of generated replays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModSelection {
+    /// Use the given mods.
+    Fixed(Mods),
+    /// Pick one of [`COMMON_MOD_COMBOS`].
+    Random,
+}
+
+/// Options for [`generate_replay`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorOptions {
+    /// Game mode of the replay.
+    pub game_mode: GameMode,
+    /// Length of the replay in milliseconds.
+    pub duration: i64,
+    /// Time between frames in milliseconds.
+    pub frame_interval: i64,
+    /// Number of columns for osu!mania replays.
+    pub key_count: u32,
+    /// Noise added to the cursor position.
+    pub cursor_noise: CursorNoise,
+    /// How the keys are tapped.
+    pub tapping: TappingPattern,
+    /// The mods of the replay.
+    pub mods: ModSelection,
+    /// Seed of the random number generator, the same options always generate the same replay.
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            game_mode: GameMode::Osu,
+            duration: 60_000,
+            frame_interval: 16,
+            key_count: 4,
+            cursor_noise: CursorNoise::Gaussian { std_dev: 1.0 },
+            tapping: TappingPattern::Alternate,
+            mods: ModSelection::Fixed(Mods::NONE),
+            seed: 0,
+        }
+    }
+}
+
+/// Small xorshift random number generator, good enough for generating test data.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // The state must not be zero
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform random number in `0.0..1.0`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normally distributed random number.
+    pub(crate) fn next_gaussian(&mut self) -> f64 {
+        // Box-Muller transform, 1 - u avoids ln(0)
+        let (u, v) = (1.0 - self.next_f64(), self.next_f64());
+        (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+}
+
+/// Times of the taps in milliseconds.
+fn tap_times(options: &GeneratorOptions, rng: &mut Rng) -> Vec<i64> {
+    let mut taps = Vec::new();
+    let mut time = 500 + (rng.next_f64() * 500.0) as i64;
+    while time < options.duration {
+        taps.push(time);
+        time += match options.tapping {
+            TappingPattern::Streams { bpm } if taps.len() % 16 != 0 => {
+                (60_000.0 / bpm / 4.0 + rng.next_gaussian() * 3.0).max(1.0) as i64
+            }
+            _ => 150 + (rng.next_f64() * 350.0) as i64,
+        };
+    }
+    taps
+}
+
+fn noise(options: &GeneratorOptions, time: i64, rng: &mut Rng) -> (f32, f32) {
+    match options.cursor_noise {
+        CursorNoise::None => (0.0, 0.0),
+        CursorNoise::Gaussian { std_dev } => (
+            rng.next_gaussian() as f32 * std_dev,
+            rng.next_gaussian() as f32 * std_dev,
+        ),
+        CursorNoise::Tremor {
+            amplitude,
+            frequency,
+        } => {
+            let phase = std::f32::consts::TAU * frequency * time as f32 / 1000.0;
+            (amplitude * phase.sin(), amplitude * (phase * 1.3).cos())
+        }
+    }
+}
+
+fn osu_frames(options: &GeneratorOptions, rng: &mut Rng) -> Vec<ReplayData> {
+    let taps = tap_times(options, rng);
+    // Each tap hits a random object, the cursor eases from one object to the next
+    let targets: Vec<(f32, f32)> = taps
+        .iter()
+        .map(|_| {
+            (
+                32.0 + rng.next_f64() as f32 * (PLAYFIELD_WIDTH - 64.0),
+                32.0 + rng.next_f64() as f32 * (PLAYFIELD_HEIGHT - 64.0),
+            )
+        })
+        .collect();
+    let center = (PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+    let mut next = 0;
+    let mut release = i64::MIN;
+    let mut key = Keys::K2;
+
+    (0..=options.duration / options.frame_interval)
+        .map(|i| {
+            let time = i * options.frame_interval;
+            while next < taps.len() && taps[next] <= time {
+                key = match options.tapping {
+                    TappingPattern::SingleTap => Keys::K1,
+                    _ if key == Keys::K1 => Keys::K2,
+                    _ => Keys::K1,
+                };
+                release = taps[next] + 40 + (rng.next_f64() * 40.0) as i64;
+                next += 1;
+            }
+
+            let (from, from_time) = match next {
+                0 => (center, 0),
+                n => (targets[n - 1], taps[n - 1]),
+            };
+            let (to, to_time) = match taps.get(next) {
+                Some(&tap) => (targets[next], tap),
+                None => (from, from_time + 1),
+            };
+            let t = ((time - from_time) as f32 / (to_time - from_time) as f32).clamp(0.0, 1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            let (dx, dy) = noise(options, time, rng);
+
+            let keys = if time < release {
+                key | key_mouse_button(key)
+            } else {
+                Keys::empty()
+            };
+            ReplayData {
+                time,
+                x: (from.0 + (to.0 - from.0) * eased + dx).clamp(0.0, PLAYFIELD_WIDTH),
+                y: (from.1 + (to.1 - from.1) * eased + dy).clamp(0.0, PLAYFIELD_HEIGHT),
+                keys: keys.bits(),
+            }
+        })
+        .collect()
+}
+
+/// Keyboard keys also set the bit of the corresponding mouse button.
+fn key_mouse_button(key: Keys) -> Keys {
+    if key == Keys::K1 {
+        Keys::M1
+    } else {
+        Keys::M2
+    }
+}
+
+fn mania_frames(options: &GeneratorOptions, rng: &mut Rng) -> Vec<ReplayData> {
+    let key_count = options.key_count.clamp(1, 18);
+    let mut releases = vec![0; key_count as usize];
+
+    (0..=options.duration / options.frame_interval)
+        .map(|i| {
+            let time = i * options.frame_interval;
+            let mut held = 0u32;
+            for (column, release) in releases.iter_mut().enumerate() {
+                if time >= *release + 100 && rng.next_f64() < 0.05 {
+                    *release = time + 30 + (rng.next_f64() * 300.0) as i64;
+                }
+                if time < *release {
+                    held |= 1 << column;
+                }
+            }
+            // osu!mania stores the held columns in the x coordinate
+            ReplayData {
+                time,
+                x: held as f32,
+                y: 0.0,
+                keys: 0,
+            }
+        })
+        .collect()
+}
+
+/// Number of presses in the frames, used as the number of judged objects.
+fn press_count(frames: &[ReplayData]) -> u32 {
+    let state = |frame: &ReplayData| frame.keys | frame.x as u32;
+    frames
+        .windows(2)
+        .map(|pair| (state(&pair[1]) & !state(&pair[0])).count_ones())
+        .sum()
+}
+
+/// Generate a random replay with plausible frames, for benchmarks, tests and data augmentation.
+///
+/// osu!standard replays move the cursor from object to object, hitting each with a tap of the
+/// configured [`TappingPattern`], with [`CursorNoise`] on top. osu!mania replays press random
+/// columns. Other game modes use the osu!standard frames. Hit counts are distributed randomly
+/// over the presses, mostly as 300s.
+/// # Example
+/// ```
+/// use osu_replay_parser::testing::{generate_replay, CursorNoise, GeneratorOptions, ModSelection};
+///
+/// let replay = generate_replay(&GeneratorOptions {
+///     cursor_noise: CursorNoise::Tremor { amplitude: 3.0, frequency: 8.0 },
+///     mods: ModSelection::Random,
+///     seed: 42,
+///     ..GeneratorOptions::default()
+/// });
+/// assert!(replay.n300 > 0);
+/// let actions = replay.get_actions().expect("Error getting actions");
+/// assert_eq!(actions.len(), 60_000 / 16 + 2);
+/// ```
+#[cfg(feature = "md5")]
+pub fn generate_replay(options: &GeneratorOptions) -> Replay {
+    generate_replay_with_hasher(options, &BuiltinMd5)
+}
+
+/// Like [`generate_replay`], computing the beatmap and replay hashes with another MD5
+/// implementation.
+pub fn generate_replay_with_hasher(options: &GeneratorOptions, hasher: &dyn Hasher) -> Replay {
+    let mut rng = Rng::new(options.seed);
+    let mods = match options.mods {
+        ModSelection::Fixed(mods) => mods,
+        ModSelection::Random => {
+            COMMON_MOD_COMBOS[(rng.next_u64() % COMMON_MOD_COMBOS.len() as u64) as usize]
+        }
+    };
+    let frames = match options.game_mode {
+        GameMode::Mania => mania_frames(options, &mut rng),
+        _ => osu_frames(options, &mut rng),
+    };
+
+    let mut counts = [0u16; 4];
+    for _ in 0..press_count(&frames) {
+        let roll = rng.next_f64();
+        let judgement = [0.9, 0.97, 0.99].iter().filter(|&&p| roll >= p).count();
+        counts[judgement] = counts[judgement].saturating_add(1);
+    }
+    let [n300, n100, n50, n_miss] = counts;
+    let total_score = 300 * u32::from(n300) + 100 * u32::from(n100) + 50 * u32::from(n50);
+
+    let seed_frame = ReplayData {
+        time: SEED_FRAME_TIME,
+        x: 0.0,
+        y: 0.0,
+        keys: (rng.next_u64() & 0xffff) as u32,
+    };
+    let frames = from_timed(frames, Some(&seed_frame));
+    let compressed_data = compress_frames(&frames);
+
+    Replay {
+        game_mode: options.game_mode,
+        version: GENERATED_VERSION,
+        beatmap_md5: hasher.md5_hex(&options.seed.to_le_bytes()),
+        player_name: "Generated".to_string(),
+        replay_md5: hasher.md5_hex(&compressed_data),
+        n300,
+        n100,
+        n50,
+        n_miss,
+        total_score,
+        perfect: n_miss == 0,
+        mods: mods.bits(),
+        compressed_data,
+        ..Replay::default()
+    }
+}
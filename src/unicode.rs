@@ -0,0 +1,126 @@
+/// Canonical compositions of a base character and a combining mark, sorted by base and mark.
+///
+/// Covers the precomposed Latin characters up to Latin Extended-B and the voiced kana.
+#[cfg(not(feature = "nfc"))]
+#[rustfmt::skip]
+const COMPOSITIONS: [(char, char, char); 310] = [
+    ('\u{41}', '\u{300}', '\u{c0}'), ('\u{41}', '\u{301}', '\u{c1}'), ('\u{41}', '\u{302}', '\u{c2}'), ('\u{41}', '\u{303}', '\u{c3}'),
+    ('\u{41}', '\u{304}', '\u{100}'), ('\u{41}', '\u{306}', '\u{102}'), ('\u{41}', '\u{307}', '\u{226}'), ('\u{41}', '\u{308}', '\u{c4}'),
+    ('\u{41}', '\u{30a}', '\u{c5}'), ('\u{41}', '\u{30c}', '\u{1cd}'), ('\u{41}', '\u{30f}', '\u{200}'), ('\u{41}', '\u{311}', '\u{202}'),
+    ('\u{41}', '\u{328}', '\u{104}'), ('\u{43}', '\u{301}', '\u{106}'), ('\u{43}', '\u{302}', '\u{108}'), ('\u{43}', '\u{307}', '\u{10a}'),
+    ('\u{43}', '\u{30c}', '\u{10c}'), ('\u{43}', '\u{327}', '\u{c7}'), ('\u{44}', '\u{30c}', '\u{10e}'), ('\u{45}', '\u{300}', '\u{c8}'),
+    ('\u{45}', '\u{301}', '\u{c9}'), ('\u{45}', '\u{302}', '\u{ca}'), ('\u{45}', '\u{304}', '\u{112}'), ('\u{45}', '\u{306}', '\u{114}'),
+    ('\u{45}', '\u{307}', '\u{116}'), ('\u{45}', '\u{308}', '\u{cb}'), ('\u{45}', '\u{30c}', '\u{11a}'), ('\u{45}', '\u{30f}', '\u{204}'),
+    ('\u{45}', '\u{311}', '\u{206}'), ('\u{45}', '\u{327}', '\u{228}'), ('\u{45}', '\u{328}', '\u{118}'), ('\u{47}', '\u{301}', '\u{1f4}'),
+    ('\u{47}', '\u{302}', '\u{11c}'), ('\u{47}', '\u{306}', '\u{11e}'), ('\u{47}', '\u{307}', '\u{120}'), ('\u{47}', '\u{30c}', '\u{1e6}'),
+    ('\u{47}', '\u{327}', '\u{122}'), ('\u{48}', '\u{302}', '\u{124}'), ('\u{48}', '\u{30c}', '\u{21e}'), ('\u{49}', '\u{300}', '\u{cc}'),
+    ('\u{49}', '\u{301}', '\u{cd}'), ('\u{49}', '\u{302}', '\u{ce}'), ('\u{49}', '\u{303}', '\u{128}'), ('\u{49}', '\u{304}', '\u{12a}'),
+    ('\u{49}', '\u{306}', '\u{12c}'), ('\u{49}', '\u{307}', '\u{130}'), ('\u{49}', '\u{308}', '\u{cf}'), ('\u{49}', '\u{30c}', '\u{1cf}'),
+    ('\u{49}', '\u{30f}', '\u{208}'), ('\u{49}', '\u{311}', '\u{20a}'), ('\u{49}', '\u{328}', '\u{12e}'), ('\u{4a}', '\u{302}', '\u{134}'),
+    ('\u{4b}', '\u{30c}', '\u{1e8}'), ('\u{4b}', '\u{327}', '\u{136}'), ('\u{4c}', '\u{301}', '\u{139}'), ('\u{4c}', '\u{30c}', '\u{13d}'),
+    ('\u{4c}', '\u{327}', '\u{13b}'), ('\u{4e}', '\u{300}', '\u{1f8}'), ('\u{4e}', '\u{301}', '\u{143}'), ('\u{4e}', '\u{303}', '\u{d1}'),
+    ('\u{4e}', '\u{30c}', '\u{147}'), ('\u{4e}', '\u{327}', '\u{145}'), ('\u{4f}', '\u{300}', '\u{d2}'), ('\u{4f}', '\u{301}', '\u{d3}'),
+    ('\u{4f}', '\u{302}', '\u{d4}'), ('\u{4f}', '\u{303}', '\u{d5}'), ('\u{4f}', '\u{304}', '\u{14c}'), ('\u{4f}', '\u{306}', '\u{14e}'),
+    ('\u{4f}', '\u{307}', '\u{22e}'), ('\u{4f}', '\u{308}', '\u{d6}'), ('\u{4f}', '\u{30b}', '\u{150}'), ('\u{4f}', '\u{30c}', '\u{1d1}'),
+    ('\u{4f}', '\u{30f}', '\u{20c}'), ('\u{4f}', '\u{311}', '\u{20e}'), ('\u{4f}', '\u{31b}', '\u{1a0}'), ('\u{4f}', '\u{328}', '\u{1ea}'),
+    ('\u{52}', '\u{301}', '\u{154}'), ('\u{52}', '\u{30c}', '\u{158}'), ('\u{52}', '\u{30f}', '\u{210}'), ('\u{52}', '\u{311}', '\u{212}'),
+    ('\u{52}', '\u{327}', '\u{156}'), ('\u{53}', '\u{301}', '\u{15a}'), ('\u{53}', '\u{302}', '\u{15c}'), ('\u{53}', '\u{30c}', '\u{160}'),
+    ('\u{53}', '\u{326}', '\u{218}'), ('\u{53}', '\u{327}', '\u{15e}'), ('\u{54}', '\u{30c}', '\u{164}'), ('\u{54}', '\u{326}', '\u{21a}'),
+    ('\u{54}', '\u{327}', '\u{162}'), ('\u{55}', '\u{300}', '\u{d9}'), ('\u{55}', '\u{301}', '\u{da}'), ('\u{55}', '\u{302}', '\u{db}'),
+    ('\u{55}', '\u{303}', '\u{168}'), ('\u{55}', '\u{304}', '\u{16a}'), ('\u{55}', '\u{306}', '\u{16c}'), ('\u{55}', '\u{308}', '\u{dc}'),
+    ('\u{55}', '\u{30a}', '\u{16e}'), ('\u{55}', '\u{30b}', '\u{170}'), ('\u{55}', '\u{30c}', '\u{1d3}'), ('\u{55}', '\u{30f}', '\u{214}'),
+    ('\u{55}', '\u{311}', '\u{216}'), ('\u{55}', '\u{31b}', '\u{1af}'), ('\u{55}', '\u{328}', '\u{172}'), ('\u{57}', '\u{302}', '\u{174}'),
+    ('\u{59}', '\u{301}', '\u{dd}'), ('\u{59}', '\u{302}', '\u{176}'), ('\u{59}', '\u{304}', '\u{232}'), ('\u{59}', '\u{308}', '\u{178}'),
+    ('\u{5a}', '\u{301}', '\u{179}'), ('\u{5a}', '\u{307}', '\u{17b}'), ('\u{5a}', '\u{30c}', '\u{17d}'), ('\u{61}', '\u{300}', '\u{e0}'),
+    ('\u{61}', '\u{301}', '\u{e1}'), ('\u{61}', '\u{302}', '\u{e2}'), ('\u{61}', '\u{303}', '\u{e3}'), ('\u{61}', '\u{304}', '\u{101}'),
+    ('\u{61}', '\u{306}', '\u{103}'), ('\u{61}', '\u{307}', '\u{227}'), ('\u{61}', '\u{308}', '\u{e4}'), ('\u{61}', '\u{30a}', '\u{e5}'),
+    ('\u{61}', '\u{30c}', '\u{1ce}'), ('\u{61}', '\u{30f}', '\u{201}'), ('\u{61}', '\u{311}', '\u{203}'), ('\u{61}', '\u{328}', '\u{105}'),
+    ('\u{63}', '\u{301}', '\u{107}'), ('\u{63}', '\u{302}', '\u{109}'), ('\u{63}', '\u{307}', '\u{10b}'), ('\u{63}', '\u{30c}', '\u{10d}'),
+    ('\u{63}', '\u{327}', '\u{e7}'), ('\u{64}', '\u{30c}', '\u{10f}'), ('\u{65}', '\u{300}', '\u{e8}'), ('\u{65}', '\u{301}', '\u{e9}'),
+    ('\u{65}', '\u{302}', '\u{ea}'), ('\u{65}', '\u{304}', '\u{113}'), ('\u{65}', '\u{306}', '\u{115}'), ('\u{65}', '\u{307}', '\u{117}'),
+    ('\u{65}', '\u{308}', '\u{eb}'), ('\u{65}', '\u{30c}', '\u{11b}'), ('\u{65}', '\u{30f}', '\u{205}'), ('\u{65}', '\u{311}', '\u{207}'),
+    ('\u{65}', '\u{327}', '\u{229}'), ('\u{65}', '\u{328}', '\u{119}'), ('\u{67}', '\u{301}', '\u{1f5}'), ('\u{67}', '\u{302}', '\u{11d}'),
+    ('\u{67}', '\u{306}', '\u{11f}'), ('\u{67}', '\u{307}', '\u{121}'), ('\u{67}', '\u{30c}', '\u{1e7}'), ('\u{67}', '\u{327}', '\u{123}'),
+    ('\u{68}', '\u{302}', '\u{125}'), ('\u{68}', '\u{30c}', '\u{21f}'), ('\u{69}', '\u{300}', '\u{ec}'), ('\u{69}', '\u{301}', '\u{ed}'),
+    ('\u{69}', '\u{302}', '\u{ee}'), ('\u{69}', '\u{303}', '\u{129}'), ('\u{69}', '\u{304}', '\u{12b}'), ('\u{69}', '\u{306}', '\u{12d}'),
+    ('\u{69}', '\u{308}', '\u{ef}'), ('\u{69}', '\u{30c}', '\u{1d0}'), ('\u{69}', '\u{30f}', '\u{209}'), ('\u{69}', '\u{311}', '\u{20b}'),
+    ('\u{69}', '\u{328}', '\u{12f}'), ('\u{6a}', '\u{302}', '\u{135}'), ('\u{6a}', '\u{30c}', '\u{1f0}'), ('\u{6b}', '\u{30c}', '\u{1e9}'),
+    ('\u{6b}', '\u{327}', '\u{137}'), ('\u{6c}', '\u{301}', '\u{13a}'), ('\u{6c}', '\u{30c}', '\u{13e}'), ('\u{6c}', '\u{327}', '\u{13c}'),
+    ('\u{6e}', '\u{300}', '\u{1f9}'), ('\u{6e}', '\u{301}', '\u{144}'), ('\u{6e}', '\u{303}', '\u{f1}'), ('\u{6e}', '\u{30c}', '\u{148}'),
+    ('\u{6e}', '\u{327}', '\u{146}'), ('\u{6f}', '\u{300}', '\u{f2}'), ('\u{6f}', '\u{301}', '\u{f3}'), ('\u{6f}', '\u{302}', '\u{f4}'),
+    ('\u{6f}', '\u{303}', '\u{f5}'), ('\u{6f}', '\u{304}', '\u{14d}'), ('\u{6f}', '\u{306}', '\u{14f}'), ('\u{6f}', '\u{307}', '\u{22f}'),
+    ('\u{6f}', '\u{308}', '\u{f6}'), ('\u{6f}', '\u{30b}', '\u{151}'), ('\u{6f}', '\u{30c}', '\u{1d2}'), ('\u{6f}', '\u{30f}', '\u{20d}'),
+    ('\u{6f}', '\u{311}', '\u{20f}'), ('\u{6f}', '\u{31b}', '\u{1a1}'), ('\u{6f}', '\u{328}', '\u{1eb}'), ('\u{72}', '\u{301}', '\u{155}'),
+    ('\u{72}', '\u{30c}', '\u{159}'), ('\u{72}', '\u{30f}', '\u{211}'), ('\u{72}', '\u{311}', '\u{213}'), ('\u{72}', '\u{327}', '\u{157}'),
+    ('\u{73}', '\u{301}', '\u{15b}'), ('\u{73}', '\u{302}', '\u{15d}'), ('\u{73}', '\u{30c}', '\u{161}'), ('\u{73}', '\u{326}', '\u{219}'),
+    ('\u{73}', '\u{327}', '\u{15f}'), ('\u{74}', '\u{30c}', '\u{165}'), ('\u{74}', '\u{326}', '\u{21b}'), ('\u{74}', '\u{327}', '\u{163}'),
+    ('\u{75}', '\u{300}', '\u{f9}'), ('\u{75}', '\u{301}', '\u{fa}'), ('\u{75}', '\u{302}', '\u{fb}'), ('\u{75}', '\u{303}', '\u{169}'),
+    ('\u{75}', '\u{304}', '\u{16b}'), ('\u{75}', '\u{306}', '\u{16d}'), ('\u{75}', '\u{308}', '\u{fc}'), ('\u{75}', '\u{30a}', '\u{16f}'),
+    ('\u{75}', '\u{30b}', '\u{171}'), ('\u{75}', '\u{30c}', '\u{1d4}'), ('\u{75}', '\u{30f}', '\u{215}'), ('\u{75}', '\u{311}', '\u{217}'),
+    ('\u{75}', '\u{31b}', '\u{1b0}'), ('\u{75}', '\u{328}', '\u{173}'), ('\u{77}', '\u{302}', '\u{175}'), ('\u{79}', '\u{301}', '\u{fd}'),
+    ('\u{79}', '\u{302}', '\u{177}'), ('\u{79}', '\u{304}', '\u{233}'), ('\u{79}', '\u{308}', '\u{ff}'), ('\u{7a}', '\u{301}', '\u{17a}'),
+    ('\u{7a}', '\u{307}', '\u{17c}'), ('\u{7a}', '\u{30c}', '\u{17e}'), ('\u{c4}', '\u{304}', '\u{1de}'), ('\u{c5}', '\u{301}', '\u{1fa}'),
+    ('\u{c6}', '\u{301}', '\u{1fc}'), ('\u{c6}', '\u{304}', '\u{1e2}'), ('\u{d5}', '\u{304}', '\u{22c}'), ('\u{d6}', '\u{304}', '\u{22a}'),
+    ('\u{d8}', '\u{301}', '\u{1fe}'), ('\u{dc}', '\u{300}', '\u{1db}'), ('\u{dc}', '\u{301}', '\u{1d7}'), ('\u{dc}', '\u{304}', '\u{1d5}'),
+    ('\u{dc}', '\u{30c}', '\u{1d9}'), ('\u{e4}', '\u{304}', '\u{1df}'), ('\u{e5}', '\u{301}', '\u{1fb}'), ('\u{e6}', '\u{301}', '\u{1fd}'),
+    ('\u{e6}', '\u{304}', '\u{1e3}'), ('\u{f5}', '\u{304}', '\u{22d}'), ('\u{f6}', '\u{304}', '\u{22b}'), ('\u{f8}', '\u{301}', '\u{1ff}'),
+    ('\u{fc}', '\u{300}', '\u{1dc}'), ('\u{fc}', '\u{301}', '\u{1d8}'), ('\u{fc}', '\u{304}', '\u{1d6}'), ('\u{fc}', '\u{30c}', '\u{1da}'),
+    ('\u{1b7}', '\u{30c}', '\u{1ee}'), ('\u{1ea}', '\u{304}', '\u{1ec}'), ('\u{1eb}', '\u{304}', '\u{1ed}'), ('\u{226}', '\u{304}', '\u{1e0}'),
+    ('\u{227}', '\u{304}', '\u{1e1}'), ('\u{22e}', '\u{304}', '\u{230}'), ('\u{22f}', '\u{304}', '\u{231}'), ('\u{292}', '\u{30c}', '\u{1ef}'),
+    ('\u{3046}', '\u{3099}', '\u{3094}'), ('\u{304b}', '\u{3099}', '\u{304c}'), ('\u{304d}', '\u{3099}', '\u{304e}'), ('\u{304f}', '\u{3099}', '\u{3050}'),
+    ('\u{3051}', '\u{3099}', '\u{3052}'), ('\u{3053}', '\u{3099}', '\u{3054}'), ('\u{3055}', '\u{3099}', '\u{3056}'), ('\u{3057}', '\u{3099}', '\u{3058}'),
+    ('\u{3059}', '\u{3099}', '\u{305a}'), ('\u{305b}', '\u{3099}', '\u{305c}'), ('\u{305d}', '\u{3099}', '\u{305e}'), ('\u{305f}', '\u{3099}', '\u{3060}'),
+    ('\u{3061}', '\u{3099}', '\u{3062}'), ('\u{3064}', '\u{3099}', '\u{3065}'), ('\u{3066}', '\u{3099}', '\u{3067}'), ('\u{3068}', '\u{3099}', '\u{3069}'),
+    ('\u{306f}', '\u{3099}', '\u{3070}'), ('\u{306f}', '\u{309a}', '\u{3071}'), ('\u{3072}', '\u{3099}', '\u{3073}'), ('\u{3072}', '\u{309a}', '\u{3074}'),
+    ('\u{3075}', '\u{3099}', '\u{3076}'), ('\u{3075}', '\u{309a}', '\u{3077}'), ('\u{3078}', '\u{3099}', '\u{3079}'), ('\u{3078}', '\u{309a}', '\u{307a}'),
+    ('\u{307b}', '\u{3099}', '\u{307c}'), ('\u{307b}', '\u{309a}', '\u{307d}'), ('\u{309d}', '\u{3099}', '\u{309e}'), ('\u{30a6}', '\u{3099}', '\u{30f4}'),
+    ('\u{30ab}', '\u{3099}', '\u{30ac}'), ('\u{30ad}', '\u{3099}', '\u{30ae}'), ('\u{30af}', '\u{3099}', '\u{30b0}'), ('\u{30b1}', '\u{3099}', '\u{30b2}'),
+    ('\u{30b3}', '\u{3099}', '\u{30b4}'), ('\u{30b5}', '\u{3099}', '\u{30b6}'), ('\u{30b7}', '\u{3099}', '\u{30b8}'), ('\u{30b9}', '\u{3099}', '\u{30ba}'),
+    ('\u{30bb}', '\u{3099}', '\u{30bc}'), ('\u{30bd}', '\u{3099}', '\u{30be}'), ('\u{30bf}', '\u{3099}', '\u{30c0}'), ('\u{30c1}', '\u{3099}', '\u{30c2}'),
+    ('\u{30c4}', '\u{3099}', '\u{30c5}'), ('\u{30c6}', '\u{3099}', '\u{30c7}'), ('\u{30c8}', '\u{3099}', '\u{30c9}'), ('\u{30cf}', '\u{3099}', '\u{30d0}'),
+    ('\u{30cf}', '\u{309a}', '\u{30d1}'), ('\u{30d2}', '\u{3099}', '\u{30d3}'), ('\u{30d2}', '\u{309a}', '\u{30d4}'), ('\u{30d5}', '\u{3099}', '\u{30d6}'),
+    ('\u{30d5}', '\u{309a}', '\u{30d7}'), ('\u{30d8}', '\u{3099}', '\u{30d9}'), ('\u{30d8}', '\u{309a}', '\u{30da}'), ('\u{30db}', '\u{3099}', '\u{30dc}'),
+    ('\u{30db}', '\u{309a}', '\u{30dd}'), ('\u{30ef}', '\u{3099}', '\u{30f7}'), ('\u{30f0}', '\u{3099}', '\u{30f8}'), ('\u{30f1}', '\u{3099}', '\u{30f9}'),
+    ('\u{30f2}', '\u{3099}', '\u{30fa}'), ('\u{30fd}', '\u{3099}', '\u{30fe}'),
+];
+
+#[cfg(not(feature = "nfc"))]
+fn compose(base: char, mark: char) -> Option<char> {
+    COMPOSITIONS
+        .binary_search_by(|&(b, m, _)| (b, m).cmp(&(base, mark)))
+        .ok()
+        .map(|i| COMPOSITIONS[i].2)
+}
+
+/// Normalize the string to Unicode normalization form C.
+#[cfg(feature = "nfc")]
+pub(crate) fn compose_nfc(string: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+
+    string.nfc().collect()
+}
+
+/// Compose base characters followed by combining marks into precomposed characters.
+///
+/// An approximation of Unicode normalization form C for the scripts in [`COMPOSITIONS`],
+/// other characters are kept as they are. Marks are not reordered and Hangul is not composed.
+#[cfg(not(feature = "nfc"))]
+pub(crate) fn compose_nfc(string: &str) -> String {
+    let mut composed = String::with_capacity(string.len());
+    let mut pending: Option<char> = None;
+    for c in string.chars() {
+        pending = match pending {
+            Some(base) => match compose(base, c) {
+                Some(precomposed) => Some(precomposed),
+                None => {
+                    composed.push(base);
+                    Some(c)
+                }
+            },
+            None => Some(c),
+        };
+    }
+    composed.extend(pending);
+
+    composed
+}
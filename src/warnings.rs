@@ -0,0 +1,199 @@
+use std::fmt;
+
+use crate::compression::LzmaProperties;
+use crate::decoder::MAX_DECOMPRESSED_SIZE;
+use crate::errors::{InvalidUtf8, ReplayDataError};
+use crate::ids::is_md5_hex;
+use crate::parser::ParseOptions;
+use crate::replay::{Mods, Replay, ReplayData, ReplayFormat, Utf8Handling};
+
+/// Player names longer than this are reported as [`Warning::SuspiciousLength`], osu! limits
+/// names to far fewer characters.
+pub const MAX_PLAYER_NAME_LENGTH: usize = 32;
+
+/// A soft problem of a replay that does not prevent it from being parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// Bits of the mods that do not belong to any known mod.
+    UnknownModBits(u32),
+    /// A hash is not 32 hexadecimal characters, parsing fails instead with
+    /// [`HashValidation::Strict`](crate::parser::HashValidation::Strict).
+    MalformedHash {
+        /// Name of the field, `beatmap_md5` or `replay_md5`.
+        field: &'static str,
+    },
+    /// A field is implausibly long or short for replays written by osu!.
+    SuspiciousLength {
+        /// Name of the field.
+        field: &'static str,
+        /// Length of the field.
+        len: u64,
+    },
+    /// The perfect field is neither 0 nor 1, the byte is kept in `perfect_raw`.
+    InvalidPerfect(u8),
+    /// The replay predates the online score ID, see [`ReplayFormat::LegacyFormat`].
+    LegacyFormat,
+    /// Invalid UTF-8 in the decompressed replay data was replaced, see [`Utf8Handling::Lossy`].
+    InvalidUtf8(InvalidUtf8),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnknownModBits(bits) => write!(f, "Unknown mod bits {:#x}", bits),
+            Warning::MalformedHash { field } => write!(f, "Malformed MD5 hash in {}", field),
+            Warning::SuspiciousLength { field, len } => {
+                write!(f, "Suspicious length {} of {}", len, field)
+            }
+            Warning::InvalidPerfect(byte) => write!(f, "Invalid perfect byte {}", byte),
+            Warning::LegacyFormat => write!(f, "Legacy replay format without online score ID"),
+            Warning::InvalidUtf8(invalid) => write!(
+                f,
+                "{} invalid UTF-8 bytes at offset {} replaced",
+                invalid.len, invalid.offset
+            ),
+        }
+    }
+}
+
+/// The warnings found while parsing or decoding a replay.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Warnings::default()
+    }
+
+    /// Add a warning.
+    pub fn push(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Iterate over the warnings in the order they were found.
+    pub fn iter(&self) -> std::slice::Iter<'_, Warning> {
+        self.warnings.iter()
+    }
+
+    /// Number of warnings.
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Whether no warnings were found.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+impl Extend<Warning> for Warnings {
+    fn extend<I: IntoIterator<Item = Warning>>(&mut self, iter: I) {
+        self.warnings.extend(iter);
+    }
+}
+
+impl IntoIterator for Warnings {
+    type Item = Warning;
+    type IntoIter = std::vec::IntoIter<Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.iter()
+    }
+}
+
+impl Replay {
+    /// Parse a replay like [`Self::parse_with_options`] and check its header for soft problems,
+    /// see [`Self::header_warnings`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::warnings::Warning;
+    /// use osu_replay_parser::{ParseOptions, Replay};
+    ///
+    /// let mut input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    /// let (_, warnings) = Replay::parse_with_warnings(&input, &ParseOptions::default()).unwrap();
+    /// assert!(warnings.is_empty());
+    ///
+    /// // Set the highest bit of the mods, which is not used by any mod
+    /// input[105] |= 0x80;
+    /// let (_, warnings) = Replay::parse_with_warnings(&input, &ParseOptions::default()).unwrap();
+    /// assert_eq!(warnings.iter().next(), Some(&Warning::UnknownModBits(1 << 31)));
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the replay file is invalid or cannot be parsed.
+    pub fn parse_with_warnings<'a>(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<(Replay, Warnings), ReplayDataError<'a>> {
+        let replay = Replay::parse_with_options(input, options)?;
+        let warnings = replay.header_warnings();
+        Ok((replay, warnings))
+    }
+
+    /// Check the header for unknown mod bits, malformed hashes, invalid perfect bytes,
+    /// implausible lengths and the legacy format.
+    pub fn header_warnings(&self) -> Warnings {
+        let mut warnings = Warnings::new();
+        let unknown = self.mods & !Mods::all().bits();
+        if unknown != 0 {
+            warnings.push(Warning::UnknownModBits(unknown));
+        }
+        for (field, hash) in [
+            ("beatmap_md5", &self.beatmap_md5),
+            ("replay_md5", &self.replay_md5),
+        ] {
+            if !is_md5_hex(hash) {
+                warnings.push(Warning::MalformedHash { field });
+            }
+        }
+        if let Some(byte) = self.perfect_raw {
+            warnings.push(Warning::InvalidPerfect(byte));
+        }
+        if self.player_name.chars().count() > MAX_PLAYER_NAME_LENGTH {
+            warnings.push(Warning::SuspiciousLength {
+                field: "player_name",
+                len: self.player_name.chars().count() as u64,
+            });
+        }
+        // Header only parses leave the compressed data empty
+        let declared_size = LzmaProperties::from_header(&self.compressed_data)
+            .and_then(|properties| properties.unpacked_size);
+        if let Some(size) = declared_size.filter(|&size| size > MAX_DECOMPRESSED_SIZE) {
+            warnings.push(Warning::SuspiciousLength {
+                field: "decompressed_size",
+                len: size,
+            });
+        }
+        if self.format() == ReplayFormat::LegacyFormat {
+            warnings.push(Warning::LegacyFormat);
+        }
+
+        warnings
+    }
+
+    /// Decode the compressed replay data, replacing invalid UTF-8 instead of failing, see
+    /// [`Utf8Handling::Lossy`].
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded or a frame
+    /// cannot be parsed.
+    pub fn get_actions_with_warnings(
+        &self,
+    ) -> Result<(Vec<ReplayData>, Warnings), ReplayDataError<'static>> {
+        let (frames, invalid) = self.get_actions_with_utf8_handling(Utf8Handling::Lossy)?;
+        let mut warnings = Warnings::new();
+        warnings.extend(invalid.into_iter().map(Warning::InvalidUtf8));
+        Ok((frames, warnings))
+    }
+}
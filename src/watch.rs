@@ -0,0 +1,227 @@
+//! Replay folders are watched for new replays, e.g. to upload plays as soon as osu! saves them.
+//!
+//! Changes are reported by the operating system through the [`notify`] crate, so folders with
+//! many replays are not scanned. A file is reported once its size stopped changing between two
+//! polls, as osu! may still be writing it when it first appears. Files that were modified are
+//! reported again.
+
+use std::collections::HashMap;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use notify::event::EventKind;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::errors::ReplayDataError;
+use crate::parser::ParseOptions;
+use crate::replay::Replay;
+
+/// Default time between two polls of [`ReplayWatcher::run`] while a file is being written.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size and modification time of a replay file, to detect new and changed files.
+type FileState = (u64, Option<SystemTime>);
+
+/// Watches folders for new `.osr` files and parses them.
+/// # Example
+/// ```
+/// use osu_replay_parser::watch::ReplayWatcher;
+///
+/// let dir = std::env::temp_dir().join("osu_replay_parser_watch_example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let mut watcher = ReplayWatcher::new(&dir).expect("Error watching folder");
+///
+/// std::fs::copy("assets/replay.osr", dir.join("new.osr")).unwrap();
+/// // The file is reported once a poll sees the same size as the previous one
+/// let replays = loop {
+///     let replays = watcher.poll().unwrap();
+///     if !replays.is_empty() {
+///         break replays;
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(20));
+/// };
+/// assert_eq!(replays.len(), 1);
+/// assert_eq!(replays[0].0, dir.join("new.osr"));
+/// assert!(replays[0].1.is_ok());
+///
+/// assert!(watcher.poll().unwrap().is_empty());
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ReplayWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    interval: Duration,
+    options: ParseOptions,
+    /// New or changed files waiting for their size to settle, with their state at the last poll.
+    pending: HashMap<PathBuf, Option<FileState>>,
+}
+
+impl ReplayWatcher {
+    /// Watch a folder. Replays already in the folder are not reported.
+    /// # Errors
+    /// Returns an error if the folder cannot be watched.
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let watcher = ReplayWatcher {
+            watcher: notify::recommended_watcher(sender).map_err(notify_error)?,
+            events,
+            interval: DEFAULT_POLL_INTERVAL,
+            options: ParseOptions::default(),
+            pending: HashMap::new(),
+        };
+        watcher.watch(dir)
+    }
+
+    /// Also watch another folder, e.g. the local replays of osu!stable next to the exported
+    /// ones. Replays already in the folder are not reported.
+    /// # Errors
+    /// Returns an error if the folder cannot be watched.
+    pub fn watch<P: AsRef<Path>>(mut self, dir: P) -> io::Result<Self> {
+        self.watcher
+            .watch(dir.as_ref(), RecursiveMode::NonRecursive)
+            .map_err(notify_error)?;
+        Ok(self)
+    }
+
+    /// Time between two polls of [`Self::run`] while files are being written,
+    /// [`DEFAULT_POLL_INTERVAL`] by default.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Options used to parse the new replays, e.g. [`ParseOptions::header_only`] if only the
+    /// metadata is needed.
+    pub fn parse_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Mark the replay files of a change as pending, or forget them if they were removed.
+    fn handle(&mut self, event: notify::Result<Event>) -> io::Result<()> {
+        let event = event.map_err(notify_error)?;
+        if event.kind.is_access() {
+            return Ok(());
+        }
+        for path in event.paths {
+            if !is_replay_file(&path) {
+                continue;
+            }
+            if matches!(event.kind, EventKind::Remove(_)) {
+                self.pending.remove(&path);
+            } else {
+                self.pending.insert(path, None);
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the changes since the last poll and parse the replays that appeared or changed
+    /// and are complete, sorted by path.
+    ///
+    /// Files that cannot be read, e.g. because they were deleted in the meantime, are skipped.
+    /// # Errors
+    /// Returns an error if the operating system reports an error watching the folders. Errors of
+    /// individual replays are part of the result.
+    pub fn poll(&mut self) -> io::Result<Vec<(PathBuf, Result<Replay, ReplayDataError<'static>>)>> {
+        while let Ok(event) = self.events.try_recv() {
+            self.handle(event)?;
+        }
+
+        let mut replays = Vec::new();
+        self.pending.retain(|path, last| {
+            let Ok(state) = file_state(path) else {
+                return false;
+            };
+            if *last != Some(state) {
+                *last = Some(state);
+                return true;
+            }
+            if let Ok(input) = std::fs::read(path) {
+                let replay = Replay::parse_with_options(&input, &self.options)
+                    .map_err(ReplayDataError::into_owned);
+                replays.push((path.clone(), replay));
+            }
+            false
+        });
+        replays.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(replays)
+    }
+
+    /// Pass every new replay to `on_replay`, until it returns [`ControlFlow::Break`].
+    ///
+    /// While no file is being written, the thread sleeps until the next change, otherwise the
+    /// files are polled every [`Self::interval`].
+    /// # Errors
+    /// Returns an error if the operating system reports an error watching the folders.
+    pub fn run<F>(&mut self, mut on_replay: F) -> io::Result<()>
+    where
+        F: FnMut(PathBuf, Result<Replay, ReplayDataError<'static>>) -> ControlFlow<()>,
+    {
+        loop {
+            for (path, replay) in self.poll()? {
+                if on_replay(path, replay).is_break() {
+                    return Ok(());
+                }
+            }
+            if self.pending.is_empty() {
+                let Ok(event) = self.events.recv() else {
+                    return Ok(());
+                };
+                self.handle(event)?;
+            } else {
+                thread::sleep(self.interval);
+            }
+        }
+    }
+}
+
+/// The folders osu! stores replays in on this computer, those that exist.
+///
+/// These are the `Replays` folder of osu!stable with exported replays, its `Data/r` folder with
+/// the local replays of every play and the `exports` folder of osu!lazer.
+pub fn osu_replay_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(local) = std::env::var_os("LOCALAPPDATA").map(PathBuf::from) {
+        dirs.push(local.join("osu!").join("Replays"));
+        dirs.push(local.join("osu!").join("Data").join("r"));
+    }
+    if let Some(roaming) = std::env::var_os("APPDATA").map(PathBuf::from) {
+        dirs.push(roaming.join("osu").join("exports"));
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    if let Some(data_home) = data_home {
+        dirs.push(data_home.join("osu").join("exports"));
+    }
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        dirs.push(home.join("Library/Application Support/osu/exports"));
+    }
+
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
+
+fn is_replay_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("osr"))
+}
+
+fn notify_error(error: notify::Error) -> io::Error {
+    match error.kind {
+        notify::ErrorKind::Io(error) => error,
+        _ => io::Error::other(error),
+    }
+}
+
+fn file_state(path: &Path) -> io::Result<FileState> {
+    let metadata = std::fs::metadata(path)?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
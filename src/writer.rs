@@ -0,0 +1,401 @@
+use std::fmt::Write;
+use std::io;
+
+use lzma_rs::compress::{Options, UnpackedSize};
+use lzma_rs::lzma_compress_with_options;
+use serde_json::{json, Value};
+
+use crate::errors::{ParseError, ReplayDataError};
+use crate::frames::{offset_keeps_order, FramesExt};
+use crate::parser::player_name;
+use crate::replay::{
+    Mods, Replay, ReplayData, FIRST_LAZER_VERSION, FIRST_SCORE_ID_VERSION, LONG_SCORE_ID_VERSION,
+};
+use crate::statistics::HitStatistics;
+
+fn write_uleb128<W: io::Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Length of a length-prefixed field, which cannot exceed `u32::MAX` bytes.
+fn field_length(length: usize) -> io::Result<u32> {
+    u32::try_from(length).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Empty strings are written as absent unless `empty_present` is set.
+fn write_osr_string<W: io::Write>(
+    writer: &mut W,
+    string: &str,
+    empty_present: bool,
+) -> io::Result<()> {
+    if string.is_empty() && !empty_present {
+        return writer.write_all(&[0x00]);
+    }
+    writer.write_all(&[0x0b])?;
+    write_uleb128(writer, string.len() as u64)?;
+    writer.write_all(string.as_bytes())
+}
+
+pub(crate) fn osr_string_bytes(string: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(string.len() + 3);
+    write_osr_string(&mut bytes, string, false).expect("Writing to a Vec cannot fail");
+    bytes
+}
+
+/// Encode frames in the text format stored in the compressed replay data, `time|x|y|keys,` per frame.
+///
+/// Coordinates are written with the shortest representation that parses back to the same value,
+/// or rounded to `precision` decimal places if given.
+pub fn encode_frames(frames: &[ReplayData], precision: Option<usize>) -> String {
+    let mut text = String::with_capacity(frames.len() * 24);
+    for frame in frames {
+        // Writing to a String cannot fail
+        let _ = match precision {
+            Some(precision) => write!(
+                text,
+                "{}|{:.*}|{:.*}|{},",
+                frame.time, precision, frame.x, precision, frame.y, frame.keys
+            ),
+            None => write!(
+                text,
+                "{}|{}|{}|{},",
+                frame.time, frame.x, frame.y, frame.keys
+            ),
+        };
+    }
+
+    text
+}
+
+/// LZMA compress data the way osu! stores it, with the uncompressed size in the header.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    let options = Options {
+        unpacked_size: UnpackedSize::WriteToHeader(Some(data.len() as u64)),
+    };
+    lzma_compress_with_options(&mut &data[..], &mut compressed, &options)
+        .expect("Writing to a Vec cannot fail");
+
+    compressed
+}
+
+/// Encode and compress frames into the compressed replay data format.
+pub fn compress_frames(frames: &[ReplayData]) -> Vec<u8> {
+    compress(encode_frames(frames, None).as_bytes())
+}
+
+/// Acronyms of the osu!lazer mods changing the playback speed.
+const LAZER_SPEED_MODS: [&str; 7] = ["DT", "NC", "HT", "DC", "WU", "WD", "AS"];
+
+/// Create the additional score information osu!lazer appends to replays from the hit counts of a replay.
+///
+/// Mods are not included, so osu!lazer converts them from the replay's mods.
+pub fn lazer_data_from_hit_counts(replay: &Replay) -> Vec<u8> {
+    let statistics: Vec<(&str, u16)> = match replay.hit_statistics() {
+        HitStatistics::Osu {
+            n300,
+            n100,
+            n50,
+            n_miss,
+            ..
+        } => vec![
+            ("great", n300),
+            ("ok", n100),
+            ("meh", n50),
+            ("miss", n_miss),
+        ],
+        HitStatistics::Taiko {
+            great, ok, miss, ..
+        } => vec![("great", great), ("ok", ok), ("miss", miss)],
+        HitStatistics::Catch {
+            fruits,
+            droplets,
+            tiny_droplets,
+            tiny_droplet_misses,
+            misses,
+        } => vec![
+            ("great", fruits),
+            ("large_tick_hit", droplets),
+            ("small_tick_hit", tiny_droplets),
+            ("small_tick_miss", tiny_droplet_misses),
+            ("miss", misses),
+        ],
+        HitStatistics::Mania {
+            max,
+            n300,
+            n200,
+            n100,
+            n50,
+            miss,
+        } => vec![
+            ("perfect", max),
+            ("great", n300),
+            ("good", n200),
+            ("ok", n100),
+            ("meh", n50),
+            ("miss", miss),
+        ],
+    };
+
+    let statistics: serde_json::Map<String, Value> = statistics
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| (name.to_string(), u32::from(count).into()))
+        .collect();
+    let json = json!({
+        "online_id": replay.online_score_id,
+        "statistics": statistics,
+        "total_score_without_mods": replay.total_score,
+    });
+
+    compress(json.to_string().as_bytes())
+}
+
+impl Replay {
+    /// Write the replay in the `.osr` format.
+    ///
+    /// The online score ID is written as an integer for versions before 2014-07-21, the
+    /// additional mod info and the osu!lazer data are written if present. Empty strings are
+    /// written as absent with a single `0x00` byte, unless
+    /// [`empty_strings_present`](Self::empty_strings_present) is set.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay {
+    ///     beatmap_md5: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+    ///     ..Replay::default()
+    /// };
+    /// let mut bytes = Vec::new();
+    /// replay.write(&mut bytes).expect("Error writing replay");
+    /// // Game mode and version, then the beatmap hash is present, the player name is not
+    /// assert_eq!(bytes[5..7], [0x0b, 32]);
+    /// assert_eq!(bytes[39], 0x00);
+    ///
+    /// let parsed = Replay::parse(&bytes).expect("Error parsing replay");
+    /// assert!(parsed.player_name.is_empty());
+    /// ```
+    /// # Errors
+    /// Returns an error if writing fails or the online score ID does not fit into the field of the replay's version,
+    /// and an error of kind `InvalidInput` if the compressed or osu!lazer data is longer than `u32::MAX` bytes.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let w = &mut writer;
+        w.write_all(&[self.game_mode.into()])?;
+        w.write_all(&self.version.to_le_bytes())?;
+        write_osr_string(w, &self.beatmap_md5, self.empty_strings_present)?;
+        // The raw bytes are only used as long as the name was not changed
+        let raw_player_name = self.player_name_raw.as_ref().filter(|raw| {
+            player_name::<false>(raw).is_ok_and(|(_, (name, _))| name == self.player_name)
+        });
+        match raw_player_name {
+            Some(raw) => w.write_all(raw)?,
+            None => write_osr_string(w, &self.player_name, self.empty_strings_present)?,
+        }
+        write_osr_string(w, &self.replay_md5, self.empty_strings_present)?;
+        for count in [
+            self.n300,
+            self.n100,
+            self.n50,
+            self.n_geki,
+            self.n_katu,
+            self.n_miss,
+        ] {
+            w.write_all(&count.to_le_bytes())?;
+        }
+        w.write_all(&self.total_score.to_le_bytes())?;
+        w.write_all(&self.greatest_combo.to_le_bytes())?;
+        w.write_all(&[self.perfect_byte()])?;
+        w.write_all(&self.mods.to_le_bytes())?;
+        write_osr_string(w, &self.life_bar, self.empty_strings_present)?;
+        w.write_all(&self.time_stamp.to_le_bytes())?;
+        w.write_all(&field_length(self.compressed_data.len())?.to_le_bytes())?;
+        w.write_all(&self.compressed_data)?;
+        if self.version >= LONG_SCORE_ID_VERSION {
+            w.write_all(&self.online_score_id.to_le_bytes())?;
+        } else if self.version >= FIRST_SCORE_ID_VERSION {
+            let id = i32::try_from(self.online_score_id)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            w.write_all(&id.to_le_bytes())?;
+        }
+        if let Some(accuracy) = self.additional_mod_info {
+            w.write_all(&accuracy.to_le_bytes())?;
+        }
+        if let Some(data) = &self.lazer_data {
+            w.write_all(&field_length(data.len())?.to_le_bytes())?;
+            w.write_all(data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Encode the replay in the `.osr` format, see [`Self::write`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::Replay;
+    /// use std::fs;
+    ///
+    /// let input = fs::read("assets/replay.osr").expect("Error reading file");
+    /// let replay = Replay::parse(&input).expect("Error parsing replay");
+    /// assert_eq!(replay.to_bytes().expect("Error writing replay"), input);
+    /// ```
+    /// # Errors
+    /// Returns an error if the online score ID does not fit into the field of the replay's version
+    /// or the compressed or osu!lazer data is longer than `u32::MAX` bytes.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.compressed_data.len() + 256);
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Convert the replay to another format version.
+    ///
+    /// The online score ID is narrowed to an integer for versions before 2014-07-21 and removed
+    /// for versions before 2012-10-08, see [`ReplayFormat`](crate::replay::ReplayFormat). The osu!lazer
+    /// data is dropped for osu!stable versions and created from the hit counts with
+    /// [`lazer_data_from_hit_counts`] for osu!lazer versions if missing.
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if the online score ID does not fit into the field of the target version.
+    pub fn convert_to_version(&self, version: u32) -> Result<Replay, ReplayDataError<'static>> {
+        if (FIRST_SCORE_ID_VERSION..LONG_SCORE_ID_VERSION).contains(&version)
+            && i32::try_from(self.online_score_id).is_err()
+        {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+
+        let mut replay = self.clone();
+        replay.version = version;
+        if version < FIRST_SCORE_ID_VERSION {
+            replay.online_score_id = 0;
+        }
+        if version < FIRST_LAZER_VERSION {
+            replay.lazer_data = None;
+        } else if replay.lazer_data.is_none() {
+            replay.lazer_data = Some(lazer_data_from_hit_counts(&replay));
+        }
+
+        Ok(replay)
+    }
+
+    /// A copy of the replay with the frames after the skipped intro shifted by `ms` milliseconds,
+    /// see [`FramesExt::apply_offset`].
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::frames::FramesExt;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let corrected = replay.with_offset(15).expect("Error re-encoding frames");
+    ///
+    /// let before = replay.get_actions().expect("Error getting actions");
+    /// let after = corrected.get_actions().expect("Error getting actions");
+    /// let (first, _) = before.timed().last().unwrap();
+    /// let (second, _) = after.timed().last().unwrap();
+    /// assert_eq!(second - first, 15);
+    /// ```
+    /// # Errors
+    /// Returns a `ReplayDataError` if the compressed replay data cannot be decoded, or
+    /// `ParseError::InvalidValue` if a negative offset is larger than the skip gap.
+    pub fn with_offset(&self, ms: i64) -> Result<Replay, ReplayDataError<'static>> {
+        let frames = self.get_actions_with_progress(|_, _| {})?;
+        if !offset_keeps_order(&frames, ms) {
+            return Err(ParseError::InvalidValue.into());
+        }
+        let mut replay = self.clone();
+        replay.compressed_data = compress_frames(&frames.apply_offset(ms));
+
+        Ok(replay)
+    }
+
+    /// A copy of the replay played with other speed mods, e.g. a Double Time variant for
+    /// testing renderers.
+    ///
+    /// Frame times are stored in beatmap time, so the frames are kept and still hit the same
+    /// objects when played back at the new speed. Use [`FramesExt::scale_time`] with the
+    /// [`Mods::speed_multiplier`] to convert the frames to real time instead.
+    ///
+    /// Only the `mods` of the osu!lazer data are changed: the speed changing mods of osu!lazer
+    /// (DT, NC, HT, DC, WU, WD and AS) are replaced by the new one, the other mods with their
+    /// settings and the rest of the score information are kept. The version is kept as well, as
+    /// it names the client that recorded the frames, and the replay MD5 hash is not updated.
+    /// # Example
+    /// ```
+    /// use osu_replay_parser::replay::Mods;
+    /// use osu_replay_parser::Replay;
+    ///
+    /// let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    /// let nightcore = replay.with_speed_mods(Mods::NIGHTCORE).expect("Error changing mods");
+    /// assert!(nightcore.mods().contains(Mods::DOUBLE_TIME | Mods::NIGHTCORE));
+    /// assert_eq!(nightcore.mods().speed_multiplier(), 1.5);
+    /// assert_eq!(nightcore.version, replay.version);
+    ///
+    /// let info = replay.lazer_score_info().unwrap().unwrap();
+    /// let nightcore_info = nightcore.lazer_score_info().unwrap().unwrap();
+    /// assert_eq!(nightcore_info.mods[0].acronym, "NC");
+    /// assert_eq!(nightcore_info.client_version, info.client_version);
+    /// assert_eq!(nightcore_info.maximum_statistics, info.maximum_statistics);
+    ///
+    /// let normal = nightcore.with_speed_mods(Mods::NONE).unwrap();
+    /// assert_eq!(normal.mods(), replay.mods() - Mods::DOUBLE_TIME - Mods::NIGHTCORE - Mods::HALF_TIME);
+    /// assert_eq!(normal.lazer_score_info().unwrap().unwrap(), info);
+    /// ```
+    /// # Errors
+    /// Returns `ParseError::InvalidValue` if `speed_mods` contains other mods than Double Time,
+    /// Nightcore and Half Time or both a speed-up and a slow-down, and a `ReplayDataError` if the
+    /// osu!lazer data cannot be decoded.
+    pub fn with_speed_mods(&self, speed_mods: Mods) -> Result<Replay, ReplayDataError<'static>> {
+        let all_speed_mods = Mods::DOUBLE_TIME | Mods::NIGHTCORE | Mods::HALF_TIME;
+        let mut speed_mods = speed_mods;
+        if speed_mods.contains(Mods::NIGHTCORE) {
+            speed_mods |= Mods::DOUBLE_TIME;
+        }
+        if !all_speed_mods.contains(speed_mods)
+            || speed_mods.contains(Mods::DOUBLE_TIME | Mods::HALF_TIME)
+        {
+            return Err(ReplayDataError::Parse(ParseError::InvalidValue));
+        }
+
+        let mut replay = self.clone();
+        replay.mods = ((self.mods() - all_speed_mods) | speed_mods).bits();
+        if let Some(lazer_data) = &self.lazer_data {
+            let json = Replay::decompress_lzma(lazer_data)?;
+            let mut json: Value =
+                serde_json::from_str(&json).map_err(|_| ParseError::InvalidValue)?;
+            let speed_mod = if speed_mods.contains(Mods::NIGHTCORE) {
+                Some("NC")
+            } else if speed_mods.contains(Mods::DOUBLE_TIME) {
+                Some("DT")
+            } else if speed_mods.contains(Mods::HALF_TIME) {
+                Some("HT")
+            } else {
+                None
+            };
+            let mods = json
+                .get("mods")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|lazer_mod| {
+                    !lazer_mod
+                        .get("acronym")
+                        .and_then(Value::as_str)
+                        .is_some_and(|acronym| LAZER_SPEED_MODS.contains(&acronym))
+                })
+                .cloned()
+                .chain(speed_mod.map(|acronym| json!({ "acronym": acronym })))
+                .collect();
+            if let Some(object) = json.as_object_mut() {
+                object.insert("mods".to_string(), Value::Array(mods));
+            }
+            replay.lazer_data = Some(compress(json.to_string().as_bytes()));
+        }
+
+        Ok(replay)
+    }
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "archive")]
+
+use std::fs;
+use std::io;
+
+use osu_replay_parser::archive::{ArchiveEntry, ReplayArchive};
+use osu_replay_parser::errors::ReplayDataError;
+
+/// Files of `assets/replays.zip` and `assets/replays.tar`, created with `zip` and GNU `tar`.
+const ENTRIES: [&str; 3] = ["pack/replay.osr", "pack/notes.txt", "pack/readme.txt"];
+
+fn open(path: &str) -> (Vec<u8>, ReplayArchive) {
+    let data = fs::read(path).expect("Error reading fixture");
+    let archive = ReplayArchive::from_bytes(data.clone()).expect("Error opening archive");
+    (data, archive)
+}
+
+fn entry<'a>(archive: &'a ReplayArchive, name: &str) -> &'a ArchiveEntry {
+    archive
+        .entries()
+        .iter()
+        .find(|entry| entry.name == name)
+        .expect("Missing entry")
+}
+
+fn assert_invalid_data<T>(result: Result<T, ReplayDataError<'static>>, message: &str) {
+    match result {
+        Err(ReplayDataError::Io(e)) => {
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert!(e.to_string().contains(message), "{}", e);
+        }
+        Err(e) => panic!("Expected an InvalidData error, got {:?}", e),
+        Ok(_) => panic!("Expected an InvalidData error"),
+    }
+}
+
+fn assert_contents(archive: &ReplayArchive) {
+    let names: Vec<_> = archive
+        .entries()
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    assert_eq!(names, ENTRIES);
+
+    let replay = fs::read("assets/replay.osr").expect("Error reading replay");
+    let read = |name| {
+        archive
+            .read(entry(archive, name))
+            .expect("Error reading entry")
+    };
+    assert_eq!(read("pack/replay.osr"), replay);
+    assert_eq!(read("pack/readme.txt"), b"Tournament replays\n");
+    let notes = String::from_utf8(read("pack/notes.txt")).expect("Notes are not UTF-8");
+    assert_eq!(notes.lines().count(), 120);
+    assert_eq!(
+        notes.lines().nth(8),
+        Some("Match 8: RenLephy vs. Player 1, replay.osr")
+    );
+
+    let replays: Vec<_> = archive.replays().collect();
+    assert_eq!(replays.len(), 1);
+    let (name, parsed) = &replays[0];
+    assert_eq!(*name, "pack/replay.osr");
+    assert_eq!(
+        parsed.as_ref().expect("Error parsing replay").player_name,
+        "RenLephy"
+    );
+}
+
+#[test]
+fn zip_fixture() {
+    let (_, archive) = open("assets/replays.zip");
+    assert_contents(&archive);
+}
+
+#[test]
+fn tar_fixture() {
+    let (_, archive) = open("assets/replays.tar");
+    assert_contents(&archive);
+}
+
+#[test]
+fn zip_crc_mismatch() {
+    let (mut data, _) = open("assets/replays.zip");
+    let offset = data
+        .windows(10)
+        .position(|window| window == b"Tournament")
+        .expect("Missing stored entry");
+    data[offset] = b't';
+
+    let archive = ReplayArchive::from_bytes(data).expect("Error opening archive");
+    assert_invalid_data(archive.read(entry(&archive, "pack/readme.txt")), "CRC-32");
+    assert!(archive.read(entry(&archive, "pack/notes.txt")).is_ok());
+}
+
+#[test]
+fn zip_entry_size_limit() {
+    let (data, _) = open("assets/replays.zip");
+    let archive = ReplayArchive::from_bytes(data)
+        .expect("Error opening archive")
+        .with_max_entry_size(1000);
+    assert_invalid_data(
+        archive.read(entry(&archive, "pack/notes.txt")),
+        "size limit",
+    );
+    assert!(archive.read(entry(&archive, "pack/readme.txt")).is_ok());
+}
+
+#[test]
+fn zip_truncated() {
+    let (data, _) = open("assets/replays.zip");
+    assert_invalid_data(
+        ReplayArchive::from_bytes(data[..data.len() / 2].to_vec()),
+        "end of central directory",
+    );
+}
+
+#[test]
+fn tar_checksum_mismatch() {
+    let (mut data, _) = open("assets/replays.tar");
+    data[0] = b'P';
+    assert_invalid_data(ReplayArchive::from_bytes(data), "checksum");
+}
+
+#[test]
+fn not_an_archive() {
+    let data = fs::read("assets/replay.osr").expect("Error reading replay");
+    assert_invalid_data(ReplayArchive::from_bytes(data), "not a zip or tar archive");
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "test-data")]
+
+use osu_replay_parser::test_data::{Corpus, Fixture, FIXTURE_MANIFEST, TEST_DATA_DIR_VAR};
+
+fn assert_conforms(corpus: &Corpus) {
+    let report = corpus.run();
+    let failures: Vec<String> = report.failures.iter().map(|f| f.to_string()).collect();
+    assert!(report.is_success(), "{}", failures.join("\n"));
+    assert_eq!(report.files, corpus.paths().len());
+}
+
+#[test]
+fn assets() {
+    let corpus = Corpus::from_dir("assets").expect("Error reading assets");
+    assert!(!corpus.paths().is_empty());
+    assert_conforms(&corpus);
+}
+
+/// Runs over the replays in the directory named by `OSU_REPLAY_TEST_DATA`, if it is set.
+#[test]
+fn env_corpus() {
+    match Corpus::from_env() {
+        Ok(Some(corpus)) => assert_conforms(&corpus),
+        Ok(None) => eprintln!("{} is not set, skipping", TEST_DATA_DIR_VAR),
+        Err(e) => panic!("Error reading {}: {}", TEST_DATA_DIR_VAR, e),
+    }
+}
+
+#[test]
+fn manifest_lists_assets() {
+    let fixtures = Fixture::parse_manifest(FIXTURE_MANIFEST).expect("Error parsing manifest");
+    let corpus = Corpus::from_dir("assets").expect("Error reading assets");
+    let mut names: Vec<_> = fixtures.iter().map(|f| f.name.as_str()).collect();
+    names.sort_unstable();
+    let assets: Vec<_> = corpus
+        .paths()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(names, assets);
+
+    for fixture in &fixtures {
+        assert!(fixture.url.ends_with(&format!("/assets/{}", fixture.name)));
+        assert!(fixture.md5.is_some(), "{} has no MD5 hash", fixture.name);
+    }
+}
+
+#[cfg(feature = "md5")]
+#[test]
+fn manifest_hashes_match_assets() {
+    use osu_replay_parser::hash::md5_hex;
+
+    for fixture in Fixture::parse_manifest(FIXTURE_MANIFEST).expect("Error parsing manifest") {
+        let data =
+            std::fs::read(format!("assets/{}", fixture.name)).expect("Error reading fixture");
+        assert_eq!(Some(md5_hex(&data)), fixture.md5, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn invalid_manifest() {
+    for manifest in [
+        "replay.osr https://example.com/replay.osr",
+        "replay.osr https://example.com/replay.osr 45312f8e -",
+        "replay.osr https://example.com/replay.osr not-a-hash",
+    ] {
+        let error = Fixture::parse_manifest(&format!("# comment\n\n{}", manifest)).unwrap_err();
+        assert!(error.to_string().starts_with("line 3 "), "{}", error);
+    }
+    let fixtures = Fixture::parse_manifest("a.osr https://example.com/a.osr -\n").unwrap();
+    assert_eq!(fixtures[0].md5, None);
+}
@@ -0,0 +1,116 @@
+use std::ops::ControlFlow;
+
+use osu_replay_parser::decoder::{decode_frames_with_limits, DecodeLimits};
+use osu_replay_parser::errors::{FrameError, LimitExceeded, ReplayDataError};
+use osu_replay_parser::{writer, Replay};
+
+/// Frame text of the given number of frames, each frame differing from the last.
+fn frame_text(frames: usize) -> String {
+    (0..frames)
+        .map(|i| format!("16|{}|{}|{},", i % 512, (i * 7) % 384, i % 4))
+        .collect()
+}
+
+/// Compressed frame text with the size removed from the LZMA header and the dictionary size
+/// patched, so the stream is only terminated by its end marker.
+fn unknown_size_stream(text: &str, dict_size: u32) -> Vec<u8> {
+    let mut compressed_data = writer::compress(text.as_bytes());
+    compressed_data[1..5].copy_from_slice(&dict_size.to_le_bytes());
+    compressed_data[5..13].copy_from_slice(&u64::MAX.to_le_bytes());
+    compressed_data
+}
+
+fn is_size_exceeded<T>(result: &Result<T, ReplayDataError<'static>>, expected: u64) -> bool {
+    matches!(
+        result,
+        Err(ReplayDataError::Frame(FrameError::LimitExceeded(LimitExceeded::DecompressedSize { limit })))
+            if *limit == expected
+    )
+}
+
+#[test]
+fn large_dictionary_of_unknown_size_is_rejected_before_decompressing() {
+    let limit = 1 << 20;
+    let limits = DecodeLimits {
+        max_decompressed_size: Some(limit),
+        ..DecodeLimits::unlimited()
+    };
+    let text = frame_text(200_000);
+    assert!(text.len() as u64 > limit);
+    let compressed_data = unknown_size_stream(&text, 1 << 30);
+
+    // Valid apart from the limit
+    let unlimited = Replay {
+        compressed_data: compressed_data.clone(),
+        ..Default::default()
+    };
+    assert!(unlimited
+        .get_actions_with_limits(&DecodeLimits::unlimited())
+        .is_ok());
+
+    let replay = Replay {
+        compressed_data: compressed_data.clone(),
+        ..Default::default()
+    };
+    assert!(is_size_exceeded(&replay.get_actions_with_limits(&limits), limit));
+    assert!(is_size_exceeded(
+        &replay.get_actions_with_limits(&DecodeLimits::untrusted()),
+        DecodeLimits::untrusted().max_decompressed_size.unwrap()
+    ));
+
+    // Only the header is read, the truncated rest of the stream is never decompressed
+    let mut visited = 0;
+    let truncated = decode_frames_with_limits(&compressed_data[..64], &limits, |_| {
+        visited += 1;
+        ControlFlow::<()>::Continue(())
+    });
+    assert!(is_size_exceeded(&truncated, limit));
+    assert_eq!(visited, 0);
+}
+
+#[test]
+fn small_dictionary_of_unknown_size_fails_while_decompressing() {
+    let limit = 1 << 16;
+    let limits = DecodeLimits {
+        max_decompressed_size: Some(limit),
+        ..DecodeLimits::unlimited()
+    };
+    let text = frame_text(50_000);
+    let compressed_data = unknown_size_stream(&text, 1 << 12);
+
+    let mut decompressed = 0;
+    let result = decode_frames_with_limits(&compressed_data, &limits, |frame| {
+        decompressed += format!("{}|{}|{}|{},", frame.time, frame.x, frame.y, frame.keys).len();
+        ControlFlow::<()>::Continue(())
+    });
+    assert!(is_size_exceeded(&result, limit));
+    assert!(decompressed as u64 <= limit);
+}
+
+#[test]
+fn breaking_on_the_first_frame_stops_decoding() {
+    // Far more text than the limit, decoded a 4 KiB dictionary at a time
+    let limit = 1 << 16;
+    let limits = DecodeLimits {
+        max_decompressed_size: Some(limit),
+        ..DecodeLimits::unlimited()
+    };
+    let text = frame_text(100_000);
+    assert!(text.len() as u64 > 16 * limit);
+    let compressed_data = unknown_size_stream(&text, 1 << 12);
+
+    let mut visited = 0;
+    let first = decode_frames_with_limits(&compressed_data, &limits, |frame| {
+        visited += 1;
+        ControlFlow::Break(frame)
+    })
+    .expect("Decoding stops before the limit");
+    assert_eq!(visited, 1);
+    assert_eq!(first.map(|frame| frame.time), Some(16));
+
+    // Decoding all frames exceeds the limit
+    let all = decode_frames_with_limits(&compressed_data, &limits, |_| {
+        ControlFlow::<()>::Continue(())
+    });
+    assert!(is_size_exceeded(&all, limit));
+}
@@ -0,0 +1,45 @@
+use osu_replay_parser::parser::MAX_LENGTH_CORRECTION;
+use osu_replay_parser::Replay;
+
+/// The sample replay with its compressed length field changed by `delta` bytes.
+fn with_length_delta(delta: i64) -> (Replay, Vec<u8>) {
+    let input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    let (replay, spans) =
+        Replay::parse_with_spans(&input, &Default::default()).expect("Error parsing replay");
+    let field = spans.get("compressed_length").unwrap();
+    let length = u32::try_from(replay.compressed_data.len() as i64 + delta).unwrap();
+    let mut broken = input.clone();
+    broken[field].copy_from_slice(&length.to_le_bytes());
+
+    (replay, broken)
+}
+
+#[test]
+fn truncated_length_is_corrected() {
+    let (replay, broken) = with_length_delta(-20);
+    assert!(Replay::parse(&broken).is_err());
+
+    let (recovered, correction) =
+        Replay::parse_with_length_recovery(&broken, &Default::default()).unwrap();
+    assert_eq!(correction.unwrap().difference(), -20);
+    assert_eq!(recovered.compressed_data, replay.compressed_data);
+    assert_eq!(recovered.online_score_id, replay.online_score_id);
+}
+
+#[test]
+fn over_long_length_is_corrected() {
+    let (replay, broken) = with_length_delta(6);
+
+    let (recovered, correction) =
+        Replay::parse_with_length_recovery(&broken, &Default::default()).unwrap();
+    assert_eq!(correction.unwrap().difference(), 6);
+    assert_eq!(recovered.compressed_data, replay.compressed_data);
+    assert_eq!(recovered.online_score_id, replay.online_score_id);
+}
+
+#[test]
+fn length_beyond_the_correction_range_is_not_corrected() {
+    let (_, broken) = with_length_delta(-i64::from(MAX_LENGTH_CORRECTION) - 1);
+
+    assert!(Replay::parse_with_length_recovery(&broken, &Default::default()).is_err());
+}
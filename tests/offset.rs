@@ -0,0 +1,72 @@
+use osu_replay_parser::errors::{ParseError, ReplayDataError};
+use osu_replay_parser::frames::FramesExt;
+use osu_replay_parser::{writer, Replay, ReplayData};
+
+fn frames(times: &[i64]) -> Vec<ReplayData> {
+    times
+        .iter()
+        .map(|&time| ReplayData {
+            time,
+            x: 256.0,
+            y: 192.0,
+            keys: 0,
+        })
+        .collect()
+}
+
+fn absolute_times(frames: &[ReplayData]) -> Vec<i64> {
+    frames.timed().map(|(time, _)| time).collect()
+}
+
+/// Frames of a replay skipping its intro, with a gap of 5000 ms after the second frame.
+const SKIPPED: [i64; 5] = [0, 20, 5000, 16, 16];
+
+#[test]
+fn offset_up_to_the_skip_gap_keeps_the_intro() {
+    let shifted = frames(&SKIPPED).apply_offset(-5000);
+    assert_eq!(absolute_times(&shifted), vec![0, 20, 20, 36, 52]);
+}
+
+#[test]
+#[should_panic(expected = "offset must not be larger than the skip gap")]
+fn offset_larger_than_the_skip_gap_panics() {
+    frames(&SKIPPED).apply_offset(-5001);
+}
+
+#[test]
+fn with_offset_rejects_offset_larger_than_the_skip_gap() {
+    let replay = Replay {
+        compressed_data: writer::compress_frames(&frames(&SKIPPED)),
+        ..Default::default()
+    };
+    assert!(matches!(
+        replay.with_offset(-6000),
+        Err(ReplayDataError::Parse(ParseError::InvalidValue))
+    ));
+
+    let shifted = replay.with_offset(-4000).expect("Offset within the skip gap");
+    let actions = shifted.get_actions().expect("Error getting actions");
+    assert_eq!(absolute_times(&actions), vec![0, 20, 1020, 1036, 1052]);
+}
+
+#[test]
+fn offset_without_skip_shifts_all_frames() {
+    let times = [0, -1, 16, 16, 17];
+    let seed = ReplayData {
+        time: -12345,
+        x: 0.0,
+        y: 0.0,
+        keys: 7,
+    };
+    let mut unskipped = frames(&times);
+    unskipped.push(seed);
+
+    let later = unskipped.apply_offset(30);
+    assert_eq!(absolute_times(&later), vec![30, 29, 45, 61, 78]);
+    assert_eq!(later.last(), Some(&seed));
+
+    // Frames before the start of the replay cannot be reordered by a negative offset
+    let earlier = unskipped.apply_offset(-10_000);
+    assert_eq!(absolute_times(&earlier), vec![-10_000, -10_001, -9985, -9969, -9952]);
+    assert_eq!(earlier.apply_offset(10_000), unskipped);
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::Path;
+
+use osu_replay_parser::osu_db::{OsuDb, OsuDbBeatmap};
+#[cfg(feature = "md5")]
+use osu_replay_parser::pairing::find_beatmap;
+use osu_replay_parser::pairing::find_beatmap_in_db;
+use osu_replay_parser::Replay;
+
+/// Songs folder with two beatmap sets, indexed by `assets/osu!.db` (version 20250107) and
+/// `assets/osu!_20191105.db`, which still has entry sizes and double star ratings.
+const SONGS_DIR: &str = "assets/Songs";
+
+const NORMAL_MD5: &str = "ee588ea4d5889e80eea0b01d2f2243a3";
+const HARD_MD5: &str = "e9dc632b6917ed8122b3ba50252d7387";
+const EASY_MD5: &str = "b290e2c45986959522c80f28e910c58b";
+
+fn beatmap(md5: &str, folder_name: &str, file_name: &str, ids: (i32, i32)) -> OsuDbBeatmap {
+    OsuDbBeatmap {
+        md5: md5.to_string(),
+        folder_name: folder_name.to_string(),
+        file_name: file_name.to_string(),
+        beatmap_id: ids.0,
+        beatmap_set_id: ids.1,
+    }
+}
+
+fn expected_beatmaps() -> Vec<OsuDbBeatmap> {
+    vec![
+        beatmap(
+            NORMAL_MD5,
+            "1001 Artist - Title",
+            "Artist - Title (Mapper) [Normal].osu",
+            (2001, 1001),
+        ),
+        beatmap(
+            HARD_MD5,
+            "1001 Artist - Title",
+            "Artist - Title (Mapper) [Hard].osu",
+            (2002, 1001),
+        ),
+        beatmap(
+            EASY_MD5,
+            "1002 Another Artist - Another Title",
+            "Another Artist - Another Title (Mapper) [Easy].osu",
+            (2003, 1002),
+        ),
+    ]
+}
+
+fn replay_of(beatmap_md5: &str) -> Replay {
+    let data = fs::read("assets/replay.osr").expect("Error reading replay");
+    let mut replay = Replay::parse(&data).expect("Error parsing replay");
+    replay.beatmap_md5 = beatmap_md5.to_string();
+    replay
+}
+
+#[test]
+fn osu_db_fixture() {
+    let db = OsuDb::from_file("assets/osu!.db").expect("Error parsing osu!.db");
+    assert_eq!(db.version, 20250107);
+    assert_eq!(db.player_name, "RenLephy");
+    assert_eq!(db.beatmaps, expected_beatmaps());
+    assert_eq!(db.find(&HARD_MD5.to_uppercase()), Some(&db.beatmaps[1]));
+    assert_eq!(db.find("00000000000000000000000000000000"), None);
+}
+
+#[test]
+fn osu_db_fixture_with_entry_sizes() {
+    let db = OsuDb::from_file("assets/osu!_20191105.db").expect("Error parsing osu!.db");
+    assert_eq!(db.version, 20191105);
+    assert_eq!(db.player_name, "RenLephy");
+    assert_eq!(db.beatmaps, expected_beatmaps());
+}
+
+#[test]
+fn osu_db_truncated() {
+    let data = fs::read("assets/osu!.db").expect("Error reading osu!.db");
+    assert!(OsuDb::parse(&data[..data.len() / 2]).is_err());
+}
+
+#[test]
+fn find_beatmap_in_db_fixture() {
+    let db = OsuDb::from_file("assets/osu!.db").expect("Error parsing osu!.db");
+    let path = find_beatmap_in_db(&replay_of(EASY_MD5), &db, SONGS_DIR).expect("Missing beatmap");
+    assert_eq!(
+        path,
+        Path::new(SONGS_DIR)
+            .join("1002 Another Artist - Another Title")
+            .join("Another Artist - Another Title (Mapper) [Easy].osu")
+    );
+    assert!(path.is_file());
+    assert_eq!(
+        find_beatmap_in_db(
+            &replay_of("0123456789abcdef0123456789abcdef"),
+            &db,
+            SONGS_DIR
+        ),
+        None
+    );
+}
+
+#[cfg(feature = "md5")]
+#[test]
+fn find_beatmap_fixture() {
+    let db = OsuDb::from_file("assets/osu!.db").expect("Error parsing osu!.db");
+    for md5 in [NORMAL_MD5, HARD_MD5, EASY_MD5] {
+        let replay = replay_of(md5);
+        let path = find_beatmap(&replay, SONGS_DIR)
+            .expect("Error searching Songs folder")
+            .expect("Missing beatmap");
+        assert_eq!(Some(path), find_beatmap_in_db(&replay, &db, SONGS_DIR));
+    }
+
+    let replay = replay_of("0123456789abcdef0123456789abcdef");
+    assert_eq!(
+        find_beatmap(&replay, SONGS_DIR).expect("Error searching Songs folder"),
+        None
+    );
+}
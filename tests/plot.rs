@@ -0,0 +1,41 @@
+#![cfg(feature = "plot")]
+
+use osu_replay_parser::beatmap::{Beatmap, HitObjectKind};
+use osu_replay_parser::plot::{self, PlotOptions};
+use osu_replay_parser::Replay;
+
+/// A beatmap with one slider whose length is replaced after parsing.
+fn beatmap_with_slider_length(length: f32) -> Beatmap {
+    let mut beatmap = Beatmap::parse(
+        "[Difficulty]\nCircleSize:4\n\n[HitObjects]\n200,200,2000,2,0,L|300:200,1,100\n",
+    )
+    .unwrap();
+    if let HitObjectKind::Slider { length: old, .. } = &mut beatmap.hit_objects[0].kind {
+        *old = length;
+    }
+    beatmap
+}
+
+#[test]
+fn bogus_slider_lengths_are_plotted_without_panicking() {
+    let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    let actions = replay.get_actions().expect("Error getting actions");
+    let options = PlotOptions {
+        time_range: Some((0, 10_000)),
+        ..PlotOptions::default()
+    };
+
+    for length in [-5.0, f32::NAN, f32::INFINITY, 0.0] {
+        let beatmap = beatmap_with_slider_length(length);
+        let svg = plot::svg(&actions, Some(&beatmap), &options).unwrap();
+        assert!(!svg.contains("<polyline points=\"200.0,200.0"));
+        assert_eq!(svg.matches("<circle cx=\"200.0\" cy=\"200.0\"").count(), 1);
+    }
+
+    let beatmap = beatmap_with_slider_length(1e30);
+    let svg = plot::svg(&actions, Some(&beatmap), &options).unwrap();
+    // The head, then one point per step
+    let slider = svg.split("<polyline points=\"200.0,200.0").nth(1).unwrap();
+    let points = slider.split('"').next().unwrap().split(' ').count();
+    assert_eq!(points, 4097);
+}
@@ -0,0 +1,46 @@
+use osu_replay_parser::parser::ParseStatus;
+use osu_replay_parser::replay::FIRST_LAZER_VERSION;
+use osu_replay_parser::Replay;
+
+fn lazer_replay() -> Vec<u8> {
+    let replay = Replay::from_file("assets/replay.osr").expect("Error parsing replay");
+    let lazer = replay
+        .convert_to_version(FIRST_LAZER_VERSION)
+        .expect("Error converting replay");
+    lazer.to_bytes().expect("Error writing replay")
+}
+
+#[test]
+fn lazer_replay_without_trailer_is_incomplete() {
+    let input = lazer_replay();
+    let replay = Replay::parse(&input).unwrap();
+    let trailer = 4 + replay.lazer_data.as_ref().unwrap().len();
+    let options = Default::default();
+
+    let cut = &input[..input.len() - trailer];
+    assert!(matches!(
+        Replay::parse_streaming(cut, &options),
+        Ok(ParseStatus::Incomplete(_))
+    ));
+    assert!(matches!(
+        Replay::parse_streaming(&input[..input.len() - 1], &options),
+        Ok(ParseStatus::Incomplete(_))
+    ));
+    match Replay::parse_streaming(&input, &options) {
+        Ok(ParseStatus::Complete(complete)) => assert_eq!(complete.lazer_data, replay.lazer_data),
+        other => panic!("Expected a complete replay, got {:?}", other),
+    }
+}
+
+#[test]
+fn string_length_overflowing_32_bits_is_rejected() {
+    let mut input = std::fs::read("assets/replay.osr").expect("Error reading file");
+    // The beatmap MD5 follows the game mode and version, its ULEB128 length gets 6 bytes
+    input.splice(6..7, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+    assert!(Replay::parse(&input).is_err());
+    assert!(Replay::parse_streaming(&input, &Default::default()).is_err());
+
+    // A 5 byte ULEB128 with bits beyond 32
+    input.splice(6..12, [0xFF, 0xFF, 0xFF, 0xFF, 0x1F]);
+    assert!(Replay::parse_streaming(&input, &Default::default()).is_err());
+}